@@ -1,10 +1,57 @@
 //! This Cargo build script finds the linker on setups where using a .cargo/config.toml file would
 //! be inconvenient, e.g. in a Cargo workspace.
 
-use std::{env, fs, path};
+use std::{env, fs, path, process::Command};
+
+#[path = "src/memory_check.rs"]
+mod memory_check;
+
+/// Memory scripts shipped with the BSP
+const MEMORY_SCRIPTS: [&str; 3] = ["mem_hpc.x", "sdram_hpc.x", "mem_sysctrl.x"];
+
+/// `git describe` of the checkout the BSP is built from, or "unknown", e.g., when built from
+/// crates.io
+fn git_describe() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| String::from_utf8(out.stdout).ok())
+            .map(|s| s.trim().to_owned())
+    };
+
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/index");
+    }
+    git(&["describe", "--always", "--dirty", "--tags"]).unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Enabled Cargo features, sorted and comma-separated
+fn features() -> String {
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|f| f.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    features.join(",")
+}
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=dma.x");
+    for script in MEMORY_SCRIPTS {
+        memory_check::check_memory_script(script);
+    }
+
+    // Reported by `headsail_bsp::version`
+    let describe = git_describe();
+    println!("cargo:rustc-env=HEADSAIL_BSP_GIT_DESCRIBE={describe}");
+    println!("cargo:rustc-env=HEADSAIL_BSP_FEATURES={}", features());
 
     // Put link script in our output directory and ensure it's on the linker search path
     let out = &path::PathBuf::from(env::var_os("OUT_DIR").unwrap());
@@ -18,5 +65,6 @@ fn main() {
     }
     fs::copy("sdram_hpc.x", out.join("sdram_hpc.x")).unwrap();
     fs::copy("mem_sysctrl.x", out.join("mem_sysctrl.x")).unwrap();
+    fs::copy("dma.x", out.join("dma.x")).unwrap();
     println!("cargo:rustc-link-search={}", out.display());
 }
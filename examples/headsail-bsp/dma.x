@@ -0,0 +1,34 @@
+/* Sections for buffers accessed by DMA, sa. `headsail_bsp::dma_static!`
+ *
+ * Include this fragment from the memory script and alias REGION_DMA to a
+ * memory region that is visible to the DMA engine.
+ */
+SECTIONS
+{
+  /* Zeroed by `headsail_bsp::dma::init` */
+  .dma_bss (NOLOAD) : ALIGN(32)
+  {
+    __sdma_bss = .;
+    *(.dma_bss .dma_bss.*);
+    . = ALIGN(32);
+    __edma_bss = .;
+  } > REGION_DMA
+
+  /* Left uninitialized */
+  .dma_noinit (NOLOAD) : ALIGN(32)
+  {
+    __sdma_noinit = .;
+    *(.dma_noinit .dma_noinit.*);
+    . = ALIGN(32);
+    __edma_noinit = .;
+  } > REGION_DMA
+
+  /* Buffers with canaries, sa. the `dma-canary` feature. Empty without it. */
+  .dma_canaries : ALIGN(8)
+  {
+    __sdma_canaries = .;
+    KEEP(*(.dma_canaries .dma_canaries.*));
+    __edma_canaries = .;
+  } > REGION_RODATA
+}
+INSERT AFTER .bss;
@@ -0,0 +1,34 @@
+//! Compare busy-wait delays against `mcycle`
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{delay, riscv::register::mcycle, rt::entry, sprintln};
+
+#[entry]
+fn main() -> ! {
+    sprintln!("Delay example");
+
+    let correction = delay::calibrate();
+    sprintln!("Correction factor: {}/65536", correction);
+
+    for cycles in [1_000, 10_000, 100_000, 1_000_000] {
+        let start = mcycle::read();
+        delay::cycles(cycles);
+        let elapsed = mcycle::read().wrapping_sub(start);
+        sprintln!("delay::cycles({}) took {} cycles", cycles, elapsed);
+    }
+
+    let start = mcycle::read();
+    delay::micros(1_000);
+    let elapsed = mcycle::read().wrapping_sub(start);
+    sprintln!(
+        "delay::micros(1000) took {} cycles, expected {}",
+        elapsed,
+        delay::DEFAULT_CORE_HZ / 1_000
+    );
+    loop {}
+}
@@ -0,0 +1,58 @@
+//! Serve hardware-in-the-loop requests over APB UART0
+//!
+//! `Command::DlaInference` only validates the descriptor. Invalid ones are
+//! answered with `Status::Failed`, followed by the wire encoding of the
+//! `ParseError`. Other application commands are answered with
+//! `Status::Unsupported`.
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    apb_uart::ApbUart0,
+    dla::{LayerDescriptor, ParseError},
+    hil::{Command, Handler, Server, Status},
+    rt::entry,
+};
+
+/// Descriptors are parsed in place, which requires alignment
+#[repr(C, align(4))]
+struct Blob([u8; LayerDescriptor::SIZE]);
+
+#[derive(Default)]
+struct Validator {
+    error: Option<ParseError>,
+}
+
+impl Handler for Validator {
+    fn handle(&mut self, cmd: Command, payload: &[u8], _: &mut [u8]) -> Result<usize, Status> {
+        match cmd {
+            Command::DlaInference => {
+                let mut blob = Blob([0; LayerDescriptor::SIZE]);
+                let len = payload.len().min(blob.0.len());
+                blob.0[..len].copy_from_slice(&payload[..len]);
+                match LayerDescriptor::from_bytes(&blob.0[..len]) {
+                    Ok(_) => Ok(0),
+                    Err(e) => {
+                        self.error = Some(e);
+                        Err(Status::Failed)
+                    }
+                }
+            }
+            _ => Err(Status::Unsupported),
+        }
+    }
+
+    fn failure_detail(&mut self, resp: &mut [u8]) -> usize {
+        self.error.take().map_or(0, |e| e.to_wire(resp))
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let (soc_freq, baud) = (30_000_000, 115_200);
+    let uart = ApbUart0::init(soc_freq, baud);
+
+    // Safety: this firmware has nothing to protect from the host
+    let mut server = unsafe { Server::<_, 256>::new(uart) };
+    server.run(&mut Validator::default())
+}
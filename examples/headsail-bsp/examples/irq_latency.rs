@@ -0,0 +1,111 @@
+//! Measure machine timer interrupt latency on hart 0 and check it against
+//! [MAX_LATENCY_CYCLES]
+//!
+//! The interrupted code keeps storing `mcycle` while waiting, and the handler
+//! subtracts the last stored value from `mcycle` at its first instruction.
+//! The result covers the trap entry and the dispatch of the runtime, plus
+//! whatever the interrupted code ran with interrupts masked since its last
+//! store. It is measured twice: spinning, and with the [work] queue loaded.
+//!
+//! `scripts/robot/irq_latency.robot` runs this on the VP. The VP counts one
+//! cycle per instruction, so the figures are instruction counts there.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use headsail_bsp::{
+    riscv::{self, register::mcycle},
+    rt::entry,
+    sprintln,
+    testutil::LatencyStats,
+    work, CLINT,
+};
+
+/// Documented bound on the latency measured here, in `mcycle` cycles
+const MAX_LATENCY_CYCLES: u32 = 500;
+const ITERATIONS: usize = 64;
+
+/// `mcycle` last stored by the interrupted code
+static SHADOW: AtomicU64 = AtomicU64::new(0);
+static LATENCY: AtomicU64 = AtomicU64::new(0);
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+#[entry]
+fn main() -> ! {
+    // Pending only once armed
+    CLINT::mtimecmp0().write(u64::MAX);
+    unsafe {
+        riscv::register::mie::set_mtimer();
+        riscv::interrupt::enable();
+    }
+
+    let spin = measure(|| {
+        while !FIRED.load(Ordering::Relaxed) {
+            SHADOW.store(mcycle::read64(), Ordering::Relaxed);
+        }
+    });
+    let loaded = measure(|| {
+        for _ in 0..work::WORK_QUEUE_LEN {
+            work::defer(stamp, 0).ok();
+        }
+        work::run_pending();
+    });
+
+    let mut ok = true;
+    for (name, stats) in [("spin", spin), ("work queue", loaded)] {
+        sprintln!(
+            "{}: min {} median {} max {} cycles over {} interrupts",
+            name,
+            stats.min,
+            stats.median,
+            stats.max,
+            stats.samples
+        );
+        ok &= stats.max <= MAX_LATENCY_CYCLES;
+    }
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {}
+}
+
+/// Fire the timer [ITERATIONS] times while `wait` runs until it has fired
+fn measure(mut wait: impl FnMut()) -> LatencyStats {
+    let mut samples = [0u32; ITERATIONS];
+    for sample in samples.iter_mut() {
+        FIRED.store(false, Ordering::Relaxed);
+        SHADOW.store(mcycle::read64(), Ordering::Relaxed);
+        // Two ticks, so the compare value is not already behind `mtime`
+        CLINT::mtimecmp0().write(CLINT::mtime().read() + 2);
+        wait();
+        *sample = LATENCY.load(Ordering::Relaxed).min(u32::MAX as u64) as u32;
+    }
+    LatencyStats::from_samples(&mut samples).unwrap()
+}
+
+/// Deferred work that keeps the queue loaded until the timer fires
+fn stamp(_: usize) {
+    SHADOW.store(mcycle::read64(), Ordering::Relaxed);
+    if !FIRED.load(Ordering::Relaxed) {
+        work::defer(stamp, 0).ok();
+    }
+}
+
+#[export_name = "MachineTimer"]
+fn machine_timer() {
+    let entry = mcycle::read64();
+    LATENCY.store(
+        entry.wrapping_sub(SHADOW.load(Ordering::Relaxed)),
+        Ordering::Relaxed,
+    );
+    CLINT::mtimecmp0().write(u64::MAX);
+    FIRED.store(true, Ordering::Relaxed);
+}
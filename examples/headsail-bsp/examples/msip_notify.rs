@@ -0,0 +1,30 @@
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{rt::entry, sprintln, HartId, CLINT};
+
+#[entry]
+fn main() -> ! {
+    sprintln!("MSIP notification example");
+
+    // The runtime only starts hart 0, so it notifies itself
+    CLINT::notify_core(HartId::H0);
+    let pending = CLINT::is_msip_pending(HartId::H0);
+    let from = CLINT::wait_notification();
+    let cleared = !CLINT::is_msip_pending(HartId::H0);
+    sprintln!("notified by hart {}", from as u16);
+
+    CLINT::set_msip(HartId::H1);
+    let other = CLINT::is_msip_pending(HartId::H1);
+    CLINT::clear_msip(HartId::H1);
+
+    if pending && from == HartId::H0 && cleared && other {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {}
+}
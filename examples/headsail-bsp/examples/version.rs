@@ -0,0 +1,18 @@
+//! Print the BSP version banner
+//!
+//! `scripts/robot/bsp_version.robot` checks that the banner makes it into the
+//! binary.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{rt::entry, sprintln};
+
+#[entry]
+fn main() -> ! {
+    sprintln!("{}", headsail_bsp::version());
+    loop {}
+}
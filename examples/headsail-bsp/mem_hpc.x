@@ -9,3 +9,7 @@ REGION_ALIAS("REGION_DATA", BOOTRAM);
 REGION_ALIAS("REGION_BSS", BOOTRAM);
 REGION_ALIAS("REGION_HEAP", BOOTRAM);
 REGION_ALIAS("REGION_STACK", BOOTRAM);
+/* Keep DMA buffers alongside other data */
+REGION_ALIAS("REGION_DMA", BOOTRAM);
+
+INCLUDE dma.x
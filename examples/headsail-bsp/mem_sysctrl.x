@@ -9,4 +9,8 @@ REGION_ALIAS("REGION_DATA", BANK0);
 REGION_ALIAS("REGION_RODATA", BANK1);
 REGION_ALIAS("REGION_BSS", BANK1);
 REGION_ALIAS("REGION_HEAP", BANK1);
-REGION_ALIAS("REGION_STACK", BANK1);
\ No newline at end of file
+REGION_ALIAS("REGION_STACK", BANK1);
+/* SysCtrl uDMA can access the L2 banks */
+REGION_ALIAS("REGION_DMA", BANK1);
+
+INCLUDE dma.x
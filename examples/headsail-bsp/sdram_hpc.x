@@ -9,3 +9,7 @@ REGION_ALIAS("REGION_DATA", SDRAM);
 REGION_ALIAS("REGION_BSS", SDRAM);
 REGION_ALIAS("REGION_HEAP", SDRAM);
 REGION_ALIAS("REGION_STACK", SDRAM);
+/* Keep DMA buffers alongside other data */
+REGION_ALIAS("REGION_DMA", SDRAM);
+
+INCLUDE dma.x
@@ -140,14 +140,14 @@ impl<const BASE_ADDR: usize> ApbUart<BASE_ADDR> {
     pub fn flush(&mut self) {
         // Wait for hardware to report completion
         #[cfg(feature = "asic")]
-        while !self.is_transmit_empty() {}
+        crate::poll::wait(|| self.is_transmit_empty());
     }
 
     #[inline]
     pub fn putc(&mut self, c: u8) {
         // Wait for hardware to report completion
         #[cfg(feature = "asic")]
-        while !self.is_transmit_empty() {}
+        crate::poll::wait(|| self.is_transmit_empty());
 
         // Safety: UART_THR is 4-byte aligned
         unsafe { write_u8(BASE_ADDR + UART_RBR_THR_DLL_OFS, c) };
@@ -0,0 +1,157 @@
+//! Checksums for framed protocols
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xffff, no
+/// reflection, no final XOR
+///
+/// ```ignore
+/// let mut crc = Crc16::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finish(), 0x29b1);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Crc16(u16);
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc16 {
+    pub const fn new() -> Self {
+        Self(0xffff)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= (byte as u16) << 8;
+            for _ in 0..8 {
+                self.0 = if self.0 & 0x8000 != 0 {
+                    (self.0 << 1) ^ 0x1021
+                } else {
+                    self.0 << 1
+                };
+            }
+        }
+    }
+
+    pub const fn finish(&self) -> u16 {
+        self.0
+    }
+}
+
+/// CRC-16/CCITT-FALSE of `data`, sa. [Crc16]
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = Crc16::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// CRC-32/ISO-HDLC, as used by Ethernet and zlib: polynomial 0x04c11db7,
+/// reflected, initial value and final XOR 0xffffffff
+///
+/// ```ignore
+/// let mut crc = Crc32::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finish(), 0xcbf4_3926);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self(0xffff_ffff)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                self.0 = if self.0 & 1 != 0 {
+                    (self.0 >> 1) ^ 0xedb8_8320
+                } else {
+                    self.0 >> 1
+                };
+            }
+        }
+    }
+
+    pub const fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// CRC-32/ISO-HDLC of `data`, sa. [Crc32]
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// CRC-7/MMC of `data`: polynomial 0x09, initial value 0, no reflection, no
+/// final XOR
+///
+/// SD commands and the CID and CSD registers carry it in the top seven bits
+/// of their last byte, followed by a 1 stop bit.
+pub const fn crc7(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    let mut i = 0;
+    while i < data.len() {
+        let mut byte = data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            let fb = ((crc >> 6) ^ (byte >> 7)) & 1;
+            crc = (crc << 1) & 0x7f;
+            if fb != 0 {
+                crc ^= 0x09;
+            }
+            byte <<= 1;
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn crc16_check() {
+        assert_eq!(crc16(CHECK), 0x29b1);
+        assert_eq!(crc16(&[]), 0xffff);
+        let mut crc = Crc16::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0x29b1);
+    }
+
+    #[test]
+    fn crc32_check() {
+        assert_eq!(crc32(CHECK), 0xcbf4_3926);
+        assert_eq!(crc32(&[]), 0);
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn crc7_check() {
+        assert_eq!(crc7(CHECK), 0x75);
+        // GO_IDLE_STATE and SEND_IF_COND as sent to SD cards
+        assert_eq!((crc7(&[0x40, 0, 0, 0, 0]) << 1) | 1, 0x95);
+        assert_eq!((crc7(&[0x48, 0, 0, 0x01, 0xaa]) << 1) | 1, 0x87);
+    }
+}
@@ -0,0 +1,108 @@
+//! Busy-wait delays
+//!
+//! [cycles] is built on an assembly loop that the compiler cannot optimize
+//! away. The loop cost differs between cores and memories, so call
+//! [calibrate] once at start-up for delays within a few percent of nominal.
+//! [nanos] and [micros] convert to cycles using the frequency set with
+//! [set_core_hz]. Drivers taking timeouts in `mcycle` cycles should convert
+//! with [us_to_cycles] and [cycles_to_us] rather than doing the arithmetic
+//! themselves.
+use riscv::register::mcycle;
+
+/// Core clock frequency assumed until [set_core_hz] is called
+pub const DEFAULT_CORE_HZ: u32 = match () {
+    // Renode runs the CPUs at 100 MIPS by default
+    #[cfg(feature = "vp")]
+    () => 100_000_000,
+    // 30 MHz reference clock without PLLs configured
+    #[cfg(not(feature = "vp"))]
+    () => 30_000_000,
+};
+
+/// Number of cycles measured by [calibrate]
+const CALIBRATION_CYCLES: u32 = 100_000;
+
+static mut CORE_HZ: u32 = DEFAULT_CORE_HZ;
+
+/// Ratio of requested to measured cycles in 16.16 fixed point
+static mut CORRECTION: u32 = 1 << 16;
+
+/// Set the core clock frequency used by [nanos] and [micros]
+pub fn set_core_hz(hz: u32) {
+    unsafe { CORE_HZ = hz };
+}
+
+/// Core clock frequency used by [nanos] and [micros]
+pub fn core_hz() -> u32 {
+    unsafe { CORE_HZ }
+}
+
+/// Measure the cost of the delay loop on the running core using `mcycle` as
+/// the reference, and correct subsequent delays accordingly
+///
+/// Returns the 16.16 fixed point correction factor.
+pub fn calibrate() -> u32 {
+    unsafe { CORRECTION = 1 << 16 };
+
+    let start = mcycle::read();
+    cycles(CALIBRATION_CYCLES);
+    let elapsed = mcycle::read().wrapping_sub(start) as u64;
+
+    let correction = ((CALIBRATION_CYCLES as u64) << 16) / elapsed.max(1);
+    let correction = correction.clamp(1, u32::MAX as u64) as u32;
+    unsafe { CORRECTION = correction };
+    correction
+}
+
+/// Block for approximately `n` core clock cycles
+#[inline]
+pub fn cycles(n: u32) {
+    let n = ((n as u64 * unsafe { CORRECTION } as u64) >> 16) as u32;
+    riscv::asm::delay(n);
+}
+
+/// Number of core clock cycles in `ns` nanoseconds at [core_hz]
+#[inline]
+pub fn ns_to_cycles(ns: u32) -> u64 {
+    ns as u64 * core_hz() as u64 / 1_000_000_000
+}
+
+/// Number of core clock cycles in `us` microseconds at [core_hz]
+#[inline]
+pub fn us_to_cycles(us: u32) -> u64 {
+    us as u64 * core_hz() as u64 / 1_000_000
+}
+
+/// Number of whole microseconds in `n` core clock cycles at [core_hz]
+#[inline]
+pub fn cycles_to_us(n: u64) -> u64 {
+    n * 1_000_000 / core_hz().max(1) as u64
+}
+
+/// Block for approximately `ns` nanoseconds
+#[inline]
+pub fn nanos(ns: u32) {
+    cycles(ns_to_cycles(ns).min(u32::MAX as u64) as u32);
+}
+
+/// Block for approximately `us` microseconds
+#[inline]
+pub fn micros(us: u32) {
+    cycles(us_to_cycles(us).min(u32::MAX as u64) as u32);
+}
+
+/// [nanos] and [micros] as an [embedded_hal::delay::DelayNs], e.g., for
+/// [BitBangI2c](crate::i2c::BitBangI2c)
+#[cfg(feature = "i2c")]
+pub struct Delay;
+
+#[cfg(feature = "i2c")]
+impl embedded_hal::delay::DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        nanos(ns);
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        micros(us);
+    }
+}
@@ -0,0 +1,387 @@
+//! On-flash encoding of DLA layer descriptors
+//!
+//! A descriptor is a [LayerDescriptor] stored as-is: little-endian, every
+//! field at an offset that is a multiple of its size and no padding between
+//! fields. [LayerDescriptor::from_bytes] therefore parses a blob in place,
+//! without unaligned loads.
+//!
+//! Blobs come from external flash and are untrusted. Everything the DLA will
+//! be programmed with is checked before a reference is handed out: the magic,
+//! the format version, register field widths, the consistency of the layer
+//! geometry and that every buffer lies within the DLA memory banks.
+//!
+//! ```ignore
+//! // Four-byte aligned, e.g., a `dma_static!` buffer
+//! flash.read(DESC_ADDR, &mut BLOB[..LayerDescriptor::SIZE])?;
+//! let desc = LayerDescriptor::from_bytes(&BLOB)?;
+//! let weights = mmap::DLA_MEM_ADDR + desc.weights.offset as usize;
+//! ```
+use core::mem::{align_of, size_of};
+
+use crate::{
+    fmt::put_wire,
+    mmap::{DLA_BANK_COUNT, DLA_BANK_SIZE},
+};
+
+/// "HDLA" in little-endian
+pub const MAGIC: u32 = u32::from_le_bytes(*b"HDLA");
+/// Format version this parser accepts
+pub const VERSION: u16 = 1;
+/// Size of the DLA data memory, the only memory descriptors may refer to
+pub const DLA_MEM_SIZE: u32 = (DLA_BANK_SIZE * DLA_BANK_COUNT) as u32;
+
+/// `flags`: add [LayerDescriptor::bias] after the MAC stage
+pub const FLAG_BIAS: u8 = 1 << 0;
+/// `flags`: apply ReLU in the post-processor
+pub const FLAG_RELU: u8 = 1 << 1;
+/// `flags`: round instead of truncating in the post-processor
+pub const FLAG_ROUNDING: u8 = 1 << 2;
+const FLAGS_KNOWN: u8 = FLAG_BIAS | FLAG_RELU | FLAG_ROUNDING;
+
+/// Widths of the DLA register fields the descriptor is programmed into
+const MAX_INPUT_DIM: u32 = (1 << 9) - 1;
+const MAX_KERNEL_DIM: u32 = (1 << 4) - 1;
+const MAX_PAD: u32 = (1 << 4) - 1;
+const MAX_STRIDE: u32 = 1 << 4;
+const MAX_CLIP: u32 = (1 << 5) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Blob is shorter than [LayerDescriptor::SIZE]
+    TooShort,
+    /// Blob is not four-byte aligned
+    Misaligned,
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// `size` does not match this version of the format
+    BadSize,
+    /// `field` is `value`, outside the range the DLA accepts, which ends
+    /// at `limit`. Reserved fields and bits have a limit of zero.
+    InvalidConfig {
+        field: &'static str,
+        value: u32,
+        limit: u32,
+    },
+    /// A buffer length does not match the layer geometry
+    BadLength,
+    /// A buffer extends past the DLA memory banks
+    OutOfBounds,
+    /// The output buffer overlaps an input buffer
+    Overlap,
+}
+
+impl ParseError {
+    /// Longest encoding written by [ParseError::to_wire]
+    pub const WIRE_LEN: usize = 10 + Self::WIRE_FIELD_LEN;
+    /// Field names are cut to this many bytes by [ParseError::to_wire]
+    const WIRE_FIELD_LEN: usize = 16;
+
+    /// Encode for a host, e.g., in a [hil](crate::hil) response
+    ///
+    /// Like the other driver errors, a stable code byte is followed by the
+    /// fields as little-endian u32s:
+    ///
+    /// | Code | Variant              | Fields                                     |
+    /// | :-   | :-                   | :-                                         |
+    /// | 0    | `TooShort`           |                                            |
+    /// | 1    | `Misaligned`         |                                            |
+    /// | 2    | `BadMagic`           |                                            |
+    /// | 3    | `UnsupportedVersion` | version                                    |
+    /// | 4    | `BadSize`            |                                            |
+    /// | 5    | `InvalidConfig`      | `value`, `limit`, `len: u8`, `field` ASCII |
+    /// | 6    | `BadLength`          |                                            |
+    /// | 7    | `OutOfBounds`        |                                            |
+    /// | 8    | `Overlap`            |                                            |
+    ///
+    /// `field` is cut to 16 bytes. Returns the length, or 0 if `buf` is too
+    /// short.
+    pub fn to_wire(&self, buf: &mut [u8]) -> usize {
+        match *self {
+            ParseError::TooShort => put_wire(buf, 0, &[]),
+            ParseError::Misaligned => put_wire(buf, 1, &[]),
+            ParseError::BadMagic => put_wire(buf, 2, &[]),
+            ParseError::UnsupportedVersion(v) => put_wire(buf, 3, &[v as u32]),
+            ParseError::BadSize => put_wire(buf, 4, &[]),
+            ParseError::InvalidConfig {
+                field,
+                value,
+                limit,
+            } => {
+                let name = &field.as_bytes()[..field.len().min(Self::WIRE_FIELD_LEN)];
+                let len = put_wire(buf, 5, &[value, limit]);
+                if len == 0 || buf.len() < len + 1 + name.len() {
+                    return 0;
+                }
+                buf[len] = name.len() as u8;
+                buf[len + 1..len + 1 + name.len()].copy_from_slice(name);
+                len + 1 + name.len()
+            }
+            ParseError::BadLength => put_wire(buf, 6, &[]),
+            ParseError::OutOfBounds => put_wire(buf, 7, &[]),
+            ParseError::Overlap => put_wire(buf, 8, &[]),
+        }
+    }
+}
+
+/// Location of a buffer in the DLA memory banks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct Region {
+    /// From the start of the first bank, [mmap::DLA_MEM_ADDR]
+    ///
+    /// [mmap::DLA_MEM_ADDR]: crate::mmap::DLA_MEM_ADDR
+    pub offset: u32,
+    /// In bytes
+    pub len: u32,
+}
+
+impl Region {
+    /// Exclusive end, or `None` if outside of the DLA memory
+    fn end(&self) -> Option<u32> {
+        self.offset
+            .checked_add(self.len)
+            .filter(|&end| end <= DLA_MEM_SIZE)
+    }
+
+    fn overlaps(&self, other: &Region) -> bool {
+        self.len != 0
+            && other.len != 0
+            && self.offset < other.offset + other.len
+            && other.offset < self.offset + self.len
+    }
+}
+
+/// One convolution layer: 8-bit input and weights, 16-bit bias
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C, align(4))]
+pub struct LayerDescriptor {
+    /// [MAGIC]
+    pub magic: u32,
+    /// [VERSION]
+    pub version: u16,
+    /// [LayerDescriptor::SIZE]
+    pub size: u16,
+    /// `width * height * channels` bytes, channel-major
+    pub input: Region,
+    /// `kernel_width * kernel_height * channels * kernels` bytes
+    pub weights: Region,
+    /// Two bytes per kernel, or empty without [FLAG_BIAS]
+    pub bias: Region,
+    /// At least one byte per output element
+    pub output: Region,
+    pub width: u16,
+    pub height: u16,
+    pub channels: u16,
+    /// Number of kernels, i.e., output channels
+    pub kernels: u16,
+    pub kernel_width: u8,
+    pub kernel_height: u8,
+    /// 1 to 16
+    pub stride_x: u8,
+    pub stride_y: u8,
+    pub pad_top: u8,
+    pub pad_right: u8,
+    pub pad_bottom: u8,
+    pub pad_left: u8,
+    pub pad_value: u8,
+    /// Bits clipped after the MAC stage
+    pub mac_clip: u8,
+    /// Bits clipped after the post-processor
+    pub pp_clip: u8,
+    /// `FLAG_*` bits
+    pub flags: u8,
+    /// Must be zero
+    pub reserved: u32,
+}
+
+// The in-place parse relies on there being no padding
+const _: () = assert!(size_of::<LayerDescriptor>() == 64);
+const _: () = assert!(cfg!(target_endian = "little"));
+
+impl LayerDescriptor {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Validate the descriptor at the start of `bytes` and view it in place
+    pub fn from_bytes(bytes: &[u8]) -> Result<&Self, ParseError> {
+        if bytes.len() < Self::SIZE {
+            return Err(ParseError::TooShort);
+        }
+        if bytes.as_ptr() as usize & (align_of::<Self>() - 1) != 0 {
+            return Err(ParseError::Misaligned);
+        }
+        // SAFETY: length and alignment were checked above, the struct has no
+        // padding and any bit pattern is valid for its integer fields
+        let desc = unsafe { &*(bytes.as_ptr() as *const Self) };
+        desc.validate()?;
+        Ok(desc)
+    }
+
+    /// Width and height of the output feature map
+    pub fn output_dims(&self) -> (u32, u32) {
+        let out = |input: u16, pad_a: u8, pad_b: u8, kernel: u8, stride: u8| {
+            (input as u32 + pad_a as u32 + pad_b as u32).saturating_sub(kernel as u32)
+                / (stride as u32).max(1)
+                + 1
+        };
+        (
+            out(
+                self.width,
+                self.pad_left,
+                self.pad_right,
+                self.kernel_width,
+                self.stride_x,
+            ),
+            out(
+                self.height,
+                self.pad_top,
+                self.pad_bottom,
+                self.kernel_height,
+                self.stride_y,
+            ),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ParseError> {
+        if self.magic != MAGIC {
+            return Err(ParseError::BadMagic);
+        }
+        if self.version != VERSION {
+            return Err(ParseError::UnsupportedVersion(self.version));
+        }
+        if self.size as usize != Self::SIZE {
+            return Err(ParseError::BadSize);
+        }
+        self.validate_fields()?;
+        self.validate_lengths()?;
+        self.validate_regions()
+    }
+
+    fn validate_fields(&self) -> Result<(), ParseError> {
+        let padded_width = self.width as u32 + self.pad_left as u32 + self.pad_right as u32;
+        let padded_height = self.height as u32 + self.pad_top as u32 + self.pad_bottom as u32;
+        // Limits of the register fields, and the kernel must fit the padded
+        // input at least once
+        let ranges = [
+            ("width", self.width as u32, 1, MAX_INPUT_DIM),
+            ("height", self.height as u32, 1, MAX_INPUT_DIM),
+            ("channels", self.channels as u32, 1, u16::MAX as u32),
+            ("kernels", self.kernels as u32, 1, u16::MAX as u32),
+            ("kernel_width", self.kernel_width as u32, 1, MAX_KERNEL_DIM),
+            (
+                "kernel_height",
+                self.kernel_height as u32,
+                1,
+                MAX_KERNEL_DIM,
+            ),
+            ("kernel_width", self.kernel_width as u32, 1, padded_width),
+            ("kernel_height", self.kernel_height as u32, 1, padded_height),
+            ("stride_x", self.stride_x as u32, 1, MAX_STRIDE),
+            ("stride_y", self.stride_y as u32, 1, MAX_STRIDE),
+            ("pad_top", self.pad_top as u32, 0, MAX_PAD),
+            ("pad_right", self.pad_right as u32, 0, MAX_PAD),
+            ("pad_bottom", self.pad_bottom as u32, 0, MAX_PAD),
+            ("pad_left", self.pad_left as u32, 0, MAX_PAD),
+            ("mac_clip", self.mac_clip as u32, 0, MAX_CLIP),
+            ("pp_clip", self.pp_clip as u32, 0, MAX_CLIP),
+            ("reserved", self.reserved, 0, 0),
+        ];
+        for (field, value, min, limit) in ranges {
+            if !(min..=limit).contains(&value) {
+                return Err(ParseError::InvalidConfig {
+                    field,
+                    value,
+                    limit,
+                });
+            }
+        }
+        if self.flags & !FLAGS_KNOWN != 0 {
+            return Err(ParseError::InvalidConfig {
+                field: "flags",
+                value: self.flags as u32,
+                limit: FLAGS_KNOWN as u32,
+            });
+        }
+        Ok(())
+    }
+
+    fn validate_lengths(&self) -> Result<(), ParseError> {
+        // Dimensions are at most 16 bits wide, so the products fit in u64
+        let input = self.width as u64 * self.height as u64 * self.channels as u64;
+        let weights = self.kernel_width as u64
+            * self.kernel_height as u64
+            * self.channels as u64
+            * self.kernels as u64;
+        let bias = match self.flags & FLAG_BIAS {
+            0 => 0,
+            _ => 2 * self.kernels as u64,
+        };
+        let (out_w, out_h) = self.output_dims();
+        let output = out_w as u64 * out_h as u64 * self.kernels as u64;
+
+        let ok = self.input.len as u64 == input
+            && self.weights.len as u64 == weights
+            && self.bias.len as u64 == bias
+            && self.output.len as u64 >= output;
+        ok.then_some(()).ok_or(ParseError::BadLength)
+    }
+
+    fn validate_regions(&self) -> Result<(), ParseError> {
+        let regions = [self.input, self.weights, self.bias, self.output];
+        if regions.iter().any(|r| r.end().is_none()) {
+            return Err(ParseError::OutOfBounds);
+        }
+        if regions[..3].iter().any(|r| r.overlaps(&self.output)) {
+            return Err(ParseError::Overlap);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_wire_codes() {
+        let cases = [
+            (ParseError::TooShort, vec![0]),
+            (ParseError::Misaligned, vec![1]),
+            (ParseError::BadMagic, vec![2]),
+            (ParseError::UnsupportedVersion(0x102), vec![3, 2, 1, 0, 0]),
+            (ParseError::BadSize, vec![4]),
+            (ParseError::BadLength, vec![6]),
+            (ParseError::OutOfBounds, vec![7]),
+            (ParseError::Overlap, vec![8]),
+        ];
+        for (e, wire) in cases {
+            let mut buf = [0; ParseError::WIRE_LEN];
+            let n = e.to_wire(&mut buf);
+            assert_eq!(&buf[..n], &wire[..], "{:?}", e);
+            assert_eq!(e.to_wire(&mut buf[..n - 1]), 0);
+        }
+    }
+
+    #[test]
+    fn parse_error_wire_field_name() {
+        let e = ParseError::InvalidConfig {
+            field: "stride",
+            value: 17,
+            limit: 16,
+        };
+        let mut buf = [0; ParseError::WIRE_LEN];
+        let n = e.to_wire(&mut buf);
+        assert_eq!(&buf[..n], b"\x05\x11\0\0\0\x10\0\0\0\x06stride");
+        assert_eq!(e.to_wire(&mut buf[..n - 1]), 0);
+
+        // Names are cut to fit WIRE_LEN
+        let e = ParseError::InvalidConfig {
+            field: "a_field_name_longer_than_16",
+            value: 0,
+            limit: 0,
+        };
+        assert_eq!(e.to_wire(&mut buf), ParseError::WIRE_LEN);
+        assert_eq!(buf[9], 16);
+        assert_eq!(&buf[10..], b"a_field_name_lon");
+    }
+}
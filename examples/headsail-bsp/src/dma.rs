@@ -0,0 +1,306 @@
+//! Statically allocated DMA buffers
+//!
+//! Buffers declared with [dma_static](crate::dma_static) are placed in the
+//! `.dma_bss` section described by the `dma.x` linker fragment. The fragment
+//! is included by the memory scripts shipped with the BSP, so applications
+//! linking `-Tmem_sysctrl.x`, `-Tmem_hpc.x` or `-Tsdram_hpc.x` get the
+//! sections for free. A custom memory script must alias `REGION_DMA` and
+//! `INCLUDE dma.x`, which the application's build script can check with the
+//! `memory-check` feature, sa. `headsail_bsp::memory_check`.
+//!
+//! ```ignore
+//! headsail_bsp::dma_static!(RX_BUF: [u8; 256]);
+//!
+//! unsafe { headsail_bsp::dma::init() };
+//! let rx = unsafe { RX_BUF.get_mut() };
+//! ```
+//!
+//! # Canaries
+//!
+//! With the `dma-canary` feature, each buffer is surrounded by canaries
+//! filled with [CANARY_BYTE] by [init]: [DMA_ALIGN] bytes before it, to keep
+//! the buffer aligned, and [CANARY_TAIL_LEN] bytes after it. A transfer that
+//! runs past either end of its buffer, e.g., because of an off-by-one in a
+//! length field, is then found by [DmaStatic::check] or [check_all], and
+//! the panic handlers run [check_all] too. Without the feature, buffers have
+//! no canaries and the checks do not exist.
+use core::cell::UnsafeCell;
+
+/// Alignment of each buffer placed with [dma_static](crate::dma_static)
+pub const DMA_ALIGN: usize = 32;
+
+/// Fill of the canaries around each buffer, sa. [module
+/// documentation](self#canaries)
+#[cfg(feature = "dma-canary")]
+pub const CANARY_BYTE: u8 = 0xa5;
+/// Length of the canary after each buffer with the `dma-canary` feature
+pub const CANARY_TAIL_LEN: usize = 16;
+
+const HEAD_LEN: usize = if cfg!(feature = "dma-canary") {
+    DMA_ALIGN
+} else {
+    0
+};
+const TAIL_LEN: usize = if cfg!(feature = "dma-canary") {
+    CANARY_TAIL_LEN
+} else {
+    0
+};
+
+extern "C" {
+    static mut __sdma_bss: u32;
+    static mut __edma_bss: u32;
+    static __edma_noinit: u32;
+}
+
+#[cfg(feature = "dma-canary")]
+extern "C" {
+    static __sdma_canaries: u8;
+    static __edma_canaries: u8;
+}
+
+/// Backing storage of a buffer declared with [dma_static](crate::dma_static)
+#[repr(C, align(32))]
+pub struct DmaStatic<const N: usize> {
+    /// Canaries, empty without the `dma-canary` feature
+    #[cfg_attr(not(feature = "dma-canary"), allow(dead_code))]
+    head: UnsafeCell<[u8; HEAD_LEN]>,
+    buf: UnsafeCell<[u8; N]>,
+    #[cfg_attr(not(feature = "dma-canary"), allow(dead_code))]
+    tail: UnsafeCell<[u8; TAIL_LEN]>,
+}
+
+// Safety: access to the contents is only possible through unsafe methods
+unsafe impl<const N: usize> Sync for DmaStatic<N> {}
+
+impl<const N: usize> Default for DmaStatic<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DmaStatic<N> {
+    pub const fn new() -> Self {
+        Self {
+            head: UnsafeCell::new([0; HEAD_LEN]),
+            buf: UnsafeCell::new([0; N]),
+            tail: UnsafeCell::new([0; TAIL_LEN]),
+        }
+    }
+
+    #[inline]
+    pub fn as_mut_ptr(&self) -> *mut u8 {
+        self.buf.get() as *mut u8
+    }
+
+    /// # Safety
+    ///
+    /// The caller must make sure no other reference to the buffer exists,
+    /// including ones held by an ongoing DMA transfer.
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_mut(&'static self) -> &'static mut [u8; N] {
+        &mut *self.buf.get()
+    }
+
+    /// Check the canaries around the buffer, sa. [module
+    /// documentation](self#canaries)
+    ///
+    /// Reports the first damaged byte on the side closest to the buffer.
+    #[cfg(feature = "dma-canary")]
+    pub fn check(&self) -> Result<(), CanaryCorruption> {
+        // Safety: the canaries are only written by `fill_canaries` and by
+        // accident, never through a reference
+        let (head, tail) = unsafe { (&*self.head.get(), &*self.tail.get()) };
+        let damaged = |canary: &[u8], side, offset: usize| {
+            let count = canary.iter().filter(|&&b| b != CANARY_BYTE).count();
+            CanaryCorruption {
+                buf: self.as_mut_ptr() as usize,
+                len: N,
+                side,
+                offset,
+                written: canary[offset],
+                count,
+            }
+        };
+        if let Some(idx) = head.iter().rposition(|&b| b != CANARY_BYTE) {
+            return Err(damaged(head, CanarySide::Before, idx));
+        }
+        if let Some(idx) = tail.iter().position(|&b| b != CANARY_BYTE) {
+            return Err(damaged(tail, CanarySide::After, idx));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "dma-canary")]
+    #[doc(hidden)]
+    pub fn fill_canaries(&self) {
+        // Safety: the canaries are never handed out
+        unsafe {
+            (*self.head.get()).fill(CANARY_BYTE);
+            (*self.tail.get()).fill(CANARY_BYTE);
+        }
+    }
+}
+
+/// Which canary of a buffer was overwritten
+#[cfg(feature = "dma-canary")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanarySide {
+    /// A transfer started before the buffer
+    Before,
+    /// A transfer ran past the end of the buffer
+    After,
+}
+
+/// Damaged canary found by [DmaStatic::check] or [check_all]
+#[cfg(feature = "dma-canary")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanaryCorruption {
+    /// Address of the buffer
+    pub buf: usize,
+    pub len: usize,
+    pub side: CanarySide,
+    /// Index of the damaged byte closest to the buffer, within its canary
+    pub offset: usize,
+    /// Value found in that byte
+    pub written: u8,
+    /// Number of damaged bytes in the canary
+    pub count: usize,
+}
+
+#[cfg(feature = "dma-canary")]
+impl ufmt::uDisplay for CanaryCorruption {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        let side = match self.side {
+            CanarySide::Before => "before",
+            CanarySide::After => "after",
+        };
+        ufmt::uwrite!(
+            f,
+            "DMA canary {} buffer {}+{} damaged: {} bytes, byte {} is {}",
+            side,
+            self.buf,
+            self.len,
+            self.count,
+            self.offset,
+            self.written
+        )
+    }
+}
+
+/// Entry of the `.dma_canaries` section, one per buffer
+#[cfg(feature = "dma-canary")]
+#[doc(hidden)]
+#[repr(C)]
+pub struct CanaryEntry {
+    pub fill: fn(),
+    pub check: fn() -> Result<(), CanaryCorruption>,
+}
+
+#[cfg(feature = "dma-canary")]
+fn canary_entries() -> &'static [CanaryEntry] {
+    // Safety: the linker collects the entries between the two symbols
+    unsafe {
+        let start = core::ptr::addr_of!(__sdma_canaries) as *const CanaryEntry;
+        let end = core::ptr::addr_of!(__edma_canaries) as *const CanaryEntry;
+        core::slice::from_raw_parts(start, end.offset_from(start) as usize)
+    }
+}
+
+/// Check the canaries of every buffer declared with
+/// [dma_static](crate::dma_static), reporting the first damaged one
+#[cfg(feature = "dma-canary")]
+pub fn check_all() -> Result<(), CanaryCorruption> {
+    canary_entries()
+        .iter()
+        .try_for_each(|entry| (entry.check)())
+}
+
+/// Zero the `.dma_bss` section, and fill the canaries with the `dma-canary`
+/// feature
+///
+/// The Rust runtime only zeroes `.bss`, call this at the start of `main`.
+///
+/// # Safety
+///
+/// Must be called before any buffer in `.dma_bss` is used.
+pub unsafe fn init() {
+    let mut ptr = core::ptr::addr_of_mut!(__sdma_bss);
+    let end = core::ptr::addr_of_mut!(__edma_bss);
+    while ptr < end {
+        ptr.write_volatile(0);
+        ptr = ptr.add(1);
+    }
+    #[cfg(feature = "dma-canary")]
+    for entry in canary_entries() {
+        (entry.fill)();
+    }
+}
+
+/// Returns true if `ptr..ptr + len` lies within the DMA sections
+pub fn is_dma_region(ptr: *const u8, len: usize) -> bool {
+    let start = core::ptr::addr_of!(__sdma_bss) as usize;
+    let end = core::ptr::addr_of!(__edma_noinit) as usize;
+    let ptr = ptr as usize;
+    match ptr.checked_add(len) {
+        Some(ptr_end) => ptr >= start && ptr_end <= end,
+        None => false,
+    }
+}
+
+/// Declare a [DMA_ALIGN]-aligned buffer for DMA
+///
+/// `dma_static!(NAME: [u8; N])` places the buffer in `.dma_bss`, which is
+/// zeroed by [init]. `dma_static!(noinit NAME: [u8; N])` places it in
+/// `.dma_noinit`, whose contents are unspecified until written.
+///
+/// Accessing the buffer is unsafe, sa. [DmaStatic::get_mut].
+#[macro_export]
+macro_rules! dma_static {
+    ($name:ident: [u8; $n:expr]) => {
+        $crate::dma_static!(@section ".dma_bss", $name, $n);
+    };
+    (noinit $name:ident: [u8; $n:expr]) => {
+        $crate::dma_static!(@section ".dma_noinit", $name, $n);
+    };
+    (@section $section:literal, $name:ident, $n:expr) => {
+        const _: () = assert!($n > 0, "DMA buffer must not be empty");
+        const _: () = assert!(
+            core::mem::align_of::<$crate::dma::DmaStatic<{ $n }>>() == $crate::dma::DMA_ALIGN
+        );
+
+        #[link_section = $section]
+        static $name: $crate::dma::DmaStatic<{ $n }> = $crate::dma::DmaStatic::new();
+
+        $crate::__dma_canary_entry!($name);
+    };
+}
+
+/// Register a buffer for [check_all], expanding to nothing without the
+/// `dma-canary` feature
+#[cfg(feature = "dma-canary")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dma_canary_entry {
+    ($name:ident) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".dma_canaries"]
+            static ENTRY: $crate::dma::CanaryEntry = $crate::dma::CanaryEntry {
+                fill: || $name.fill_canaries(),
+                check: || $name.check(),
+            };
+        };
+    };
+}
+
+#[cfg(not(feature = "dma-canary"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dma_canary_entry {
+    ($name:ident) => {};
+}
@@ -0,0 +1,117 @@
+//! Allocation-free event waiting for simple main loops
+//!
+//! Interrupt handlers signal a [Flag], and the main loop sleeps in `wfi`
+//! until one of the flags it waits for is raised:
+//!
+//! ```ignore
+//! static SPIM_DONE: Flag = Flag::new();
+//! static UART_RX: Flag = Flag::new();
+//!
+//! // In the respective interrupt handlers
+//! SPIM_DONE.set();
+//! UART_RX.set();
+//!
+//! match event::select2(&SPIM_DONE, &UART_RX, None) {
+//!     Which::A => { /* SPIM transfer finished */ }
+//!     Which::B => { /* byte received */ }
+//!     Which::Timeout => unreachable!(),
+//! }
+//! ```
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use riscv::register::mcycle;
+
+/// Event flag raised from interrupt context and consumed by the main loop
+pub struct Flag(AtomicBool);
+
+impl Default for Flag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Flag {
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    #[inline]
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Clear the flag, returning whether it was set
+    #[inline]
+    pub fn take(&self) -> bool {
+        // Not all targets support atomic swap, so exclude interrupts instead
+        riscv::interrupt::free(|| {
+            let set = self.0.load(Ordering::Acquire);
+            self.0.store(false, Ordering::Release);
+            set
+        })
+    }
+}
+
+/// Sleep until `flag` is raised or `timeout` cycles have passed, sa.
+/// [select2]
+///
+/// Returns whether the flag was raised, and clears it.
+pub fn wait(flag: &Flag, timeout: Option<u64>) -> bool {
+    static NEVER: Flag = Flag::new();
+    select2(flag, &NEVER, timeout) == Which::A
+}
+
+/// Flag that ended [select2]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Which {
+    A,
+    B,
+    Timeout,
+}
+
+/// Sleep until `a` or `b` is raised, or `timeout` cycles have passed
+///
+/// The raised flag is cleared. If both are raised, `a` wins and `b` stays set
+/// for the next call. The timeout is only checked when the core wakes up, so
+/// it is accurate to the rate of interrupts, e.g., a periodic timer.
+pub fn select2(a: &Flag, b: &Flag, timeout: Option<u64>) -> Which {
+    select2_with(a, b, timeout, || {})
+}
+
+/// Like [select2], with `on_wake` called each time the core wakes up without
+/// either flag raised
+pub fn select2_with<F>(a: &Flag, b: &Flag, timeout: Option<u64>, mut on_wake: F) -> Which
+where
+    F: FnMut(),
+{
+    let start = mcycle::read64();
+    loop {
+        // Check and sleep with interrupts disabled, so that an interrupt
+        // between the check and `wfi` still wakes the core
+        let which = riscv::interrupt::free(|| {
+            if a.take() {
+                return Some(Which::A);
+            }
+            if b.take() {
+                return Some(Which::B);
+            }
+            if let Some(timeout) = timeout {
+                if mcycle::read64().wrapping_sub(start) >= timeout {
+                    return Some(Which::Timeout);
+                }
+            }
+            riscv::asm::wfi();
+            None
+        });
+
+        match which {
+            Some(which) => return which,
+            None => on_wake(),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Saturating fixed-point arithmetic for DSP on cores without an FPU
+//!
+//! [Q15] and [Q31] hold values in `[-1, 1)` with 15 and 31 fractional bits.
+//! Results that do not fit saturate to [Q15::MIN] / [Q15::MAX] instead of
+//! wrapping, and products are rounded to nearest.
+//!
+//! ```ignore
+//! const TAPS: [Q15; 3] = [Q15::from_f32(0.25), Q15::from_f32(0.5), Q15::from_f32(0.25)];
+//! q15_fir(&TAPS, &input, &mut output);
+//! ```
+use core::ops::{Add, Div, Mul, Sub};
+
+/// Signed Q1.15
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Q15(pub i16);
+
+/// Signed Q1.31
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Q31(pub i32);
+
+/// [Q31] in the notation that spells out the integer bit
+pub type Q1_31 = Q31;
+
+const fn round_f64(x: f64) -> f64 {
+    if x < 0.0 {
+        x - 0.5
+    } else {
+        x + 0.5
+    }
+}
+
+const fn sat_i16(x: i64) -> i16 {
+    if x > i16::MAX as i64 {
+        i16::MAX
+    } else if x < i16::MIN as i64 {
+        i16::MIN
+    } else {
+        x as i16
+    }
+}
+
+const fn sat_i32(x: i64) -> i32 {
+    if x > i32::MAX as i64 {
+        i32::MAX
+    } else if x < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        x as i32
+    }
+}
+
+macro_rules! impl_q {
+    ($q:ident, $int:ty, $frac:literal, $sat:ident) => {
+        impl $q {
+            pub const ZERO: Self = Self(0);
+            /// Largest value, `1 - 2^-FRAC`
+            pub const MAX: Self = Self(<$int>::MAX);
+            /// -1
+            pub const MIN: Self = Self(<$int>::MIN);
+
+            /// Convert `x`, rounding to nearest and saturating outside `[-1, 1)`
+            ///
+            /// Meant for constants, since floating point is emulated in
+            /// software at run-time.
+            pub const fn from_f32(x: f32) -> Self {
+                // `as` saturates, which also maps NaN to 0
+                Self(round_f64(x as f64 * (1u64 << $frac) as f64) as $int)
+            }
+
+            pub const fn to_bits(self) -> $int {
+                self.0
+            }
+        }
+
+        impl From<f32> for $q {
+            fn from(x: f32) -> Self {
+                Self::from_f32(x)
+            }
+        }
+
+        impl Add for $q {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0.saturating_add(rhs.0))
+            }
+        }
+
+        impl Sub for $q {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+        }
+
+        impl Mul for $q {
+            type Output = Self;
+
+            /// Only `-1 * -1` saturates
+            fn mul(self, rhs: Self) -> Self {
+                let product = self.0 as i64 * rhs.0 as i64;
+                Self($sat((product + (1 << ($frac - 1))) >> $frac))
+            }
+        }
+
+        impl Div for $q {
+            type Output = Self;
+
+            /// Saturates when `|self| >= |rhs|`. Division by zero saturates
+            /// towards the sign of `self`.
+            fn div(self, rhs: Self) -> Self {
+                if rhs.0 == 0 {
+                    return if self.0 < 0 { Self::MIN } else { Self::MAX };
+                }
+                Self($sat(((self.0 as i64) << $frac) / rhs.0 as i64))
+            }
+        }
+    };
+}
+
+impl_q!(Q15, i16, 15, sat_i16);
+impl_q!(Q31, i32, 31, sat_i32);
+
+/// Multiply-accumulate `a[i] * b[i]` into a Q2.30 accumulator
+#[inline]
+fn mac(a: &[Q15], b: &[Q15]) -> i64 {
+    let mut acc = 0;
+    // Four independent products per iteration keep the multiplier busy
+    let (a4, b4) = (a.chunks_exact(4), b.chunks_exact(4));
+    let (a_rem, b_rem) = (a4.remainder(), b4.remainder());
+    for (x, y) in a4.zip(b4) {
+        acc += (x[0].0 as i32 * y[0].0 as i32) as i64
+            + (x[1].0 as i32 * y[1].0 as i32) as i64
+            + (x[2].0 as i32 * y[2].0 as i32) as i64
+            + (x[3].0 as i32 * y[3].0 as i32) as i64;
+    }
+    for (x, y) in a_rem.iter().zip(b_rem) {
+        acc += (x.0 as i32 * y.0 as i32) as i64;
+    }
+    acc
+}
+
+/// Dot product of `a` and `b`, saturated to [Q31]
+///
+/// The sum is exact until the final conversion. Both slices must have the
+/// same length.
+pub fn q15_vector_dot(a: &[Q15], b: &[Q15]) -> Q31 {
+    assert_eq!(a.len(), b.len());
+    Q31(sat_i32(mac(a, b) << 1))
+}
+
+/// Filter `input` with the FIR `coeffs` in software
+///
+/// `output[n] = sum(coeffs[k] * input[n + coeffs.len() - 1 - k])`, so `input`
+/// must hold `output.len() + coeffs.len() - 1` samples. For block-wise
+/// filtering, keep the last `coeffs.len() - 1` input samples of a block in
+/// front of the next one.
+pub fn q15_fir(coeffs: &[Q15], input: &[Q15], output: &mut [Q15]) {
+    assert!(!coeffs.is_empty());
+    assert!(input.len() >= output.len() + coeffs.len() - 1);
+
+    let taps = coeffs.len();
+    for (n, out) in output.iter_mut().enumerate() {
+        let window = &input[n..n + taps];
+        let acc: i64 = coeffs
+            .iter()
+            .zip(window.iter().rev())
+            .map(|(c, x)| (c.0 as i32 * x.0 as i32) as i64)
+            .sum();
+        *out = Q15(sat_i16((acc + (1 << 14)) >> 15));
+    }
+}
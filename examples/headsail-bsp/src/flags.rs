@@ -0,0 +1,49 @@
+//! Printing helpers for status registers and responses
+
+/// Write the names of the bits in `value` that are set, separated by `|`
+///
+/// Prints `-` if none of the named bits are set.
+pub(crate) fn write_flags<E>(
+    value: u32,
+    names: &[(u32, &str)],
+    mut write_str: impl FnMut(&str) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut first = true;
+    for &(bit, name) in names {
+        if value & (1 << bit) != 0 {
+            if !first {
+                write_str(" | ")?;
+            }
+            write_str(name)?;
+            first = false;
+        }
+    }
+    if first {
+        write_str("-")?;
+    }
+    Ok(())
+}
+
+/// Implement [core::fmt::Debug] and [ufmt::uDisplay] for a newtype over an
+/// integer by printing the set bits listed in `$names`
+macro_rules! impl_flags_fmt {
+    ($ty:ty, $names:expr) => {
+        impl core::fmt::Debug for $ty {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(concat!(stringify!($ty), "("))?;
+                $crate::flags::write_flags(self.0 as u32, $names, |s| f.write_str(s))?;
+                f.write_str(")")
+            }
+        }
+
+        impl ufmt::uDisplay for $ty {
+            fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+            where
+                W: ufmt::uWrite + ?Sized,
+            {
+                $crate::flags::write_flags(self.0 as u32, $names, |s| f.write_str(s))
+            }
+        }
+    };
+}
+pub(crate) use impl_flags_fmt;
@@ -0,0 +1,148 @@
+//! SPI NOR flash status registers
+//!
+//! Bit positions follow the common Winbond W25Q layout, read with commands
+//! 0x05, 0x35 and 0x15.
+use crate::flags::impl_flags_fmt;
+
+/// Status register 1
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StatusReg1(pub u8);
+
+impl StatusReg1 {
+    /// Write in progress, i.e., program, erase or status register write
+    pub const fn wip(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+    /// Write enable latch
+    pub const fn wel(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+    /// Block protect bits BP0..=BP2
+    pub const fn block_protect(&self) -> u8 {
+        (self.0 >> 2) & 0b111
+    }
+    /// Top/bottom protect
+    pub const fn tb(&self) -> bool {
+        self.0 & (1 << 5) != 0
+    }
+    /// Sector/block protect
+    pub const fn sec(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+    /// Status register protect 0
+    pub const fn srp0(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+}
+
+const SR1_FLAGS: &[(u32, &str)] = &[
+    (0, "WIP"),
+    (1, "WEL"),
+    (2, "BP0"),
+    (3, "BP1"),
+    (4, "BP2"),
+    (5, "TB"),
+    (6, "SEC"),
+    (7, "SRP0"),
+];
+impl_flags_fmt!(StatusReg1, SR1_FLAGS);
+
+/// Status register 2
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StatusReg2(pub u8);
+
+impl StatusReg2 {
+    /// Status register protect 1
+    pub const fn srp1(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+    /// Quad enable
+    pub const fn qe(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+    /// Security register lock bits LB1..=LB3
+    pub const fn lock_bits(&self) -> u8 {
+        (self.0 >> 3) & 0b111
+    }
+    /// Complement protect
+    pub const fn cmp(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+    /// Erase/program suspended
+    pub const fn sus(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+}
+
+const SR2_FLAGS: &[(u32, &str)] = &[
+    (0, "SRP1"),
+    (1, "QE"),
+    (3, "LB1"),
+    (4, "LB2"),
+    (5, "LB3"),
+    (6, "CMP"),
+    (7, "SUS"),
+];
+impl_flags_fmt!(StatusReg2, SR2_FLAGS);
+
+/// Status register 3
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StatusReg3(pub u8);
+
+impl StatusReg3 {
+    /// Write protect selection, individual block locks when set
+    pub const fn wps(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+    /// Output driver strength
+    pub const fn drv(&self) -> u8 {
+        (self.0 >> 5) & 0b11
+    }
+    /// /HOLD or /RESET function of the HOLD pin, /RESET when set
+    pub const fn hold_rst(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+}
+
+const SR3_FLAGS: &[(u32, &str)] = &[(2, "WPS"), (5, "DRV0"), (6, "DRV1"), (7, "HOLD_RST")];
+impl_flags_fmt!(StatusReg3, SR3_FLAGS);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_reg1() {
+        // Page program in progress after WRITE ENABLE
+        let sr = StatusReg1(0x03);
+        assert!(sr.wip() && sr.wel());
+        assert_eq!(sr.block_protect(), 0);
+        // Whole array protected
+        let sr = StatusReg1(0x1c);
+        assert!(!sr.wip() && !sr.wel());
+        assert_eq!(sr.block_protect(), 0b111);
+        assert!(!sr.tb() && !sr.sec() && !sr.srp0());
+        assert_eq!(format!("{:?}", StatusReg1(0x03)), "StatusReg1(WIP | WEL)");
+    }
+
+    #[test]
+    fn status_reg2() {
+        let sr = StatusReg2(0x02);
+        assert!(sr.qe() && !sr.srp1() && !sr.sus());
+        let sr = StatusReg2(0x38);
+        assert_eq!(sr.lock_bits(), 0b111);
+        assert_eq!(format!("{:?}", sr), "StatusReg2(LB1 | LB2 | LB3)");
+    }
+
+    #[test]
+    fn status_reg3() {
+        // W25Q128JV default, output driver strength 25 %
+        let sr = StatusReg3(0x60);
+        assert_eq!(sr.drv(), 0b11);
+        assert!(!sr.wps() && !sr.hold_rst());
+        assert_eq!(
+            format!("{:?}", StatusReg3(0x84)),
+            "StatusReg3(WPS | HOLD_RST)"
+        );
+    }
+}
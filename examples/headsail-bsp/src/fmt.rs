@@ -0,0 +1,375 @@
+//! Integer formatting without format machinery, and formatting into
+//! fixed-size buffers
+//!
+//! For hot logging paths that only print literals and integers. The output
+//! matches `{}` for decimals and `{:0width$x}` for hex, in both `core::fmt`
+//! and `ufmt`.
+//!
+//! ```ignore
+//! let mut line = LineBuf::<32>::new();
+//! line.str("tick ").u32(n).str(" spim_busy ").u32(busy as u32).str("\r\n");
+//! uart.write(line.as_bytes());
+//! ```
+//!
+//! [write_bounded] takes `core::fmt` arguments instead, for text that has to
+//! fit a frame, e.g., an error message in a [hil](crate::hil) response:
+//!
+//! ```ignore
+//! let msg = fmt::write_bounded(&mut resp, format_args!("{:?}", err));
+//! ```
+
+/// Length of the longest `u32`, `4294967295`
+pub const U32_MAX_LEN: usize = 10;
+/// Length of the longest `i32`, `-2147483648`
+pub const I32_MAX_LEN: usize = 11;
+/// Length of the longest `u32` in hex
+pub const HEX_MAX_LEN: usize = 8;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Appended by [write_bounded] to output that did not fit
+pub const TRUNCATION_MARKER: &str = "...";
+
+/// Format `n` in decimal into the end of `buf`, returning the digits
+pub fn fmt_u32(mut n: u32, buf: &mut [u8; U32_MAX_LEN]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    &buf[start..]
+}
+
+/// Format `n` in decimal into the end of `buf`, with a leading `-` if
+/// negative
+pub fn fmt_i32(n: i32, buf: &mut [u8; I32_MAX_LEN]) -> &[u8] {
+    let mut digits = [0; U32_MAX_LEN];
+    let len = fmt_u32(n.unsigned_abs(), &mut digits).len();
+    let start = buf.len() - len;
+    buf[start..].copy_from_slice(&digits[U32_MAX_LEN - len..]);
+    if n < 0 {
+        buf[start - 1] = b'-';
+        return &buf[start - 1..];
+    }
+    &buf[start..]
+}
+
+/// Format `n` in lowercase hex, zero-padded to `width` digits, into the end
+/// of `buf`
+///
+/// `width` is capped at [HEX_MAX_LEN]. Like `core::fmt`, no digits are
+/// dropped when `n` needs more than `width`.
+pub fn fmt_hex(mut n: u32, width: usize, buf: &mut [u8; HEX_MAX_LEN]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = HEX_DIGITS[(n & 0xf) as usize];
+        n >>= 4;
+        if n == 0 {
+            break;
+        }
+    }
+    let pad_start = HEX_MAX_LEN - width.min(HEX_MAX_LEN);
+    if pad_start < start {
+        buf[pad_start..start].fill(b'0');
+        start = pad_start;
+    }
+    &buf[start..]
+}
+
+/// Fixed-size line assembled from literals and integers, sa. [module
+/// documentation](self)
+///
+/// Output that does not fit is dropped and flagged in
+/// [LineBuf::truncated].
+pub struct LineBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> Default for LineBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> LineBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        let n = bytes.len().min(N - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+        self.truncated |= n < bytes.len();
+        self
+    }
+
+    pub fn str(&mut self, s: &str) -> &mut Self {
+        self.bytes(s.as_bytes())
+    }
+
+    pub fn u32(&mut self, n: u32) -> &mut Self {
+        let mut buf = [0; U32_MAX_LEN];
+        self.bytes(fmt_u32(n, &mut buf))
+    }
+
+    pub fn i32(&mut self, n: i32) -> &mut Self {
+        let mut buf = [0; I32_MAX_LEN];
+        self.bytes(fmt_i32(n, &mut buf))
+    }
+
+    /// Sa. [fmt_hex]
+    pub fn hex(&mut self, n: u32, width: usize) -> &mut Self {
+        let mut buf = [0; HEX_MAX_LEN];
+        self.bytes(fmt_hex(n, width, &mut buf))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Whether some output did not fit
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Empty the line for reuse
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+}
+
+/// Writing never fails, output that does not fit is flagged in
+/// [LineBuf::truncated]
+impl<const N: usize> ufmt_write::uWrite for LineBuf<N> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.str(s);
+        Ok(())
+    }
+}
+
+/// Format `args` into `buf`, returning the text written
+///
+/// Output that does not fit is cut at a character boundary and ends in
+/// [TRUNCATION_MARKER], or as much of it as fits. Never panics, also not
+/// when a `Display` impl returns an error, which ends the output early.
+pub fn write_bounded<'b>(buf: &'b mut [u8], args: core::fmt::Arguments) -> &'b str {
+    let mut w = BoundedWriter {
+        buf,
+        len: 0,
+        truncated: false,
+    };
+    let _ = core::fmt::write(&mut w, args);
+
+    let BoundedWriter {
+        buf,
+        mut len,
+        truncated,
+    } = w;
+    if truncated {
+        let marker = TRUNCATION_MARKER.as_bytes();
+        let keep = floor_char_boundary(&buf[..len], buf.len().saturating_sub(marker.len()));
+        let n = marker.len().min(buf.len() - keep);
+        buf[keep..keep + n].copy_from_slice(&marker[..n]);
+        len = keep + n;
+    }
+    // Only whole characters and the ASCII marker are written
+    core::str::from_utf8(&buf[..len]).unwrap_or("")
+}
+
+struct BoundedWriter<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+    truncated: bool,
+}
+
+impl core::fmt::Write for BoundedWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let room = self.buf.len() - self.len;
+        let n = floor_char_boundary(s.as_bytes(), room);
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() {
+            self.truncated = true;
+            return Err(core::fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Largest index up to `max` that does not split a UTF-8 character of `s`
+fn floor_char_boundary(s: &[u8], max: usize) -> usize {
+    if max >= s.len() {
+        return s.len();
+    }
+    // Continuation bytes are 0b10xx_xxxx
+    let mut idx = max;
+    while idx > 0 && s[idx] & 0xc0 == 0x80 {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Write `code` and then `fields` as little-endian u32s to `buf`, for the
+/// `to_wire` encodings of the driver errors. Returns the length, or 0 if
+/// `buf` is too short.
+// Unused when no driver with a wire encoding is enabled
+#[allow(dead_code)]
+pub(crate) fn put_wire(buf: &mut [u8], code: u8, fields: &[u32]) -> usize {
+    let len = 1 + 4 * fields.len();
+    if buf.len() < len {
+        return 0;
+    }
+    buf[0] = code;
+    for (chunk, field) in buf[1..len].chunks_exact_mut(4).zip(fields) {
+        chunk.copy_from_slice(&field.to_le_bytes());
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const U32S: [u32; 8] = [0, 1, 9, 10, 99, 1000, 123_456_789, u32::MAX];
+    const I32S: [i32; 8] = [0, 1, -1, 10, -10, -123_456_789, i32::MAX, i32::MIN];
+
+    #[test]
+    fn u32_matches_core_fmt() {
+        for n in U32S {
+            let mut buf = [0; U32_MAX_LEN];
+            assert_eq!(fmt_u32(n, &mut buf), format!("{}", n).as_bytes());
+        }
+    }
+
+    #[test]
+    fn i32_matches_core_fmt() {
+        for n in I32S {
+            let mut buf = [0; I32_MAX_LEN];
+            assert_eq!(fmt_i32(n, &mut buf), format!("{}", n).as_bytes());
+        }
+    }
+
+    #[test]
+    fn hex_matches_core_fmt() {
+        for n in [0, 0xa, 0xff, 0x1234, 0xdead_beef, u32::MAX] {
+            for width in 0..=HEX_MAX_LEN {
+                let mut buf = [0; HEX_MAX_LEN];
+                let expected = format!("{:0width$x}", n, width = width);
+                assert_eq!(fmt_hex(n, width, &mut buf), expected.as_bytes());
+            }
+            // Wider than a u32 is capped
+            let mut buf = [0; HEX_MAX_LEN];
+            assert_eq!(fmt_hex(n, 12, &mut buf), format!("{:08x}", n).as_bytes());
+        }
+    }
+
+    #[test]
+    fn line_buf() {
+        let mut line = LineBuf::<32>::new();
+        line.str("tick ")
+            .u32(42)
+            .str(" t ")
+            .i32(-7)
+            .str(" ")
+            .hex(0xbeef, 8);
+        assert_eq!(
+            line.as_bytes(),
+            format!("tick {} t {} {:08x}", 42, -7, 0xbeef).as_bytes()
+        );
+        assert!(!line.truncated());
+
+        let mut line = LineBuf::<8>::new();
+        line.str("n ").u32(u32::MAX);
+        assert_eq!(line.as_bytes(), b"n 429496");
+        assert!(line.truncated());
+        // Nothing more fits
+        line.str("x");
+        assert_eq!(line.as_bytes(), b"n 429496");
+
+        line.clear();
+        assert!(line.as_bytes().is_empty() && !line.truncated());
+        line.i32(i32::MIN);
+        assert_eq!(line.as_bytes(), b"-2147483");
+    }
+
+    #[test]
+    fn bounded_fits() {
+        let mut buf = [0; 5];
+        assert_eq!(write_bounded(&mut buf, format_args!("hello")), "hello");
+        let mut buf = [0; 16];
+        assert_eq!(write_bounded(&mut buf, format_args!("a{}b", 12)), "a12b");
+    }
+
+    #[test]
+    fn bounded_truncates_at_char_boundary() {
+        let mut buf = [0; 8];
+        assert_eq!(
+            write_bounded(&mut buf, format_args!("héllo wörld")),
+            "héll..."
+        );
+        // Cutting after two bytes would split the é
+        let mut buf = [0; 5];
+        assert_eq!(write_bounded(&mut buf, format_args!("héllo")), "h...");
+        let mut buf = [0; 3];
+        assert_eq!(write_bounded(&mut buf, format_args!("a\u{1f600}")), "...");
+    }
+
+    #[test]
+    fn bounded_shorter_than_marker() {
+        let mut buf = [0; 2];
+        assert_eq!(write_bounded(&mut buf, format_args!("hello")), "..");
+        let mut buf = [];
+        assert_eq!(write_bounded(&mut buf, format_args!("hello")), "");
+    }
+
+    #[test]
+    fn bounded_display_error() {
+        struct Fails;
+        impl core::fmt::Display for Fails {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("ab")?;
+                Err(core::fmt::Error)
+            }
+        }
+        let mut buf = [0; 16];
+        assert_eq!(write_bounded(&mut buf, format_args!("{}cd", Fails)), "ab");
+    }
+
+    #[test]
+    fn char_boundary() {
+        let s = "a\u{1f600}b".as_bytes();
+        assert_eq!(floor_char_boundary(s, 0), 0);
+        assert_eq!(floor_char_boundary(s, 1), 1);
+        for max in 2..=4 {
+            assert_eq!(floor_char_boundary(s, max), 1);
+        }
+        assert_eq!(floor_char_boundary(s, 5), 5);
+        assert_eq!(floor_char_boundary(s, 9), s.len());
+    }
+
+    #[test]
+    fn wire() {
+        let mut buf = [0xff; 9];
+        assert_eq!(put_wire(&mut buf, 3, &[]), 1);
+        assert_eq!(buf[0], 3);
+        assert_eq!(put_wire(&mut buf, 8, &[0x0102_0304, 5]), 9);
+        assert_eq!(buf, [8, 4, 3, 2, 1, 5, 0, 0, 0]);
+        assert_eq!(put_wire(&mut buf[..8], 8, &[1, 2]), 0);
+        assert_eq!(put_wire(&mut [], 0, &[]), 0);
+    }
+}
@@ -0,0 +1,644 @@
+//! Framed request/response protocol for scripted hardware-in-the-loop tests
+//!
+//! The host sends requests over a serial link and the firmware answers each
+//! with exactly one response. Frames are delimited with SLIP (RFC 1055) and
+//! carry
+//!
+//! | Field     | Size       | Notes                                      |
+//! | :-        | :-         | :-                                         |
+//! | `cmd`     | 1          | [Command], or'd with [RESPONSE] in replies |
+//! | `len`     | 2          | Payload length, little-endian              |
+//! | `payload` | `len`      | Responses start with a [Status] byte       |
+//! | `crc`     | 2          | [crc16] over the fields above, LE          |
+//!
+//! Frames that are truncated, fail the CRC or don't fit the receive buffer
+//! are answered with an error [Status] and otherwise discarded, so the host
+//! can retry. A lost delimiter costs at most the frame it belonged to.
+//!
+//! Responses with [Status::Failed] carry the bytes written by
+//! [Handler::failure_detail] after the status, typically the `to_wire`
+//! encoding of a driver error, e.g.,
+//! [FlashError::to_wire](crate::sysctrl::spi_flash::FlashError::to_wire).
+//!
+//! Multi-byte payload fields are little-endian. [Command::Ping],
+//! [Command::Peek], [Command::Poke] and [Command::Version] are served by
+//! [Server]; the remaining commands are passed to a [Handler] provided by the
+//! application.
+use crate::{
+    crc::{crc16, Crc16},
+    slip::{self, DecodeError},
+};
+
+/// Bit set in the command byte of responses
+pub const RESPONSE: u8 = 0x80;
+
+/// Command byte of responses to frames that could not be parsed
+pub const NO_CMD: u8 = 0xff;
+
+/// `cmd` + `len`
+const HEADER_LEN: usize = 3;
+const CRC_LEN: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Command {
+    /// Empty payload. Responds with the board ID set with
+    /// [Server::set_board_id], or nothing.
+    Ping = 0x00,
+    /// `addr: u32, len: u16`. Responds with `len` bytes read from `addr`.
+    ///
+    /// Word-aligned requests are read one word at a time, so registers can be
+    /// peeked.
+    Peek = 0x01,
+    /// `addr: u32, data: [u8]`. Writes `data` to `addr`.
+    ///
+    /// Word-aligned requests are written one word at a time, so registers can
+    /// be poked.
+    Poke = 0x02,
+    /// Empty payload. Responds with the BSP [version](crate::version) in the
+    /// format of [Version::encode](crate::version::Version::encode).
+    Version = 0x03,
+    /// Run the SPIM self-test. Payload and response are application defined.
+    SpimSelfTest = 0x10,
+    /// `addr: u32, len: u16`. Responds with `len` bytes of flash from `addr`.
+    ReadFlash = 0x11,
+    /// Payload is a DLA descriptor blob. Responds with the inference result.
+    DlaInference = 0x12,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = Status;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0x00 => Self::Ping,
+            0x01 => Self::Peek,
+            0x02 => Self::Poke,
+            0x03 => Self::Version,
+            0x10 => Self::SpimSelfTest,
+            0x11 => Self::ReadFlash,
+            0x12 => Self::DlaInference,
+            _ => return Err(Status::UnknownCommand),
+        })
+    }
+}
+
+/// First byte of each response payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum Status {
+    Ok = 0,
+    UnknownCommand = 1,
+    BadCrc = 2,
+    /// Frame is shorter than its header or `len` field claims
+    Truncated = 3,
+    /// Frame is longer than its `len` field claims, or the payload has the
+    /// wrong size for the command
+    BadLength = 4,
+    /// Frame did not fit the receive buffer
+    Overflow = 5,
+    /// Frame contained an invalid SLIP escape sequence
+    Framing = 6,
+    /// Command is not implemented by the [Handler]
+    Unsupported = 7,
+    /// Command was understood but failed
+    Failed = 8,
+}
+
+/// Byte-oriented serial link
+pub trait Link {
+    /// Block until a byte is received
+    fn read_byte(&mut self) -> u8;
+    fn write(&mut self, buf: &[u8]);
+}
+
+impl<const BASE_ADDR: usize> Link for crate::apb_uart::ApbUart<BASE_ADDR> {
+    #[inline]
+    fn read_byte(&mut self) -> u8 {
+        self.getc()
+    }
+
+    #[inline]
+    fn write(&mut self, buf: &[u8]) {
+        crate::apb_uart::ApbUart::write(self, buf);
+    }
+}
+
+/// Application commands, i.e., anything besides [Command::Ping],
+/// [Command::Peek], [Command::Poke] and [Command::Version]
+pub trait Handler {
+    /// Handle `cmd`, writing the response data to `resp`
+    ///
+    /// Returns the number of bytes written. `resp` is one byte shorter than
+    /// the server's buffer to leave room for the status.
+    fn handle(&mut self, cmd: Command, payload: &[u8], resp: &mut [u8]) -> Result<usize, Status>;
+
+    /// Write the details of the error that made [Handler::handle] return
+    /// [Status::Failed] to `resp`, returning the number of bytes written
+    ///
+    /// Sends no details by default.
+    fn failure_detail(&mut self, resp: &mut [u8]) -> usize {
+        let _ = resp;
+        0
+    }
+}
+
+/// Handler that implements no application commands
+impl Handler for () {
+    fn handle(&mut self, _: Command, _: &[u8], _: &mut [u8]) -> Result<usize, Status> {
+        Err(Status::Unsupported)
+    }
+}
+
+/// Serves requests over `link` using two `N`-byte buffers for frames
+pub struct Server<L: Link, const N: usize> {
+    link: L,
+    rx: [u8; N],
+    tx: [u8; N],
+    board_id: Option<[u8; 16]>,
+}
+
+impl<L: Link, const N: usize> Server<L, N> {
+    /// # Safety
+    ///
+    /// [Command::Peek] and [Command::Poke] give the host unchecked access to
+    /// the whole address space, including memory owned by Rust code.
+    pub unsafe fn new(link: L) -> Self {
+        Self {
+            link,
+            rx: [0; N],
+            tx: [0; N],
+            board_id: None,
+        }
+    }
+
+    /// Identify the board in responses to [Command::Ping]
+    pub fn set_board_id(&mut self, id: [u8; 16]) {
+        self.board_id = Some(id);
+    }
+
+    pub fn free(self) -> L {
+        self.link
+    }
+
+    /// Serve requests forever
+    pub fn run<H: Handler>(&mut self, handler: &mut H) -> ! {
+        loop {
+            self.serve_one(handler);
+        }
+    }
+
+    /// Receive one request and send the response
+    ///
+    /// Returns the command that was served, or `None` if the frame was
+    /// rejected.
+    pub fn serve_one<H: Handler>(&mut self, handler: &mut H) -> Option<Command> {
+        let (cmd, result) = match self.receive() {
+            Ok((cmd, payload)) => match Command::try_from(cmd) {
+                Ok(command) => (cmd, self.dispatch(command, payload, handler)),
+                Err(status) => (cmd, Err(status)),
+            },
+            Err((cmd, status)) => (cmd, Err(status)),
+        };
+
+        match result {
+            Ok(len) => {
+                self.tx[0] = Status::Ok as u8;
+                self.respond(cmd, len + 1);
+                Command::try_from(cmd).ok()
+            }
+            Err(status) => {
+                self.tx[0] = status as u8;
+                let detail = match status {
+                    Status::Failed => handler.failure_detail(&mut self.tx[1..]).min(N - 1),
+                    _ => 0,
+                };
+                self.respond(cmd, 1 + detail);
+                None
+            }
+        }
+    }
+
+    fn dispatch<H: Handler>(
+        &mut self,
+        cmd: Command,
+        payload: (usize, usize),
+        handler: &mut H,
+    ) -> Result<usize, Status> {
+        let payload = &self.rx[payload.0..payload.1];
+        let resp = &mut self.tx[1..];
+        match cmd {
+            Command::Ping => match self.board_id {
+                Some(id) if id.len() <= resp.len() => {
+                    resp[..id.len()].copy_from_slice(&id);
+                    Ok(id.len())
+                }
+                _ => Ok(0),
+            },
+            Command::Peek => {
+                let (addr, len) = match payload {
+                    [a0, a1, a2, a3, l0, l1] => (
+                        u32::from_le_bytes([*a0, *a1, *a2, *a3]) as usize,
+                        u16::from_le_bytes([*l0, *l1]) as usize,
+                    ),
+                    _ => return Err(Status::BadLength),
+                };
+                if len > resp.len() {
+                    return Err(Status::Overflow);
+                }
+                // Safety: the host has been granted access in [Server::new]
+                unsafe { peek(addr, &mut resp[..len]) };
+                Ok(len)
+            }
+            Command::Poke => {
+                if payload.len() < 4 {
+                    return Err(Status::BadLength);
+                }
+                let (addr, data) = payload.split_at(4);
+                let addr = u32::from_le_bytes([addr[0], addr[1], addr[2], addr[3]]) as usize;
+                // Safety: the host has been granted access in [Server::new]
+                unsafe { poke(addr, data) };
+                Ok(0)
+            }
+            Command::Version => {
+                if !payload.is_empty() {
+                    return Err(Status::BadLength);
+                }
+                crate::version().encode(resp).ok_or(Status::Overflow)
+            }
+            _ => {
+                let len = handler.handle(cmd, payload, resp)?;
+                if len > resp.len() {
+                    return Err(Status::Overflow);
+                }
+                Ok(len)
+            }
+        }
+    }
+
+    /// Receive a frame, returning its command byte and the payload range in
+    /// `rx`
+    ///
+    /// On error, the command byte is returned if it could be trusted.
+    fn receive(&mut self) -> Result<(u8, (usize, usize)), (u8, Status)> {
+        let len = self.receive_slip().map_err(|status| (NO_CMD, status))?;
+        let frame = &self.rx[..len];
+
+        if len < HEADER_LEN + CRC_LEN {
+            return Err((NO_CMD, Status::Truncated));
+        }
+        let (body, crc) = frame.split_at(len - CRC_LEN);
+        if crc16(body) != u16::from_le_bytes([crc[0], crc[1]]) {
+            return Err((NO_CMD, Status::BadCrc));
+        }
+
+        let cmd = body[0];
+        let payload_len = u16::from_le_bytes([body[1], body[2]]) as usize;
+        let got = body.len() - HEADER_LEN;
+        if payload_len > got {
+            return Err((cmd, Status::Truncated));
+        }
+        if payload_len < got {
+            return Err((cmd, Status::BadLength));
+        }
+
+        Ok((cmd, (HEADER_LEN, body.len())))
+    }
+
+    /// Receive and unescape one non-empty SLIP frame into `rx`
+    fn receive_slip(&mut self) -> Result<usize, Status> {
+        let mut decoder = slip::Decoder::new();
+        loop {
+            let byte = self.link.read_byte();
+            if let Some(res) = decoder.push(byte, &mut self.rx) {
+                return res.map_err(|e| match e {
+                    DecodeError::Overflow => Status::Overflow,
+                    DecodeError::Framing => Status::Framing,
+                });
+            }
+        }
+    }
+
+    /// Send `tx[..len]` as the payload of a response to `cmd`
+    fn respond(&mut self, cmd: u8, len: usize) {
+        // NO_CMD already has the response bit set
+        let header = [cmd | RESPONSE, len as u8, (len >> 8) as u8];
+
+        let mut crc = Crc16::new();
+        crc.update(&header);
+        crc.update(&self.tx[..len]);
+
+        // Escape into a small buffer to avoid a link transaction per byte
+        let crc = crc.finish().to_le_bytes();
+        let link = &mut self.link;
+        slip::Encoder::<32>::new()
+            .encode(&[&header, &self.tx[..len], &crc], |bytes| link.write(bytes));
+    }
+}
+
+/// # Safety
+///
+/// `addr..addr + buf.len()` must be readable
+unsafe fn peek(addr: usize, buf: &mut [u8]) {
+    if (addr | buf.len()) & 0b11 == 0 {
+        for (idx, chunk) in buf.chunks_exact_mut(4).enumerate() {
+            let word = core::ptr::read_volatile((addr + idx * 4) as *const u32);
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+    } else {
+        for (idx, byte) in buf.iter_mut().enumerate() {
+            *byte = core::ptr::read_volatile((addr + idx) as *const u8);
+        }
+    }
+}
+
+/// # Safety
+///
+/// `addr..addr + data.len()` must be writable
+unsafe fn poke(addr: usize, data: &[u8]) {
+    if (addr | data.len()) & 0b11 == 0 {
+        for (idx, chunk) in data.chunks_exact(4).enumerate() {
+            let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            core::ptr::write_volatile((addr + idx * 4) as *mut u32, word);
+        }
+    } else {
+        for (idx, byte) in data.iter().enumerate() {
+            core::ptr::write_volatile((addr + idx) as *mut u8, *byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// Link fed from a queue of received bytes, recording what is written
+    #[derive(Default)]
+    struct MockLink {
+        rx: VecDeque<u8>,
+        tx: Vec<u8>,
+    }
+
+    impl Link for MockLink {
+        fn read_byte(&mut self) -> u8 {
+            self.rx
+                .pop_front()
+                .expect("server read past the last request")
+        }
+
+        fn write(&mut self, buf: &[u8]) {
+            self.tx.extend_from_slice(buf);
+        }
+    }
+
+    /// Unescaped request frame with a correct CRC
+    fn frame(cmd: u8, len: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![cmd];
+        frame.extend(len.to_le_bytes());
+        frame.extend(payload);
+        frame.extend(crc16(&frame).to_le_bytes());
+        frame
+    }
+
+    fn slip(frame: &[u8]) -> Vec<u8> {
+        let mut wire = Vec::new();
+        slip::Encoder::<8>::new().encode(&[frame], |bytes| wire.extend_from_slice(bytes));
+        wire
+    }
+
+    fn mock_server<const N: usize>(wire: &[u8]) -> Server<MockLink, N> {
+        let link = MockLink {
+            rx: wire.iter().copied().collect(),
+            tx: Vec::new(),
+        };
+        unsafe { Server::new(link) }
+    }
+
+    /// Command byte and payload of a response
+    type Response = (u8, Vec<u8>);
+
+    /// Responses sent by `server`, checking their
+    /// framing
+    fn responses<const N: usize>(server: Server<MockLink, N>) -> Vec<Response> {
+        let mut dec = slip::Decoder::new();
+        let mut buf = [0; 512];
+        let mut out = Vec::new();
+        for byte in server.free().tx {
+            if let Some(res) = dec.push(byte, &mut buf) {
+                let frame = &buf[..res.unwrap()];
+                let (body, crc) = frame.split_at(frame.len() - CRC_LEN);
+                assert_eq!(crc16(body), u16::from_le_bytes([crc[0], crc[1]]));
+                let len = u16::from_le_bytes([body[1], body[2]]) as usize;
+                assert_eq!(len, body.len() - HEADER_LEN);
+                out.push((body[0], body[HEADER_LEN..].to_vec()));
+            }
+        }
+        out
+    }
+
+    /// Handler echoing [Command::SpimSelfTest] payloads and failing
+    /// [Command::ReadFlash] with a detail
+    struct Echo;
+
+    impl Handler for Echo {
+        fn handle(
+            &mut self,
+            cmd: Command,
+            payload: &[u8],
+            resp: &mut [u8],
+        ) -> Result<usize, Status> {
+            match cmd {
+                Command::SpimSelfTest => {
+                    resp[..payload.len()].copy_from_slice(payload);
+                    Ok(payload.len())
+                }
+                Command::ReadFlash => Err(Status::Failed),
+                _ => Err(Status::Unsupported),
+            }
+        }
+
+        fn failure_detail(&mut self, resp: &mut [u8]) -> usize {
+            resp[..2].copy_from_slice(&[9, 1]);
+            2
+        }
+    }
+
+    /// Serve all requests in `wire`, returning what each `serve_one`
+    /// returned and the responses
+    fn serve(
+        wire: &[u8],
+        requests: usize,
+        handler: &mut impl Handler,
+    ) -> (Vec<Option<Command>>, Vec<Response>) {
+        let mut server = mock_server::<32>(wire);
+        let served = (0..requests).map(|_| server.serve_one(handler)).collect();
+        assert!(server.link.rx.is_empty(), "unread request bytes");
+        (served, responses(server))
+    }
+
+    #[test]
+    fn ping() {
+        let wire = slip(&frame(0x00, 0, &[]));
+        let (served, resp) = serve(&wire, 1, &mut ());
+        assert_eq!(served, [Some(Command::Ping)]);
+        assert_eq!(resp, [(RESPONSE, vec![Status::Ok as u8])]);
+
+        let mut server = mock_server::<32>(&wire);
+        server.set_board_id(*b"headsail-board-1");
+        server.serve_one(&mut ());
+        let mut expected = vec![Status::Ok as u8];
+        expected.extend(b"headsail-board-1");
+        assert_eq!(responses(server), [(RESPONSE, expected)]);
+    }
+
+    #[test]
+    fn handler_commands() {
+        let mut wire = slip(&frame(0x10, 3, &[1, slip::END, 3]));
+        wire.extend(slip(&frame(0x11, 0, &[])));
+        wire.extend(slip(&frame(0x12, 0, &[])));
+        let (served, resp) = serve(&wire, 3, &mut Echo);
+        assert_eq!(served, [Some(Command::SpimSelfTest), None, None]);
+        assert_eq!(
+            resp,
+            [
+                (0x90, vec![Status::Ok as u8, 1, slip::END, 3]),
+                (0x91, vec![Status::Failed as u8, 9, 1]),
+                (0x92, vec![Status::Unsupported as u8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_command() {
+        let (served, resp) = serve(&slip(&frame(0x42, 0, &[])), 1, &mut ());
+        assert_eq!(served, [None]);
+        assert_eq!(resp, [(0xc2, vec![Status::UnknownCommand as u8])]);
+    }
+
+    #[test]
+    fn truncated() {
+        // Shorter than a header and CRC
+        let mut wire = slip(&[0x00, 0x00, 0x00, 0x00]);
+        // Shorter than `len` claims
+        wire.extend(slip(&frame(0x10, 4, &[1, 2])));
+        let (served, resp) = serve(&wire, 2, &mut Echo);
+        assert_eq!(served, [None, None]);
+        assert_eq!(
+            resp,
+            [
+                (NO_CMD, vec![Status::Truncated as u8]),
+                (0x90, vec![Status::Truncated as u8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_length() {
+        let mut wire = slip(&frame(0x10, 1, &[1, 2]));
+        wire.extend(slip(&frame(0x03, 1, &[0])));
+        let (served, resp) = serve(&wire, 2, &mut Echo);
+        assert_eq!(served, [None, None]);
+        assert_eq!(
+            resp,
+            [
+                (0x90, vec![Status::BadLength as u8]),
+                (0x83, vec![Status::BadLength as u8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_crc_then_retry() {
+        let good = frame(0x10, 2, &[5, 6]);
+        let mut bad = good.clone();
+        // A byte dropped by the link
+        bad.remove(3);
+        let mut wire = slip(&bad);
+        wire.extend(slip(&good));
+        let (served, resp) = serve(&wire, 2, &mut Echo);
+        assert_eq!(served, [None, Some(Command::SpimSelfTest)]);
+        assert_eq!(
+            resp,
+            [
+                (NO_CMD, vec![Status::BadCrc as u8]),
+                (0x90, vec![Status::Ok as u8, 5, 6]),
+            ]
+        );
+    }
+
+    #[test]
+    fn overflow_then_retry() {
+        let mut wire = slip(&frame(0x10, 40, &[0; 40]));
+        wire.extend(slip(&frame(0x00, 0, &[])));
+        let (served, resp) = serve(&wire, 2, &mut ());
+        assert_eq!(served, [None, Some(Command::Ping)]);
+        assert_eq!(
+            resp,
+            [
+                (NO_CMD, vec![Status::Overflow as u8]),
+                (RESPONSE, vec![Status::Ok as u8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn handler_response_overflow() {
+        // Fills the 31 bytes after the status, but not more
+        let (_, resp) = serve(&slip(&frame(0x10, 27, &[7; 27])), 1, &mut Echo);
+        assert_eq!(resp[0].1.len(), 28);
+
+        struct TooLong;
+        impl Handler for TooLong {
+            fn handle(&mut self, _: Command, _: &[u8], resp: &mut [u8]) -> Result<usize, Status> {
+                Ok(resp.len() + 1)
+            }
+        }
+        let (served, resp) = serve(&slip(&frame(0x10, 0, &[])), 1, &mut TooLong);
+        assert_eq!(served, [None]);
+        assert_eq!(resp, [(0x90, vec![Status::Overflow as u8])]);
+    }
+
+    #[test]
+    fn framing() {
+        let mut wire = vec![
+            slip::END,
+            0x00,
+            slip::ESC,
+            0x01,
+            0x00,
+            0x00,
+            0x00,
+            slip::END,
+        ];
+        wire.extend(slip(&frame(0x00, 0, &[])));
+        let (served, resp) = serve(&wire, 2, &mut ());
+        assert_eq!(served, [None, Some(Command::Ping)]);
+        assert_eq!(
+            resp,
+            [
+                (NO_CMD, vec![Status::Framing as u8]),
+                (RESPONSE, vec![Status::Ok as u8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn version() {
+        let mut expected = vec![Status::Ok as u8; 256];
+        let len = crate::version().encode(&mut expected[1..]).unwrap();
+        expected.truncate(1 + len);
+
+        let wire = slip(&frame(0x03, 0, &[]));
+        let mut server = mock_server::<256>(&wire);
+        assert_eq!(server.serve_one(&mut ()), Some(Command::Version));
+        assert_eq!(responses(server), [(0x83, expected)]);
+
+        // Too long for the response buffer
+        let mut server = mock_server::<8>(&wire);
+        assert_eq!(server.serve_one(&mut ()), None);
+        assert_eq!(responses(server), [(0x83, vec![Status::Overflow as u8])]);
+    }
+}
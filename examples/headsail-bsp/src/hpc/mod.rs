@@ -1,5 +1,8 @@
 //! Abstractions that only exist on HPC
 mod hart_id;
 mod interrupt;
+mod notify;
+mod speed;
 pub use hart_id::*;
 pub use interrupt::*;
+pub use speed::*;
@@ -0,0 +1,98 @@
+//! Inter-hart notification over the CLINT software interrupt
+//!
+//! Each hart has a machine software interrupt pending bit, MSIP, that any
+//! hart can set. [CLINT::notify_core] records the sender and sets the MSIP of
+//! the target, and [CLINT::wait_notification] sleeps in `wfi` until its own
+//! MSIP is set and returns who set it. No trap is taken, so no `MachineSoft`
+//! handler is needed:
+//!
+//! ```ignore
+//! // Hart 1, e.g., after filling a shared buffer
+//! CLINT::notify_core(HartId::H0);
+//!
+//! // Hart 0
+//! let from = CLINT::wait_notification();
+//! ```
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use riscv::register::{mhartid, mie};
+use riscv_pac::HartIdNumber;
+
+use crate::{HartId, CLINT};
+
+const HARTS: usize = HartId::MAX_HART_ID_NUMBER + 1;
+
+/// `SENDERS[target][sender]`: `sender` has notified `target` and the
+/// notification has not been collected
+static SENDERS: [[AtomicBool; HARTS]; HARTS] =
+    [const { [const { AtomicBool::new(false) }; HARTS] }; HARTS];
+
+impl CLINT {
+    /// Set the MSIP of `hart`, pending its machine software interrupt
+    #[inline]
+    pub fn set_msip(hart: HartId) {
+        Self::mswi().msip(hart).pend();
+    }
+
+    #[inline]
+    pub fn clear_msip(hart: HartId) {
+        Self::mswi().msip(hart).unpend();
+    }
+
+    #[inline]
+    pub fn is_msip_pending(hart: HartId) -> bool {
+        Self::mswi().msip(hart).is_pending()
+    }
+
+    /// Wake `hart` from [CLINT::wait_notification], or raise its
+    /// `MachineSoft` interrupt if it has one enabled
+    ///
+    /// Notifications from the same sender that have not been collected yet
+    /// are merged into one.
+    pub fn notify_core(hart: HartId) {
+        SENDERS[hart.number()][current_hart().number()].store(true, Ordering::Release);
+        Self::set_msip(hart);
+    }
+
+    /// Sleep until another hart, or this one, calls [CLINT::notify_core] on
+    /// this hart, and return the sender
+    ///
+    /// Returns right away if a notification is already waiting. With several
+    /// waiting, the lowest sender is returned first and the others stay
+    /// waiting for the next call. Interrupts are disabled while sleeping,
+    /// and the enable of the software interrupt is restored afterwards.
+    pub fn wait_notification() -> HartId {
+        let me = current_hart();
+        riscv::interrupt::free(|| {
+            // `wfi` only wakes on interrupts enabled in `mie`
+            let msoft = mie::read().msoft();
+            unsafe { mie::set_msoft() };
+            let sender = loop {
+                // Clear before collecting, so that a notification arriving
+                // after the check pends MSIP again and `wfi` falls through
+                Self::clear_msip(me);
+                if let Some(sender) = take_sender(me) {
+                    break sender;
+                }
+                while !Self::is_msip_pending(me) {
+                    riscv::asm::wfi();
+                }
+            };
+            if !msoft {
+                unsafe { mie::clear_msoft() };
+            }
+            sender
+        })
+    }
+}
+
+fn current_hart() -> HartId {
+    HartId::from_number(mhartid::read()).unwrap()
+}
+
+fn take_sender(target: HartId) -> Option<HartId> {
+    let slots = &SENDERS[target.number()];
+    let sender = slots.iter().position(|s| s.load(Ordering::Acquire))?;
+    slots[sender].store(false, Ordering::Relaxed);
+    HartId::from_number(sender).ok()
+}
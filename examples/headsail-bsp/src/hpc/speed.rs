@@ -0,0 +1,80 @@
+//! Execution speed relative to `mtime`, for delays that hold in the VP
+//!
+//! Renode executes instructions at a configured rate that has nothing to do
+//! with the nominal core clock, so cycle-counted delays are off by that
+//! ratio against the simulated wall clock. [VpSpeedMonitor::calibrate]
+//! measures the ratio against the CLINT timer and [SimulationDelay] scales
+//! delays by it. On the ASIC, the measured factor is close to 1.
+//!
+//! ```ignore
+//! let factor = VpSpeedMonitor::calibrate();
+//! SimulationDelay::from_us(500, factor).wait();
+//! ```
+use core::hint::black_box;
+
+use riscv::register::mcycle;
+
+use super::CLINT;
+use crate::delay;
+
+/// `freq` of the CLINT
+const MTIME_HZ: u64 = 32_768;
+/// Loop iterations run by [VpSpeedMonitor::calibrate]
+const CALIBRATION_ITERS: u32 = 1_000_000;
+
+/// Cycles executed per `mtime` second over [delay::core_hz]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct SpeedFactor(pub f32);
+
+impl SpeedFactor {
+    /// Nominal speed, as on hardware
+    pub const NOMINAL: Self = Self(1.0);
+}
+
+/// Measures the execution speed of the running hart
+pub struct VpSpeedMonitor;
+
+impl VpSpeedMonitor {
+    /// Run a fixed loop and compare the cycles it took with the `mtime` ticks
+    /// that passed
+    ///
+    /// Takes about 30 ms of `mtime` on hardware, and whatever that amounts to
+    /// in the VP.
+    pub fn calibrate() -> SpeedFactor {
+        let (start_ticks, start_cycles) = (CLINT::mtime().read(), mcycle::read64());
+        for i in 0..CALIBRATION_ITERS {
+            black_box(i);
+        }
+        let ticks = CLINT::mtime().read().wrapping_sub(start_ticks).max(1);
+        let cycles = mcycle::read64().wrapping_sub(start_cycles);
+
+        let sim_hz = cycles as f32 * MTIME_HZ as f32 / ticks as f32;
+        SpeedFactor(sim_hz / delay::core_hz() as f32)
+    }
+}
+
+/// Busy-wait lasting a given time of `mtime`, sa. [module
+/// documentation](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimulationDelay {
+    cycles: u32,
+}
+
+impl SimulationDelay {
+    pub fn from_us(us: u32, factor: SpeedFactor) -> Self {
+        let cycles = us as f32 * delay::core_hz() as f32 / 1_000_000.0 * factor.0;
+        // `as` saturates, and maps NaN to 0
+        Self {
+            cycles: cycles as u32,
+        }
+    }
+
+    /// Number of core cycles the delay lasts
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    pub fn wait(&self) {
+        delay::cycles(self.cycles);
+    }
+}
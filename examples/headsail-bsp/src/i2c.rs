@@ -0,0 +1,193 @@
+//! Register access for I2C devices
+//!
+//! The helpers and device drivers are generic over [embedded_hal::i2c::I2c],
+//! so they work on top of any bus implementation, e.g., the HPC APB I2C or
+//! [BitBangI2c].
+pub mod bitbang;
+pub mod gpio_expander;
+pub mod scan;
+pub mod smbus;
+
+pub use bitbang::BitBangI2c;
+use embedded_hal::i2c::{self, ErrorKind, NoAcknowledgeSource, Operation, SevenBitAddress};
+pub use gpio_expander::GpioExpander;
+pub use scan::{scan, ScanResult};
+pub use smbus::Pec;
+
+use crate::sealed::Sealed;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum I2cError {
+    /// No device acknowledged the address
+    AddressNack,
+    /// The device did not acknowledge a data byte
+    DataNack,
+    /// Another master took over the bus
+    ArbitrationLost,
+    /// Misplaced start or stop condition
+    Bus,
+    /// The bus did not finish the transfer in time
+    Timeout,
+    /// SMBus packet error check failed
+    Pec,
+    /// SMBus block is empty or too long, sa. [smbus::MAX_BLOCK_LEN]
+    BlockLength,
+    /// Any other error reported by the bus implementation
+    Other,
+}
+
+impl From<ErrorKind> for I2cError {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => I2cError::AddressNack,
+            ErrorKind::NoAcknowledge(_) => I2cError::DataNack,
+            ErrorKind::ArbitrationLoss => I2cError::ArbitrationLost,
+            ErrorKind::Bus => I2cError::Bus,
+            _ => I2cError::Other,
+        }
+    }
+}
+
+impl i2c::Error for I2cError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            I2cError::AddressNack => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            I2cError::DataNack => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+            I2cError::ArbitrationLost => ErrorKind::ArbitrationLoss,
+            I2cError::Bus => ErrorKind::Bus,
+            I2cError::Timeout | I2cError::Pec | I2cError::BlockLength | I2cError::Other => {
+                ErrorKind::Other
+            }
+        }
+    }
+}
+
+/// Register address sent before each access, u8 for most sensors and u16
+/// (big-endian) for larger EEPROMs
+pub trait RegisterAddress: Copy + Sealed {
+    type Bytes: AsRef<[u8]>;
+
+    fn to_bytes(self) -> Self::Bytes;
+}
+
+impl Sealed for u8 {}
+impl RegisterAddress for u8 {
+    type Bytes = [u8; 1];
+
+    fn to_bytes(self) -> Self::Bytes {
+        [self]
+    }
+}
+
+impl Sealed for u16 {}
+impl RegisterAddress for u16 {
+    type Bytes = [u8; 2];
+
+    fn to_bytes(self) -> Self::Bytes {
+        self.to_be_bytes()
+    }
+}
+
+/// Registers of the device at `addr`, addressed with `R`
+///
+/// Reads write the register address and read the value after a repeated
+/// start. Writes send the address and value in a single write.
+///
+/// ```ignore
+/// let mut regs = I2cRegisterMap::<_>::new(&mut bus, 0x76);
+/// let id = regs.read_u8(0xd0)?;
+/// regs.modify_u8(0xf4, |v| v | 0b11)?;
+/// ```
+pub struct I2cRegisterMap<'i, I, R = u8> {
+    bus: &'i mut I,
+    addr: SevenBitAddress,
+    _reg: core::marker::PhantomData<R>,
+}
+
+impl<'i, I: i2c::I2c, R: RegisterAddress> I2cRegisterMap<'i, I, R> {
+    pub fn new(bus: &'i mut I, addr: SevenBitAddress) -> Self {
+        Self {
+            bus,
+            addr,
+            _reg: core::marker::PhantomData,
+        }
+    }
+
+    pub fn address(&self) -> SevenBitAddress {
+        self.addr
+    }
+
+    /// Read `buf.len()` bytes starting from `reg`
+    ///
+    /// The register address is written and the data read with a repeated
+    /// start in between, without a stop. Most devices auto-increment the
+    /// register address during the read.
+    pub fn read_buf(&mut self, reg: R, buf: &mut [u8]) -> Result<(), I2cError> {
+        self.bus
+            .write_read(self.addr, reg.to_bytes().as_ref(), buf)
+            .map_err(kind)
+    }
+
+    /// Write `data` starting from `reg`
+    pub fn write_buf(&mut self, reg: R, data: &[u8]) -> Result<(), I2cError> {
+        let reg = reg.to_bytes();
+        // Adjacent writes are sent without a repeated start in between
+        self.bus
+            .transaction(
+                self.addr,
+                &mut [Operation::Write(reg.as_ref()), Operation::Write(data)],
+            )
+            .map_err(kind)
+    }
+
+    pub fn read_u8(&mut self, reg: R) -> Result<u8, I2cError> {
+        let mut buf = [0u8; 1];
+        self.read_buf(reg, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    pub fn write_u8(&mut self, reg: R, val: u8) -> Result<(), I2cError> {
+        self.write_buf(reg, &[val])
+    }
+
+    /// Read-modify-write of a single register
+    ///
+    /// The bus is not held in between, so the read and write are not atomic
+    /// with respect to other masters.
+    pub fn modify_u8(&mut self, reg: R, f: impl FnOnce(u8) -> u8) -> Result<(), I2cError> {
+        let val = self.read_u8(reg)?;
+        self.write_u8(reg, f(val))
+    }
+
+    pub fn read_u16_be(&mut self, reg: R) -> Result<u16, I2cError> {
+        let mut buf = [0u8; 2];
+        self.read_buf(reg, &mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    pub fn read_u16_le(&mut self, reg: R) -> Result<u16, I2cError> {
+        let mut buf = [0u8; 2];
+        self.read_buf(reg, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub fn write_u16_be(&mut self, reg: R, val: u16) -> Result<(), I2cError> {
+        self.write_buf(reg, &val.to_be_bytes())
+    }
+
+    pub fn write_u16_le(&mut self, reg: R, val: u16) -> Result<(), I2cError> {
+        self.write_buf(reg, &val.to_le_bytes())
+    }
+
+    pub fn read_u32_be(&mut self, reg: R) -> Result<u32, I2cError> {
+        let mut buf = [0u8; 4];
+        self.read_buf(reg, &mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+}
+
+#[inline]
+fn kind<E: i2c::Error>(e: E) -> I2cError {
+    e.kind().into()
+}
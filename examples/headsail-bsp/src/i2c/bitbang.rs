@@ -0,0 +1,335 @@
+//! Software I2C master on any pair of open-drain pins
+//!
+//! Meant for bringing up sensors before a peripheral driver is available, or
+//! when the peripheral is in use. The bus runs at roughly the requested
+//! frequency. The delays do not account for the time spent toggling the
+//! pins.
+//!
+//! Both pins must behave as open-drain: `set_high` releases the line,
+//! `set_low` drives it low and `is_high` reads back the actual line level.
+//! SysCtrl pads can be used through [Gpio::into_open_drain].
+//!
+//! [Gpio::into_open_drain]: crate::sysctrl::gpio::Gpio::into_open_drain
+//!
+//! # Multi-master
+//!
+//! Lost arbitration is always detected, and reported as
+//! [I2cError::ArbitrationLost]. With [BitBangI2c::enable_multi_master], the
+//! clock also follows the wired-AND of SCL: a high period ends as soon as any
+//! master pulls SCL low, and a low period lasts until all have released it.
+//! The master has no view of START and STOP conditions of others, so callers
+//! must check [BitBangI2c::is_bus_busy] before each transfer, and back off
+//! exponentially while it is busy or arbitration is lost:
+//!
+//! ```ignore
+//! let mut backoff_us = 10;
+//! loop {
+//!     if !bus.is_bus_busy() {
+//!         match bus.write(addr, &frame) {
+//!             Err(I2cError::ArbitrationLost) => {}
+//!             res => break res,
+//!         }
+//!     }
+//!     delay.delay_us(backoff_us);
+//!     backoff_us = (backoff_us * 2).min(10_000);
+//! }
+//! ```
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    i2c::{self, Operation, SevenBitAddress},
+};
+
+use super::I2cError;
+
+/// SMBus limit for a device holding SCL low
+const DEFAULT_STRETCH_TIMEOUT_US: u32 = 25_000;
+/// Resolution of watching SCL while it is high in multi-master mode
+const SYNC_POLL_NS: u32 = 250;
+
+/// Bit-banged I2C master, sa. [module documentation](self)
+pub struct BitBangI2c<'d, SDA, SCL, D> {
+    sda: SDA,
+    scl: SCL,
+    delay: &'d mut D,
+    half_period_ns: u32,
+    stretch_timeout_us: u32,
+    multi_master: bool,
+}
+
+impl<'d, SDA, SCL, D> BitBangI2c<'d, SDA, SCL, D>
+where
+    SDA: InputPin + OutputPin,
+    SCL: InputPin + OutputPin,
+    D: DelayNs,
+{
+    /// Release both lines and run the bus at `freq_hz`, e.g., 100 kHz
+    pub fn new(
+        mut sda: SDA,
+        mut scl: SCL,
+        delay: &'d mut D,
+        freq_hz: u32,
+    ) -> Result<Self, I2cError> {
+        sda.set_high().map_err(pin)?;
+        scl.set_high().map_err(pin)?;
+        Ok(Self {
+            sda,
+            scl,
+            delay,
+            half_period_ns: 500_000_000 / freq_hz.max(1),
+            stretch_timeout_us: DEFAULT_STRETCH_TIMEOUT_US,
+            multi_master: false,
+        })
+    }
+
+    /// Time a device may hold SCL low before [I2cError::Timeout] is returned
+    pub fn set_stretch_timeout_us(&mut self, timeout_us: u32) {
+        self.stretch_timeout_us = timeout_us;
+    }
+
+    /// Synchronize SCL with other masters on the bus, sa. [module
+    /// documentation](self#multi-master)
+    ///
+    /// There is no synchronization hardware, so each SCL high period is
+    /// watched in software, polling every [SYNC_POLL_NS] ns.
+    pub fn enable_multi_master(&mut self) {
+        self.multi_master = true;
+    }
+
+    /// Whether the bus is in use, as far as can be seen from the lines
+    ///
+    /// Watches both lines for one SCL period, the bus free time between a
+    /// STOP and the next START. The bus is busy if either line is low at any
+    /// point, and also if a pin cannot be read. A master between START and
+    /// STOP with both lines released for longer than that is not seen.
+    pub fn is_bus_busy(&mut self) -> bool {
+        let mut watched_ns = 0;
+        loop {
+            let idle = matches!(
+                (self.sda.is_high(), self.scl.is_high()),
+                (Ok(true), Ok(true))
+            );
+            if !idle {
+                return true;
+            }
+            if watched_ns >= 2 * self.half_period_ns {
+                return false;
+            }
+            self.delay.delay_ns(SYNC_POLL_NS);
+            watched_ns += SYNC_POLL_NS;
+        }
+    }
+
+    /// Release the pins
+    pub fn free(self) -> (SDA, SCL) {
+        (self.sda, self.scl)
+    }
+
+    /// Address the device with a write until it acknowledges, at most
+    /// `max_attempts` times
+    ///
+    /// EEPROMs do not acknowledge while an internal write cycle is in
+    /// progress.
+    pub fn wait_for_ack(
+        &mut self,
+        addr: SevenBitAddress,
+        max_attempts: u32,
+    ) -> Result<(), I2cError> {
+        for _ in 0..max_attempts {
+            let result = self.start().and_then(|_| self.write_byte(addr << 1));
+            let stop = self.finish(result);
+            match result.and(stop) {
+                Ok(()) => return Ok(()),
+                Err(I2cError::DataNack) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(I2cError::AddressNack)
+    }
+
+    #[inline]
+    fn half(&mut self) {
+        self.delay.delay_ns(self.half_period_ns);
+    }
+
+    /// Rest of the SCL high period. In multi-master mode, it ends when another
+    /// master pulls SCL low first.
+    fn scl_high(&mut self) -> Result<(), I2cError> {
+        if !self.multi_master {
+            self.half();
+            return Ok(());
+        }
+        let mut waited_ns = 0;
+        while waited_ns < self.half_period_ns && self.scl.is_high().map_err(pin)? {
+            self.delay.delay_ns(SYNC_POLL_NS);
+            waited_ns += SYNC_POLL_NS;
+        }
+        Ok(())
+    }
+
+    /// Release SCL and wait for devices stretching the clock, or, in
+    /// multi-master mode, for other masters to end their low period
+    fn scl_release(&mut self) -> Result<(), I2cError> {
+        self.scl.set_high().map_err(pin)?;
+        let mut waited_us = 0;
+        while self.scl.is_low().map_err(pin)? {
+            if waited_us >= self.stretch_timeout_us {
+                return Err(I2cError::Timeout);
+            }
+            self.delay.delay_us(1);
+            waited_us += 1;
+        }
+        Ok(())
+    }
+
+    /// START, or repeated START if SCL is held low
+    fn start(&mut self) -> Result<(), I2cError> {
+        self.sda.set_high().map_err(pin)?;
+        self.half();
+        self.scl_release()?;
+        if self.sda.is_low().map_err(pin)? {
+            // Another master or a stuck device holds SDA
+            return Err(I2cError::ArbitrationLost);
+        }
+        self.half();
+        self.sda.set_low().map_err(pin)?;
+        self.half();
+        self.scl.set_low().map_err(pin)
+    }
+
+    fn stop(&mut self) -> Result<(), I2cError> {
+        self.sda.set_low().map_err(pin)?;
+        self.half();
+        self.scl_release()?;
+        self.half();
+        self.sda.set_high().map_err(pin)?;
+        self.half();
+        Ok(())
+    }
+
+    /// STOP after a transfer. On lost arbitration, the bus belongs to another
+    /// master, so only let go of the lines.
+    fn finish(&mut self, result: Result<(), I2cError>) -> Result<(), I2cError> {
+        match result {
+            Err(I2cError::ArbitrationLost) => {
+                self.sda.set_high().map_err(pin)?;
+                self.scl.set_high().map_err(pin)
+            }
+            _ => self.stop(),
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) -> Result<(), I2cError> {
+        if bit {
+            self.sda.set_high().map_err(pin)?;
+        } else {
+            self.sda.set_low().map_err(pin)?;
+        }
+        self.half();
+        self.scl_release()?;
+        if bit && self.sda.is_low().map_err(pin)? {
+            return Err(I2cError::ArbitrationLost);
+        }
+        self.scl_high()?;
+        self.scl.set_low().map_err(pin)
+    }
+
+    fn read_bit(&mut self) -> Result<bool, I2cError> {
+        self.sda.set_high().map_err(pin)?;
+        self.half();
+        self.scl_release()?;
+        let bit = self.sda.is_high().map_err(pin)?;
+        self.scl_high()?;
+        self.scl.set_low().map_err(pin)?;
+        Ok(bit)
+    }
+
+    /// Returns [I2cError::DataNack] if the device does not acknowledge
+    fn write_byte(&mut self, byte: u8) -> Result<(), I2cError> {
+        for i in (0..8).rev() {
+            self.write_bit(byte & (1 << i) != 0)?;
+        }
+        match self.read_bit()? {
+            false => Ok(()),
+            true => Err(I2cError::DataNack),
+        }
+    }
+
+    /// Read a byte and acknowledge it, or NACK the last byte of a read
+    fn read_byte(&mut self, ack: bool) -> Result<u8, I2cError> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+        self.write_bit(!ack)?;
+        Ok(byte)
+    }
+
+    fn run(
+        &mut self,
+        addr: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError> {
+        let is_read = |op: &Operation<'_>| matches!(op, Operation::Read(_));
+
+        let mut prev_read = None;
+        for i in 0..operations.len() {
+            let read = is_read(&operations[i]);
+            // Adjacent operations of the same kind are merged
+            if prev_read != Some(read) {
+                self.start()?;
+                self.write_byte((addr << 1) | read as u8)
+                    .map_err(|e| match e {
+                        I2cError::DataNack => I2cError::AddressNack,
+                        e => e,
+                    })?;
+            }
+            prev_read = Some(read);
+            let last_of_kind = operations
+                .get(i + 1)
+                .is_none_or(|next| is_read(next) != read);
+
+            match &mut operations[i] {
+                Operation::Write(data) => {
+                    for byte in data.iter() {
+                        self.write_byte(*byte)?;
+                    }
+                }
+                Operation::Read(buf) => {
+                    let len = buf.len();
+                    for (j, byte) in buf.iter_mut().enumerate() {
+                        *byte = self.read_byte(!(last_of_kind && j + 1 == len))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<SDA, SCL, D> i2c::ErrorType for BitBangI2c<'_, SDA, SCL, D> {
+    type Error = I2cError;
+}
+
+impl<SDA, SCL, D> i2c::I2c for BitBangI2c<'_, SDA, SCL, D>
+where
+    SDA: InputPin + OutputPin,
+    SCL: InputPin + OutputPin,
+    D: DelayNs,
+{
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.run(address, operations);
+        // Leave the bus idle, also after an error
+        let stop = self.finish(result);
+        result.and(stop)
+    }
+}
+
+#[inline]
+fn pin<E>(_: E) -> I2cError {
+    I2cError::Other
+}
@@ -0,0 +1,212 @@
+//! I2C GPIO expanders: NXP PCF8574 and Microchip MCP23017
+//!
+//! The output latch is cached, so changing a single pin is one I2C write
+//! rather than a read-modify-write. Pins are handed out one at a time as
+//! [embedded_hal::digital] pins, e.g., for drivers that take a reset or chip
+//! select line:
+//!
+//! ```ignore
+//! let mut exp = GpioExpander::new(&mut bus, Expander::Mcp23017, 0x20);
+//! exp.set_outputs(0x00ff)?;
+//! exp.pin::<3>().set_high()?;
+//! let buttons = exp.read_port()? >> 8;
+//! ```
+//!
+//! Datasheets:
+//! <https://www.nxp.com/docs/en/data-sheet/PCF8574_PCF8574A.pdf>,
+//! <https://ww1.microchip.com/downloads/en/devicedoc/20001952c.pdf>
+use embedded_hal::{
+    digital::{self, ErrorType, InputPin, OutputPin, StatefulOutputPin},
+    i2c::{I2c, SevenBitAddress},
+};
+
+use super::{kind, I2cError, I2cRegisterMap};
+
+/// MCP23017 registers with IOCON.BANK = 0, the reset default. Port B
+/// follows port A, so both are accessed as one little-endian u16.
+const MCP_IODIR: u8 = 0x00;
+const MCP_GPIO: u8 = 0x12;
+const MCP_OLAT: u8 = 0x14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expander {
+    /// 8 quasi-bidirectional pins. A pin is an input while its latch is
+    /// high, with a weak pull-up.
+    Pcf8574,
+    /// 16 pins with direction registers, port A in the low byte and port B
+    /// in the high byte
+    Mcp23017,
+}
+
+impl Expander {
+    pub const fn pins(&self) -> u8 {
+        match self {
+            Expander::Pcf8574 => 8,
+            Expander::Mcp23017 => 16,
+        }
+    }
+}
+
+/// GPIO expander at `addr` on bus `I`, sa. [module documentation](self)
+pub struct GpioExpander<I> {
+    bus: I,
+    chip: Expander,
+    addr: SevenBitAddress,
+    /// Last value written to the output latch
+    latch: u16,
+    /// Pins driven by the latch
+    outputs: u16,
+}
+
+impl<I: I2c> GpioExpander<I> {
+    /// Assumes the device is in its power-on state, where all pins are
+    /// inputs. The bus is not accessed.
+    pub fn new(bus: I, chip: Expander, addr: SevenBitAddress) -> Self {
+        // The PCF8574 latch resets high, the MCP23017 one low
+        let latch = match chip {
+            Expander::Pcf8574 => 0xff,
+            Expander::Mcp23017 => 0,
+        };
+        Self {
+            bus,
+            chip,
+            addr,
+            latch,
+            outputs: 0,
+        }
+    }
+
+    pub fn free(self) -> I {
+        self.bus
+    }
+
+    pub fn chip(&self) -> Expander {
+        self.chip
+    }
+
+    /// Make the pins set in `mask` outputs and the rest inputs
+    ///
+    /// Outputs drive their cached latch value. On the PCF8574, inputs are
+    /// latched high, outputs keep their latch value.
+    pub fn set_outputs(&mut self, mask: u16) -> Result<(), I2cError> {
+        let mask = mask & self.pin_mask();
+        self.outputs = mask;
+        match self.chip {
+            Expander::Pcf8574 => self.write_latch(self.latch),
+            Expander::Mcp23017 => self.regs().write_u16_le(MCP_IODIR, !mask),
+        }
+    }
+
+    /// Read the level of all pins, outputs included
+    pub fn read_port(&mut self) -> Result<u16, I2cError> {
+        match self.chip {
+            Expander::Pcf8574 => {
+                let mut buf = [0u8];
+                self.bus.read(self.addr, &mut buf).map_err(kind)?;
+                Ok(buf[0] as u16)
+            }
+            Expander::Mcp23017 => self.regs().read_u16_le(MCP_GPIO),
+        }
+    }
+
+    /// Set the output latch of all pins
+    ///
+    /// Only pins made outputs with [GpioExpander::set_outputs] are driven.
+    /// The value is cached, so reading it back with
+    /// [GpioExpander::latch] costs no I2C access.
+    pub fn write_port(&mut self, val: u16) -> Result<(), I2cError> {
+        self.write_latch(val & self.pin_mask())
+    }
+
+    /// Cached output latch
+    pub fn latch(&self) -> u16 {
+        self.latch
+    }
+
+    /// Borrow pin `BIT` as an [embedded_hal::digital] pin
+    ///
+    /// # Panics
+    ///
+    /// If the chip has no pin `BIT`
+    pub fn pin<const BIT: u8>(&mut self) -> GpioExpanderPin<'_, I, BIT> {
+        assert!(BIT < self.chip.pins());
+        GpioExpanderPin { exp: self }
+    }
+
+    fn set_pin(&mut self, bit: u8, high: bool) -> Result<(), I2cError> {
+        let latch = match high {
+            true => self.latch | 1 << bit,
+            false => self.latch & !(1 << bit),
+        };
+        if latch == self.latch {
+            return Ok(());
+        }
+        self.write_latch(latch)
+    }
+
+    fn write_latch(&mut self, latch: u16) -> Result<(), I2cError> {
+        match self.chip {
+            Expander::Pcf8574 => {
+                // Writing a low to an input would turn it into an output
+                let byte = (latch | !self.outputs) as u8;
+                self.bus.write(self.addr, &[byte]).map_err(kind)?;
+            }
+            Expander::Mcp23017 => self.regs().write_u16_le(MCP_OLAT, latch)?,
+        }
+        self.latch = latch;
+        Ok(())
+    }
+
+    fn pin_mask(&self) -> u16 {
+        u16::MAX >> (16 - self.chip.pins())
+    }
+
+    fn regs(&mut self) -> I2cRegisterMap<'_, I> {
+        I2cRegisterMap::new(&mut self.bus, self.addr)
+    }
+}
+
+/// Pin `BIT` of a [GpioExpander], sa. [GpioExpander::pin]
+pub struct GpioExpanderPin<'e, I, const BIT: u8> {
+    exp: &'e mut GpioExpander<I>,
+}
+
+impl digital::Error for I2cError {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+impl<I, const BIT: u8> ErrorType for GpioExpanderPin<'_, I, BIT> {
+    type Error = I2cError;
+}
+
+impl<I: I2c, const BIT: u8> OutputPin for GpioExpanderPin<'_, I, BIT> {
+    fn set_low(&mut self) -> Result<(), I2cError> {
+        self.exp.set_pin(BIT, false)
+    }
+
+    fn set_high(&mut self) -> Result<(), I2cError> {
+        self.exp.set_pin(BIT, true)
+    }
+}
+
+impl<I: I2c, const BIT: u8> StatefulOutputPin for GpioExpanderPin<'_, I, BIT> {
+    fn is_set_high(&mut self) -> Result<bool, I2cError> {
+        Ok(self.exp.latch & 1 << BIT != 0)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, I2cError> {
+        Ok(self.exp.latch & 1 << BIT == 0)
+    }
+}
+
+impl<I: I2c, const BIT: u8> InputPin for GpioExpanderPin<'_, I, BIT> {
+    fn is_high(&mut self) -> Result<bool, I2cError> {
+        Ok(self.exp.read_port()? & 1 << BIT != 0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, I2cError> {
+        Ok(self.exp.read_port()? & 1 << BIT == 0)
+    }
+}
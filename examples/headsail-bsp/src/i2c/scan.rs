@@ -0,0 +1,73 @@
+//! Bus scan for bring-up, sa. [scan]
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+use ufmt::{uDisplay, uWrite, uwrite};
+
+/// First and last address probed by [scan]. The others are reserved by the
+/// I2C specification.
+pub const SCAN_ADDRS: core::ops::RangeInclusive<SevenBitAddress> = 0x08..=0x77;
+
+/// Addresses that acknowledged during [scan]
+///
+/// Displays as a grid in the style of Linux `i2cdetect`, with the address
+/// of each device that answered and `--` for the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanResult {
+    pub present: [bool; 128],
+}
+
+impl ScanResult {
+    #[inline]
+    pub fn is_present(&self, addr: SevenBitAddress) -> bool {
+        self.present.get(addr as usize).copied().unwrap_or(false)
+    }
+
+    /// Addresses that acknowledged, in ascending order
+    pub fn iter_present(&self) -> impl Iterator<Item = SevenBitAddress> + '_ {
+        (0..128u8).filter(|&addr| self.present[addr as usize])
+    }
+
+    pub fn count(&self) -> usize {
+        self.present.iter().filter(|&&p| p).count()
+    }
+}
+
+/// Probe every address in [SCAN_ADDRS] with a zero-byte write
+///
+/// Any error counts as absent. Devices that do not tolerate an empty write,
+/// such as some EEPROMs in the middle of a write cycle, may be missed.
+pub fn scan<I: I2c>(bus: &mut I) -> ScanResult {
+    let mut present = [false; 128];
+    for addr in SCAN_ADDRS {
+        present[addr as usize] = bus.write(addr, &[]).is_ok();
+    }
+    ScanResult { present }
+}
+
+impl uDisplay for ScanResult {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let digit = |n: u8| HEX[n as usize & 0xf] as char;
+
+        f.write_str("    ")?;
+        for col in 0..16 {
+            uwrite!(f, "  {}", digit(col))?;
+        }
+        for row in 0..8u8 {
+            uwrite!(f, "\r\n{}0:", digit(row))?;
+            for col in 0..16u8 {
+                let addr = row << 4 | col;
+                if !SCAN_ADDRS.contains(&addr) {
+                    f.write_str("   ")?;
+                } else if self.present[addr as usize] {
+                    uwrite!(f, " {}{}", digit(row), digit(col))?;
+                } else {
+                    f.write_str(" --")?;
+                }
+            }
+        }
+        f.write_str("\r\n")
+    }
+}
@@ -0,0 +1,359 @@
+//! SMBus block transfers and packet error checking (PEC)
+//!
+//! A block starts with a count byte of 1..=[MAX_BLOCK_LEN]. With PEC, a
+//! CRC-8 over every byte of the transfer, the address bytes included,
+//! follows the data. A mismatch is reported as [I2cError::Pec].
+//!
+//! ```ignore
+//! let mut dev = I2cRegisterMap::<_>::new(&mut bus, 0x0b);
+//! let mut name = [0u8; 32];
+//! let len = dev.block_read(0x21, &mut name, Pec::Enabled)?;
+//! ```
+//!
+//! Specification: <https://smbus.org/specs/SMBus_3_3_20240512.pdf>
+use embedded_hal::i2c::{I2c, Operation};
+
+use super::{kind, I2cError, I2cRegisterMap};
+
+/// Longest SMBus block in bytes, excluding the count byte
+pub const MAX_BLOCK_LEN: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pec {
+    Disabled,
+    Enabled,
+}
+
+/// SMBus PEC, CRC-8 with polynomial x^8 + x^2 + x + 1, continued from `crc`
+pub const fn pec_update(mut crc: u8, data: &[u8]) -> u8 {
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+impl<I: I2c> I2cRegisterMap<'_, I, u8> {
+    /// SMBus block read of command `cmd` into `buf`, returning the count
+    /// sent by the device
+    ///
+    /// The count is not known before the read, so `buf.len()` bytes are read
+    /// after it, plus the PEC byte if enabled. Devices send 0xff or repeat
+    /// data past the end of the block. Keep `buf` as short as the longest
+    /// block the command returns, to avoid reading more than needed.
+    ///
+    /// Fails with [I2cError::BlockLength] if `buf` is empty or longer than
+    /// [MAX_BLOCK_LEN], or the count is zero or more than `buf.len()`.
+    pub fn block_read(&mut self, cmd: u8, buf: &mut [u8], pec: Pec) -> Result<usize, I2cError> {
+        if buf.is_empty() || buf.len() > MAX_BLOCK_LEN {
+            return Err(I2cError::BlockLength);
+        }
+        let mut raw = [0u8; 1 + MAX_BLOCK_LEN + 1];
+        let raw_len = 1 + buf.len() + (pec == Pec::Enabled) as usize;
+        self.bus
+            .write_read(self.addr, &[cmd], &mut raw[..raw_len])
+            .map_err(kind)?;
+
+        let count = raw[0] as usize;
+        if count == 0 || count > buf.len() {
+            return Err(I2cError::BlockLength);
+        }
+        if pec == Pec::Enabled {
+            let crc = pec_update(0, &[self.addr << 1, cmd, (self.addr << 1) | 1]);
+            if pec_update(crc, &raw[..1 + count]) != raw[1 + count] {
+                return Err(I2cError::Pec);
+            }
+        }
+        buf[..count].copy_from_slice(&raw[1..1 + count]);
+        Ok(count)
+    }
+
+    /// SMBus block write of `data` to command `cmd`
+    ///
+    /// Fails with [I2cError::BlockLength] if `data` is empty or longer than
+    /// [MAX_BLOCK_LEN].
+    pub fn block_write(&mut self, cmd: u8, data: &[u8], pec: Pec) -> Result<(), I2cError> {
+        if data.is_empty() || data.len() > MAX_BLOCK_LEN {
+            return Err(I2cError::BlockLength);
+        }
+        let header = [cmd, data.len() as u8];
+        // Adjacent writes are sent without a repeated start in between
+        let res = match pec {
+            Pec::Enabled => {
+                let crc = pec_update(pec_update(0, &[self.addr << 1]), &header);
+                let crc = [pec_update(crc, data)];
+                let mut ops = [
+                    Operation::Write(&header),
+                    Operation::Write(data),
+                    Operation::Write(&crc),
+                ];
+                self.bus.transaction(self.addr, &mut ops)
+            }
+            Pec::Disabled => {
+                let mut ops = [Operation::Write(&header), Operation::Write(data)];
+                self.bus.transaction(self.addr, &mut ops)
+            }
+        };
+        res.map_err(kind)
+    }
+
+    /// SMBus write byte with PEC. Without PEC, use
+    /// [I2cRegisterMap::write_u8].
+    pub fn write_u8_pec(&mut self, cmd: u8, val: u8) -> Result<(), I2cError> {
+        let crc = pec_update(0, &[self.addr << 1, cmd, val]);
+        self.bus.write(self.addr, &[cmd, val, crc]).map_err(kind)
+    }
+
+    /// SMBus read byte with PEC. Without PEC, use [I2cRegisterMap::read_u8].
+    pub fn read_u8_pec(&mut self, cmd: u8) -> Result<u8, I2cError> {
+        let mut buf = [0u8; 2];
+        self.bus
+            .write_read(self.addr, &[cmd], &mut buf)
+            .map_err(kind)?;
+        let crc = pec_update(0, &[self.addr << 1, cmd, (self.addr << 1) | 1, buf[0]]);
+        if crc != buf[1] {
+            return Err(I2cError::Pec);
+        }
+        Ok(buf[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, SevenBitAddress};
+    use std::{collections::VecDeque, vec, vec::Vec};
+
+    const ADDR: u8 = 0x0b;
+
+    /// Bus with one device that answers reads from `rx` and records the bytes
+    /// written in each transaction. Other addresses are NACKed.
+    #[derive(Default)]
+    struct MockBus {
+        rx: VecDeque<u8>,
+        writes: Vec<Vec<u8>>,
+        read_lens: Vec<usize>,
+    }
+
+    impl MockBus {
+        fn with_rx(rx: &[u8]) -> Self {
+            Self {
+                rx: rx.iter().copied().collect(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl ErrorType for MockBus {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for MockBus {
+        fn transaction(
+            &mut self,
+            address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            if address != ADDR {
+                return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            }
+            let mut written = Vec::new();
+            for op in operations {
+                match op {
+                    Operation::Write(data) => written.extend_from_slice(data),
+                    Operation::Read(buf) => {
+                        self.read_lens.push(buf.len());
+                        for b in buf.iter_mut() {
+                            *b = self.rx.pop_front().unwrap_or(0xff);
+                        }
+                    }
+                }
+            }
+            self.writes.push(written);
+            Ok(())
+        }
+    }
+
+    /// PEC a device appends to a block read of `cmd` returning `block`
+    fn read_pec(cmd: u8, block: &[u8]) -> u8 {
+        let crc = pec_update(0, &[ADDR << 1, cmd, (ADDR << 1) | 1, block.len() as u8]);
+        pec_update(crc, block)
+    }
+
+    #[test]
+    fn pec_matches_crc8_smbus() {
+        // CRC-8/SMBUS check value
+        assert_eq!(pec_update(0, b"123456789"), 0xf4);
+        assert_eq!(pec_update(0, &[]), 0);
+        // Continuing from a partial CRC equals one pass over the whole input
+        assert_eq!(pec_update(pec_update(0, b"1234"), b"56789"), 0xf4);
+    }
+
+    #[test]
+    fn block_read_without_pec() {
+        let mut bus = MockBus::with_rx(&[3, b'a', b'b', b'c', 0xff, 0xff]);
+        let mut buf = [0u8; 5];
+        let len = I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .block_read(0x21, &mut buf, Pec::Disabled)
+            .unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(&buf[..3], b"abc");
+        assert_eq!(bus.writes, [vec![0x21]]);
+        // Count byte plus `buf.len()` data bytes
+        assert_eq!(bus.read_lens, [6]);
+    }
+
+    #[test]
+    fn block_read_with_pec() {
+        let block = b"bq40z50";
+        let mut rx = vec![block.len() as u8];
+        rx.extend_from_slice(block);
+        rx.push(read_pec(0x21, block));
+        let mut bus = MockBus::with_rx(&rx);
+        let mut buf = [0u8; 7];
+        let len = I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .block_read(0x21, &mut buf, Pec::Enabled)
+            .unwrap();
+        assert_eq!(&buf[..len], block);
+        assert_eq!(bus.read_lens, [1 + 7 + 1]);
+    }
+
+    #[test]
+    fn block_read_short_block_with_pec() {
+        // PEC follows the count'th byte, not the end of `buf`
+        let mut bus = MockBus::with_rx(&[2, 0x12, 0x34, read_pec(0x05, &[0x12, 0x34])]);
+        let mut buf = [0u8; 4];
+        let len = I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .block_read(0x05, &mut buf, Pec::Enabled)
+            .unwrap();
+        assert_eq!(&buf[..len], [0x12, 0x34]);
+    }
+
+    #[test]
+    fn block_read_detects_pec_mismatch() {
+        let pec = read_pec(0x21, b"abc");
+        for bad in [pec ^ 0x01, pec ^ 0x80] {
+            let mut bus = MockBus::with_rx(&[3, b'a', b'b', b'c', bad]);
+            let mut buf = [0u8; 3];
+            let res =
+                I2cRegisterMap::<_>::new(&mut bus, ADDR).block_read(0x21, &mut buf, Pec::Enabled);
+            assert_eq!(res, Err(I2cError::Pec));
+            // Corrupted data is not handed out
+            assert_eq!(buf, [0; 3]);
+        }
+
+        // A PEC computed for another command does not match either
+        let mut bus = MockBus::with_rx(&[3, b'a', b'b', b'c', read_pec(0x22, b"abc")]);
+        let res =
+            I2cRegisterMap::<_>::new(&mut bus, ADDR).block_read(0x21, &mut [0; 3], Pec::Enabled);
+        assert_eq!(res, Err(I2cError::Pec));
+    }
+
+    #[test]
+    fn block_read_rejects_bad_lengths() {
+        for count in [0, 4, 0xff] {
+            let mut bus = MockBus::with_rx(&[count, 1, 2, 3]);
+            let res = I2cRegisterMap::<_>::new(&mut bus, ADDR).block_read(
+                0x21,
+                &mut [0; 3],
+                Pec::Disabled,
+            );
+            assert_eq!(res, Err(I2cError::BlockLength), "count {count}");
+        }
+
+        let mut bus = MockBus::default();
+        let mut dev = I2cRegisterMap::<_>::new(&mut bus, ADDR);
+        assert_eq!(
+            dev.block_read(0x21, &mut [], Pec::Disabled),
+            Err(I2cError::BlockLength)
+        );
+        assert_eq!(
+            dev.block_read(0x21, &mut [0; MAX_BLOCK_LEN + 1], Pec::Disabled),
+            Err(I2cError::BlockLength)
+        );
+        // Rejected before touching the bus
+        assert!(bus.writes.is_empty());
+    }
+
+    #[test]
+    fn block_write_without_pec() {
+        let mut bus = MockBus::default();
+        I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .block_write(0x44, &[1, 2, 3], Pec::Disabled)
+            .unwrap();
+        // One transaction, no repeated start between header and data
+        assert_eq!(bus.writes, [vec![0x44, 3, 1, 2, 3]]);
+        assert!(bus.read_lens.is_empty());
+    }
+
+    #[test]
+    fn block_write_with_pec() {
+        let mut bus = MockBus::default();
+        I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .block_write(0x44, &[1, 2, 3], Pec::Enabled)
+            .unwrap();
+        let crc = pec_update(0, &[ADDR << 1, 0x44, 3, 1, 2, 3]);
+        assert_eq!(bus.writes, [vec![0x44, 3, 1, 2, 3, crc]]);
+    }
+
+    #[test]
+    fn block_write_rejects_bad_lengths() {
+        let mut bus = MockBus::default();
+        let mut dev = I2cRegisterMap::<_>::new(&mut bus, ADDR);
+        assert_eq!(
+            dev.block_write(0x44, &[], Pec::Enabled),
+            Err(I2cError::BlockLength)
+        );
+        assert_eq!(
+            dev.block_write(0x44, &[0; MAX_BLOCK_LEN + 1], Pec::Enabled),
+            Err(I2cError::BlockLength)
+        );
+        dev.block_write(0x44, &[0; MAX_BLOCK_LEN], Pec::Disabled)
+            .unwrap();
+        assert_eq!(bus.writes.len(), 1);
+        assert_eq!(bus.writes[0].len(), 2 + MAX_BLOCK_LEN);
+    }
+
+    #[test]
+    fn byte_pec_round_trip() {
+        let mut bus = MockBus::default();
+        I2cRegisterMap::<_>::new(&mut bus, ADDR)
+            .write_u8_pec(0x10, 0x5a)
+            .unwrap();
+        let crc = pec_update(0, &[ADDR << 1, 0x10, 0x5a]);
+        assert_eq!(bus.writes, [vec![0x10, 0x5a, crc]]);
+
+        let crc = pec_update(0, &[ADDR << 1, 0x10, (ADDR << 1) | 1, 0x5a]);
+        let mut bus = MockBus::with_rx(&[0x5a, crc]);
+        let mut dev = I2cRegisterMap::<_>::new(&mut bus, ADDR);
+        assert_eq!(dev.read_u8_pec(0x10), Ok(0x5a));
+
+        let mut bus = MockBus::with_rx(&[0x5a, crc ^ 0x01]);
+        let mut dev = I2cRegisterMap::<_>::new(&mut bus, ADDR);
+        assert_eq!(dev.read_u8_pec(0x10), Err(I2cError::Pec));
+    }
+
+    #[test]
+    fn bus_errors_are_mapped() {
+        let mut bus = MockBus::with_rx(&[3, 1, 2, 3]);
+        let mut dev = I2cRegisterMap::<_>::new(&mut bus, ADDR + 1);
+        assert_eq!(
+            dev.block_read(0x21, &mut [0; 3], Pec::Disabled),
+            Err(I2cError::AddressNack)
+        );
+        assert_eq!(
+            dev.block_write(0x21, &[1], Pec::Enabled),
+            Err(I2cError::AddressNack)
+        );
+    }
+}
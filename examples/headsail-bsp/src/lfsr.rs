@@ -0,0 +1,74 @@
+//! Fast non-cryptographic pseudo-random numbers
+//!
+//! Useful for test patterns, jitter and random backoff. Headsail has no TRNG,
+//! so seeding is left to the caller.
+
+/// Galois LFSR taps for x^32 + x^7 + x^5 + x^3 + x^2 + x + 1, a maximal-length
+/// polynomial
+const TAPS: u32 = 0x8000_0057;
+
+/// 32-bit Galois linear feedback shift register
+///
+/// Not suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct Lfsr32 {
+    state: u32,
+}
+
+impl Lfsr32 {
+    /// Create an LFSR from `seed`
+    ///
+    /// Zero is the only state an LFSR never leaves, so a zero seed is replaced
+    /// with a fixed non-zero value.
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0xace1_ace1 } else { seed },
+        }
+    }
+
+    #[inline]
+    fn step(&mut self) -> u32 {
+        let lsb = self.state & 1;
+        self.state >>= 1;
+        if lsb != 0 {
+            self.state ^= TAPS;
+        }
+        lsb
+    }
+
+    /// Clock out 32 bits
+    pub fn next_u32(&mut self) -> u32 {
+        let mut word = 0;
+        for _ in 0..32 {
+            word = (word << 1) | self.step();
+        }
+        word
+    }
+
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for Lfsr32 {
+    fn next_u32(&mut self) -> u32 {
+        Lfsr32::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Lfsr32::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Lfsr32::fill_bytes(self, dest);
+        Ok(())
+    }
+}
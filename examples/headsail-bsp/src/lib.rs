@@ -1,5 +1,9 @@
 //! A light-weight memory map based board support package for Headsail.
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+
+// For build scripts, which run on the host
+#[cfg(feature = "memory-check")]
+extern crate std;
 
 // Pick an optional PAC based on the target CPU. Some drivers may depend on it.
 #[cfg(feature = "hpc-pac")]
@@ -51,12 +55,82 @@ mod ufmt_panic;
 pub use ufmt;
 
 pub mod apb_uart;
+#[cfg(feature = "crc")]
+pub mod crc;
+#[cfg(feature = "delay")]
+pub mod delay;
+#[cfg(feature = "dla")]
+pub mod dla;
+#[cfg(feature = "dma")]
+pub mod dma;
+#[cfg(feature = "event")]
+pub mod event;
+#[cfg(feature = "fixedpoint")]
+pub mod fixedpoint;
+#[cfg(any(feature = "sd", feature = "flash", feature = "spim"))]
+mod flags;
+#[cfg(feature = "flash")]
+pub mod flash;
+#[cfg(feature = "fmt")]
+pub mod fmt;
+#[cfg(feature = "hil")]
+pub mod hil;
+#[cfg(feature = "i2c")]
+pub mod i2c;
+#[cfg(feature = "lfsr")]
+pub mod lfsr;
+#[cfg(feature = "memory-check")]
+pub mod memory_check;
 pub mod mmap;
 mod mmio;
+pub mod poll;
+pub mod prelude;
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(feature = "sd")]
+pub mod sd;
 pub mod sdram;
+#[cfg(feature = "slip")]
+pub mod slip;
+#[cfg(feature = "nv-config")]
+pub mod storage;
+#[cfg(feature = "strict-mmio")]
+pub mod strict;
+#[cfg(feature = "sync")]
+pub mod sync;
 pub mod tb;
+#[cfg(feature = "test-util")]
+pub mod testutil;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod version;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
+#[cfg(feature = "work")]
+pub mod work;
 
 pub use mmio::*;
 pub use riscv;
 #[cfg(feature = "rt")]
 pub use riscv_rt as rt;
+pub use version::version;
+
+/// Supertrait of the type-state and other traits that only the BSP
+/// implements, so that new implementations are not breaking changes
+#[cfg(any(feature = "sysctrl", feature = "i2c"))]
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Record a [trace::Event] if the `trace` feature is enabled, e.g.,
+/// `trace_event!(SpimTxStart { len })`
+// Unused when no driver that records events is enabled
+#[allow(unused_macros)]
+macro_rules! trace_event {
+    ($($event:tt)*) => {
+        #[cfg(feature = "trace")]
+        $crate::trace::push($crate::trace::Event::$($event)*);
+    };
+}
+#[allow(unused_imports)]
+pub(crate) use trace_event;
@@ -0,0 +1,43 @@
+//! Checks of memory scripts, for build scripts
+//!
+//! Buffers declared with `dma_static!` need the sections of the `dma.x`
+//! linker fragment. The memory scripts shipped with the BSP provide them and
+//! are checked by the BSP's own build script. An application linking its own
+//! memory script checks it from its build script:
+//!
+//! ```toml
+//! [build-dependencies]
+//! headsail-bsp = { path = "../../headsail-bsp", features = ["memory-check"] }
+//! ```
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     headsail_bsp::memory_check::check_memory_script("memory.x");
+//! }
+//! ```
+use std::{fs, path::Path, println};
+
+/// Parts of a memory script required for the DMA sections
+pub const REQUIRED: [&str; 2] = ["REGION_ALIAS(\"REGION_DMA\"", "INCLUDE dma.x"];
+
+/// Make sure the memory script at `path` provides the DMA sections, and
+/// rerun the build script when it changes
+///
+/// # Panics
+///
+/// If the script cannot be read or lacks one of [REQUIRED]
+pub fn check_memory_script(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    println!("cargo:rerun-if-changed={}", path.display());
+    let s = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("cannot read memory script {}: {e}", path.display()));
+    for required in REQUIRED {
+        if !s.contains(required) {
+            panic!(
+                "{} is missing `{required}`, required for the DMA sections in dma.x",
+                path.display()
+            );
+        }
+    }
+}
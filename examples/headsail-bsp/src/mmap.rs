@@ -146,3 +146,9 @@ pub const TIMER3_ADDR: usize = 0x5_0030;
 
 // Base addres for SDRAM configuration registers
 pub const SDRAM_CONFIG_ADDR: usize = 0xFFD0_0000;
+
+// DLA configuration registers and data memory banks
+pub const DLA_ADDR: usize = 0xFF70_0000 | EXT_ACCESS_BIT;
+pub const DLA_MEM_ADDR: usize = 0x7000_0000 | EXT_ACCESS_BIT;
+pub const DLA_BANK_SIZE: usize = 0x8000;
+pub const DLA_BANK_COUNT: usize = 16;
@@ -61,3 +61,41 @@ pub fn toggle_u32(addr: usize, toggle_bits: u32) {
     r ^= toggle_bits;
     write_u32(addr, r);
 }
+
+/// Follow-up of [reg_write] and [reg_modify]
+///
+/// With `strict-mmio`, reads the register back and fences, so that the write
+/// has reached the peripheral before the driver carries on.
+// Unused without the uDMA drivers
+#[allow(dead_code)]
+#[inline(always)]
+pub(crate) fn after_write(_readback: impl FnOnce() -> u32) {
+    #[cfg(feature = "strict-mmio")]
+    {
+        _readback();
+        riscv::asm::fence();
+    }
+}
+
+/// `reg.write(f)` for the uDMA drivers, sa. [after_write]
+#[allow(unused_macros)]
+macro_rules! reg_write {
+    ($reg:expr, $f:expr) => {{
+        let reg = $reg;
+        reg.write($f);
+        crate::mmio::after_write(|| reg.read().bits());
+    }};
+}
+
+/// `reg.modify(f)` for the uDMA drivers, sa. [after_write]
+#[allow(unused_macros)]
+macro_rules! reg_modify {
+    ($reg:expr, $f:expr) => {{
+        let reg = $reg;
+        reg.modify($f);
+        crate::mmio::after_write(|| reg.read().bits());
+    }};
+}
+
+#[allow(unused_imports)]
+pub(crate) use {reg_modify, reg_write};
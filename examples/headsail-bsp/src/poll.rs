@@ -0,0 +1,154 @@
+//! Busy-wait polling with optional timeouts
+//!
+//! Prefer the macros over open-coded `while` loops. They name the expected
+//! state of the register rather than the state being waited out, which is
+//! where polarity mistakes tend to creep in:
+//!
+//! ```ignore
+//! // Wait for the transmitter to go idle
+//! poll_bit_clear!(udma.uart_status(), tx_busy);
+//! // Same, but give up after 30_000 cycles
+//! poll_bit_clear!(udma.uart_status(), tx_busy, 30_000)?;
+//! // Wait for the channel to finish
+//! poll_eq!(udma.spim_tx_saddr().read().bits(), 0);
+//! ```
+//!
+//! Without a timeout, the macros return the number of `mcycle` cycles spent
+//! waiting. With a timeout in `mcycle` cycles, they return
+//! `Result<u64, PollTimeout>`.
+use riscv::register::mcycle;
+
+/// A poll gave up before its condition was met
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PollTimeout {
+    /// `mcycle` cycles spent waiting
+    pub waited_cycles: u64,
+}
+
+/// Spin until `cond` returns true, returning the number of cycles waited
+#[inline]
+pub fn wait<F>(mut cond: F) -> u64
+where
+    F: FnMut() -> bool,
+{
+    let start = mcycle::read64();
+    while !cond() {}
+    mcycle::read64().wrapping_sub(start)
+}
+
+/// Spin until `cond` returns true or `timeout_cycles` have passed
+#[inline]
+pub fn wait_timeout<F>(cond: F, timeout_cycles: u64) -> Result<u64, PollTimeout>
+where
+    F: FnMut() -> bool,
+{
+    wait_with(cond, Some(timeout_cycles), || {})
+}
+
+/// Like [wait_timeout], with `on_idle` called between polls
+///
+/// `on_idle` can feed a watchdog or do other short housekeeping. Time spent in
+/// it counts towards the timeout.
+pub fn wait_with<F, I>(
+    mut cond: F,
+    timeout_cycles: Option<u64>,
+    mut on_idle: I,
+) -> Result<u64, PollTimeout>
+where
+    F: FnMut() -> bool,
+    I: FnMut(),
+{
+    let start = mcycle::read64();
+    loop {
+        let waited_cycles = mcycle::read64().wrapping_sub(start);
+        if cond() {
+            return Ok(waited_cycles);
+        }
+        if let Some(timeout) = timeout_cycles {
+            if waited_cycles >= timeout {
+                return Err(PollTimeout { waited_cycles });
+            }
+        }
+        on_idle();
+    }
+}
+
+/// Condition of the poll macros
+///
+/// With `strict-mmio`, a met condition is evaluated once more. If the second
+/// read disagrees, it is recorded in [strict](crate::strict) and polling
+/// continues.
+#[doc(hidden)]
+#[inline(always)]
+pub fn checked<F>(_name: &'static str, mut cond: F) -> impl FnMut() -> bool
+where
+    F: FnMut() -> bool,
+{
+    move || {
+        if !cond() {
+            return false;
+        }
+        #[cfg(feature = "strict-mmio")]
+        if !cond() {
+            crate::strict::record(_name);
+            return false;
+        }
+        true
+    }
+}
+
+/// Wait for a single-bit register field to read as 1
+///
+/// `poll_bit_set!(reg, field)` or `poll_bit_set!(reg, field, timeout_cycles)`,
+/// sa. [poll](crate::poll).
+#[macro_export]
+macro_rules! poll_bit_set {
+    ($reg:expr, $field:ident) => {
+        $crate::poll::wait($crate::poll::checked(stringify!($reg), || {
+            $reg.read().$field().bit_is_set()
+        }))
+    };
+    ($reg:expr, $field:ident, $timeout_cycles:expr) => {
+        $crate::poll::wait_timeout(
+            $crate::poll::checked(stringify!($reg), || $reg.read().$field().bit_is_set()),
+            $timeout_cycles,
+        )
+    };
+}
+
+/// Wait for a single-bit register field to read as 0
+///
+/// `poll_bit_clear!(reg, field)` or
+/// `poll_bit_clear!(reg, field, timeout_cycles)`, sa. [poll](crate::poll).
+#[macro_export]
+macro_rules! poll_bit_clear {
+    ($reg:expr, $field:ident) => {
+        $crate::poll::wait($crate::poll::checked(stringify!($reg), || {
+            $reg.read().$field().bit_is_clear()
+        }))
+    };
+    ($reg:expr, $field:ident, $timeout_cycles:expr) => {
+        $crate::poll::wait_timeout(
+            $crate::poll::checked(stringify!($reg), || $reg.read().$field().bit_is_clear()),
+            $timeout_cycles,
+        )
+    };
+}
+
+/// Wait for `expr` to evaluate to `value`
+///
+/// `expr` is evaluated on every poll. `poll_eq!(expr, value)` or
+/// `poll_eq!(expr, value, timeout_cycles)`, sa. [poll](crate::poll).
+#[macro_export]
+macro_rules! poll_eq {
+    ($expr:expr, $value:expr) => {
+        $crate::poll::wait($crate::poll::checked(stringify!($expr), || $expr == $value))
+    };
+    ($expr:expr, $value:expr, $timeout_cycles:expr) => {
+        $crate::poll::wait_timeout(
+            $crate::poll::checked(stringify!($expr), || $expr == $value),
+            $timeout_cycles,
+        )
+    };
+}
@@ -0,0 +1,41 @@
+//! Driver, error and trait types most applications need
+//!
+//! ```ignore
+//! use headsail_bsp::prelude::*;
+//! ```
+//!
+//! Only items of enabled features are included. Raw register access stays
+//! available through [pac](crate::pac) and the `enqueue_*` methods of the
+//! uDMA drivers, but is not part of the prelude.
+pub use crate::poll::PollTimeout;
+pub use ufmt::{uDisplay, uWrite, uwrite, uwriteln};
+
+#[cfg(feature = "event")]
+pub use crate::event::{select2, Flag, Which};
+
+#[cfg(feature = "i2c")]
+pub use crate::i2c::{BitBangI2c, I2cError, I2cRegisterMap, Pec};
+#[cfg(feature = "nv-config")]
+pub use crate::storage::{NvConfig, NvConfigError};
+#[cfg(feature = "spi-adc")]
+pub use crate::sysctrl::spi_adc::{AdcError, SpiAdc};
+#[cfg(feature = "spi-can")]
+pub use crate::sysctrl::spi_can::{CanError, Mcp2515};
+#[cfg(feature = "spi-eeprom")]
+pub use crate::sysctrl::spi_eeprom::{EepromError, SpiEeprom};
+#[cfg(feature = "spi-flash")]
+pub use crate::sysctrl::spi_flash::{FlashError, ReliableFlash, SpiFlash};
+#[cfg(feature = "spim")]
+pub use crate::sysctrl::udma::spim::{
+    ChipSelect, CompletionMode, DisablePolicy, PowerPolicy, SpimConfig, SpimError, SpimTransaction,
+    UdmaSpim,
+};
+#[cfg(feature = "udma-uart")]
+pub use crate::sysctrl::udma::uart::{UartError, UdmaUart};
+#[cfg(all(feature = "sysctrl", feature = "pac"))]
+pub use crate::sysctrl::udma::{Disabled, Enabled, Udma};
+#[cfg(feature = "sysctrl")]
+pub use crate::sysctrl::{
+    gpio::{Gpio, Input, Output},
+    soc_ctrl::Pads,
+};
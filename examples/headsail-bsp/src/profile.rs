@@ -0,0 +1,161 @@
+//! Cycle and instruction counts per task, for cooperative multitasking
+//!
+//! Bracket each run of a task with [TaskProfiler::enter] and
+//! [TaskProfiler::exit]. The `mcycle` and `minstret` deltas are summed per
+//! task ID, and [TaskProfiler::report] prints them as a table:
+//!
+//! ```ignore
+//! loop {
+//!     TaskProfiler::enter(TASK_FILTER);
+//!     filter.step();
+//!     TaskProfiler::exit(TASK_FILTER);
+//!
+//!     TaskProfiler::enter(TASK_LINK);
+//!     link.poll();
+//!     TaskProfiler::exit(TASK_LINK);
+//! }
+//! // ...
+//! TaskProfiler::report(&mut uart)?;
+//! ```
+//!
+//! The counters are those of the hart the code runs on, and keep counting
+//! in interrupt handlers, so time spent in handlers is charged to the task
+//! they interrupted. On the VP, each instruction counts as one cycle.
+use core::ptr::addr_of_mut;
+
+use riscv::register::{mcycle, minstret};
+use ufmt::{uWrite, uwrite, uwriteln};
+
+/// Number of task IDs, from 0
+pub const MAX_TASKS: usize = 16;
+
+/// Counts summed over the runs of one task
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerfAccumulator {
+    pub cycles: u64,
+    pub instructions: u64,
+    /// Number of [TaskProfiler::exit] calls counted
+    pub runs: u32,
+}
+
+impl PerfAccumulator {
+    const ZERO: Self = Self {
+        cycles: 0,
+        instructions: 0,
+        runs: 0,
+    };
+
+    /// Instructions per cycle in thousandths, or `None` if no cycles were
+    /// counted
+    pub fn ipc_milli(&self) -> Option<u64> {
+        (self.cycles != 0).then(|| self.instructions * 1000 / self.cycles)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Snapshot {
+    cycles: u64,
+    instructions: u64,
+}
+
+static mut ACCUMULATORS: [PerfAccumulator; MAX_TASKS] = [PerfAccumulator::ZERO; MAX_TASKS];
+/// Counters at the last [TaskProfiler::enter], while a task is running
+static mut STARTED: [Option<Snapshot>; MAX_TASKS] = [None; MAX_TASKS];
+
+/// Sa. [module documentation](self)
+pub struct TaskProfiler;
+
+impl TaskProfiler {
+    /// Mark the start of a run of `task_id`
+    ///
+    /// IDs from [MAX_TASKS] up are ignored. Entering a task that is already
+    /// running restarts its run.
+    #[inline]
+    pub fn enter(task_id: u8) {
+        let snapshot = Snapshot {
+            cycles: mcycle::read64(),
+            instructions: minstret::read64(),
+        };
+        riscv::interrupt::free(|| unsafe {
+            if let Some(started) = (*addr_of_mut!(STARTED)).get_mut(task_id as usize) {
+                *started = Some(snapshot);
+            }
+        });
+    }
+
+    /// Mark the end of a run of `task_id` and add it to the task's counts
+    ///
+    /// Ignored if the task is not running.
+    #[inline]
+    pub fn exit(task_id: u8) {
+        let (cycles, instructions) = (mcycle::read64(), minstret::read64());
+        riscv::interrupt::free(|| unsafe {
+            let Some(started) = (*addr_of_mut!(STARTED)).get_mut(task_id as usize) else {
+                return;
+            };
+            let Some(start) = started.take() else {
+                return;
+            };
+            let acc = &mut (*addr_of_mut!(ACCUMULATORS))[task_id as usize];
+            acc.cycles += cycles.wrapping_sub(start.cycles);
+            acc.instructions += instructions.wrapping_sub(start.instructions);
+            acc.runs = acc.runs.saturating_add(1);
+        });
+    }
+
+    /// Counts of `task_id` so far, zero for IDs from [MAX_TASKS] up
+    pub fn get(task_id: u8) -> PerfAccumulator {
+        riscv::interrupt::free(|| unsafe {
+            (*addr_of_mut!(ACCUMULATORS))
+                .get(task_id as usize)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Clear the counts and forget running tasks
+    pub fn reset() {
+        riscv::interrupt::free(|| unsafe {
+            *addr_of_mut!(ACCUMULATORS) = [PerfAccumulator::ZERO; MAX_TASKS];
+            *addr_of_mut!(STARTED) = [None; MAX_TASKS];
+        });
+    }
+
+    /// Write a line per task that has run, with its cycle and instruction
+    /// counts and IPC, to `w`, e.g., a [UdmaUart](crate::sysctrl::udma::UdmaUart)
+    pub fn report<W>(w: &mut W) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwriteln!(w, "task runs cycles instructions ipc")?;
+        for task_id in 0..MAX_TASKS as u8 {
+            let acc = Self::get(task_id);
+            if acc.runs == 0 {
+                continue;
+            }
+            uwrite!(
+                w,
+                "{} {} {} {} ",
+                task_id,
+                acc.runs,
+                acc.cycles,
+                acc.instructions
+            )?;
+            match acc.ipc_milli() {
+                Some(ipc) => {
+                    let frac = ipc % 1000;
+                    uwriteln!(
+                        w,
+                        "{}.{}{}{}",
+                        ipc / 1000,
+                        frac / 100,
+                        frac / 10 % 10,
+                        frac % 10
+                    )?
+                }
+                None => uwriteln!(w, "-")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,610 @@
+//! SD card SPI-mode response and register types
+//!
+//! Bit positions follow the SD Physical Layer Simplified Specification,
+//! sections 7.3.2 (Responses), 5.2 (CID register) and 5.3 (CSD register).
+use crate::{crc::crc7, flags::impl_flags_fmt};
+
+/// Byte to clock out while reading from the card
+///
+/// The card treats anything else on MOSI as the start of a new command. With
+/// the uDMA SPIM, set this as `SpimConfig::tx_idle_byte`.
+pub const SPI_IDLE_BYTE: u8 = 0xff;
+
+/// R1 response, returned for every command in SPI mode
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct R1(pub u8);
+
+impl R1 {
+    /// The card is in idle state and running the initialization process
+    pub const fn idle(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+    pub const fn erase_reset(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+    pub const fn illegal_command(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+    pub const fn crc_error(&self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+    pub const fn erase_sequence_error(&self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+    pub const fn address_error(&self) -> bool {
+        self.0 & (1 << 5) != 0
+    }
+    pub const fn parameter_error(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+
+    /// Bit 7 is always zero in a valid response. The card keeps MISO high,
+    /// i.e., returns `0xff`, while it has no response to give.
+    pub const fn is_valid(&self) -> bool {
+        self.0 & (1 << 7) == 0
+    }
+
+    /// Returns true if any of the error bits are set
+    pub const fn is_error(&self) -> bool {
+        self.0 & 0b0111_1110 != 0
+    }
+}
+
+const R1_FLAGS: &[(u32, &str)] = &[
+    (0, "IDLE"),
+    (1, "ERASE_RESET"),
+    (2, "ILLEGAL_COMMAND"),
+    (3, "CRC_ERROR"),
+    (4, "ERASE_SEQUENCE_ERROR"),
+    (5, "ADDRESS_ERROR"),
+    (6, "PARAMETER_ERROR"),
+];
+impl_flags_fmt!(R1, R1_FLAGS);
+
+/// R2 response to SEND_STATUS (CMD13)
+///
+/// The R1 byte is in the high byte, followed by the second status byte.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct R2(pub u16);
+
+impl R2 {
+    pub const fn from_bytes(bytes: [u8; 2]) -> Self {
+        Self(u16::from_be_bytes(bytes))
+    }
+    pub const fn r1(&self) -> R1 {
+        R1((self.0 >> 8) as u8)
+    }
+    pub const fn card_locked(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+    /// Write protect erase skip or lock/unlock command failed
+    pub const fn wp_erase_skip(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+    pub const fn error(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+    pub const fn cc_error(&self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+    pub const fn card_ecc_failed(&self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+    pub const fn wp_violation(&self) -> bool {
+        self.0 & (1 << 5) != 0
+    }
+    pub const fn erase_param(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+    pub const fn out_of_range(&self) -> bool {
+        self.0 & (1 << 7) != 0
+    }
+}
+
+const R2_FLAGS: &[(u32, &str)] = &[
+    (0, "CARD_LOCKED"),
+    (1, "WP_ERASE_SKIP"),
+    (2, "ERROR"),
+    (3, "CC_ERROR"),
+    (4, "CARD_ECC_FAILED"),
+    (5, "WP_VIOLATION"),
+    (6, "ERASE_PARAM"),
+    (7, "OUT_OF_RANGE"),
+    (8, "IDLE"),
+    (9, "ERASE_RESET"),
+    (10, "ILLEGAL_COMMAND"),
+    (11, "CRC_ERROR"),
+    (12, "ERASE_SEQUENCE_ERROR"),
+    (13, "ADDRESS_ERROR"),
+    (14, "PARAMETER_ERROR"),
+];
+impl_flags_fmt!(R2, R2_FLAGS);
+
+/// Operation conditions register, returned in R3
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Ocr(pub u32);
+
+impl Ocr {
+    /// The card has not finished the power up routine
+    pub const fn busy(&self) -> bool {
+        self.0 & (1 << 31) == 0
+    }
+    /// Card capacity status, i.e., SDHC or SDXC. Only valid when not busy.
+    pub const fn ccs(&self) -> bool {
+        self.0 & (1 << 30) != 0
+    }
+    /// Supported VDD voltage window, bits 15..=23 of OCR for 2.7 V to 3.6 V in
+    /// 100 mV steps
+    pub const fn voltage_window(&self) -> u16 {
+        ((self.0 >> 15) & 0x1ff) as u16
+    }
+    /// Returns true if the card supports 3.2–3.3 V or 3.3–3.4 V
+    pub const fn supports_3v3(&self) -> bool {
+        self.0 & ((1 << 20) | (1 << 21)) != 0
+    }
+}
+
+const OCR_FLAGS: &[(u32, &str)] = &[
+    (15, "2V7_2V8"),
+    (16, "2V8_2V9"),
+    (17, "2V9_3V0"),
+    (18, "3V0_3V1"),
+    (19, "3V1_3V2"),
+    (20, "3V2_3V3"),
+    (21, "3V3_3V4"),
+    (22, "3V4_3V5"),
+    (23, "3V5_3V6"),
+    (30, "CCS"),
+    (31, "POWER_UP"),
+];
+impl_flags_fmt!(Ocr, OCR_FLAGS);
+
+/// R3 response to READ_OCR (CMD58)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct R3 {
+    pub r1: R1,
+    pub ocr: Ocr,
+}
+
+impl R3 {
+    pub const fn from_bytes(bytes: [u8; 5]) -> Self {
+        Self {
+            r1: R1(bytes[0]),
+            ocr: Ocr(u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]])),
+        }
+    }
+}
+
+/// R7 response to SEND_IF_COND (CMD8)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct R7 {
+    pub r1: R1,
+    pub data: u32,
+}
+
+impl R7 {
+    /// Voltage accepted value for 2.7–3.6 V
+    pub const VOLTAGE_2V7_3V6: u8 = 0b0001;
+
+    pub const fn from_bytes(bytes: [u8; 5]) -> Self {
+        Self {
+            r1: R1(bytes[0]),
+            data: u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]),
+        }
+    }
+    pub const fn voltage_accepted(&self) -> u8 {
+        ((self.data >> 8) & 0xf) as u8
+    }
+    /// Echo of the check pattern sent with CMD8
+    pub const fn check_pattern(&self) -> u8 {
+        self.data as u8
+    }
+
+    /// Returns true if the card echoed `pattern` and accepts 2.7–3.6 V
+    pub const fn is_valid(&self, pattern: u8) -> bool {
+        self.r1.is_valid()
+            && !self.r1.illegal_command()
+            && self.voltage_accepted() == Self::VOLTAGE_2V7_3V6
+            && self.check_pattern() == pattern
+    }
+}
+
+/// Bits `msb..=lsb` of a 128-bit register, numbered as in the specification
+/// with bit 127 the MSB of the first byte
+const fn reg_bits(reg: &[u8; 16], msb: u32, lsb: u32) -> u32 {
+    let mut v = 0;
+    let mut i = msb;
+    loop {
+        let byte = reg[15 - (i / 8) as usize];
+        v = (v << 1) | ((byte >> (i % 8)) & 1) as u32;
+        if i == lsb {
+            return v;
+        }
+        i -= 1;
+    }
+}
+
+/// Whether the last byte of `reg` holds the CRC7 of the others and the stop
+/// bit
+const fn reg_crc_ok(reg: &[u8; 16]) -> bool {
+    let (data, _) = reg.split_at(15);
+    reg[15] == (crc7(data) << 1) | 1
+}
+
+/// Card identification register, read with SEND_CID (CMD10)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cid(pub [u8; 16]);
+
+impl Cid {
+    /// Manufacturer ID, assigned by the SD Card Association
+    pub const fn manufacturer_id(&self) -> u8 {
+        self.0[0]
+    }
+    /// OEM/application ID, two ASCII characters
+    pub const fn oem_id(&self) -> [u8; 2] {
+        [self.0[1], self.0[2]]
+    }
+    /// Product name, five ASCII characters
+    pub const fn product_name(&self) -> [u8; 5] {
+        [self.0[3], self.0[4], self.0[5], self.0[6], self.0[7]]
+    }
+    /// Product revision `n.m` as `(n, m)`
+    pub const fn product_revision(&self) -> (u8, u8) {
+        (self.0[8] >> 4, self.0[8] & 0xf)
+    }
+    pub const fn serial_number(&self) -> u32 {
+        u32::from_be_bytes([self.0[9], self.0[10], self.0[11], self.0[12]])
+    }
+    /// Manufacturing date as `(year, month)`, e.g., `(2023, 7)`
+    pub const fn manufacturing_date(&self) -> (u16, u8) {
+        let mdt = reg_bits(&self.0, 19, 8);
+        (2000 + (mdt >> 4) as u16, (mdt & 0xf) as u8)
+    }
+    pub const fn crc_ok(&self) -> bool {
+        reg_crc_ok(&self.0)
+    }
+}
+
+/// Layout of a [Csd], from its `CSD_STRUCTURE` field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsdVersion {
+    /// Standard capacity
+    V1,
+    /// High and extended capacity
+    V2,
+    /// Ultra capacity
+    V3,
+}
+
+/// Card-specific data register, read with SEND_CSD (CMD9)
+///
+/// Fields at the same position in all layouts are decoded regardless of
+/// [Csd::version].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Csd(pub [u8; 16]);
+
+impl Csd {
+    /// Raw bits `msb..=lsb` for fields not decoded here
+    ///
+    /// # Panics
+    ///
+    /// If `msb` is over 127, `lsb` is over `msb`, or the field is wider than
+    /// 32 bits
+    pub const fn bits(&self, msb: u32, lsb: u32) -> u32 {
+        assert!(
+            msb <= 127 && lsb <= msb && msb - lsb < 32,
+            "CSD field must be msb..=lsb within bits 127..=0 and at most 32 bits wide"
+        );
+        reg_bits(&self.0, msb, lsb)
+    }
+
+    /// `None` for the reserved `CSD_STRUCTURE` value
+    pub const fn version(&self) -> Option<CsdVersion> {
+        match self.bits(127, 126) {
+            0 => Some(CsdVersion::V1),
+            1 => Some(CsdVersion::V2),
+            2 => Some(CsdVersion::V3),
+            _ => None,
+        }
+    }
+    /// Data read access time 1, raw
+    pub const fn taac(&self) -> u8 {
+        self.bits(119, 112) as u8
+    }
+    /// Data read access time 2, in units of 100 clock cycles
+    pub const fn nsac(&self) -> u8 {
+        self.bits(111, 104) as u8
+    }
+    /// Raw `TRAN_SPEED`, sa. [Csd::max_transfer_rate]
+    pub const fn tran_speed(&self) -> u8 {
+        self.bits(103, 96) as u8
+    }
+    /// Maximum data transfer rate per line in bit/s, or `None` for a
+    /// reserved encoding
+    pub const fn max_transfer_rate(&self) -> Option<u32> {
+        /// Time values of `TRAN_SPEED` bits 6..=3, times ten
+        const MULT: [u32; 16] = [
+            0, 10, 12, 13, 15, 20, 25, 30, 35, 40, 45, 50, 55, 60, 70, 80,
+        ];
+        let speed = self.tran_speed();
+        let unit = speed & 0b111;
+        let mult = MULT[((speed >> 3) & 0xf) as usize];
+        if unit > 3 || mult == 0 {
+            return None;
+        }
+        // Units of 100 kbit/s to 100 Mbit/s, divided by ten for `MULT`
+        Some(10_000 * 10u32.pow(unit as u32) * mult)
+    }
+    /// Card command classes supported, one bit per class
+    pub const fn ccc(&self) -> u16 {
+        self.bits(95, 84) as u16
+    }
+    /// Maximum read block length as a power of two
+    pub const fn read_bl_len(&self) -> u8 {
+        self.bits(83, 80) as u8
+    }
+    pub const fn read_bl_partial(&self) -> bool {
+        self.bits(79, 79) != 0
+    }
+    pub const fn write_blk_misalign(&self) -> bool {
+        self.bits(78, 78) != 0
+    }
+    pub const fn read_blk_misalign(&self) -> bool {
+        self.bits(77, 77) != 0
+    }
+    /// The card implements the driver stage register
+    pub const fn dsr_imp(&self) -> bool {
+        self.bits(76, 76) != 0
+    }
+    /// Device size in the units of [Csd::version]
+    pub const fn c_size(&self) -> Option<u32> {
+        match self.version() {
+            Some(CsdVersion::V1) => Some(self.bits(73, 62)),
+            Some(CsdVersion::V2) => Some(self.bits(69, 48)),
+            Some(CsdVersion::V3) => Some(self.bits(75, 48)),
+            None => None,
+        }
+    }
+    /// Device size multiplier, only in [CsdVersion::V1]
+    pub const fn c_size_mult(&self) -> Option<u8> {
+        match self.version() {
+            Some(CsdVersion::V1) => Some(self.bits(49, 47) as u8),
+            _ => None,
+        }
+    }
+    /// Capacity of the user data area
+    pub const fn capacity_bytes(&self) -> Option<u64> {
+        let Some(c_size) = self.c_size() else {
+            return None;
+        };
+        let c_size = c_size as u64 + 1;
+        match self.c_size_mult() {
+            // (C_SIZE + 1) * 2^(C_SIZE_MULT + 2) blocks of 2^READ_BL_LEN bytes
+            Some(mult) => Some(c_size << (mult + 2 + self.read_bl_len())),
+            // (C_SIZE + 1) * 512 KiB
+            None => Some(c_size << 19),
+        }
+    }
+    /// Erase of single 512-byte blocks is allowed
+    pub const fn erase_blk_en(&self) -> bool {
+        self.bits(46, 46) != 0
+    }
+    /// Erase sector size in write blocks, minus one
+    pub const fn sector_size(&self) -> u8 {
+        self.bits(45, 39) as u8
+    }
+    /// Write protect group size in erase sectors, minus one
+    pub const fn wp_grp_size(&self) -> u8 {
+        self.bits(38, 32) as u8
+    }
+    pub const fn wp_grp_enable(&self) -> bool {
+        self.bits(31, 31) != 0
+    }
+    /// Write time as a power of two multiple of the read access time
+    pub const fn r2w_factor(&self) -> u8 {
+        self.bits(28, 26) as u8
+    }
+    /// Maximum write block length as a power of two
+    pub const fn write_bl_len(&self) -> u8 {
+        self.bits(25, 22) as u8
+    }
+    pub const fn write_bl_partial(&self) -> bool {
+        self.bits(21, 21) != 0
+    }
+    pub const fn file_format_grp(&self) -> bool {
+        self.bits(15, 15) != 0
+    }
+    /// The contents have been copied, e.g., the card is not the original
+    pub const fn copy(&self) -> bool {
+        self.bits(14, 14) != 0
+    }
+    pub const fn perm_write_protect(&self) -> bool {
+        self.bits(13, 13) != 0
+    }
+    pub const fn tmp_write_protect(&self) -> bool {
+        self.bits(12, 12) != 0
+    }
+    pub const fn file_format(&self) -> u8 {
+        self.bits(11, 10) as u8
+    }
+    pub const fn crc_ok(&self) -> bool {
+        reg_crc_ok(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn r1() {
+        // GO_IDLE_STATE
+        let r = R1(0x01);
+        assert!(r.is_valid() && r.idle() && !r.is_error());
+        // SEND_IF_COND to a version 1 card
+        let r = R1(0x05);
+        assert!(r.idle() && r.illegal_command() && r.is_error());
+        // No response yet
+        assert!(!R1(0xff).is_valid());
+        assert_eq!(format!("{:?}", R1(0x05)), "R1(IDLE | ILLEGAL_COMMAND)");
+        assert_eq!(format!("{:?}", R1(0x00)), "R1(-)");
+    }
+
+    #[test]
+    fn r2() {
+        let r = R2::from_bytes([0x00, 0x00]);
+        assert!(r.r1().is_valid() && !r.r1().is_error());
+        let r = R2::from_bytes([0x20, 0x80]);
+        assert!(r.r1().address_error() && r.out_of_range());
+        assert!(!r.card_locked() && !r.error());
+        assert_eq!(format!("{:?}", r), "R2(OUT_OF_RANGE | ADDRESS_ERROR)");
+    }
+
+    #[test]
+    fn r3_ocr() {
+        // READ_OCR while still initializing
+        let r = R3::from_bytes([0x01, 0x00, 0xff, 0x80, 0x00]);
+        assert!(r.r1.idle() && r.ocr.busy());
+        assert_eq!(r.ocr.voltage_window(), 0x1ff);
+        assert!(r.ocr.supports_3v3());
+        // High capacity card after initialization
+        let r = R3::from_bytes([0x00, 0xc0, 0xff, 0x80, 0x00]);
+        assert!(!r.ocr.busy() && r.ocr.ccs());
+        // Standard capacity card supporting 3.2–3.4 V only
+        let ocr = Ocr(0x8030_0000);
+        assert!(!ocr.busy() && !ocr.ccs() && ocr.supports_3v3());
+        assert_eq!(ocr.voltage_window(), 0b0_0110_0000);
+        assert_eq!(format!("{:?}", ocr), "Ocr(3V2_3V3 | 3V3_3V4 | POWER_UP)");
+    }
+
+    #[test]
+    fn r7() {
+        let r = R7::from_bytes([0x01, 0x00, 0x00, 0x01, 0xaa]);
+        assert_eq!(r.voltage_accepted(), R7::VOLTAGE_2V7_3V6);
+        assert_eq!(r.check_pattern(), 0xaa);
+        assert!(r.is_valid(0xaa));
+        assert!(!r.is_valid(0x55));
+        // Version 1 cards reject CMD8
+        assert!(!R7::from_bytes([0x05, 0xff, 0xff, 0xff, 0xff]).is_valid(0xaa));
+    }
+
+    /// `reg` with the CRC7 and stop bit in its last byte
+    fn with_crc(mut reg: [u8; 16]) -> [u8; 16] {
+        reg[15] = (crc7(&reg[..15]) << 1) | 1;
+        reg
+    }
+
+    #[test]
+    fn reg_bits_spec_numbering() {
+        let mut reg = [0; 16];
+        reg[0] = 0x80;
+        reg[1] = 0x5a;
+        reg[15] = 0x01;
+        assert_eq!(reg_bits(&reg, 127, 127), 1);
+        assert_eq!(reg_bits(&reg, 126, 120), 0);
+        assert_eq!(reg_bits(&reg, 0, 0), 1);
+        assert_eq!(reg_bits(&reg, 7, 0), 0x01);
+        // Across a byte boundary
+        assert_eq!(reg_bits(&reg, 123, 116), 0x05);
+        // Widest field
+        assert_eq!(reg_bits(&reg, 127, 96), 0x805a_0000);
+    }
+
+    #[test]
+    fn cid() {
+        let cid = Cid(with_crc([
+            0x03, b'S', b'D', b'S', b'U', b'0', b'8', b'G', 0x80, 0x12, 0x34, 0x56, 0x78, 0x01,
+            0x77, 0x00,
+        ]));
+        assert_eq!(cid.manufacturer_id(), 0x03);
+        assert_eq!(&cid.oem_id(), b"SD");
+        assert_eq!(&cid.product_name(), b"SU08G");
+        assert_eq!(cid.product_revision(), (8, 0));
+        assert_eq!(cid.serial_number(), 0x1234_5678);
+        assert_eq!(cid.manufacturing_date(), (2023, 7));
+        assert!(cid.crc_ok());
+
+        let mut bad = cid;
+        bad.0[9] ^= 1;
+        assert!(!bad.crc_ok());
+        // Stop bit missing
+        let mut bad = cid;
+        bad.0[15] &= !1;
+        assert!(!bad.crc_ok());
+    }
+
+    #[test]
+    fn csd_v1() {
+        // 2 GB standard capacity card
+        let csd = Csd(with_crc([
+            0x00, 0x26, 0x00, 0x32, 0x5f, 0x5a, 0x83, 0xbf, 0xff, 0xdb, 0xff, 0xff, 0x92, 0x80,
+            0x40, 0x00,
+        ]));
+        assert_eq!(csd.version(), Some(CsdVersion::V1));
+        assert!(csd.crc_ok());
+        assert_eq!(csd.taac(), 0x26);
+        assert_eq!(csd.max_transfer_rate(), Some(25_000_000));
+        assert_eq!(csd.ccc(), 0x5f5);
+        assert_eq!(csd.read_bl_len(), 10);
+        assert!(csd.read_bl_partial() && !csd.dsr_imp());
+        assert_eq!(csd.c_size(), Some(3839));
+        assert_eq!(csd.c_size_mult(), Some(7));
+        assert_eq!(csd.capacity_bytes(), Some(3840 * 512 * 1024));
+        assert!(csd.erase_blk_en());
+        assert_eq!(csd.sector_size(), 0x7f);
+        assert_eq!(csd.wp_grp_size(), 0x7f);
+        assert!(csd.wp_grp_enable());
+        assert_eq!(csd.r2w_factor(), 4);
+        assert_eq!(csd.write_bl_len(), 10);
+        assert!(csd.copy() && !csd.perm_write_protect() && !csd.tmp_write_protect());
+    }
+
+    #[test]
+    fn csd_v2() {
+        // 8 GB high capacity card
+        let csd = Csd(with_crc([
+            0x40, 0x0e, 0x00, 0x32, 0x5b, 0x59, 0x00, 0x00, 0x3b, 0x37, 0x7f, 0x80, 0x0a, 0x40,
+            0x00, 0x00,
+        ]));
+        assert_eq!(csd.version(), Some(CsdVersion::V2));
+        assert!(csd.crc_ok());
+        assert_eq!(csd.taac(), 0x0e);
+        assert_eq!(csd.max_transfer_rate(), Some(25_000_000));
+        assert_eq!(csd.ccc(), 0x5b5);
+        assert_eq!(csd.read_bl_len(), 9);
+        assert_eq!(csd.c_size(), Some(0x3b37));
+        assert_eq!(csd.c_size_mult(), None);
+        assert_eq!(csd.capacity_bytes(), Some((0x3b37 + 1) * 512 * 1024));
+        assert!(csd.erase_blk_en() && !csd.wp_grp_enable());
+        assert_eq!(csd.sector_size(), 0x7f);
+        assert_eq!(csd.r2w_factor(), 2);
+        assert_eq!(csd.write_bl_len(), 9);
+        assert!(!csd.copy() && !csd.file_format_grp());
+    }
+
+    #[test]
+    fn csd_reserved_version() {
+        let csd = Csd(with_crc([0xc0; 16]));
+        assert_eq!(csd.version(), None);
+        assert_eq!(csd.c_size(), None);
+        assert_eq!(csd.capacity_bytes(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "CSD field")]
+    fn csd_bits_past_msb() {
+        Csd([0; 16]).bits(128, 127);
+    }
+
+    #[test]
+    #[should_panic(expected = "CSD field")]
+    fn csd_bits_reversed() {
+        Csd([0; 16]).bits(10, 11);
+    }
+
+    #[test]
+    #[should_panic(expected = "CSD field")]
+    fn csd_bits_too_wide() {
+        Csd([0; 16]).bits(32, 0);
+    }
+}
@@ -0,0 +1,230 @@
+//! SLIP (RFC 1055) framing, independent of the link
+//!
+//! Frames are terminated with [END]. [END] and [ESC] bytes within a frame are
+//! replaced with two-byte escape sequences. [Encoder] also sends a leading
+//! [END], which flushes line noise on the receiving side.
+//!
+//! ```ignore
+//! let mut enc = Encoder::<32>::new();
+//! enc.encode(&[&header, payload], |bytes| link.write(bytes));
+//!
+//! let mut dec = Decoder::new();
+//! let len = loop {
+//!     if let Some(res) = dec.push(link.read_byte(), &mut buf) {
+//!         break res?;
+//!     }
+//! };
+//! ```
+
+pub const END: u8 = 0xc0;
+pub const ESC: u8 = 0xdb;
+pub const ESC_END: u8 = 0xdc;
+pub const ESC_ESC: u8 = 0xdd;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The frame does not fit the receive buffer. The rest of the frame was
+    /// discarded.
+    Overflow,
+    /// [ESC] was followed by something other than [ESC_END] or [ESC_ESC]
+    Framing,
+}
+
+/// Escapes frames into an `N`-byte staging buffer, handing it to the link
+/// whenever it fills up
+pub struct Encoder<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for Encoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Encoder<N> {
+    pub const fn new() -> Self {
+        // Room for an escape sequence
+        assert!(N >= 2);
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Staging buffer, e.g., to check that a link using DMA can reach it
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Send `parts` back to back as one frame, calling `write` with chunks of
+    /// at most `N` bytes
+    pub fn encode<F>(&mut self, parts: &[&[u8]], mut write: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        self.len = 0;
+        self.push(&[END]);
+        for &byte in parts.iter().flat_map(|part| part.iter()) {
+            if self.len + 2 > N {
+                write(&self.buf[..self.len]);
+                self.len = 0;
+            }
+            match byte {
+                END => self.push(&[ESC, ESC_END]),
+                ESC => self.push(&[ESC, ESC_ESC]),
+                _ => self.push(&[byte]),
+            }
+        }
+        if self.len == N {
+            write(&self.buf[..self.len]);
+            self.len = 0;
+        }
+        self.push(&[END]);
+        write(&self.buf[..self.len]);
+    }
+
+    #[inline]
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+/// Unescapes a byte stream into frames, one byte at a time
+#[derive(Default)]
+pub struct Decoder {
+    len: usize,
+    escaped: bool,
+    error: Option<DecodeError>,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            escaped: false,
+            error: None,
+        }
+    }
+
+    /// Add `byte` to the frame being decoded into `buf`
+    ///
+    /// Returns the frame length once a non-empty frame ends. Delimiters
+    /// without a frame in between are skipped. On error, the rest of the
+    /// frame is consumed before the error is returned, so the decoder is
+    /// again at a frame boundary. Pass the same `buf` until a frame ends.
+    pub fn push(&mut self, byte: u8, buf: &mut [u8]) -> Option<Result<usize, DecodeError>> {
+        let byte = match byte {
+            END if self.len == 0 && self.error.is_none() => {
+                // Idle line or a leading delimiter
+                self.escaped = false;
+                return None;
+            }
+            END => {
+                let res = self.error.take().map_or(Ok(self.len), Err);
+                *self = Self::new();
+                return Some(res);
+            }
+            ESC => {
+                self.escaped = true;
+                return None;
+            }
+            _ if self.escaped => {
+                self.escaped = false;
+                match byte {
+                    ESC_END => END,
+                    ESC_ESC => ESC,
+                    _ => {
+                        self.error.get_or_insert(DecodeError::Framing);
+                        byte
+                    }
+                }
+            }
+            _ => byte,
+        };
+
+        match buf.get_mut(self.len) {
+            Some(b) => {
+                *b = byte;
+                self.len += 1;
+            }
+            None => {
+                self.error.get_or_insert(DecodeError::Overflow);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<const N: usize>(parts: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        Encoder::<N>::new().encode(parts, |bytes| {
+            assert!(bytes.len() <= N);
+            out.extend_from_slice(bytes);
+        });
+        out
+    }
+
+    /// Frames decoded from `stream` into a `cap`-byte buffer
+    fn decode(stream: &[u8], cap: usize) -> Vec<Result<Vec<u8>, DecodeError>> {
+        let mut dec = Decoder::new();
+        let mut buf = vec![0; cap];
+        let mut frames = Vec::new();
+        for &byte in stream {
+            if let Some(res) = dec.push(byte, &mut buf) {
+                frames.push(res.map(|len| buf[..len].to_vec()));
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn escapes() {
+        let wire = encode::<32>(&[&[1, END, 2], &[ESC, 3]]);
+        assert_eq!(wire, [END, 1, ESC, ESC_END, 2, ESC, ESC_ESC, 3, END]);
+    }
+
+    #[test]
+    fn small_staging_buffer() {
+        let frame = [END, ESC, 0, END, END, ESC];
+        let wire = encode::<32>(&[&frame]);
+        assert_eq!(encode::<2>(&[&frame]), wire);
+        assert_eq!(encode::<3>(&[&frame]), wire);
+    }
+
+    #[test]
+    fn round_trip() {
+        let frame: Vec<u8> = (0..=255).collect();
+        let wire = encode::<16>(&[&frame[..100], &frame[100..]]);
+        assert_eq!(decode(&wire, 256), [Ok(frame)]);
+    }
+
+    #[test]
+    fn empty_frames_skipped() {
+        assert_eq!(decode(&[END, END, 7, END, END], 8), [Ok(vec![7])]);
+    }
+
+    #[test]
+    fn overflow_resyncs() {
+        let mut wire = encode::<32>(&[&[1, 2, 3, 4, 5]]);
+        wire.extend(encode::<32>(&[&[6, ESC]]));
+        assert_eq!(
+            decode(&wire, 4),
+            [Err(DecodeError::Overflow), Ok(vec![6, ESC])]
+        );
+    }
+
+    #[test]
+    fn framing_resyncs() {
+        let mut wire = vec![END, 1, ESC, 0x42, 2, END];
+        wire.extend(encode::<32>(&[&[3]]));
+        assert_eq!(decode(&wire, 8), [Err(DecodeError::Framing), Ok(vec![3])]);
+    }
+}
@@ -0,0 +1,4 @@
+//! Persistent data on external memories
+pub mod nv_config;
+
+pub use nv_config::{NvConfig, NvConfigError};
@@ -0,0 +1,259 @@
+//! Configuration struct kept in SPI flash across power cycles
+//!
+//! [NvConfig] keeps two copies of the struct, A and B, in adjacent sectors,
+//! and each save overwrites the older one. A save interrupted by a power
+//! loss or failing halfway leaves a copy that does not pass its CRC, and the
+//! other copy is still loaded. Each copy starts with a header:
+//!
+//! | Offset | Field     | Type  | Description                                    |
+//! | :-     | :-        | :-    | :-                                             |
+//! | 0      | `magic`   | `u32` | [MAGIC]                                        |
+//! | 4      | `version` | `u16` | Layout version of `T`, set by the caller       |
+//! | 6      | `len`     | `u16` | Size of `T` in bytes                           |
+//! | 8      | `seq`     | `u32` | Incremented on each save                       |
+//! | 12     | `crc`     | `u32` | [CRC-32](crate::crc::Crc32) of the above and T |
+//!
+//! All fields are little-endian, and `T` follows as laid out in memory.
+//!
+//! ```ignore
+//! #[derive(Clone, Copy, Pod, Zeroable)]
+//! #[repr(C)]
+//! struct Calibration {
+//!     offset: i32,
+//!     gain: u32,
+//! }
+//!
+//! let mut nv = NvConfig::<Calibration>::new(0x10_0000, 1);
+//! let cal = match nv.load(&mut flash) {
+//!     Ok(cal) => cal,
+//!     Err(NvConfigError::NotFound) => DEFAULT_CAL,
+//!     Err(e) => return Err(e),
+//! };
+//! nv.save(&mut flash, &Calibration { offset: -3, ..cal })?;
+//! ```
+use core::{marker::PhantomData, mem::size_of};
+
+use bytemuck::Pod;
+
+use crate::{
+    crc::Crc32,
+    sysctrl::spi_flash::{FlashError, SpiFlash, SECTOR_SIZE},
+};
+
+/// `b"HSNV"` as read in little-endian
+pub const MAGIC: u32 = u32::from_le_bytes(*b"HSNV");
+
+const HEADER_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NvConfigError {
+    /// Neither copy has ever been written
+    NotFound,
+    /// No copy passes its CRC
+    Corrupt,
+    /// The newest copy was saved with another version or size of `T`
+    VersionMismatch {
+        version: u16,
+        len: u16,
+    },
+    Flash(FlashError),
+}
+
+impl From<FlashError> for NvConfigError {
+    fn from(e: FlashError) -> Self {
+        NvConfigError::Flash(e)
+    }
+}
+
+/// Copy found by [NvConfig::scan]
+#[derive(Clone, Copy, Debug)]
+struct Stored {
+    /// 0 for A, 1 for B
+    slot: u32,
+    seq: u32,
+    version: u16,
+    len: u16,
+}
+
+/// A `T` stored in the two sectors at `base`, sa. [module
+/// documentation](self)
+pub struct NvConfig<T> {
+    base: u32,
+    version: u16,
+    /// Newest valid copy, as of the last load or save
+    newest: Option<Stored>,
+    /// `newest` is known
+    scanned: bool,
+    _pd: PhantomData<T>,
+}
+
+impl<T: Pod> NvConfig<T> {
+    /// Store copies in the sectors at `base` and `base` + [SECTOR_SIZE],
+    /// tagged with `version`
+    ///
+    /// Bump `version` whenever the layout of `T` changes, so that copies of
+    /// an older layout are reported as [NvConfigError::VersionMismatch]
+    /// instead of being misread.
+    pub const fn new(base: u32, version: u16) -> Self {
+        const {
+            assert!(
+                HEADER_LEN + size_of::<T>() <= SECTOR_SIZE,
+                "configuration does not fit in a sector"
+            )
+        };
+        Self {
+            base,
+            version,
+            newest: None,
+            scanned: false,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Read the newest copy that passes its CRC
+    ///
+    /// The copy is read into a `T` on the stack, which must be in memory
+    /// visible to the uDMA.
+    pub fn load(&mut self, flash: &mut SpiFlash) -> Result<T, NvConfigError> {
+        let newest = self.scan(flash)?;
+        if newest.version != self.version || newest.len as usize != size_of::<T>() {
+            return Err(NvConfigError::VersionMismatch {
+                version: newest.version,
+                len: newest.len,
+            });
+        }
+        let mut config = T::zeroed();
+        flash.read(
+            self.slot_addr(newest.slot) + HEADER_LEN as u32,
+            bytemuck::bytes_of_mut(&mut config),
+        )?;
+        Ok(config)
+    }
+
+    /// Write `config` over the older copy
+    ///
+    /// The newer copy is left untouched, so it is still loaded if the save
+    /// fails. `config` must be in memory visible to the uDMA. Erasing a
+    /// sector takes up to 400 ms.
+    pub fn save(&mut self, flash: &mut SpiFlash, config: &T) -> Result<(), NvConfigError> {
+        if !self.scanned {
+            // Corrupt or foreign copies are overwritten all the same
+            if let Err(NvConfigError::Flash(e)) = self.scan(flash) {
+                return Err(e.into());
+            }
+        }
+        let (slot, seq) = match self.newest {
+            Some(c) => (c.slot ^ 1, c.seq.wrapping_add(1)),
+            None => (0, 0),
+        };
+        let data = bytemuck::bytes_of(config);
+        let len = size_of::<T>() as u16;
+        let header = header(self.version, len, seq, data);
+
+        let addr = self.slot_addr(slot);
+        flash.erase_sector(addr)?;
+        // The header goes last, so an interrupted save leaves no magic
+        flash.write(addr + HEADER_LEN as u32, data)?;
+        flash.write(addr, &header)?;
+
+        // The write is verified the way the next boot will read it
+        self.scanned = false;
+        match self.scan(flash) {
+            Ok(c) if c.slot == slot && c.seq == seq => Ok(()),
+            Ok(_) | Err(NvConfigError::NotFound) | Err(NvConfigError::Corrupt) => {
+                Err(NvConfigError::Corrupt)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sequence number of the newest copy as of the last load or save, for
+    /// telling how often the configuration was saved
+    pub fn seq(&self) -> Option<u32> {
+        self.newest.map(|c| c.seq)
+    }
+
+    fn slot_addr(&self, slot: u32) -> u32 {
+        self.base + slot * SECTOR_SIZE as u32
+    }
+
+    /// Find the newest copy that passes its CRC
+    fn scan(&mut self, flash: &mut SpiFlash) -> Result<Stored, NvConfigError> {
+        let mut newest: Option<Stored> = None;
+        let mut written = false;
+        for slot in 0..2 {
+            let Some(c) = self.check_copy(flash, slot, &mut written)? else {
+                continue;
+            };
+            // Sequence numbers wrap. Of two valid copies, one is one ahead.
+            let newer = newest.is_none_or(|n| (c.seq.wrapping_sub(n.seq) as i32) > 0);
+            if newer {
+                newest = Some(c);
+            }
+        }
+        self.newest = newest;
+        self.scanned = true;
+        match newest {
+            Some(c) => Ok(c),
+            None if written => Err(NvConfigError::Corrupt),
+            None => Err(NvConfigError::NotFound),
+        }
+    }
+
+    /// The copy in `slot` if its CRC matches. Sets `written` if the slot
+    /// holds anything but erased flash.
+    fn check_copy(
+        &self,
+        flash: &mut SpiFlash,
+        slot: u32,
+        written: &mut bool,
+    ) -> Result<Option<Stored>, NvConfigError> {
+        let addr = self.slot_addr(slot);
+        let mut h = [0u8; HEADER_LEN];
+        flash.read(addr, &mut h)?;
+        let word = |i: usize| u32::from_le_bytes([h[i], h[i + 1], h[i + 2], h[i + 3]]);
+        let half = |i: usize| u16::from_le_bytes([h[i], h[i + 1]]);
+
+        if h.iter().any(|&b| b != 0xff) {
+            *written = true;
+        }
+        let len = half(6);
+        if word(0) != MAGIC || HEADER_LEN + len as usize > SECTOR_SIZE {
+            return Ok(None);
+        }
+
+        let mut crc = Crc32::new();
+        crc.update(&h[..12]);
+        let mut chunk = [0u8; 64];
+        let mut offset = 0;
+        while offset < len as usize {
+            let n = (len as usize - offset).min(chunk.len());
+            flash.read(addr + (HEADER_LEN + offset) as u32, &mut chunk[..n])?;
+            crc.update(&chunk[..n]);
+            offset += n;
+        }
+        if crc.finish() != word(12) {
+            return Ok(None);
+        }
+        Ok(Some(Stored {
+            slot,
+            seq: word(8),
+            version: half(4),
+            len,
+        }))
+    }
+}
+
+fn header(version: u16, len: u16, seq: u32, data: &[u8]) -> [u8; HEADER_LEN] {
+    let mut h = [0u8; HEADER_LEN];
+    h[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    h[4..6].copy_from_slice(&version.to_le_bytes());
+    h[6..8].copy_from_slice(&len.to_le_bytes());
+    h[8..12].copy_from_slice(&seq.to_le_bytes());
+    let mut crc = Crc32::new();
+    crc.update(&h[..12]);
+    crc.update(data);
+    h[12..16].copy_from_slice(&crc.finish().to_le_bytes());
+    h
+}
@@ -0,0 +1,38 @@
+//! Debug mode for ordering and read glitches in the uDMA drivers
+//!
+//! With the `strict-mmio` feature, every register write of the uDMA drivers
+//! is read back and followed by a `fence`. Every poll that sees its condition
+//! met reads the register once more. If the second read disagrees, the poll
+//! carries on and the mismatch is recorded here with the source text of the
+//! register, e.g., `udma.uart_status()`.
+//!
+//! This rules out write buffering and reordering as the cause of a bug at the
+//! cost of roughly doubling the register traffic. Check the counters after
+//! the suspicious transfer:
+//!
+//! ```ignore
+//! if strict::mismatches() != 0 {
+//!     sprintln!("unstable read of {}", strict::last_mismatch().unwrap());
+//! }
+//! ```
+static mut MISMATCHES: u32 = 0;
+static mut LAST_MISMATCH: Option<&'static str> = None;
+
+/// Number of polls whose second read disagreed with the first, since reset
+pub fn mismatches() -> u32 {
+    unsafe { MISMATCHES }
+}
+
+/// Register of the most recent mismatch
+pub fn last_mismatch() -> Option<&'static str> {
+    riscv::interrupt::free(|| unsafe { LAST_MISMATCH })
+}
+
+pub(crate) fn record(register: &'static str) {
+    // Not all targets support atomic read-modify-write, so exclude interrupts
+    // instead
+    riscv::interrupt::free(|| unsafe {
+        MISMATCHES = MISMATCHES.wrapping_add(1);
+        LAST_MISMATCH = Some(register);
+    });
+}
@@ -0,0 +1,4 @@
+//! Sharing drivers between the main loop and interrupt handlers
+mod mutex;
+
+pub use mutex::{BspMutex, PeripheralMutex};
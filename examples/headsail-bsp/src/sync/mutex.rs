@@ -0,0 +1,96 @@
+//! Mutexes over `critical_section`, usable with or without a scheduler
+//!
+//! Drivers take `&mut self`, so sharing one with an interrupt handler needs
+//! interior mutability. Drivers created at run time go in an `Option`:
+//!
+//! ```ignore
+//! static UART: BspMutex<Option<UdmaUart<'static, Enabled>>> = BspMutex::new(None);
+//!
+//! UART.lock(|uart| *uart = Some(udma.split().unwrap().uart.unwrap().enable(setup)));
+//!
+//! // In the interrupt handler
+//! UART.lock(|uart| {
+//!     if let Some(uart) = uart {
+//!         uart.write_str("tick\r\n");
+//!     }
+//! });
+//! ```
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+/// Value accessed with interrupts masked
+pub struct BspMutex<T>(Mutex<RefCell<T>>);
+
+impl<T> BspMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self(Mutex::new(RefCell::new(value)))
+    }
+
+    /// Call `f` with the value in a critical section
+    ///
+    /// Keep `f` short, as it delays all interrupts.
+    ///
+    /// # Panics
+    ///
+    /// If called again from within `f` on the same mutex
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.0.borrow_ref_mut(cs)))
+    }
+
+    /// Access without a critical section, as `&mut self` already proves
+    /// exclusive access
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().get_mut()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().into_inner()
+    }
+}
+
+/// [BspMutex] that records the priority ceiling of the resource, i.e., the
+/// highest priority of the tasks sharing the value
+///
+/// SysCtrl has no interrupt priority threshold to raise, so [lock](Self::lock)
+/// masks all interrupts, which is always at least the ceiling. The ceiling is
+/// kept for checking the application's priority assignment, e.g., with
+/// [check_priority](Self::check_priority) at task start.
+pub struct PeripheralMutex<T> {
+    inner: BspMutex<T>,
+    ceiling: u8,
+}
+
+impl<T> PeripheralMutex<T> {
+    pub const fn new(value: T, ceiling: u8) -> Self {
+        Self {
+            inner: BspMutex::new(value),
+            ceiling,
+        }
+    }
+
+    #[inline]
+    pub fn ceiling(&self) -> u8 {
+        self.ceiling
+    }
+
+    /// Returns false if a task of `priority` is above the ceiling, i.e., the
+    /// resource was not declared for it
+    #[inline]
+    pub fn check_priority(&self, priority: u8) -> bool {
+        priority <= self.ceiling
+    }
+
+    /// Sa. [BspMutex::lock]
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.inner.lock(f)
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
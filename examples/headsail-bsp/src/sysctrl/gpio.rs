@@ -1,20 +1,30 @@
 use core::marker::PhantomData;
 
 use super::{mmap, soc_ctrl};
-use crate::{mask_u32, toggle_u32, unmask_u32};
+use crate::{
+    delay, mask_u32, poll, poll::PollTimeout, read_u32, sealed::Sealed, toggle_u32, unmask_u32,
+};
 
 /// Type-state trait for GPIO in different states
-pub trait GpioState {}
+pub trait GpioState: Sealed {}
 
 pub struct Uninit;
+impl Sealed for Uninit {}
 impl GpioState for Uninit {}
 
 pub struct Input;
+impl Sealed for Input {}
 impl GpioState for Input {}
 
 pub struct Output;
+impl Sealed for Output {}
 impl GpioState for Output {}
 
+/// Driven low or released, for buses with an external pull-up such as I2C
+pub struct OpenDrain;
+impl Sealed for OpenDrain {}
+impl GpioState for OpenDrain {}
+
 /// To obtain an instance:
 ///
 /// 1. Obtain [Pads] with [sysctrl::soc_ctrl::Pads::take]
@@ -33,6 +43,7 @@ impl<const IDX: u32> Gpio<IDX, Uninit> {
 
     pub fn into_input(self) -> Gpio<IDX, Input> {
         unmask_u32(mmap::GPIO_DIR, 1 << IDX);
+        mask_u32(mmap::GPIO_EN, 1 << IDX);
 
         Gpio { _pd: PhantomData }
     }
@@ -42,6 +53,17 @@ impl<const IDX: u32> Gpio<IDX, Uninit> {
 
         Gpio { _pd: PhantomData }
     }
+
+    /// The pad has no open-drain mode, so driving low switches it to an
+    /// output with a low level and releasing switches it back to an input.
+    /// Starts released.
+    pub fn into_open_drain(self) -> Gpio<IDX, OpenDrain> {
+        unmask_u32(mmap::GPIO_DIR, 1 << IDX);
+        unmask_u32(mmap::GPIO_OUT, 1 << IDX);
+        mask_u32(mmap::GPIO_EN, 1 << IDX);
+
+        Gpio { _pd: PhantomData }
+    }
 }
 
 impl<const IDX: u32> Gpio<IDX, Output> {
@@ -58,6 +80,111 @@ impl<const IDX: u32> Gpio<IDX, Output> {
     }
 }
 
+/// Level of the pulse to measure with [Gpio::measure_pulse_width_us]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PulsePolarity {
+    High,
+    Low,
+}
+
+impl<const IDX: u32> Gpio<IDX, Input> {
+    pub fn is_high(&self) -> bool {
+        read_u32(mmap::GPIO_IN) & (1 << IDX) != 0
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+
+    /// Measure the width of the next complete pulse of `polarity` in
+    /// microseconds
+    ///
+    /// SysCtrl has no timer input capture, so the edges are timestamped with
+    /// `mcycle` while polling the pad. Resolution is that of the poll loop,
+    /// and interrupts taken during the pulse skew the result. Cycles are
+    /// converted using [delay::set_core_hz].
+    ///
+    /// A pulse already in progress is skipped. Returns [PollTimeout] if no
+    /// complete pulse is seen within `timeout_us`.
+    pub fn measure_pulse_width_us(
+        &self,
+        polarity: PulsePolarity,
+        timeout_us: u32,
+    ) -> Result<u32, PollTimeout> {
+        let active = polarity == PulsePolarity::High;
+        let timeout = delay::us_to_cycles(timeout_us);
+
+        let mut budget = timeout;
+        let mut wait_level = |level: bool| {
+            let waited = poll::wait_timeout(|| self.is_high() == level, budget).map_err(|_| {
+                PollTimeout {
+                    waited_cycles: timeout,
+                }
+            })?;
+            budget = budget.saturating_sub(waited);
+            Ok(waited)
+        };
+
+        // Wait out a pulse in progress, then for the leading edge
+        wait_level(!active)?;
+        wait_level(active)?;
+        let width = wait_level(!active)?;
+        Ok(delay::cycles_to_us(width) as u32)
+    }
+}
+
+impl<const IDX: u32> Gpio<IDX, OpenDrain> {
+    /// Let the pull-up take the line high
+    pub fn release_line(&mut self) {
+        unmask_u32(mmap::GPIO_DIR, 1 << IDX);
+    }
+
+    pub fn drive_low(&mut self) {
+        mask_u32(mmap::GPIO_DIR, 1 << IDX);
+    }
+
+    /// Level of the line, which stays low while another device drives it
+    pub fn is_high(&self) -> bool {
+        read_u32(mmap::GPIO_IN) & (1 << IDX) != 0
+    }
+}
+
+// embedded-hal is only a dependency of the I2C helpers
+#[cfg(feature = "i2c")]
+mod eh1 {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+    use super::{Gpio, OpenDrain};
+
+    impl<const IDX: u32> ErrorType for Gpio<IDX, OpenDrain> {
+        type Error = Infallible;
+    }
+
+    impl<const IDX: u32> OutputPin for Gpio<IDX, OpenDrain> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.drive_low();
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.release_line();
+            Ok(())
+        }
+    }
+
+    impl<const IDX: u32> InputPin for Gpio<IDX, OpenDrain> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(Gpio::<IDX, OpenDrain>::is_high(self))
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(!Gpio::<IDX, OpenDrain>::is_high(self))
+        }
+    }
+}
+
 impl<const IDX: u32, S: GpioState> Gpio<IDX, S> {
     /// Release pad back to its original function
     ///
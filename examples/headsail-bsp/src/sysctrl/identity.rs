@@ -0,0 +1,86 @@
+//! Stable per-board identifier for provisioning
+//!
+//! The ID is taken from the first source that answers, in order:
+//!
+//! 1. The unique ID of the SPI NOR flash (command 0x4B)
+//! 2. The CID register of the SD card, read by the caller
+//!
+//! SysCtrl has no chip ID registers to fall back on. The first ID found is
+//! cached, and later calls return it without touching the hardware.
+use super::spi_flash::SpiFlash;
+
+/// Where a [BoardId] came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdSource {
+    /// 64-bit flash unique ID, followed by the JEDEC ID and zero padding
+    FlashUniqueId,
+    /// SD card CID with the CRC byte cleared
+    SdCid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardId {
+    pub id: [u8; 16],
+    pub source: IdSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IdentityError {
+    /// None of the sources were available
+    NoSource,
+}
+
+static mut BOARD_ID: Option<BoardId> = None;
+
+/// Return the cached board ID, or read one from `flash` or `sd_cid`
+///
+/// A flash that does not respond, or returns all zeros or all ones, is
+/// skipped. `sd_cid` is the 16-byte response to SEND_CID (CMD10).
+pub fn board_id(
+    flash: Option<&mut SpiFlash>,
+    sd_cid: Option<&[u8; 16]>,
+) -> Result<BoardId, IdentityError> {
+    if let Some(id) = unsafe { BOARD_ID } {
+        return Ok(id);
+    }
+
+    let id = flash
+        .and_then(from_flash)
+        .or_else(|| sd_cid.map(from_sd_cid))
+        .ok_or(IdentityError::NoSource)?;
+    unsafe { BOARD_ID = Some(id) };
+    Ok(id)
+}
+
+/// Cached board ID, if [board_id] has succeeded before
+pub fn cached() -> Option<BoardId> {
+    unsafe { BOARD_ID }
+}
+
+fn from_flash(flash: &mut SpiFlash) -> Option<BoardId> {
+    let unique = flash.read_unique_id().ok()?;
+    // Floating or absent devices read as all zeros or all ones
+    if unique.iter().all(|&b| b == 0) || unique.iter().all(|&b| b == 0xff) {
+        return None;
+    }
+    let jedec = flash.jedec_id().ok()?;
+
+    let mut id = [0u8; 16];
+    id[..8].copy_from_slice(&unique);
+    id[8..11].copy_from_slice(&jedec);
+    Some(BoardId {
+        id,
+        source: IdSource::FlashUniqueId,
+    })
+}
+
+fn from_sd_cid(cid: &[u8; 16]) -> BoardId {
+    let mut id = *cid;
+    // CRC7 is derived from the rest and carries no information
+    id[15] = 0;
+    BoardId {
+        id,
+        source: IdSource::SdCid,
+    }
+}
@@ -4,8 +4,16 @@
 
 pub(crate) const SYSCTRL_ADDR: usize = 0x1a10_0000;
 
+/// Start of the L2 memory banks, the only memory visible to the uDMA
+pub const UDMA_MEM_START: usize = 0x1c00_0000;
+/// End of the L2 memory banks (exclusive)
+pub const UDMA_MEM_END: usize = 0x1c01_0000;
+
 pub(crate) const GPIO_ADDR: usize = SYSCTRL_ADDR + 0x1000;
 pub(crate) const GPIO_DIR: usize = GPIO_ADDR + 0x0;
+/// Input sampling enable
+pub(crate) const GPIO_EN: usize = GPIO_ADDR + 0x4;
+pub(crate) const GPIO_IN: usize = GPIO_ADDR + 0x8;
 pub(crate) const GPIO_OUT: usize = GPIO_ADDR + 0xc;
 
 pub(crate) const SOC_CONTROL_ADDR: usize = SYSCTRL_ADDR + 0x4000;
@@ -1,6 +1,16 @@
 //! Abstractions that only exist on SysCtrl
 pub mod gpio;
+#[cfg(feature = "spi-flash")]
+pub mod identity;
 pub mod soc_ctrl;
+#[cfg(feature = "spi-adc")]
+pub mod spi_adc;
+#[cfg(feature = "spi-can")]
+pub mod spi_can;
+#[cfg(feature = "spi-eeprom")]
+pub mod spi_eeprom;
+#[cfg(feature = "spi-flash")]
+pub mod spi_flash;
 #[cfg(feature = "pac")]
 pub mod udma;
 
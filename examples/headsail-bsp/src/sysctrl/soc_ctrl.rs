@@ -3,6 +3,10 @@ use riscv::interrupt;
 use super::{gpio::Gpio, mmap};
 use crate::{mask_u32, read_u32, unmask_u32, write_u32};
 
+/// Pads that exist, as a mask of pad indices
+#[cfg(feature = "spim")]
+const PAD_MASK: u32 = (1 << 19) - 1;
+
 #[repr(u32)]
 pub(crate) enum PadFn {
     //Default = 0,
@@ -95,6 +99,49 @@ impl<const IDX: u32> Pad<IDX> {
     }
 }
 
+/// PADMUX0 and PADMUX1 bits selecting GPIO for the pads in the `pads` mask
+#[cfg(feature = "spim")]
+fn padmux_gpio_bits(pads: u32) -> (u32, u32) {
+    let (mut mux0, mut mux1) = (0, 0);
+    for idx in (0..19).filter(|idx| pads & (1 << idx) != 0) {
+        if idx <= 15 {
+            mux0 |= (PadFn::Gpio as u32) << (idx * 2);
+        } else {
+            mux1 |= (PadFn::Gpio as u32) << ((idx - 16) * 2);
+        }
+    }
+    (mux0, mux1)
+}
+
+/// Switch the pads in the `pads` mask to GPIO, either driven low or released
+/// with the input buffer off
+///
+/// The GPIO registers are set up before the pads are switched, so the pads
+/// do not glitch. Indices past the last pad are ignored.
+#[cfg(feature = "spim")]
+pub(crate) fn pads_into_gpio(pads: u32, drive_low: bool) {
+    let pads = pads & PAD_MASK;
+    if drive_low {
+        unmask_u32(mmap::GPIO_OUT, pads);
+        mask_u32(mmap::GPIO_DIR, pads);
+    } else {
+        unmask_u32(mmap::GPIO_DIR, pads);
+        unmask_u32(mmap::GPIO_EN, pads);
+    }
+    let (mux0, mux1) = padmux_gpio_bits(pads);
+    mask_u32(mmap::PADMUX0, mux0);
+    mask_u32(mmap::PADMUX1, mux1);
+}
+
+/// Return the pads in the `pads` mask to their original function, sa.
+/// [Gpio::release](super::gpio::Gpio::release)
+#[cfg(feature = "spim")]
+pub(crate) fn pads_release(pads: u32) {
+    let (mux0, mux1) = padmux_gpio_bits(pads & PAD_MASK);
+    unmask_u32(mmap::PADMUX0, mux0);
+    unmask_u32(mmap::PADMUX1, mux1);
+}
+
 /// Enables subsystems based on the mask. Returns the previous value in register.
 pub fn ss_enable(ss_bits: u32) -> u32 {
     let pvalue = read_u32(mmap::SS_RESET_EN);
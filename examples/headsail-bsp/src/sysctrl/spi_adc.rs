@@ -0,0 +1,187 @@
+//! Driver for TI ADS1118 / ADS1018 SPI ADCs
+//!
+//! These are the SPI members of the ADS1x1x family; ADS1115 and ADS1015 share
+//! the register layout but are I2C-only. Each 16-bit frame writes the config
+//! register while the previous conversion result is clocked out. Datasheet:
+//! <https://www.ti.com/lit/ds/symlink/ads1118.pdf>
+use crate::{
+    delay,
+    sysctrl::udma::{
+        spim::{ChipSelect, SpimConfig, SpimError},
+        Enabled, UdmaSpim,
+    },
+};
+
+/// Start a single-shot conversion
+const CFG_SS: u16 = 1 << 15;
+const CFG_MUX_SHIFT: u16 = 12;
+/// AINx versus GND
+const CFG_MUX_SINGLE_ENDED: u16 = 0b100;
+const CFG_PGA_SHIFT: u16 = 9;
+/// Power-down single-shot mode
+const CFG_MODE_SINGLE: u16 = 1 << 8;
+const CFG_DR_SHIFT: u16 = 5;
+const CFG_PULL_UP_EN: u16 = 1 << 3;
+/// Write the config register
+const CFG_NOP_VALID: u16 = 0b01 << 1;
+/// Reserved, must be written as 1
+const CFG_RESERVED: u16 = 1;
+
+/// Full-scale range of the programmable gain amplifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum PgaGain {
+    /// ±6.144 V
+    Fsr6v144 = 0b000,
+    /// ±4.096 V
+    Fsr4v096 = 0b001,
+    /// ±2.048 V
+    Fsr2v048 = 0b010,
+    /// ±1.024 V
+    Fsr1v024 = 0b011,
+    /// ±0.512 V
+    Fsr0v512 = 0b100,
+    /// ±0.256 V
+    Fsr0v256 = 0b101,
+}
+
+/// Samples per second, as specified for ADS1118
+///
+/// ADS1018 runs 16x faster at each setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum DataRate {
+    Sps8 = 0b000,
+    Sps16 = 0b001,
+    Sps32 = 0b010,
+    Sps64 = 0b011,
+    Sps128 = 0b100,
+    Sps250 = 0b101,
+    Sps475 = 0b110,
+    Sps860 = 0b111,
+}
+
+impl DataRate {
+    /// Worst case conversion time, including the 10% oscillator tolerance
+    const fn conversion_us(self) -> u32 {
+        let sps = match self {
+            DataRate::Sps8 => 8,
+            DataRate::Sps16 => 16,
+            DataRate::Sps32 => 32,
+            DataRate::Sps64 => 64,
+            DataRate::Sps128 => 128,
+            DataRate::Sps250 => 250,
+            DataRate::Sps475 => 475,
+            DataRate::Sps860 => 860,
+        };
+        1_100_000 / sps
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AdcError {
+    /// Channel is not below `CHANNELS`
+    InvalidChannel,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for AdcError {
+    fn from(value: SpimError) -> Self {
+        AdcError::Spim(value)
+    }
+}
+
+/// Single-ended ADS1118 / ADS1018 on `CHANNELS` inputs, starting from AIN0
+pub struct SpiAdc<'s, 'u, const CHANNELS: usize> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+    channel: u8,
+    gain: PgaGain,
+    data_rate: DataRate,
+}
+
+impl<'s, 'u, const CHANNELS: usize> SpiAdc<'s, 'u, CHANNELS> {
+    const VALID_CHANNELS: () = assert!(CHANNELS > 0 && CHANNELS <= 4);
+
+    /// The device runs in SPI mode 1, so `spim` is reconfigured with CPHA set
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect) -> Self {
+        let () = Self::VALID_CHANNELS;
+
+        spim.set_config(SpimConfig {
+            cpol: false,
+            cpha: true,
+            ..spim.config()
+        });
+        Self {
+            spim,
+            cs,
+            channel: 0,
+            gain: PgaGain::Fsr2v048,
+            data_rate: DataRate::Sps128,
+        }
+    }
+
+    /// Select the input and conversion parameters used by subsequent reads
+    pub fn configure(
+        &mut self,
+        channel: u8,
+        gain: PgaGain,
+        data_rate: DataRate,
+    ) -> Result<(), AdcError> {
+        if channel as usize >= CHANNELS {
+            return Err(AdcError::InvalidChannel);
+        }
+        self.channel = channel;
+        self.gain = gain;
+        self.data_rate = data_rate;
+
+        self.frame(self.config_word())?;
+        Ok(())
+    }
+
+    /// Run a single-shot conversion on the configured channel
+    pub fn read_conversion(&mut self) -> Result<i16, AdcError> {
+        self.frame(self.config_word() | CFG_SS)?;
+        delay::micros(self.data_rate.conversion_us());
+        Ok(self.frame(self.config_word())? as i16)
+    }
+
+    /// Run a single-shot conversion on each channel in turn
+    ///
+    /// The configured channel is restored afterwards.
+    pub fn read_all(&mut self) -> Result<[i16; CHANNELS], AdcError> {
+        let channel = self.channel;
+        let mut results = [0; CHANNELS];
+        for (ch, result) in results.iter_mut().enumerate() {
+            self.channel = ch as u8;
+            match self.read_conversion() {
+                Ok(value) => *result = value,
+                Err(e) => {
+                    self.channel = channel;
+                    return Err(e);
+                }
+            }
+        }
+        self.channel = channel;
+        Ok(results)
+    }
+
+    fn config_word(&self) -> u16 {
+        ((CFG_MUX_SINGLE_ENDED | self.channel as u16) << CFG_MUX_SHIFT)
+            | ((self.gain as u16) << CFG_PGA_SHIFT)
+            | CFG_MODE_SINGLE
+            | ((self.data_rate as u16) << CFG_DR_SHIFT)
+            | CFG_PULL_UP_EN
+            | CFG_NOP_VALID
+            | CFG_RESERVED
+    }
+
+    /// Write `config` and return the conversion result clocked out meanwhile
+    fn frame(&mut self, config: u16) -> Result<u16, SpimError> {
+        let tx = config.to_be_bytes();
+        let mut rx = [0u8; 2];
+        self.spim.transaction(self.cs)?.transfer(&mut rx, &tx)?;
+        Ok(u16::from_be_bytes(rx))
+    }
+}
@@ -0,0 +1,400 @@
+//! Driver for the Microchip MCP2515 SPI CAN controller
+//!
+//! Frames are sent from the first free of the three transmit buffers and
+//! received from the two receive buffers, receive buffer 0 rolling over into
+//! receive buffer 1. Everything is polled; the INT pin is not used.
+//!
+//! ```ignore
+//! let mut can = Mcp2515::new(&mut spim, ChipSelect::Cs1, 8_000_000);
+//! can.init(CanBitrate::Kbps500)?;
+//! can.set_filter(0, CanId::Standard(0x120), CanId::Standard(0x7f0))?;
+//! can.send_frame(CanId::Standard(0x123), &[0; 8])?;
+//! if let Some(frame) = can.receive_frame() {
+//!     handle(frame.id, frame.data());
+//! }
+//! ```
+//!
+//! Datasheet: <https://ww1.microchip.com/downloads/en/DeviceDoc/MCP2515-Family-Data-Sheet-DS20001801K.pdf>
+use riscv::register::mcycle;
+
+use crate::{
+    delay,
+    sysctrl::udma::{
+        spim::{ChipSelect, SpimConfig, SpimError},
+        Enabled, UdmaSpim,
+    },
+};
+
+const CMD_RESET: u8 = 0xc0;
+const CMD_READ: u8 = 0x03;
+const CMD_WRITE: u8 = 0x02;
+const CMD_BIT_MODIFY: u8 = 0x05;
+const CMD_READ_STATUS: u8 = 0xa0;
+/// OR with 0x04 for receive buffer 1. Reading clears the buffer's RXnIF.
+const CMD_READ_RX_BUFFER: u8 = 0x90;
+/// OR with 2 * n for transmit buffer n
+const CMD_LOAD_TX_BUFFER: u8 = 0x40;
+/// OR with 1 << n for transmit buffer n
+const CMD_RTS: u8 = 0x80;
+
+const REG_CANSTAT: u8 = 0x0e;
+const REG_CANCTRL: u8 = 0x0f;
+const REG_CNF3: u8 = 0x28;
+const REG_CANINTF: u8 = 0x2c;
+/// TXBnCTRL is at this plus 0x10 * n
+const REG_TXB0CTRL: u8 = 0x30;
+const REG_RXB0CTRL: u8 = 0x60;
+const REG_RXB1CTRL: u8 = 0x70;
+const REG_RXM0SIDH: u8 = 0x20;
+
+const TXBCTRL_TXREQ: u8 = 1 << 3;
+/// Accept any frame, ignoring filters and masks
+const RXBCTRL_RXM_ANY: u8 = 0b11 << 5;
+/// Roll buffer 0 over into buffer 1
+const RXB0CTRL_BUKT: u8 = 1 << 2;
+const CANCTRL_REQOP_MASK: u8 = 0b111 << 5;
+const STATUS_RX0IF: u8 = 1 << 0;
+const STATUS_RX1IF: u8 = 1 << 1;
+const SIDL_EXIDE: u8 = 1 << 3;
+const SIDL_SRR: u8 = 1 << 4;
+const DLC_RTR: u8 = 1 << 6;
+
+/// The device needs 128 oscillator cycles after reset, under 20 us for
+/// oscillators above 6.4 MHz
+const RESET_US: u32 = 20;
+const MODE_TIMEOUT_US: u32 = 1000;
+
+/// Filter registers RXF0..RXF5, the gap between 2 and 3 is the mask block
+const FILTER_REGS: [u8; 6] = [0x00, 0x04, 0x08, 0x10, 0x14, 0x18];
+
+/// Bit time in time quanta, longest first. Segments are limited to 8 TQ.
+const BIT_TQ: core::ops::RangeInclusive<u32> = 8..=16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CanError {
+    /// The oscillator cannot be divided down to the bitrate
+    UnsupportedBitrate,
+    /// The device did not enter the requested mode, e.g., it is not
+    /// connected
+    ModeTimeout,
+    /// All three transmit buffers are waiting for the bus
+    TxBusy,
+    /// Filter number is not below 6
+    InvalidFilter,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for CanError {
+    fn from(value: SpimError) -> Self {
+        CanError::Spim(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanBitrate {
+    Kbps125,
+    Kbps250,
+    Kbps500,
+    Kbps1000,
+}
+
+impl CanBitrate {
+    pub const fn bps(self) -> u32 {
+        match self {
+            CanBitrate::Kbps125 => 125_000,
+            CanBitrate::Kbps250 => 250_000,
+            CanBitrate::Kbps500 => 500_000,
+            CanBitrate::Kbps1000 => 1_000_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanId {
+    /// 11 bits
+    Standard(u16),
+    /// 29 bits
+    Extended(u32),
+}
+
+impl CanId {
+    /// SIDH, SIDL, EID8 and EID0 as laid out in the buffer, filter and mask
+    /// registers
+    fn to_regs(self) -> [u8; 4] {
+        match self {
+            CanId::Standard(id) => {
+                let id = id & 0x7ff;
+                [(id >> 3) as u8, ((id & 0x7) << 5) as u8, 0, 0]
+            }
+            CanId::Extended(id) => {
+                let id = id & 0x1fff_ffff;
+                [
+                    (id >> 21) as u8,
+                    (((id >> 18) & 0x7) << 5) as u8 | SIDL_EXIDE | ((id >> 16) & 0x3) as u8,
+                    (id >> 8) as u8,
+                    id as u8,
+                ]
+            }
+        }
+    }
+
+    fn from_regs(regs: &[u8]) -> Self {
+        let sid = ((regs[0] as u32) << 3) | ((regs[1] as u32) >> 5);
+        if regs[1] & SIDL_EXIDE == 0 {
+            return CanId::Standard(sid as u16);
+        }
+        CanId::Extended(
+            (sid << 18)
+                | (((regs[1] & 0x3) as u32) << 16)
+                | ((regs[2] as u32) << 8)
+                | regs[3] as u32,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanFrame {
+    pub id: CanId,
+    /// Remote transmission request, carries no data
+    pub rtr: bool,
+    /// Data length, at most 8
+    pub len: u8,
+    pub data: [u8; 8],
+}
+
+impl CanFrame {
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+enum Mode {
+    Normal = 0b000,
+    Loopback = 0b010,
+    Config = 0b100,
+}
+
+/// MCP2515 clocked at `osc_hz`, sa. [module documentation](self)
+pub struct Mcp2515<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+    osc_hz: u32,
+    /// Mode to return to after configuration
+    run_mode: Mode,
+}
+
+impl<'s, 'u> Mcp2515<'s, 'u> {
+    /// The device runs in SPI mode 0, so `spim` is reconfigured accordingly.
+    /// The bus is not accessed before [Mcp2515::init].
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect, osc_hz: u32) -> Self {
+        spim.set_config(SpimConfig {
+            cpol: false,
+            cpha: false,
+            ..spim.config()
+        });
+        Self {
+            spim,
+            cs,
+            osc_hz,
+            run_mode: Mode::Normal,
+        }
+    }
+
+    /// Reset the device and join the bus at `bitrate`
+    ///
+    /// Filters and masks are cleared and off, so every frame is accepted
+    /// until [Mcp2515::set_filter] is called.
+    pub fn init(&mut self, bitrate: CanBitrate) -> Result<(), CanError> {
+        let cnf = Self::bit_timing(self.osc_hz, bitrate.bps())?;
+
+        self.spim.transaction(self.cs)?.write(&[CMD_RESET])?;
+        delay::micros(RESET_US);
+        self.wait_mode(Mode::Config)?;
+
+        // CNF3, CNF2 and CNF1 are consecutive
+        self.write_regs(REG_CNF3, &cnf)?;
+        for reg in FILTER_REGS {
+            self.write_regs(reg, &[0; 4])?;
+        }
+        self.write_regs(REG_RXM0SIDH, &[0; 8])?;
+        self.write_regs(REG_RXB0CTRL, &[RXBCTRL_RXM_ANY | RXB0CTRL_BUKT])?;
+        self.write_regs(REG_RXB1CTRL, &[RXBCTRL_RXM_ANY])?;
+        self.write_regs(REG_CANINTF, &[0])?;
+
+        self.set_mode(self.run_mode)
+    }
+
+    /// Receive the device's own frames instead of joining the bus, e.g., for
+    /// testing without a transceiver
+    pub fn set_loopback(&mut self, on: bool) -> Result<(), CanError> {
+        self.run_mode = match on {
+            true => Mode::Loopback,
+            false => Mode::Normal,
+        };
+        self.set_mode(self.run_mode)
+    }
+
+    /// Accept frames whose ID matches `id` in the bits set in `mask`
+    ///
+    /// Filters 0 and 1 feed receive buffer 0 and share one mask, filters 2 to
+    /// 5 feed receive buffer 1 and share the other, so `mask` applies to all
+    /// filters of the buffer. Setting a filter turns filtering on for its
+    /// buffer, and filters left at 0 then accept standard IDs that are 0 in
+    /// the masked bits; set them to `id` as well to accept one ID range only.
+    /// A filter applies to standard or extended frames, following `id`. The
+    /// device leaves the bus while the filter is written.
+    pub fn set_filter(&mut self, n: u8, id: CanId, mask: CanId) -> Result<(), CanError> {
+        let Some(&filter_reg) = FILTER_REGS.get(n as usize) else {
+            return Err(CanError::InvalidFilter);
+        };
+        let (mask_reg, ctrl_reg, ctrl) = match n {
+            0 | 1 => (REG_RXM0SIDH, REG_RXB0CTRL, RXB0CTRL_BUKT),
+            _ => (REG_RXM0SIDH + 4, REG_RXB1CTRL, 0),
+        };
+
+        self.set_mode(Mode::Config)?;
+        self.write_regs(filter_reg, &id.to_regs())?;
+        // Masks have no EXIDE bit
+        let mut mask = mask.to_regs();
+        mask[1] &= !SIDL_EXIDE;
+        self.write_regs(mask_reg, &mask)?;
+        self.write_regs(ctrl_reg, &[ctrl])?;
+        self.set_mode(self.run_mode)
+    }
+
+    /// Queue a data frame of 8 bytes in a free transmit buffer
+    ///
+    /// Returns once the frame is queued, not when it has been sent.
+    pub fn send_frame(&mut self, id: CanId, data: &[u8; 8]) -> Result<(), CanError> {
+        let mut n = None;
+        for buf in 0..3 {
+            let mut ctrl = [0u8];
+            self.read_regs(REG_TXB0CTRL + 0x10 * buf, &mut ctrl)?;
+            if ctrl[0] & TXBCTRL_TXREQ == 0 {
+                n = Some(buf);
+                break;
+            }
+        }
+        let n = n.ok_or(CanError::TxBusy)?;
+
+        let mut load = [0u8; 14];
+        load[0] = CMD_LOAD_TX_BUFFER | (2 * n);
+        load[1..5].copy_from_slice(&id.to_regs());
+        load[5] = data.len() as u8;
+        load[6..].copy_from_slice(data);
+        self.spim.transaction(self.cs)?.write(&load)?;
+        self.spim
+            .transaction(self.cs)?
+            .write(&[CMD_RTS | (1 << n)])?;
+        Ok(())
+    }
+
+    /// Oldest received frame, if any
+    ///
+    /// SPIM errors are reported as no frame, as they can only come from a
+    /// misconfigured SPIM, e.g., parked pads.
+    pub fn receive_frame(&mut self) -> Option<CanFrame> {
+        let status = self.read_status().ok()?;
+        let cmd = if status & STATUS_RX0IF != 0 {
+            CMD_READ_RX_BUFFER
+        } else if status & STATUS_RX1IF != 0 {
+            CMD_READ_RX_BUFFER | 0x04
+        } else {
+            return None;
+        };
+
+        // SIDH, SIDL, EID8, EID0, DLC, D0..D7
+        let mut regs = [0u8; 13];
+        {
+            let mut t = self.spim.transaction(self.cs).ok()?;
+            t.write(&[cmd]).ok()?;
+            t.read(&mut regs).ok()?;
+        }
+
+        let id = CanId::from_regs(&regs[..4]);
+        let rtr = match id {
+            CanId::Standard(_) => regs[1] & SIDL_SRR != 0,
+            CanId::Extended(_) => regs[4] & DLC_RTR != 0,
+        };
+        let len = (regs[4] & 0x0f).min(8);
+        let mut data = [0; 8];
+        if !rtr {
+            data[..len as usize].copy_from_slice(&regs[5..5 + len as usize]);
+        }
+        Some(CanFrame { id, rtr, len, data })
+    }
+
+    /// CNF3, CNF2 and CNF1 for `bps`, sampling at about 75% of the bit
+    fn bit_timing(osc_hz: u32, bps: u32) -> Result<[u8; 3], CanError> {
+        // TQ = 2 * (BRP + 1) / Fosc
+        for tq in BIT_TQ.rev() {
+            let div = 2 * bps * tq;
+            if !osc_hz.is_multiple_of(div) || !(1..=64).contains(&(osc_hz / div)) {
+                continue;
+            }
+            let brp = osc_hz / div - 1;
+            let ps2 = (tq + 2) / 4;
+            let ps1 = (tq - 1 - ps2) / 2;
+            let prop = tq - 1 - ps2 - ps1;
+            // BTLMODE: PS2 set by CNF3, SJW 1
+            let cnf1 = brp as u8;
+            let cnf2 = 0x80 | ((ps1 - 1) << 3) as u8 | (prop - 1) as u8;
+            let cnf3 = (ps2 - 1) as u8;
+            return Ok([cnf3, cnf2, cnf1]);
+        }
+        Err(CanError::UnsupportedBitrate)
+    }
+
+    fn set_mode(&mut self, mode: Mode) -> Result<(), CanError> {
+        self.spim.transaction(self.cs)?.write(&[
+            CMD_BIT_MODIFY,
+            REG_CANCTRL,
+            CANCTRL_REQOP_MASK,
+            (mode as u8) << 5,
+        ])?;
+        self.wait_mode(mode)
+    }
+
+    fn wait_mode(&mut self, mode: Mode) -> Result<(), CanError> {
+        let start = mcycle::read64();
+        let timeout = delay::us_to_cycles(MODE_TIMEOUT_US);
+        loop {
+            let mut stat = [0u8];
+            self.read_regs(REG_CANSTAT, &mut stat)?;
+            if stat[0] >> 5 == mode as u8 {
+                return Ok(());
+            }
+            if mcycle::read64().wrapping_sub(start) > timeout {
+                return Err(CanError::ModeTimeout);
+            }
+        }
+    }
+
+    fn read_status(&mut self) -> Result<u8, SpimError> {
+        let mut status = [0u8];
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&[CMD_READ_STATUS])?;
+        t.read(&mut status)?;
+        Ok(status[0])
+    }
+
+    fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), SpimError> {
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&[CMD_READ, addr])?;
+        t.read(buf)
+    }
+
+    /// At most 8 consecutive registers
+    fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), SpimError> {
+        let mut buf = [0u8; 10];
+        buf[0] = CMD_WRITE;
+        buf[1] = addr;
+        buf[2..2 + data.len()].copy_from_slice(data);
+        self.spim
+            .transaction(self.cs)?
+            .write(&buf[..2 + data.len()])
+    }
+}
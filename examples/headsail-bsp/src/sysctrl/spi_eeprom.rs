@@ -0,0 +1,188 @@
+//! Driver for Microchip 25AA / 25LC series SPI EEPROMs
+//!
+//! Devices from 25xx010 (128 B) up to 25xx512 (64 KiB) are supported. Larger
+//! parts use 24-bit addresses. Datasheet for the 25LC640A, which the others
+//! follow closely: <https://ww1.microchip.com/downloads/en/DeviceDoc/21830F.pdf>
+use crate::{
+    delay,
+    sysctrl::udma::{
+        spim::{ChipSelect, SpimConfig, SpimError},
+        Enabled, UdmaSpim,
+    },
+};
+
+const CMD_WRSR: u8 = 0x01;
+const CMD_WRITE: u8 = 0x02;
+const CMD_READ: u8 = 0x03;
+const CMD_WRDI: u8 = 0x04;
+const CMD_RDSR: u8 = 0x05;
+const CMD_WREN: u8 = 0x06;
+
+/// Write in progress
+pub const STATUS_WIP: u8 = 1 << 0;
+/// Write enable latch
+pub const STATUS_WEL: u8 = 1 << 1;
+/// Block protection, 2 bits
+pub const STATUS_BP_SHIFT: u8 = 2;
+/// Write-protect pin enable
+pub const STATUS_WPEN: u8 = 1 << 7;
+
+/// Maximum write cycle time (t_WC) given by the datasheets
+const WRITE_CYCLE_US: u32 = 5_000;
+/// Time between status polls during a write cycle
+const WIP_POLL_US: u32 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EepromError {
+    /// Access extends past the end of the device
+    OutOfRange,
+    /// Write cycle did not complete within twice the specified time
+    Timeout,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for EepromError {
+    fn from(value: SpimError) -> Self {
+        EepromError::Spim(value)
+    }
+}
+
+/// 25xx EEPROM of `SIZE` bytes
+pub struct SpiEeprom<'s, 'u, const SIZE: usize> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+}
+
+impl<'s, 'u, const SIZE: usize> SpiEeprom<'s, 'u, SIZE> {
+    const VALID_SIZE: () = assert!(SIZE.is_power_of_two() && SIZE >= 128 && SIZE <= 1 << 16);
+
+    /// Page size of the 25xx part with `SIZE` bytes
+    ///
+    /// Some 25xx080 and 25xx160 variants have larger pages, but writing in
+    /// 16-byte pages is valid for those too.
+    pub const PAGE_SIZE: usize = match SIZE {
+        0..=2048 => 16,
+        4096 | 8192 => 32,
+        16384 | 32768 => 64,
+        _ => 128,
+    };
+
+    /// The device runs in SPI mode 0, so `spim` is reconfigured with CPOL and
+    /// CPHA cleared
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect) -> Self {
+        let () = Self::VALID_SIZE;
+
+        spim.set_config(SpimConfig {
+            cpol: false,
+            cpha: false,
+            ..spim.config()
+        });
+        Self { spim, cs }
+    }
+
+    /// Read `buf.len()` bytes starting from `addr`
+    pub fn read(&mut self, addr: u16, buf: &mut [u8]) -> Result<(), EepromError> {
+        Self::check_range(addr, buf.len())?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let (header, header_len) = Self::header(CMD_READ, addr);
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&header[..header_len])?;
+        t.read(buf)?;
+        Ok(())
+    }
+
+    /// Write `data` starting from `addr`
+    ///
+    /// The write is split at page boundaries, and each page waits for the
+    /// previous one to be programmed.
+    pub fn write(&mut self, addr: u16, data: &[u8]) -> Result<(), EepromError> {
+        Self::check_range(addr, data.len())?;
+
+        let mut addr = addr as usize;
+        let mut data = data;
+        while !data.is_empty() {
+            let page_remaining = Self::PAGE_SIZE - addr % Self::PAGE_SIZE;
+            let (chunk, rest) = data.split_at(page_remaining.min(data.len()));
+
+            // The latch is cleared on completion of every write cycle
+            self.write_enable()?;
+            {
+                let (header, header_len) = Self::header(CMD_WRITE, addr as u16);
+                let mut t = self.spim.transaction(self.cs)?;
+                t.write(&header[..header_len])?;
+                t.write(chunk)?;
+            }
+            self.read_status_polling()?;
+
+            addr += chunk.len();
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Set the write enable latch
+    pub fn write_enable(&mut self) -> Result<(), EepromError> {
+        self.command(CMD_WREN)
+    }
+
+    /// Clear the write enable latch
+    pub fn write_disable(&mut self) -> Result<(), EepromError> {
+        self.command(CMD_WRDI)
+    }
+
+    pub fn read_status(&mut self) -> Result<u8, EepromError> {
+        let mut status = [0u8];
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&[CMD_RDSR])?;
+        t.read(&mut status)?;
+        Ok(status[0])
+    }
+
+    /// Poll the status register until the write cycle in progress completes,
+    /// returning the final status
+    pub fn read_status_polling(&mut self) -> Result<u8, EepromError> {
+        for _ in 0..2 * WRITE_CYCLE_US / WIP_POLL_US {
+            let status = self.read_status()?;
+            if status & STATUS_WIP == 0 {
+                return Ok(status);
+            }
+            delay::micros(WIP_POLL_US);
+        }
+        Err(EepromError::Timeout)
+    }
+
+    /// Write the block protection and WPEN bits of the status register
+    pub fn write_status(&mut self, status: u8) -> Result<(), EepromError> {
+        self.write_enable()?;
+        self.spim.transaction(self.cs)?.write(&[CMD_WRSR, status])?;
+        self.read_status_polling()?;
+        Ok(())
+    }
+
+    fn command(&mut self, cmd: u8) -> Result<(), EepromError> {
+        self.spim.transaction(self.cs)?.write(&[cmd])?;
+        Ok(())
+    }
+
+    fn check_range(addr: u16, len: usize) -> Result<(), EepromError> {
+        if addr as usize + len > SIZE {
+            return Err(EepromError::OutOfRange);
+        }
+        Ok(())
+    }
+
+    /// Instruction and address bytes for `cmd` at `addr`
+    fn header(cmd: u8, addr: u16) -> ([u8; 3], usize) {
+        let [hi, lo] = addr.to_be_bytes();
+        match SIZE {
+            0..=256 => ([cmd, lo, 0], 2),
+            // 25xx040 carries A8 in bit 3 of the instruction
+            512 => ([cmd | ((hi & 1) << 3), lo, 0], 2),
+            _ => ([cmd, hi, lo], 3),
+        }
+    }
+}
@@ -0,0 +1,551 @@
+//! Driver for SPI NOR flash with the common Winbond W25Q command set
+//!
+//! Only single-lane commands with 24-bit addresses are used, which covers
+//! devices up to 16 MiB from most vendors. Datasheet:
+//! <https://www.winbond.com/resource-files/w25q128jv%20revf%2003272018%20plus.pdf>
+pub mod ecc;
+pub mod reliable;
+pub mod sfdp;
+
+pub use ecc::{CorrectedCount, PageEcc};
+pub use reliable::ReliableFlash;
+pub use sfdp::SfdpParams;
+
+use crate::{
+    delay,
+    flash::{StatusReg1, StatusReg2, StatusReg3},
+    fmt::put_wire,
+    sysctrl::udma::{
+        spim::{ChipSelect, SpimConfig, SpimError, SpimTransaction},
+        Enabled, UdmaSpim,
+    },
+};
+
+const CMD_PAGE_PROGRAM: u8 = 0x02;
+const CMD_READ: u8 = 0x03;
+const CMD_READ_SR1: u8 = 0x05;
+const CMD_WRITE_ENABLE: u8 = 0x06;
+const CMD_READ_SR3: u8 = 0x15;
+const CMD_SECTOR_ERASE: u8 = 0x20;
+const CMD_READ_SR2: u8 = 0x35;
+const CMD_READ_UNIQUE_ID: u8 = 0x4b;
+const CMD_READ_SFDP: u8 = 0x5a;
+const CMD_JEDEC_ID: u8 = 0x9f;
+const CMD_RELEASE_POWER_DOWN: u8 = 0xab;
+const CMD_POWER_DOWN: u8 = 0xb9;
+
+pub const PAGE_SIZE: usize = 256;
+/// Smallest erasable unit
+pub const SECTOR_SIZE: usize = 4096;
+
+/// Maximum page program time (t_PP) of W25Q128JV
+const PAGE_PROGRAM_US: u32 = 3_000;
+/// Maximum sector erase time (t_SE) of W25Q128JV
+const SECTOR_ERASE_US: u32 = 400_000;
+/// Time to enter deep power-down (t_DP) of W25Q128JV
+const POWER_DOWN_US: u32 = 3;
+/// Time to release from deep power-down (t_RES1) of W25Q128JV
+const RELEASE_POWER_DOWN_US: u32 = 3;
+/// Time between status polls while busy
+const WIP_POLL_US: u32 = 50;
+/// Bytes read back at a time when verifying, sa. [SpiFlash::verify_region]
+const VERIFY_CHUNK: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlashError {
+    /// Access extends past the end of the device
+    OutOfRange,
+    /// Address is not aligned to the erase or program unit
+    Unaligned,
+    /// Program or erase did not finish within the specified time
+    Timeout,
+    /// Data read back after program or erase does not match, first at
+    /// `offset` bytes past `addr`
+    VerifyFailed {
+        addr: u32,
+        offset: u32,
+    },
+    /// A bad sector could not be remapped, as all spare sectors are in use
+    NoSpareBlocks,
+    /// Device has no valid SFDP tables, or its parameters are not supported
+    /// by the driver
+    Unsupported,
+    Spim(SpimError),
+    /// More bits of the page at `addr` are wrong than its ECC can correct
+    EccUncorrectable {
+        addr: u32,
+    },
+    /// No ECC area has been set, sa. [SpiFlash::set_ecc_area]
+    NoEccArea,
+}
+
+impl From<SpimError> for FlashError {
+    fn from(value: SpimError) -> Self {
+        FlashError::Spim(value)
+    }
+}
+
+impl FlashError {
+    /// Longest encoding written by [FlashError::to_wire]
+    pub const WIRE_LEN: usize = 1 + SpimError::WIRE_LEN;
+
+    /// Encode for a host, sa. [SpimError::to_wire]
+    ///
+    /// | Code | Variant            | Fields               |
+    /// | :-   | :-                 | :-                   |
+    /// | 0    | `OutOfRange`       |                      |
+    /// | 1    | `Unaligned`        |                      |
+    /// | 2    | `Timeout`          |                      |
+    /// | 3    | `VerifyFailed`     | `addr`, `offset`     |
+    /// | 4    | `NoSpareBlocks`    |                      |
+    /// | 5    | `Unsupported`      |                      |
+    /// | 6    | `Spim`             | [SpimError] encoding |
+    /// | 7    | `EccUncorrectable` | `addr`               |
+    /// | 8    | `NoEccArea`        |                      |
+    ///
+    /// Returns the length, or 0 if `buf` is too short.
+    pub fn to_wire(&self, buf: &mut [u8]) -> usize {
+        match *self {
+            FlashError::OutOfRange => put_wire(buf, 0, &[]),
+            FlashError::Unaligned => put_wire(buf, 1, &[]),
+            FlashError::Timeout => put_wire(buf, 2, &[]),
+            FlashError::VerifyFailed { addr, offset } => put_wire(buf, 3, &[addr, offset]),
+            FlashError::NoSpareBlocks => put_wire(buf, 4, &[]),
+            FlashError::Unsupported => put_wire(buf, 5, &[]),
+            FlashError::Spim(e) => match buf.split_first_mut() {
+                Some((code, rest)) => match e.to_wire(rest) {
+                    0 => 0,
+                    n => {
+                        *code = 6;
+                        n + 1
+                    }
+                },
+                None => 0,
+            },
+            FlashError::EccUncorrectable { addr } => put_wire(buf, 7, &[addr]),
+            FlashError::NoEccArea => put_wire(buf, 8, &[]),
+        }
+    }
+}
+
+/// SPI NOR flash of `capacity` bytes
+pub struct SpiFlash<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+    capacity: u32,
+    /// Sa. [SpiFlash::verify_retries]
+    verify_retries: u32,
+    /// Sa. [SpiFlash::deep_power_down]
+    powered_down: bool,
+    /// Sa. [SpiFlash::set_ecc_area]
+    ecc_area: Option<u32>,
+}
+
+impl<'s, 'u> SpiFlash<'s, 'u> {
+    /// The device runs in SPI mode 0, so `spim` is reconfigured with CPOL and
+    /// CPHA cleared
+    ///
+    /// `capacity` is in bytes, up to 16 MiB.
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect, capacity: u32) -> Self {
+        spim.set_config(SpimConfig {
+            cpol: false,
+            cpha: false,
+            ..spim.config()
+        });
+        Self {
+            spim,
+            cs,
+            capacity: capacity.min(1 << 24),
+            verify_retries: 0,
+            powered_down: false,
+            ecc_area: None,
+        }
+    }
+
+    /// Configure the driver from the device's SFDP tables
+    ///
+    /// The driver programs in [PAGE_SIZE] pages and erases [SECTOR_SIZE]
+    /// sectors with the W25Q instructions, so devices that do not support
+    /// those are rejected with [FlashError::Unsupported].
+    pub fn from_sfdp(
+        spim: &'s mut UdmaSpim<'u, Enabled>,
+        cs: ChipSelect,
+    ) -> Result<Self, FlashError> {
+        let mut flash = Self::new(spim, cs, 0);
+        let params = flash.read_sfdp()?;
+        if (params.page_size_bytes as usize) < PAGE_SIZE
+            || params.erase_size_bytes as usize != SECTOR_SIZE
+            || params.erase_opcode != CMD_SECTOR_ERASE
+        {
+            return Err(FlashError::Unsupported);
+        }
+        flash.capacity = params.flash_size_bytes.min(1 << 24);
+        Ok(flash)
+    }
+
+    /// Read the Basic Flash Parameter Table
+    pub fn read_sfdp(&mut self) -> Result<SfdpParams, FlashError> {
+        let mut header = [0u8; sfdp::HEADER_LEN];
+        self.read_sfdp_bytes(0, &mut header)?;
+        let (addr, len) = sfdp::bfpt_location(&header).ok_or(FlashError::Unsupported)?;
+
+        let mut bfpt = [0u8; sfdp::BFPT_MAX_LEN];
+        self.read_sfdp_bytes(addr, &mut bfpt[..len])?;
+        SfdpParams::parse(&bfpt[..len]).ok_or(FlashError::Unsupported)
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Manufacturer ID, memory type and capacity code
+    pub fn jedec_id(&mut self) -> Result<[u8; 3], FlashError> {
+        let mut id = [0u8; 3];
+        let mut t = self.transaction()?;
+        t.write(&[CMD_JEDEC_ID])?;
+        t.read(&mut id)?;
+        Ok(id)
+    }
+
+    /// Factory programmed 64-bit unique ID
+    pub fn read_unique_id(&mut self) -> Result<[u8; 8], FlashError> {
+        let mut id = [0u8; 8];
+        let mut t = self.transaction()?;
+        // Instruction is followed by four dummy bytes
+        t.write(&[CMD_READ_UNIQUE_ID, 0, 0, 0, 0])?;
+        t.read(&mut id)?;
+        Ok(id)
+    }
+
+    pub fn read_status1(&mut self) -> Result<StatusReg1, FlashError> {
+        self.read_reg(CMD_READ_SR1).map(StatusReg1)
+    }
+
+    pub fn read_status2(&mut self) -> Result<StatusReg2, FlashError> {
+        self.read_reg(CMD_READ_SR2).map(StatusReg2)
+    }
+
+    pub fn read_status3(&mut self) -> Result<StatusReg3, FlashError> {
+        self.read_reg(CMD_READ_SR3).map(StatusReg3)
+    }
+
+    /// Read `buf.len()` bytes starting from `addr`
+    pub fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        self.check_range(addr, buf.len())?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let mut t = self.transaction()?;
+        t.write(&addr_cmd(CMD_READ, addr))?;
+        // The read continues for as long as CS stays asserted
+        t.continue_rx(buf)?;
+        Ok(())
+    }
+
+    /// Program `data` within a single page and wait for completion
+    ///
+    /// Programming can only clear bits, so the target is normally erased
+    /// first.
+    pub fn page_program(&mut self, addr: u32, data: &[u8]) -> Result<(), FlashError> {
+        self.begin_page_program(addr, data)?.wait()
+    }
+
+    /// Send `data` to be programmed within a single page, without waiting
+    /// for the flash to finish programming
+    ///
+    /// Returns once chip select has been released, which leaves the CPU free
+    /// to prepare the next page during the program time. The flash accepts no
+    /// other program or erase until the returned operation has completed, so
+    /// it borrows the driver until then.
+    pub fn begin_page_program(
+        &mut self,
+        addr: u32,
+        data: &[u8],
+    ) -> Result<ProgramOp<'_, 's, 'u>, FlashError> {
+        self.check_range(addr, data.len())?;
+        if addr as usize % PAGE_SIZE + data.len() > PAGE_SIZE {
+            return Err(FlashError::Unaligned);
+        }
+        if data.is_empty() {
+            return Ok(ProgramOp {
+                flash: self,
+                done: true,
+            });
+        }
+
+        self.write_enable()?;
+        {
+            let mut t = self.transaction()?;
+            t.write(&addr_cmd(CMD_PAGE_PROGRAM, addr))?;
+            t.write(data)?;
+        }
+        Ok(ProgramOp {
+            flash: self,
+            done: false,
+        })
+    }
+
+    /// Program `data` within a single page and read it back, programming it
+    /// again up to `attempts` times in total while it does not match
+    ///
+    /// The read-back is compared against `data` in small chunks, so no
+    /// page-sized buffer is needed. Programming the same data again only
+    /// clears bits that did not take the first time.
+    pub fn page_program_verified(
+        &mut self,
+        addr: u32,
+        data: &[u8],
+        attempts: usize,
+    ) -> Result<(), FlashError> {
+        let mut attempts = attempts.max(1);
+        loop {
+            self.page_program(addr, data)?;
+            match self.verify_region(addr, data.iter().copied()) {
+                Err(FlashError::VerifyFailed { .. }) if attempts > 1 => {
+                    attempts -= 1;
+                    self.verify_retries = self.verify_retries.saturating_add(1);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Compare the flash contents from `addr` on with `data`
+    ///
+    /// `data` can be generated on the fly, e.g., by decompressing an image or
+    /// by `iter::repeat(0xff)` to check an erase, so the comparison needs no
+    /// copy of the expected contents.
+    pub fn verify_region<I>(&mut self, addr: u32, data: I) -> Result<(), FlashError>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        let mut data = data.into_iter().peekable();
+        let mut expected = [0u8; VERIFY_CHUNK];
+        let mut got = [0u8; VERIFY_CHUNK];
+        let mut offset = 0;
+        while data.peek().is_some() {
+            let len = expected
+                .iter_mut()
+                .zip(&mut data)
+                .map(|(e, byte)| *e = byte)
+                .count();
+            self.read(addr + offset, &mut got[..len])?;
+            if let Some(i) = (0..len).find(|&i| got[i] != expected[i]) {
+                return Err(FlashError::VerifyFailed {
+                    addr,
+                    offset: offset + i as u32,
+                });
+            }
+            offset += len as u32;
+        }
+        Ok(())
+    }
+
+    /// Number of times [SpiFlash::page_program_verified] had to program a
+    /// page again, saturating
+    ///
+    /// A count that creeps up points to wearing flash before writes start to
+    /// fail.
+    pub fn verify_retries(&self) -> u32 {
+        self.verify_retries
+    }
+
+    /// Program `data` starting from `addr`, split at page boundaries
+    pub fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), FlashError> {
+        self.check_range(addr, data.len())?;
+
+        let mut addr = addr;
+        let mut data = data;
+        while !data.is_empty() {
+            let page_remaining = PAGE_SIZE - addr as usize % PAGE_SIZE;
+            let (chunk, rest) = data.split_at(page_remaining.min(data.len()));
+            self.page_program(addr, chunk)?;
+            addr += chunk.len() as u32;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Erase the sector at `addr`, which must be aligned to [SECTOR_SIZE]
+    pub fn erase_sector(&mut self, addr: u32) -> Result<(), FlashError> {
+        self.check_range(addr, SECTOR_SIZE)?;
+        if !(addr as usize).is_multiple_of(SECTOR_SIZE) {
+            return Err(FlashError::Unaligned);
+        }
+
+        self.write_enable()?;
+        self.spim
+            .transaction(self.cs)?
+            .write(&addr_cmd(CMD_SECTOR_ERASE, addr))?;
+        self.wait_idle(SECTOR_ERASE_US)
+    }
+
+    /// Put the device into deep power-down
+    ///
+    /// The device then ignores everything but the release instruction. The
+    /// driver remembers this and releases it before the next access, so
+    /// callers need not track it. Also call [SpiFlash::mark_powered_down]
+    /// if the supply was switched off instead, since the device may come
+    /// back up in power-down after that.
+    pub fn deep_power_down(&mut self) -> Result<(), FlashError> {
+        self.spim.transaction(self.cs)?.write(&[CMD_POWER_DOWN])?;
+        delay::micros(POWER_DOWN_US);
+        self.powered_down = true;
+        Ok(())
+    }
+
+    /// Release the device from deep power-down and wait until it accepts
+    /// instructions
+    ///
+    /// Harmless if the device was not powered down, e.g., right after
+    /// power-on when its state is unknown.
+    pub fn release_power_down(&mut self) -> Result<(), FlashError> {
+        self.spim
+            .transaction(self.cs)?
+            .write(&[CMD_RELEASE_POWER_DOWN])?;
+        delay::micros(RELEASE_POWER_DOWN_US);
+        self.powered_down = false;
+        Ok(())
+    }
+
+    /// Have the next access release the device from deep power-down first,
+    /// e.g., after its supply was switched back on
+    pub fn mark_powered_down(&mut self) {
+        self.powered_down = true;
+    }
+
+    /// Whether the next access releases the device from deep power-down
+    pub fn is_powered_down(&self) -> bool {
+        self.powered_down
+    }
+
+    /// Set the write enable latch, required before each program or erase
+    pub fn write_enable(&mut self) -> Result<(), FlashError> {
+        self.transaction()?.write(&[CMD_WRITE_ENABLE])?;
+        Ok(())
+    }
+
+    /// Poll until WIP clears, or twice `max_us` has passed
+    fn wait_idle(&mut self, max_us: u32) -> Result<(), FlashError> {
+        for _ in 0..2 * max_us / WIP_POLL_US {
+            if !self.read_status1()?.wip() {
+                return Ok(());
+            }
+            delay::micros(WIP_POLL_US);
+        }
+        Err(FlashError::Timeout)
+    }
+
+    fn read_sfdp_bytes(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        let mut t = self.transaction()?;
+        let [c, a2, a1, a0] = addr_cmd(CMD_READ_SFDP, addr);
+        // Address is followed by eight dummy clocks
+        t.write(&[c, a2, a1, a0, 0])?;
+        t.read(buf)?;
+        Ok(())
+    }
+
+    /// Open a chip select window, releasing the device from deep power-down
+    /// first if needed
+    fn transaction(&mut self) -> Result<SpimTransaction<'_, 'u>, FlashError> {
+        if self.powered_down {
+            self.release_power_down()?;
+        }
+        Ok(self.spim.transaction(self.cs)?)
+    }
+
+    fn read_reg(&mut self, cmd: u8) -> Result<u8, FlashError> {
+        let mut value = [0u8];
+        let mut t = self.transaction()?;
+        t.write(&[cmd])?;
+        t.read(&mut value)?;
+        Ok(value[0])
+    }
+
+    fn check_range(&self, addr: u32, len: usize) -> Result<(), FlashError> {
+        if addr as u64 + len as u64 > self.capacity as u64 {
+            return Err(FlashError::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+/// Page program in progress, sa. [SpiFlash::begin_page_program]
+#[must_use = "the flash rejects further programs until this completes"]
+pub struct ProgramOp<'f, 's, 'u> {
+    flash: &'f mut SpiFlash<'s, 'u>,
+    done: bool,
+}
+
+impl ProgramOp<'_, '_, '_> {
+    /// Returns true once the flash has finished programming
+    pub fn poll(&mut self) -> Result<bool, FlashError> {
+        if !self.done {
+            self.done = !self.flash.read_status1()?.wip();
+        }
+        Ok(self.done)
+    }
+
+    /// Block until programming has finished
+    pub fn wait(mut self) -> Result<(), FlashError> {
+        if self.poll()? {
+            return Ok(());
+        }
+        self.flash.wait_idle(PAGE_PROGRAM_US)
+    }
+}
+
+/// Instruction followed by a 24-bit address
+#[inline]
+fn addr_cmd(cmd: u8, addr: u32) -> [u8; 4] {
+    let [_, a2, a1, a0] = addr.to_be_bytes();
+    [cmd, a2, a1, a0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_wire_codes() {
+        let cases = [
+            (FlashError::OutOfRange, vec![0]),
+            (FlashError::Unaligned, vec![1]),
+            (FlashError::Timeout, vec![2]),
+            (
+                FlashError::VerifyFailed {
+                    addr: 0x0001_0000,
+                    offset: 7,
+                },
+                vec![3, 0, 0, 1, 0, 7, 0, 0, 0],
+            ),
+            (FlashError::NoSpareBlocks, vec![4]),
+            (FlashError::Unsupported, vec![5]),
+            (FlashError::Spim(SpimError::QueueFull), vec![6, 6]),
+            (
+                FlashError::Spim(SpimError::NotReady { sent: 2 }),
+                vec![6, 9, 2, 0, 0, 0],
+            ),
+            (
+                FlashError::EccUncorrectable { addr: 0x200 },
+                vec![7, 0, 2, 0, 0],
+            ),
+            (FlashError::NoEccArea, vec![8]),
+        ];
+        for (e, wire) in cases {
+            let mut buf = [0; FlashError::WIRE_LEN];
+            let n = e.to_wire(&mut buf);
+            assert_eq!(&buf[..n], &wire[..], "{:?}", e);
+            assert_eq!(e.to_wire(&mut buf[..n - 1]), 0);
+        }
+
+        let longest = FlashError::Spim(SpimError::ShortTransfer {
+            expected: 1,
+            received: 0,
+        });
+        let mut buf = [0; FlashError::WIRE_LEN];
+        assert_eq!(longest.to_wire(&mut buf), FlashError::WIRE_LEN);
+    }
+}
@@ -0,0 +1,158 @@
+//! Software ECC for whole pages
+//!
+//! NOR flash has no spare bytes next to a page, and a page program longer
+//! than [PAGE_SIZE] wraps around to the start of the page. The ECC of each
+//! page is therefore kept in a separate ECC area, set with
+//! [SpiFlash::set_ecc_area], which holds an [ECC_ENTRY_LEN]-byte entry per
+//! page below it.
+//!
+//! The code is the 22-bit Hamming code used for 256-byte NAND pages: line
+//! parities over the byte index and column parities over the bit index.
+//! It corrects one flipped bit per page and detects two. The code is stored
+//! inverted, so that an erased page matches its erased entry.
+//!
+//! An entry can only be programmed once after erasing, like the page
+//! itself. Erase the sector holding a page's entry before programming the
+//! page again, e.g., by keeping the data in whole areas that are erased
+//! together with their ECC sector.
+use super::{FlashError, SpiFlash, PAGE_SIZE, SECTOR_SIZE};
+
+/// Bytes per page in the ECC area: the 3-byte code and a reserved byte
+pub const ECC_ENTRY_LEN: usize = 4;
+
+/// Parity bits, two per bit of the byte index and of the bit index
+const CODE_MASK: u32 = (1 << 22) - 1;
+/// One bit of each parity pair
+const PAIR_MASK: u32 = 0x15_5555;
+
+/// Number of bits corrected in a page by
+/// [SpiFlash::read_page_with_ecc_correction], either in the data or the
+/// stored code
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CorrectedCount(pub u32);
+
+/// ECC of one [PAGE_SIZE] page, as stored in the ECC area
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageEcc(pub [u8; 3]);
+
+impl PageEcc {
+    pub fn compute(data: &[u8; PAGE_SIZE]) -> Self {
+        let [b0, b1, b2, _] = (!parity_bits(data)).to_le_bytes();
+        Self([b0, b1, b2])
+    }
+
+    /// Correct a single flipped bit in `data`, or return `None` if `data`
+    /// and the code differ in more bits than can be corrected
+    pub fn correct(&self, data: &mut [u8; PAGE_SIZE]) -> Option<CorrectedCount> {
+        let [b0, b1, b2] = self.0;
+        let stored = !u32::from_le_bytes([b0, b1, b2, 0]) & CODE_MASK;
+        let syndrome = stored ^ parity_bits(data);
+
+        if syndrome == 0 {
+            return Some(CorrectedCount(0));
+        }
+        // A flipped data bit flips exactly one parity of each pair
+        if (syndrome ^ (syndrome >> 1)) & PAIR_MASK == PAIR_MASK {
+            let odd = |k: u32| (syndrome >> (2 * k + 1)) & 1;
+            let byte = (0..8).fold(0, |acc, k| acc | (odd(k) << k));
+            let bit = (0..3).fold(0, |acc, k| acc | (odd(8 + k) << k));
+            data[byte as usize] ^= 1 << bit;
+            return Some(CorrectedCount(1));
+        }
+        // A flipped bit in the code itself
+        if syndrome.count_ones() == 1 {
+            return Some(CorrectedCount(1));
+        }
+        None
+    }
+}
+
+/// Parity pairs packed as bit `2k` for indices with bit `k` clear and bit
+/// `2k + 1` for those with it set, line parities for `k` in 0..8 and column
+/// parities from bit 16 up
+fn parity_bits(data: &[u8; PAGE_SIZE]) -> u32 {
+    let mut bits = 0u32;
+    let mut columns = 0u8;
+    for (idx, &byte) in data.iter().enumerate() {
+        columns ^= byte;
+        if byte.count_ones() % 2 == 1 {
+            for k in 0..8 {
+                bits ^= 1 << (2 * k + ((idx >> k) & 1));
+            }
+        }
+    }
+    for bit in 0..8 {
+        if (columns >> bit) & 1 == 1 {
+            for k in 0..3 {
+                bits ^= 1 << (16 + 2 * k + ((bit >> k) & 1));
+            }
+        }
+    }
+    bits
+}
+
+impl SpiFlash<'_, '_> {
+    /// Keep the ECC of the pages below `base` in the area starting at `base`
+    ///
+    /// `base` must be sector aligned, and the area of [ECC_ENTRY_LEN] bytes
+    /// per page below it must fit in the device.
+    pub fn set_ecc_area(&mut self, base: u32) -> Result<(), FlashError> {
+        if !(base as usize).is_multiple_of(SECTOR_SIZE) {
+            return Err(FlashError::Unaligned);
+        }
+        let len = base as usize / PAGE_SIZE * ECC_ENTRY_LEN;
+        self.check_range(base, len)?;
+        self.ecc_area = Some(base);
+        Ok(())
+    }
+
+    /// Program the page at `page_addr` with `data`, then its ECC entry
+    ///
+    /// The entry is read back, so programming a page whose entry has not
+    /// been erased fails with [FlashError::VerifyFailed].
+    pub fn write_page_with_ecc(
+        &mut self,
+        page_addr: u32,
+        data: &[u8; PAGE_SIZE],
+    ) -> Result<(), FlashError> {
+        let entry_addr = self.ecc_entry_addr(page_addr)?;
+        let PageEcc([b0, b1, b2]) = PageEcc::compute(data);
+        let entry = [b0, b1, b2, 0xff];
+
+        self.page_program(page_addr, data)?;
+        self.page_program(entry_addr, &entry)?;
+        self.verify_region(entry_addr, entry.iter().copied())
+    }
+
+    /// Read the page at `page_addr` into `buf` and correct it with its ECC
+    /// entry
+    ///
+    /// Fails with [FlashError::EccUncorrectable] if more than one bit is
+    /// wrong. `buf` then holds the data as read.
+    pub fn read_page_with_ecc_correction(
+        &mut self,
+        page_addr: u32,
+        buf: &mut [u8; PAGE_SIZE],
+    ) -> Result<CorrectedCount, FlashError> {
+        let entry_addr = self.ecc_entry_addr(page_addr)?;
+        let mut entry = [0u8; ECC_ENTRY_LEN];
+        self.read(page_addr, buf)?;
+        self.read(entry_addr, &mut entry)?;
+
+        let [b0, b1, b2, _] = entry;
+        PageEcc([b0, b1, b2])
+            .correct(buf)
+            .ok_or(FlashError::EccUncorrectable { addr: page_addr })
+    }
+
+    fn ecc_entry_addr(&self, page_addr: u32) -> Result<u32, FlashError> {
+        let base = self.ecc_area.ok_or(FlashError::NoEccArea)?;
+        if !(page_addr as usize).is_multiple_of(PAGE_SIZE) {
+            return Err(FlashError::Unaligned);
+        }
+        if page_addr >= base {
+            return Err(FlashError::OutOfRange);
+        }
+        Ok(base + (page_addr as usize / PAGE_SIZE * ECC_ENTRY_LEN) as u32)
+    }
+}
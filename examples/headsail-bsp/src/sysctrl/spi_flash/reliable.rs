@@ -0,0 +1,339 @@
+//! Bad sector remapping on top of [SpiFlash]
+//!
+//! The last sector of the device holds the bad block table, and the `SPARES`
+//! sectors before it are kept in reserve. The sectors below those make up the
+//! logical address space. Sectors that fail to program or erase are replaced
+//! by a spare, and their contents are carried over.
+//!
+//! The table starts with a magic word followed by 4-byte entries, each a
+//! little-endian `(logical, spare)` sector pair. Entries are appended to the
+//! erased part of the sector, so updating the table needs no erase. Later
+//! entries override earlier ones. A spare that fails itself is recorded with
+//! a logical sector of [RETIRED].
+use super::{FlashError, SpiFlash, PAGE_SIZE, SECTOR_SIZE};
+
+const MAGIC: u32 = u32::from_le_bytes(*b"BBT1");
+const MAGIC_LEN: usize = 4;
+const ENTRY_LEN: usize = 4;
+/// Logical sector of an entry that retires a bad spare
+pub const RETIRED: u16 = 0xfffe;
+const ERASED: u16 = 0xffff;
+
+/// Program attempts before a sector is considered bad
+const PROGRAM_ATTEMPTS: usize = 2;
+
+/// [SpiFlash] with `SPARES` spare sectors for remapping bad ones
+pub struct ReliableFlash<'s, 'u, const SPARES: usize> {
+    flash: SpiFlash<'s, 'u>,
+    /// Table entries in the order they were written
+    entries: [(u16, u16); SPARES],
+    len: usize,
+}
+
+impl<'s, 'u, const SPARES: usize> ReliableFlash<'s, 'u, SPARES> {
+    const VALID_SPARES: () = assert!(SPARES > 0 && SPARES <= (SECTOR_SIZE - MAGIC_LEN) / ENTRY_LEN);
+
+    /// Load the bad block table, or create an empty one if the device has
+    /// none
+    pub fn new(flash: SpiFlash<'s, 'u>) -> Result<Self, FlashError> {
+        let () = Self::VALID_SPARES;
+
+        if (flash.capacity() as usize / SECTOR_SIZE) < SPARES + 2 {
+            return Err(FlashError::OutOfRange);
+        }
+        let mut this = Self {
+            flash,
+            entries: [(ERASED, ERASED); SPARES],
+            len: 0,
+        };
+
+        let mut magic = [0u8; MAGIC_LEN];
+        this.flash.read(this.table_addr(), &mut magic)?;
+        if u32::from_le_bytes(magic) != MAGIC {
+            this.format_table()?;
+            return Ok(this);
+        }
+
+        let mut buf = [0u8; PAGE_SIZE];
+        let mut addr = this.table_addr() + MAGIC_LEN as u32;
+        'load: while this.len < SPARES {
+            let n = ((SPARES - this.len) * ENTRY_LEN).min(buf.len());
+            this.flash.read(addr, &mut buf[..n])?;
+            for entry in buf[..n].chunks_exact(ENTRY_LEN) {
+                let logical = u16::from_le_bytes([entry[0], entry[1]]);
+                let spare = u16::from_le_bytes([entry[2], entry[3]]);
+                if logical == ERASED {
+                    break 'load;
+                }
+                // Skip entries torn by a power loss
+                if this.is_spare(spare) {
+                    this.entries[this.len] = (logical, spare);
+                    this.len += 1;
+                }
+            }
+            addr += n as u32;
+        }
+        Ok(this)
+    }
+
+    pub fn free(self) -> SpiFlash<'s, 'u> {
+        self.flash
+    }
+
+    /// Size of the logical address space in bytes
+    pub fn capacity(&self) -> u32 {
+        (self.logical_sectors() * SECTOR_SIZE) as u32
+    }
+
+    /// Number of sectors remapped or retired so far
+    pub fn bad_blocks(&self) -> usize {
+        self.len
+    }
+
+    /// Read `buf.len()` bytes starting from logical address `addr`
+    pub fn read(&mut self, addr: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        self.check_range(addr, buf.len())?;
+
+        let addr = addr as usize;
+        let mut done = 0;
+        for chunk in split_sectors(addr, buf.len()) {
+            let logical = addr + done;
+            let phys = self.physical(logical / SECTOR_SIZE) * SECTOR_SIZE + logical % SECTOR_SIZE;
+            self.flash.read(phys as u32, &mut buf[done..done + chunk])?;
+            done += chunk;
+        }
+        Ok(())
+    }
+
+    /// Program `data` at logical address `addr` and verify it
+    ///
+    /// The target must be erased. A sector that still fails verification
+    /// after a second attempt is remapped to a spare, and the write is
+    /// retried there.
+    pub fn write(&mut self, addr: u32, data: &[u8]) -> Result<(), FlashError> {
+        self.check_range(addr, data.len())?;
+
+        let mut addr = addr as usize;
+        let mut data = data;
+        for chunk in split_sectors(addr, data.len()) {
+            let (sector, offset) = (addr / SECTOR_SIZE, addr % SECTOR_SIZE);
+            let (head, rest) = data.split_at(chunk);
+
+            let phys = self.physical(sector) * SECTOR_SIZE + offset;
+            match self.program_verified(phys as u32, head) {
+                Ok(()) => {}
+                Err(FlashError::VerifyFailed { .. }) => self.remap(sector, Some((offset, head)))?,
+                Err(e) => return Err(e),
+            }
+
+            addr += chunk;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Erase the logical sector at `addr`, remapping it if it does not erase
+    /// cleanly
+    pub fn erase_sector(&mut self, addr: u32) -> Result<(), FlashError> {
+        self.check_range(addr, SECTOR_SIZE)?;
+        if !(addr as usize).is_multiple_of(SECTOR_SIZE) {
+            return Err(FlashError::Unaligned);
+        }
+
+        let sector = addr as usize / SECTOR_SIZE;
+        match self.erase_verified(self.physical(sector)) {
+            Ok(()) => Ok(()),
+            Err(FlashError::VerifyFailed { .. }) => self.remap(sector, None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Test every sector with an erase, program and erase cycle, and rebuild
+    /// the bad block table from the results
+    ///
+    /// This erases the whole device, including the previous table. Returns
+    /// the number of bad sectors found.
+    pub fn scan_bad_blocks(&mut self) -> Result<usize, FlashError> {
+        self.format_table()?;
+
+        let mut bad = 0;
+        let spares = self.logical_sectors()..self.table_sector();
+        for spare in spares {
+            if !self.test_sector(spare)? {
+                self.append(RETIRED, spare as u16)?;
+                bad += 1;
+            }
+        }
+        for sector in 0..self.logical_sectors() {
+            if !self.test_sector(sector)? {
+                let spare = self.next_spare()?;
+                self.append(sector as u16, spare as u16)?;
+                bad += 1;
+            }
+        }
+        Ok(bad)
+    }
+
+    /// Move logical `sector` to a fresh spare, writing `pending` data at its
+    /// offset on the way
+    fn remap(&mut self, sector: usize, pending: Option<(usize, &[u8])>) -> Result<(), FlashError> {
+        let old = self.physical(sector);
+        loop {
+            let spare = self.next_spare()?;
+            match self.relocate(old, spare, pending) {
+                Ok(()) => return self.append(sector as u16, spare as u16),
+                Err(FlashError::VerifyFailed { .. }) => self.append(RETIRED, spare as u16)?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Copy physical sector `from` to `to`, with `pending` overlaid
+    fn relocate(
+        &mut self,
+        from: usize,
+        to: usize,
+        pending: Option<(usize, &[u8])>,
+    ) -> Result<(), FlashError> {
+        self.erase_verified(to)?;
+
+        let Some((offset, data)) = pending else {
+            return Ok(());
+        };
+        let mut page = [0u8; PAGE_SIZE];
+        for page_offset in (0..SECTOR_SIZE).step_by(PAGE_SIZE) {
+            self.flash
+                .read((from * SECTOR_SIZE + page_offset) as u32, &mut page)?;
+
+            // Overlay the part of `data` that falls on this page
+            let start = offset.max(page_offset);
+            let end = (offset + data.len()).min(page_offset + PAGE_SIZE);
+            if start < end {
+                page[start - page_offset..end - page_offset]
+                    .copy_from_slice(&data[start - offset..end - offset]);
+            }
+
+            if page.iter().any(|&b| b != 0xff) {
+                self.program_verified((to * SECTOR_SIZE + page_offset) as u32, &page)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Erase, program with zeros and erase physical `sector`, returning
+    /// whether each step verified
+    fn test_sector(&mut self, sector: usize) -> Result<bool, FlashError> {
+        match self.test_cycle(sector) {
+            Ok(()) => Ok(true),
+            Err(FlashError::VerifyFailed { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn test_cycle(&mut self, sector: usize) -> Result<(), FlashError> {
+        let zeros = [0u8; PAGE_SIZE];
+        self.erase_verified(sector)?;
+        for page_offset in (0..SECTOR_SIZE).step_by(PAGE_SIZE) {
+            self.program_verified((sector * SECTOR_SIZE + page_offset) as u32, &zeros)?;
+        }
+        self.erase_verified(sector)
+    }
+
+    fn program_verified(&mut self, addr: u32, data: &[u8]) -> Result<(), FlashError> {
+        let mut res = Ok(());
+        for _ in 0..PROGRAM_ATTEMPTS {
+            // Programming the same data again may finish off weak bits
+            self.flash.write(addr, data)?;
+            res = self.flash.verify_region(addr, data.iter().copied());
+            if !matches!(res, Err(FlashError::VerifyFailed { .. })) {
+                return res;
+            }
+        }
+        res
+    }
+
+    fn erase_verified(&mut self, sector: usize) -> Result<(), FlashError> {
+        let addr = (sector * SECTOR_SIZE) as u32;
+        self.flash.erase_sector(addr)?;
+        self.flash
+            .verify_region(addr, core::iter::repeat_n(0xff, SECTOR_SIZE))
+    }
+
+    /// Erase the table sector and write the magic word
+    fn format_table(&mut self) -> Result<(), FlashError> {
+        self.len = 0;
+        let addr = self.table_addr();
+        self.flash.erase_sector(addr)?;
+        self.flash.write(addr, &MAGIC.to_le_bytes())
+    }
+
+    fn append(&mut self, logical: u16, spare: u16) -> Result<(), FlashError> {
+        if self.len == SPARES {
+            return Err(FlashError::NoSpareBlocks);
+        }
+        let [l0, l1] = logical.to_le_bytes();
+        let [s0, s1] = spare.to_le_bytes();
+        let addr = self.table_addr() + (MAGIC_LEN + self.len * ENTRY_LEN) as u32;
+        self.flash.write(addr, &[l0, l1, s0, s1])?;
+        self.entries[self.len] = (logical, spare);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// First spare that is neither in use nor retired
+    fn next_spare(&self) -> Result<usize, FlashError> {
+        (self.logical_sectors()..self.table_sector())
+            .find(|&spare| {
+                !self.entries[..self.len]
+                    .iter()
+                    .any(|&(_, used)| used as usize == spare)
+            })
+            .ok_or(FlashError::NoSpareBlocks)
+    }
+
+    /// Physical sector currently backing logical `sector`
+    fn physical(&self, sector: usize) -> usize {
+        self.entries[..self.len]
+            .iter()
+            .rev()
+            .find(|&&(logical, _)| logical as usize == sector)
+            .map_or(sector, |&(_, spare)| spare as usize)
+    }
+
+    fn is_spare(&self, sector: u16) -> bool {
+        (self.logical_sectors()..self.table_sector()).contains(&(sector as usize))
+    }
+
+    fn table_sector(&self) -> usize {
+        self.flash.capacity() as usize / SECTOR_SIZE - 1
+    }
+
+    fn table_addr(&self) -> u32 {
+        (self.table_sector() * SECTOR_SIZE) as u32
+    }
+
+    fn logical_sectors(&self) -> usize {
+        self.table_sector() - SPARES
+    }
+
+    fn check_range(&self, addr: u32, len: usize) -> Result<(), FlashError> {
+        if addr as u64 + len as u64 > self.capacity() as u64 {
+            return Err(FlashError::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+/// Lengths of the pieces of `addr..addr + len` that fall in separate sectors
+fn split_sectors(addr: usize, len: usize) -> impl Iterator<Item = usize> {
+    let mut addr = addr;
+    let end = addr + len;
+    core::iter::from_fn(move || {
+        if addr >= end {
+            return None;
+        }
+        let chunk = (SECTOR_SIZE - addr % SECTOR_SIZE).min(end - addr);
+        addr += chunk;
+        Some(chunk)
+    })
+}
@@ -0,0 +1,110 @@
+//! JEDEC Serial Flash Discoverable Parameters (JESD216)
+//!
+//! Only the Basic Flash Parameter Table is parsed. It is present on every
+//! SFDP-capable device and describes density, erase types and fast read
+//! modes.
+
+/// SFDP header followed by the first parameter header, which the standard
+/// requires to describe the Basic Flash Parameter Table
+pub(super) const HEADER_LEN: usize = 16;
+/// 16 DWORDs as of JESD216B. Later revisions append further DWORDs.
+pub(super) const BFPT_MAX_LEN: usize = 64;
+
+const SIGNATURE: [u8; 4] = *b"SFDP";
+const BFPT_ID: u16 = 0xff00;
+
+/// Parameters read with [SpiFlash::read_sfdp](super::SpiFlash::read_sfdp)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SfdpParams {
+    /// Saturates at 4 GiB
+    pub flash_size_bytes: u32,
+    /// 256 bytes if the table predates JESD216A
+    pub page_size_bytes: u16,
+    /// Smallest supported erase unit
+    pub erase_size_bytes: u32,
+    /// Instruction for erasing [SfdpParams::erase_size_bytes]
+    pub erase_opcode: u8,
+    /// Any of the 1-1-4, 1-4-4 or 4-4-4 fast reads
+    pub supports_quad: bool,
+    /// Double transfer rate fast reads
+    pub supports_dtr: bool,
+}
+
+/// Location of the Basic Flash Parameter Table from the SFDP header
+///
+/// Returns `(address, length in bytes)`, or `None` if the signature or the
+/// first parameter header is invalid.
+pub(super) fn bfpt_location(header: &[u8; HEADER_LEN]) -> Option<(u32, usize)> {
+    if header[0..4] != SIGNATURE {
+        return None;
+    }
+    let param = &header[8..16];
+    let id = u16::from_le_bytes([param[0], param[7]]);
+    if id != BFPT_ID {
+        return None;
+    }
+    let len = (param[3] as usize * 4).min(BFPT_MAX_LEN);
+    let addr = u32::from_le_bytes([param[4], param[5], param[6], 0]);
+    // DWORDs 1 and 2 hold the mandatory density and erase information
+    (len >= 8).then_some((addr, len))
+}
+
+impl SfdpParams {
+    /// Parse the Basic Flash Parameter Table
+    ///
+    /// `bfpt` must be at least 2 DWORDs. Fields from DWORDs past its end
+    /// fall back to the values implied by the earlier revisions.
+    pub fn parse(bfpt: &[u8]) -> Option<Self> {
+        let dword = |n: usize| {
+            let b = bfpt.get(4 * (n - 1)..4 * n)?;
+            Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let dw1 = dword(1)?;
+        let dw2 = dword(2)?;
+
+        let size_bits = if dw2 & (1 << 31) == 0 {
+            dw2 as u64 + 1
+        } else {
+            1u64.checked_shl(dw2 & 0x7fff_ffff).unwrap_or(u64::MAX)
+        };
+        let flash_size_bytes = (size_bits / 8).min(u32::MAX as u64) as u32;
+
+        // DWORD1 only describes the 4 KiB erase
+        let mut erase = (dw1 & 0b11 == 0b01).then_some((4096, (dw1 >> 8) as u8));
+        // DWORDs 8 and 9 hold up to four erase types as (size exponent,
+        // opcode) byte pairs
+        if let (Some(dw8), Some(dw9)) = (dword(8), dword(9)) {
+            let types = (dw8 as u64) | ((dw9 as u64) << 32);
+            for i in 0..4 {
+                let ty = (types >> (16 * i)) as u16;
+                let (exp, opcode) = (ty as u8, (ty >> 8) as u8);
+                if exp == 0 || exp >= 32 {
+                    continue;
+                }
+                let size = 1u32 << exp;
+                if erase.is_none_or(|(smallest, _)| size < smallest) {
+                    erase = Some((size, opcode));
+                }
+            }
+        }
+        let (erase_size_bytes, erase_opcode) = erase?;
+
+        let page_size_bytes = match dword(11) {
+            Some(dw11) => 1u16 << ((dw11 >> 4) & 0xf),
+            None => 256,
+        };
+
+        let quad_1_1_4 = dw1 & (1 << 22) != 0;
+        let quad_1_4_4 = dw1 & (1 << 21) != 0;
+        let quad_4_4_4 = dword(5).is_some_and(|dw5| dw5 & (1 << 4) != 0);
+
+        Some(Self {
+            flash_size_bytes,
+            page_size_bytes,
+            erase_size_bytes,
+            erase_opcode,
+            supports_quad: quad_1_1_4 || quad_1_4_4 || quad_4_4_4,
+            supports_dtr: dw1 & (1 << 19) != 0,
+        })
+    }
+}
@@ -1,30 +1,199 @@
+#[cfg(feature = "rtic")]
+pub mod rtic_async;
+#[cfg(feature = "spim")]
+pub mod spim;
+#[cfg(feature = "udma-uart")]
 pub mod uart;
 
 use core::marker::PhantomData;
 
-use crate::pac;
+use crate::{mmio::reg_write, pac, sealed::Sealed};
+#[cfg(feature = "spim")]
+pub use spim::UdmaSpim;
+#[cfg(feature = "udma-uart")]
 pub use uart::UdmaUart;
 
 /// Type-state trait for uDMA peripherals in different states
-pub trait UdmaPeriphState {}
+pub trait UdmaPeriphState: Sealed {}
 
 pub struct Enabled;
+impl Sealed for Enabled {}
 impl UdmaPeriphState for Enabled {}
 
 pub struct Disabled;
+impl Sealed for Disabled {}
 impl UdmaPeriphState for Disabled {}
 
+/// uDMA peripherals, numbered by their bit in the clock gate register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum UdmaPeripheral {
+    Uart = 0,
+    Spim = 1,
+    I2c0 = 2,
+    I2c1 = 3,
+    Sdio = 4,
+    I2s = 5,
+    Cam = 6,
+    Filter = 7,
+}
+
+/// uDMA peripherals instantiated by the VP platform description,
+/// `vp/devel/headsail.repl`
+pub const VP_PERIPHERALS: [UdmaPeripheral; 3] = [
+    UdmaPeripheral::Uart,
+    UdmaPeripheral::Spim,
+    UdmaPeripheral::I2c0,
+];
+
+/// Version of the PAC whose uDMA register layout the BSP is written against
+///
+/// The uDMA has no version register to compare this with, sa.
+/// [Udma::check_layout], so it is reported in [version](crate::version) for
+/// logs to show. Keep in sync with the `headsail-sysctrl-pac` dependency.
+pub const PAC_VERSION: &str = "0.1.1";
+
+/// The uDMA registers are not where the PAC puts them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LayoutError {
+    /// CTRL_CFG_EVENT did not read back what was written
+    EventReadBack { wrote: u32, read: u32 },
+    /// Writing CTRL_CFG_EVENT changed the clock gate or reset register
+    Aliased,
+}
+
 /// Relocatable driver for uDMA IP
 pub struct Udma<'u>(pub &'u pac::sysctrl::Udma);
 
+/// Drivers for the peripherals found by [Udma::probe_peripheral]
 pub struct UdmaParts<'u> {
-    pub uart: UdmaUart<'u, Disabled>,
+    #[cfg(feature = "udma-uart")]
+    pub uart: Option<UdmaUart<'u, Disabled>>,
+    #[cfg(feature = "spim")]
+    pub spim: Option<UdmaSpim<'u, Disabled>>,
+    _udma: PhantomData<&'u pac::sysctrl::Udma>,
 }
 
 impl<'u> Udma<'u> {
-    pub fn split(self) -> UdmaParts<'u> {
+    /// Returns true if the clock gate bit of `periph` can be both set and
+    /// cleared
+    ///
+    /// Reads from a peripheral that is not instantiated return all zeros or
+    /// all ones. The clock gate register is restored afterwards.
+    ///
+    /// The VP does not map the uDMA control block at 0x1A102000, so there
+    /// the clock gate cannot be probed. Returns whether the VP platform
+    /// description instantiates `periph` instead, sa. [VP_PERIPHERALS].
+    pub fn probe_peripheral(&self, periph: UdmaPeripheral) -> bool {
+        if cfg!(feature = "vp") {
+            return VP_PERIPHERALS.contains(&periph);
+        }
+
+        let cg = self.0.ctrl_cfg_cg();
+        let orig = cg.read().bits();
+        let mask = 1 << periph as u32;
+
+        reg_write!(cg, |w| unsafe { w.bits(orig | mask) });
+        let set = cg.read().bits() & mask != 0;
+        reg_write!(cg, |w| unsafe { w.bits(orig & !mask) });
+        let cleared = cg.read().bits() & mask == 0;
+        reg_write!(cg, |w| unsafe { w.bits(orig) });
+
+        set && cleared
+    }
+
+    /// Check that the uDMA control registers are at the offsets of the PAC
+    ///
+    /// The uDMA has no version register, so this writes a pattern to the
+    /// plain read-write CTRL_CFG_EVENT and checks that it reads back, and
+    /// that the neighbouring clock gate and reset registers did not change.
+    /// A uDMA with shifted offsets fails either check. All registers are
+    /// restored afterwards.
+    pub fn check_layout(&self) -> Result<(), LayoutError> {
+        /// Distinct non-zero bytes, so that a byte lane or register shift
+        /// shows up
+        const PATTERN: u32 = 0xa55a_c33c;
+
+        let udma = self.0;
+        let (cg, rst) = (
+            udma.ctrl_cfg_cg().read().bits(),
+            udma.ctrl_cfg_rst().read().bits(),
+        );
+        let event = udma.ctrl_cfg_event();
+        let orig = event.read().bits();
+
+        reg_write!(event, |w| unsafe { w.bits(PATTERN) });
+        let read = event.read().bits();
+        let unchanged =
+            udma.ctrl_cfg_cg().read().bits() == cg && udma.ctrl_cfg_rst().read().bits() == rst;
+        reg_write!(event, |w| unsafe { w.bits(orig) });
+
+        if read != PATTERN {
+            return Err(LayoutError::EventReadBack {
+                wrote: PATTERN,
+                read,
+            });
+        }
+        if !unchanged {
+            return Err(LayoutError::Aliased);
+        }
+        Ok(())
+    }
+
+    /// Drivers for the peripherals that respond, after [Udma::check_layout]
+    ///
+    /// Writing a uDMA whose layout differs from the PAC has unpredictable
+    /// effects, so no drivers are handed out if the check fails. Use
+    /// [Udma::split_unchecked] to skip the check. The VP does not map the
+    /// control registers the check uses, so it is skipped there.
+    pub fn split(self) -> Result<UdmaParts<'u>, LayoutError> {
+        if !cfg!(feature = "vp") {
+            self.check_layout()?;
+        }
+        Ok(self.split_unchecked())
+    }
+
+    /// Drivers for the peripherals that respond, without checking the layout
+    pub fn split_unchecked(self) -> UdmaParts<'u> {
         UdmaParts {
-            uart: UdmaUart::<Disabled>(self.0, PhantomData),
+            #[cfg(feature = "udma-uart")]
+            uart: self
+                .probe_peripheral(UdmaPeripheral::Uart)
+                .then_some(UdmaUart::<Disabled>(
+                    self.0,
+                    PhantomData,
+                    uart::WaterMarks::DEFAULT,
+                )),
+            #[cfg(feature = "spim")]
+            spim: self
+                .probe_peripheral(UdmaPeripheral::Spim)
+                .then(|| UdmaSpim::<Disabled> {
+                    udma: self.0,
+                    cfg: spim::SpimConfig::default(),
+                    cs: None,
+                    strict_cs: true,
+                    last_eot_time: 0,
+                    min_deassert_cycles: 0,
+                    completion: spim::CompletionMode::Polling,
+                    missed_events: 0,
+                    parked: None,
+                    completion_pulse: None,
+                    _pd: PhantomData,
+                }),
+            _udma: PhantomData,
         }
     }
 }
+
+/// Write one `name: value` line per register, sa. [UdmaSpim::dump_registers]
+#[cfg(feature = "debug-registers")]
+fn dump_registers<W>(w: &mut W, regs: &[(&str, u32)]) -> Result<(), W::Error>
+where
+    W: ufmt::uWrite + ?Sized,
+{
+    for (name, value) in regs {
+        ufmt::uwriteln!(w, "{}: {:#x}", name, value)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! `Future` glue for RTIC async users
+//!
+//! This is the minimal amount of code required to await a uDMA transfer, and
+//! serves as a reference for futures of other peripherals. The SPIM event must
+//! be routed to an interrupt whose handler calls [on_spim_event].
+use core::{cell::RefCell, future::Future, task::Poll, task::Waker};
+
+use critical_section::Mutex;
+
+use super::{
+    spim::{self, SpimError},
+    Enabled, UdmaSpim,
+};
+
+/// Waker of the task awaiting a SPIM transfer
+static SPIM_WAKER: Mutex<RefCell<Option<Waker>>> = Mutex::new(RefCell::new(None));
+
+/// Wake the task awaiting a SPIM transfer, call from the SPIM interrupt
+/// handler
+pub fn on_spim_event() {
+    critical_section::with(|cs| {
+        if let Some(waker) = SPIM_WAKER.borrow_ref_mut(cs).take() {
+            waker.wake();
+        }
+    });
+}
+
+/// Full-duplex transfer where the received data replaces `data`
+///
+/// Chip select must be asserted by the caller, sa. [UdmaSpim::sot]. The
+/// transfer is started on first poll.
+pub fn spim_transfer_future(
+    spim: &'static Mutex<RefCell<UdmaSpim<'static, Enabled>>>,
+    data: &'static mut [u8],
+) -> impl Future<Output = Result<(), SpimError>> {
+    let mut started = false;
+
+    core::future::poll_fn(move |cx| {
+        critical_section::with(|cs| {
+            let mut spim = spim.borrow_ref_mut(cs);
+
+            if !started {
+                spim::check_buf(data)?;
+
+                let (ptr, len) = (data.as_mut_ptr(), data.len());
+                spim.start_rx(ptr, len);
+                spim.start_tx(ptr, len);
+                spim.enqueue_cmd_single_word(spim::data_cmd(spim::SPI_CMD_FULL_DUPL, len));
+                started = true;
+            }
+
+            // Register before checking to not miss an event firing in between
+            *SPIM_WAKER.borrow_ref_mut(cs) = Some(cx.waker().clone());
+            if spim.is_idle() {
+                SPIM_WAKER.borrow_ref_mut(cs).take();
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        })
+    })
+}
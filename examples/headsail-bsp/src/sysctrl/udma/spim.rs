@@ -0,0 +1,1662 @@
+//! uDMA SPI master (SPIM)
+//!
+//! The uDMA SPIM is driven by a stream of 32-bit command words placed in
+//! memory and fed to the peripheral over the CMD channel. Payload data moves
+//! over the TX and RX channels. A typical transaction looks like this:
+//!
+//! ```text
+//! CMD: CFG, SOT(cs), TX_DATA(len), EOT
+//! TX:  <len bytes>
+//! ```
+//!
+//! Command encodings are documented in PULP `udma_spim`:
+//! <https://github.com/pulp-platform/udma_qspi>
+pub mod calibrate;
+pub mod coalesce;
+pub mod command;
+pub mod daisy;
+pub mod fault;
+pub mod hal;
+pub mod init;
+pub mod queue;
+pub mod ready;
+pub mod regmap;
+pub mod reliable;
+pub mod round_robin;
+pub mod slots;
+pub mod stream;
+pub mod tune;
+
+use core::marker::PhantomData;
+
+use riscv::register::mcycle;
+
+use super::{Disabled, Enabled, UdmaPeripheral};
+pub use calibrate::{CalError, CalibrationTest};
+pub use coalesce::SpimCoalescer;
+pub use command::{CommandBuf, SpiCommandBuilder, UcChannel};
+pub use daisy::DaisyChain;
+pub use fault::{CsErrorAction, DmaFaultCode, DmaFaultHandler};
+pub use hal::SpimSpiDevice;
+pub use init::{InitRunner, InitStatus, InitStep};
+pub use queue::{QueueEvent, SpimQueue, SpimTransfer};
+pub use regmap::SpiRegisterMap;
+pub use reliable::{ReliableError, SpimReliableTransfer};
+pub use round_robin::{DeviceTransfer, RetryPolicy, RoundRobinEvent, SpimDevice, SpimRoundRobin};
+pub use slots::{Packet, SlotEvent, SpimSlotRing};
+pub use stream::SpimStreamWriter;
+pub use tune::{SpimAutoTuner, TuneEntry};
+
+use crate::{
+    delay,
+    event::{self, Flag},
+    flags::impl_flags_fmt,
+    fmt::put_wire,
+    mask_u32,
+    mmio::{reg_modify, reg_write},
+    pac, poll, poll_bit_clear, poll_eq,
+    sysctrl::{mmap, soc_ctrl},
+    trace_event, unmask_u32,
+};
+
+// SPI command IDs are stored in bits [31:28] of each command word
+pub(crate) const SPI_CMD_CFG: u32 = 0 << 28;
+pub(crate) const SPI_CMD_SOT: u32 = 1 << 28;
+pub(crate) const SPI_CMD_SEND_CMD: u32 = 2 << 28;
+pub(crate) const SPI_CMD_TX_DATA: u32 = 6 << 28;
+pub(crate) const SPI_CMD_RX_DATA: u32 = 7 << 28;
+pub(crate) const SPI_CMD_EOT: u32 = 9 << 28;
+pub(crate) const SPI_CMD_FULL_DUPL: u32 = 12 << 28;
+pub(crate) const SPI_CMD_SETUP_UCA: u32 = 13 << 28;
+pub(crate) const SPI_CMD_SETUP_UCS: u32 = 14 << 28;
+
+/// Word size used for all data commands issued by this driver
+const BITS_PER_WORD: u8 = 8;
+
+/// Default settle time for [PowerPolicy::AutoGate]
+///
+/// The uDMA clock gate has no documented stabilization requirement.
+pub const GATE_SETTLE_CYCLES: u32 = 0;
+
+/// The words field of TX_DATA / RX_DATA / FULL_DUPL is 16 bits wide
+pub const MAX_XFER_LEN: usize = 1 << 16;
+
+/// uDMA channel DATASIZE field values
+#[repr(u8)]
+enum DataSize {
+    Byte = 0b00,
+    Word = 0b10,
+}
+
+/// Chip select line of the uDMA SPIM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ChipSelect {
+    Cs0 = 0,
+    Cs1 = 1,
+    Cs2 = 2,
+    Cs3 = 3,
+}
+
+/// SPI configuration issued as the CFG command at the start of each
+/// transaction
+///
+/// Outside of the BSP, start from [SpimConfig::default], or
+/// [SpimConfig::DEFAULT] in a const context, and adjust with the `with_*`
+/// methods, as fields may be added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SpimConfig {
+    /// SCK = peripheral clock / (2 * (`clk_div` + 1))
+    pub clk_div: u8,
+    /// Clock polarity
+    pub cpol: bool,
+    /// Clock phase
+    pub cpha: bool,
+    /// When to open the SPIM clock gate
+    pub power: PowerPolicy,
+    /// Byte clocked out on MOSI while receiving
+    ///
+    /// With `None`, reads use RX_DATA and MOSI carries whatever is on the TX
+    /// path. Devices that interpret MOSI during reads (e.g., SD cards expect
+    /// `0xff`) need an explicit value, in which case reads are issued as
+    /// FULL_DUPL with the receive buffer prefilled with this byte.
+    pub tx_idle_byte: Option<u8>,
+}
+
+impl Default for SpimConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// SPIM clock gate policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerPolicy {
+    /// Clock gate is open from [UdmaSpim::enable] to [UdmaSpim::disable]
+    AlwaysOn,
+    /// Clock gate is opened by the first command or transfer and closed by
+    /// [UdmaSpim::eot]
+    ///
+    /// The configuration is reissued with every SOT, so nothing needs to be
+    /// replayed after the gate has been closed.
+    AutoGate {
+        /// Cycles to wait after opening the gate before issuing commands, and
+        /// after dispatching EOT before closing it
+        ///
+        /// The latter lets the SPIM finish clocking out the transaction, as
+        /// the driver has no way to observe the end of EOT.
+        settle_cycles: u32,
+    },
+}
+
+impl SpimConfig {
+    /// Same as [SpimConfig::default]
+    pub const DEFAULT: Self = Self {
+        clk_div: 0x4,
+        cpol: false,
+        cpha: false,
+        power: PowerPolicy::AlwaysOn,
+        tx_idle_byte: None,
+    };
+
+    pub const fn with_clk_div(self, clk_div: u8) -> Self {
+        Self { clk_div, ..self }
+    }
+
+    /// Clock polarity and phase
+    pub const fn with_mode(self, cpol: bool, cpha: bool) -> Self {
+        Self { cpol, cpha, ..self }
+    }
+
+    pub const fn with_power(self, power: PowerPolicy) -> Self {
+        Self { power, ..self }
+    }
+
+    pub const fn with_tx_idle_byte(self, tx_idle_byte: Option<u8>) -> Self {
+        Self {
+            tx_idle_byte,
+            ..self
+        }
+    }
+
+    /// SCK frequency for a peripheral clock of `periph_hz`, sa.
+    /// [SpimConfig::clk_div]
+    #[inline]
+    pub const fn sck_hz(&self, periph_hz: u32) -> u32 {
+        periph_hz / (2 * (self.clk_div as u32 + 1))
+    }
+
+    #[inline]
+    pub(crate) const fn cmd(&self) -> u32 {
+        SPI_CMD_CFG | ((self.cpol as u32) << 9) | ((self.cpha as u32) << 8) | self.clk_div as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpimError {
+    /// Buffer is empty, longer than [MAX_XFER_LEN] or not in memory visible
+    /// to the uDMA
+    InvalidBuffer,
+    /// TX and RX buffers of a full-duplex transfer differ in length
+    LengthMismatch,
+    /// The uDMA SPIM did not respond to [UdmaSpim::enable]
+    NotPresent,
+    /// SOT was requested while chip select is asserted
+    CsAlreadyAsserted,
+    /// EOT was requested while chip select is not asserted
+    CsNotAsserted,
+    /// Data is not a whole number of frames, sa. [UdmaSpim::send_framed] and
+    /// [DaisyChain::new]
+    PartialFrame,
+    /// All slots of a [SpimQueue] are taken
+    QueueFull,
+    /// The pads are parked, sa. [UdmaSpim::park]
+    Parked,
+    /// The RX channel did not finish in time, sa.
+    /// [UdmaSpim::receive_timeout]. The first `received` bytes of the
+    /// buffer are valid.
+    ShortTransfer { expected: usize, received: usize },
+    /// The device did not signal ready in time during
+    /// [UdmaSpim::send_with_ready_check]. The first `sent` bytes went out.
+    NotReady { sent: usize },
+}
+
+impl SpimError {
+    /// Longest encoding written by [SpimError::to_wire]
+    pub const WIRE_LEN: usize = 9;
+
+    /// Encode for a host, e.g., in a [hil](crate::hil) response
+    ///
+    /// A code byte is followed by the fields as little-endian u32s. The codes
+    /// are stable, new variants get new codes:
+    ///
+    /// | Code | Variant             | Fields                 |
+    /// | :-   | :-                  | :-                     |
+    /// | 0    | `InvalidBuffer`     |                        |
+    /// | 1    | `LengthMismatch`    |                        |
+    /// | 2    | `NotPresent`        |                        |
+    /// | 3    | `CsAlreadyAsserted` |                        |
+    /// | 4    | `CsNotAsserted`     |                        |
+    /// | 5    | `PartialFrame`      |                        |
+    /// | 6    | `QueueFull`         |                        |
+    /// | 7    | `Parked`            |                        |
+    /// | 8    | `ShortTransfer`     | `expected`, `received` |
+    /// | 9    | `NotReady`          | `sent`                 |
+    ///
+    /// Returns the length, or 0 if `buf` is too short.
+    pub fn to_wire(&self, buf: &mut [u8]) -> usize {
+        match *self {
+            SpimError::InvalidBuffer => put_wire(buf, 0, &[]),
+            SpimError::LengthMismatch => put_wire(buf, 1, &[]),
+            SpimError::NotPresent => put_wire(buf, 2, &[]),
+            SpimError::CsAlreadyAsserted => put_wire(buf, 3, &[]),
+            SpimError::CsNotAsserted => put_wire(buf, 4, &[]),
+            SpimError::PartialFrame => put_wire(buf, 5, &[]),
+            SpimError::QueueFull => put_wire(buf, 6, &[]),
+            SpimError::Parked => put_wire(buf, 7, &[]),
+            SpimError::ShortTransfer { expected, received } => {
+                put_wire(buf, 8, &[expected as u32, received as u32])
+            }
+            SpimError::NotReady { sent } => put_wire(buf, 9, &[sent as u32]),
+        }
+    }
+}
+
+/// Optional SPIM features, sa. [UdmaSpim::capabilities]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SpimCapFlags(pub u32);
+
+impl SpimCapFlags {
+    /// Four data lines
+    pub const QUAD: Self = Self(1 << 0);
+    /// RX channel restarting at the start of its buffer when full
+    pub const CIRCULAR_RX: Self = Self(1 << 1);
+    /// MOSI looped back to MISO inside the SPIM
+    pub const HW_LOOPBACK: Self = Self(1 << 2);
+    /// Chip selects that can be made active-high
+    pub const CS_ACTIVE_HIGH: Self = Self(1 << 3);
+    /// SPIM events that can be routed to the event unit, sa.
+    /// [on_event]
+    pub const EVENT_ROUTING: Self = Self(1 << 4);
+
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+const SPIM_CAP_FLAGS: &[(u32, &str)] = &[
+    (0, "QUAD"),
+    (1, "CIRCULAR_RX"),
+    (2, "HW_LOOPBACK"),
+    (3, "CS_ACTIVE_HIGH"),
+    (4, "EVENT_ROUTING"),
+];
+impl_flags_fmt!(SpimCapFlags, SPIM_CAP_FLAGS);
+
+/// What the SPIM and this driver support, for code that adapts to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SpimCaps {
+    pub flags: SpimCapFlags,
+    /// Longest transfer of a single data command, in bytes
+    pub max_transfer_len: usize,
+}
+
+impl SpimCaps {
+    #[inline]
+    pub const fn has(&self, flag: SpimCapFlags) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+/// What [UdmaSpim::disable] does with transfers still in progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisablePolicy {
+    /// Let them finish
+    Wait,
+    /// Stop them, reset the SPIM and deassert chip select with an EOT
+    Abort,
+}
+
+/// Level of parked SPIM pads, sa. [UdmaSpim::park]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinState {
+    /// Driven low as GPIO outputs
+    Low,
+    /// GPIO inputs with the input buffer off
+    HighZ,
+}
+
+/// How blocking transfers wait for the channels to finish, sa.
+/// [UdmaSpim::set_completion_mode]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompletionMode {
+    /// Spin on the channel registers
+    #[default]
+    Polling,
+    /// Sleep in `wfi` until [on_event] is called from the SPIM interrupt
+    /// handler
+    Interrupt,
+    /// Like [CompletionMode::Interrupt], but also check the channel
+    /// registers once `grace_cycles` core cycles pass without an event
+    ///
+    /// A lost event then costs at most the grace period, and is counted in
+    /// [UdmaSpim::missed_events]. As with [event::select2], the grace period
+    /// is only checked when the core wakes up, so some interrupt, e.g., a
+    /// periodic timer, must wake it at least that often.
+    InterruptWithPollFallback { grace_cycles: u32 },
+}
+
+/// EOT queued by [stop_and_reset]
+static EOT_WORD: u32 = SPI_CMD_EOT;
+
+/// Clear all SPIM channels, reset the SPIM and deassert chip select
+///
+/// The SVD documents CTRL_CFG_RST as unimplemented, so whether the reset
+/// clears the command sequencer is unverified. Chip select is deasserted by
+/// an EOT queued on the cleared CMD channel instead, which is harmless if
+/// it was not asserted. The SPIM clock is ungated for the EOT and left so.
+pub(crate) fn stop_and_reset(udma: &pac::sysctrl::Udma) {
+    reg_write!(udma.spim_cmd_cfg(), |w| w.clr().set_bit());
+    reg_write!(udma.spim_tx_cfg(), |w| w.clr().set_bit());
+    reg_write!(udma.spim_rx_cfg(), |w| w.clr().set_bit());
+    // The command sequencer may still wait for data of a stopped channel
+    let rst = 1 << UdmaPeripheral::Spim as u32;
+    reg_modify!(udma.ctrl_cfg_rst(), |r, w| unsafe {
+        w.bits(r.bits() | rst)
+    });
+    reg_modify!(udma.ctrl_cfg_rst(), |r, w| unsafe {
+        w.bits(r.bits() & !rst)
+    });
+
+    reg_modify!(udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().set_bit());
+    reg_write!(udma.spim_cmd_saddr(), |w| unsafe {
+        w.bits(core::ptr::addr_of!(EOT_WORD) as u32)
+    });
+    reg_write!(udma.spim_cmd_size(), |w| unsafe { w.bits(4) });
+    reg_write!(udma.spim_cmd_cfg(), |w| unsafe {
+        w.datasize().bits(DataSize::Word as u8).en().set_bit()
+    });
+    poll_eq!(udma.spim_cmd_saddr().read().bits(), 0);
+    poll_bit_clear!(udma.spim_cmd_cfg(), pending);
+}
+
+/// Raised by [on_event]
+static EVENT: Flag = Flag::new();
+
+/// Wake a blocking transfer waiting with [CompletionMode::Interrupt] or
+/// [CompletionMode::InterruptWithPollFallback], call from the SPIM interrupt
+/// handler
+#[inline]
+pub fn on_event() {
+    EVENT.set();
+}
+
+/// Step of a declarative SPI transaction, sa. [UdmaSpim::transaction_ops]
+pub enum Operation<'a> {
+    /// Write data, discarding whatever is clocked in
+    Write(&'a [u8]),
+    /// Read data, clocking out [SpimConfig::tx_idle_byte]
+    Read(&'a mut [u8]),
+    /// Full-duplex transfer. Buffers must be of equal length.
+    Transfer(&'a mut [u8], &'a [u8]),
+    /// Full-duplex transfer where the received data replaces the sent data
+    TransferInPlace(&'a mut [u8]),
+    /// Wait with chip select asserted, sa. [delay::nanos]
+    DelayNs(u32),
+    /// Deassert and reassert chip select with the same configuration, sa.
+    /// [SpimTransaction::pulse_cs]
+    CsPulse,
+}
+
+/// Obtain an instance by calling [Udma::split](super::Udma::split)
+pub struct UdmaSpim<'u, UdmaPeriphState> {
+    pub(crate) udma: &'u pac::sysctrl::Udma,
+    pub(crate) cfg: SpimConfig,
+    /// Chip select asserted by the last SOT, if not yet closed with EOT
+    pub(crate) cs: Option<ChipSelect>,
+    /// Reject SOT while chip select is asserted, sa. [UdmaSpim::set_strict_cs]
+    pub(crate) strict_cs: bool,
+    /// `mcycle` right after the last EOT
+    pub(crate) last_eot_time: u64,
+    /// Minimum time between EOT and the next SOT, sa.
+    /// [UdmaSpim::set_cs_hold_cycles]
+    pub(crate) min_deassert_cycles: u32,
+    /// Sa. [UdmaSpim::set_completion_mode]
+    pub(crate) completion: CompletionMode,
+    /// Events found missing by [CompletionMode::InterruptWithPollFallback],
+    /// saturating
+    pub(crate) missed_events: u32,
+    /// Mask of the pads handed to GPIO by [UdmaSpim::park]
+    pub(crate) parked: Option<u32>,
+    /// GPIO and high time in cycles, sa. [UdmaSpim::set_completion_gpio_pulse]
+    pub(crate) completion_pulse: Option<(u8, u16)>,
+    pub(crate) _pd: PhantomData<UdmaPeriphState>,
+}
+
+impl<S> UdmaSpim<'_, S> {
+    /// Number of command words the driver hands to the CMD channel at once
+    ///
+    /// Command buffers passed to [UdmaSpim::enqueue_cmd] are split into
+    /// chunks of this many words.
+    pub const CMD_FIFO_DEPTH: usize = 4;
+
+    /// Write the SPIM channel registers and the uDMA clock gates to `w`
+    ///
+    /// For finding out where a hung transfer is stuck. Reading the registers
+    /// has no side effects.
+    #[cfg(feature = "debug-registers")]
+    pub fn dump_registers<W>(&self, w: &mut W) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        let udma = self.udma;
+        super::dump_registers(
+            w,
+            &[
+                ("spim_tx_saddr", udma.spim_tx_saddr().read().bits()),
+                ("spim_tx_size", udma.spim_tx_size().read().bits()),
+                ("spim_tx_cfg", udma.spim_tx_cfg().read().bits()),
+                ("spim_rx_saddr", udma.spim_rx_saddr().read().bits()),
+                ("spim_rx_size", udma.spim_rx_size().read().bits()),
+                ("spim_rx_cfg", udma.spim_rx_cfg().read().bits()),
+                ("spim_cmd_saddr", udma.spim_cmd_saddr().read().bits()),
+                ("spim_cmd_size", udma.spim_cmd_size().read().bits()),
+                ("spim_cmd_cfg", udma.spim_cmd_cfg().read().bits()),
+                ("ctrl_cfg_cg", udma.ctrl_cfg_cg().read().bits()),
+            ],
+        )
+    }
+}
+
+impl<'u> UdmaSpim<'u, Disabled> {
+    /// Open the SPIM clock gate and check that the SPIM responds
+    ///
+    /// Returns the peripheral back with [SpimError::NotPresent] if the clock
+    /// gate bit does not read back as set, e.g., on a platform where the uDMA
+    /// is not implemented. The VP does not map the clock gate register, so
+    /// the check is skipped there, sa.
+    /// [Udma::probe_peripheral](super::Udma::probe_peripheral).
+    #[inline]
+    pub fn enable(self, cfg: SpimConfig) -> Result<UdmaSpim<'u, Enabled>, (Self, SpimError)> {
+        let cg = self.udma.ctrl_cfg_cg();
+
+        // Turn on the clock gates for SPIM
+        reg_modify!(cg, |_r, w| w.cg_spim().set_bit());
+        if !cfg!(feature = "vp") && cg.read().cg_spim().bit_is_clear() {
+            return Err((self, SpimError::NotPresent));
+        }
+
+        // With AutoGate, the gate is opened on the first transaction
+        if cfg.power != PowerPolicy::AlwaysOn {
+            reg_modify!(cg, |_r, w| w.cg_spim().clear_bit());
+        }
+
+        Ok(UdmaSpim {
+            udma: self.udma,
+            cfg,
+            cs: None,
+            strict_cs: self.strict_cs,
+            last_eot_time: self.last_eot_time,
+            min_deassert_cycles: self.min_deassert_cycles,
+            completion: self.completion,
+            missed_events: self.missed_events,
+            parked: self.parked,
+            completion_pulse: self.completion_pulse,
+            _pd: PhantomData,
+        })
+    }
+}
+
+impl<'u> UdmaSpim<'u, Enabled> {
+    /// Quiesce the SPIM and close its clock gate
+    ///
+    /// Gating the clock in the middle of a transfer freezes the channels, and
+    /// they resume from a corrupt state once re-enabled. Transfers still in
+    /// progress, e.g., started by a [SpimQueue], are first waited for or
+    /// aborted as chosen by `policy`, and chip select is deasserted. Returns
+    /// the number of aborted channel transfers, counting pending ones.
+    ///
+    /// Transaction guards borrow the driver, so none can be outstanding here.
+    pub fn disable(mut self, policy: DisablePolicy) -> (UdmaSpim<'u, Disabled>, u32) {
+        self.ungate();
+        let aborted = match policy {
+            DisablePolicy::Wait => {
+                poll::wait(|| self.channels_idle());
+                if self.cs.is_some() {
+                    self.enqueue_cmd_single_word(SPI_CMD_EOT);
+                    self.last_eot_time = mcycle::read64();
+                }
+                0
+            }
+            DisablePolicy::Abort => self.abort(),
+        };
+        self.cs = None;
+        reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().clear_bit());
+
+        (
+            UdmaSpim {
+                udma: self.udma,
+                cfg: self.cfg,
+                cs: None,
+                strict_cs: self.strict_cs,
+                last_eot_time: self.last_eot_time,
+                min_deassert_cycles: self.min_deassert_cycles,
+                completion: self.completion,
+                missed_events: self.missed_events,
+                parked: self.parked,
+                completion_pulse: self.completion_pulse,
+                _pd: PhantomData,
+            },
+            aborted,
+        )
+    }
+
+    /// Returns true once the CMD, TX and RX channels have nothing active or
+    /// queued
+    fn channels_idle(&self) -> bool {
+        let udma = self.udma;
+        self.is_idle()
+            && udma.spim_cmd_saddr().read().bits() == 0
+            && udma.spim_cmd_cfg().read().pending().bit_is_clear()
+            && udma.spim_tx_cfg().read().pending().bit_is_clear()
+            && udma.spim_rx_cfg().read().pending().bit_is_clear()
+    }
+
+    /// Stop all channels, reset the SPIM and deassert chip select, sa.
+    /// [stop_and_reset]. Returns the number of transfers stopped.
+    fn abort(&mut self) -> u32 {
+        let udma = self.udma;
+        let busy = |saddr: u32, pending: bool| (saddr != 0) as u32 + pending as u32;
+        let aborted = busy(
+            udma.spim_cmd_saddr().read().bits(),
+            udma.spim_cmd_cfg().read().pending().bit_is_set(),
+        ) + busy(
+            udma.spim_tx_saddr().read().bits(),
+            udma.spim_tx_cfg().read().pending().bit_is_set(),
+        ) + busy(
+            udma.spim_rx_saddr().read().bits(),
+            udma.spim_rx_cfg().read().pending().bit_is_set(),
+        );
+
+        stop_and_reset(udma);
+
+        if aborted != 0 {
+            trace_event!(SpimDone);
+        }
+        aborted
+    }
+
+    /// # Safety
+    ///
+    /// This will not configure the SPIM in any way.
+    #[inline]
+    pub unsafe fn steal(udma: &'static pac::sysctrl::Udma, cfg: SpimConfig) -> Self {
+        Self {
+            udma,
+            cfg,
+            cs: None,
+            strict_cs: true,
+            last_eot_time: 0,
+            min_deassert_cycles: 0,
+            completion: CompletionMode::Polling,
+            missed_events: 0,
+            parked: None,
+            completion_pulse: None,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Choose how SOT behaves while chip select is already asserted
+    ///
+    /// A second SOT corrupts the SPIM command sequencer until reset, so the
+    /// driver never issues one. With `strict` (the default), it is reported
+    /// as [SpimError::CsAlreadyAsserted]. Otherwise, SOT on the asserted line
+    /// is a no-op. SOT on a different line is always an error.
+    #[inline]
+    pub fn set_strict_cs(&mut self, strict: bool) {
+        self.strict_cs = strict;
+    }
+
+    /// Keep chip select deasserted for at least `cycles` core cycles between
+    /// an EOT and the next SOT, e.g., for a device's minimum CS high time
+    ///
+    /// [UdmaSpim::sot] spins until the time has passed, as do
+    /// [SpimTransaction::pulse_cs] and [UdmaSpim::send_framed] between
+    /// frames. Defaults to 0, which lets the latter two issue their CS
+    /// pulses in one command buffer, paced by the hardware.
+    #[inline]
+    pub fn set_cs_hold_cycles(&mut self, cycles: u32) {
+        self.min_deassert_cycles = cycles;
+    }
+
+    /// Choose how blocking transfers wait for completion, e.g., to fall back
+    /// to polling where the SPIM event line drops events
+    ///
+    /// Defaults to [CompletionMode::Polling]. Transfers started by a
+    /// [SpimQueue] or a future are not affected.
+    #[inline]
+    pub fn set_completion_mode(&mut self, mode: CompletionMode) {
+        self.completion = mode;
+    }
+
+    #[inline]
+    pub fn completion_mode(&self) -> CompletionMode {
+        self.completion
+    }
+
+    /// Number of transfers found finished without their event, sa.
+    /// [CompletionMode::InterruptWithPollFallback]
+    #[inline]
+    pub fn missed_events(&self) -> u32 {
+        self.missed_events
+    }
+
+    /// Pulse GPIO `pin` high for `duration_cycles` core cycles whenever a
+    /// blocking transfer finishes, e.g., to trigger a scope capture
+    ///
+    /// The uDMA routes SPIM events only to the event unit and the interrupt
+    /// controller, not to the GPIOs, so the pulse is driven by the driver
+    /// right where it sees the channel finish, before returning. A
+    /// full-duplex transfer gives one pulse per channel. Transfers started by
+    /// a [SpimQueue] or a future are not covered. The pin must already be a
+    /// GPIO output, sa. [Gpio::into_output](crate::sysctrl::gpio::Gpio).
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not a GPIO, i.e., 32 or above
+    #[inline]
+    pub fn set_completion_gpio_pulse(&mut self, pin: u8, duration_cycles: u16) {
+        assert!(pin < 32, "no GPIO {}", pin);
+        self.completion_pulse = Some((pin, duration_cycles));
+    }
+
+    /// Stop the pulses of [UdmaSpim::set_completion_gpio_pulse]
+    #[inline]
+    pub fn clear_completion_gpio_pulse(&mut self) {
+        self.completion_pulse = None;
+    }
+
+    /// Chip select currently asserted by [UdmaSpim::sot]
+    #[inline]
+    pub fn asserted_cs(&self) -> Option<ChipSelect> {
+        self.cs
+    }
+
+    /// Optional features available through this driver
+    ///
+    /// PULP `udma_qspi` has no revision or feature register, so these are
+    /// fixed for Headsail. The IP can do quad transfers, but the SPIM's extra
+    /// data lines are not known to reach any pads, and the uDMA channels can
+    /// run continuously, but the driver offers neither.
+    #[inline]
+    pub fn capabilities(&self) -> SpimCaps {
+        SpimCaps {
+            flags: SpimCapFlags::EVENT_ROUTING,
+            max_transfer_len: MAX_XFER_LEN,
+        }
+    }
+
+    /// Configuration used for the CFG command of subsequent transactions
+    #[inline]
+    pub fn set_config(&mut self, cfg: SpimConfig) {
+        if cfg.power == PowerPolicy::AlwaysOn {
+            reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().set_bit());
+        }
+        self.cfg = cfg;
+    }
+
+    #[inline]
+    pub fn config(&self) -> SpimConfig {
+        self.cfg
+    }
+
+    /// Access the uDMA registers directly, e.g., to set a bit the driver does
+    /// not model
+    ///
+    /// The driver needs no cached register state: the configuration is
+    /// replayed as a CFG command at every SOT. After `f`, the SPIM clock gate
+    /// is put back as [PowerPolicy] has it between transactions, and the
+    /// chip select hold time of [UdmaSpim::set_cs_hold_cycles] is counted
+    /// from the end of `f`, in case `f` toggled a chip select. The access is
+    /// recorded in the [trace](crate::trace) log.
+    ///
+    /// # Panics
+    ///
+    /// If a chip select window is open
+    pub fn with_raw<R>(&mut self, f: impl FnOnce(&pac::sysctrl::Udma) -> R) -> R {
+        assert!(self.cs.is_none(), "raw SPIM access within a transaction");
+        trace_event!(RawAccess { periph: "spim" });
+
+        let ret = f(self.udma);
+
+        match self.cfg.power {
+            PowerPolicy::AlwaysOn => {
+                reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().set_bit())
+            }
+            PowerPolicy::AutoGate { .. } => {
+                reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().clear_bit())
+            }
+        }
+        self.last_eot_time = mcycle::read64();
+        ret
+    }
+
+    /// Set the raw clock divider of subsequent transactions and return the
+    /// resulting SCK frequency for a peripheral clock of `periph_hz`
+    ///
+    /// SCK = `periph_hz` / (2 * (`div` + 1)), so even the smallest divider
+    /// halves the peripheral clock:
+    ///
+    /// | `div` | SCK             | at 30 MHz |
+    /// | :-    | :-              | :-        |
+    /// | 0     | `periph_hz`/2   | 15 MHz    |
+    /// | 1     | `periph_hz`/4   | 7.5 MHz   |
+    /// | 2     | `periph_hz`/6   | 5 MHz     |
+    /// | 4     | `periph_hz`/10  | 3 MHz     |
+    /// | 255   | `periph_hz`/512 | 58.6 kHz  |
+    ///
+    /// The BSP has no view of the peripheral clock, which depends on
+    /// [periph_clk_div_set](crate::sysctrl::soc_ctrl::periph_clk_div_set).
+    #[inline]
+    pub fn set_clock_divider(&mut self, periph_hz: u32, div: u8) -> u32 {
+        self.set_config(self.cfg.with_clk_div(div));
+        self.cfg.sck_hz(periph_hz)
+    }
+
+    /// Number of command words that can be dispatched without blocking
+    ///
+    /// The uDMA exposes no FIFO count register. The value is derived from the
+    /// bytes left in the active CMD buffer and the channel's PENDING flag.
+    #[inline]
+    pub fn cmd_fifo_remaining(&self) -> u8 {
+        let udma = &self.udma;
+
+        if udma.spim_cmd_cfg().read().pending().bit_is_set() {
+            return 0;
+        }
+        if udma.spim_cmd_saddr().read().bits() == 0 {
+            return Self::CMD_FIFO_DEPTH as u8;
+        }
+        let words_left = udma.spim_cmd_size().read().bits().div_ceil(4) as usize;
+        Self::CMD_FIFO_DEPTH.saturating_sub(words_left) as u8
+    }
+
+    /// Dispatch raw command words over the CMD channel and block until the
+    /// channel has consumed them
+    ///
+    /// `cmd` must consist of whole 32-bit command words in native byte order.
+    /// Buffers longer than [CMD_FIFO_DEPTH](Self::CMD_FIFO_DEPTH) words are
+    /// dispatched in chunks.
+    #[inline]
+    pub fn enqueue_cmd(&mut self, cmd: &[u8]) {
+        self.ungate();
+
+        for chunk in cmd.chunks(Self::CMD_FIFO_DEPTH * 4) {
+            // Poll until there is room for the chunk
+            poll::wait(|| self.cmd_fifo_remaining() as usize >= chunk.len().div_ceil(4));
+            self.start_cmd(chunk);
+        }
+
+        // Poll until finished (prevents `cmd` leakage)
+        poll_eq!(self.udma.spim_cmd_saddr().read().bits(), 0);
+        poll_bit_clear!(self.udma.spim_cmd_cfg(), pending);
+    }
+
+    /// Like [UdmaSpim::enqueue_cmd], for command words as built by
+    /// [SpiCommandBuilder] or [CommandBuf::as_words]
+    #[inline]
+    pub fn enqueue_cmd_words(&mut self, words: &[u32]) {
+        self.enqueue_cmd(words_as_bytes(words));
+    }
+
+    /// Dispatch a single command word and block until the channel has
+    /// consumed it
+    #[inline]
+    pub fn enqueue_cmd_single_word(&mut self, word: u32) {
+        self.enqueue_cmd_words(&[word]);
+    }
+
+    #[inline]
+    fn start_cmd(&mut self, cmd: &[u8]) {
+        trace_event!(SpimCmd {
+            words: cmd.len() as u32 / 4
+        });
+        let udma = &self.udma;
+
+        // Write buffer location & len
+        reg_write!(udma.spim_cmd_saddr(), |w| unsafe {
+            w.bits(cmd.as_ptr() as u32)
+        });
+        reg_write!(udma.spim_cmd_size(), |w| unsafe {
+            w.bits(cmd.len() as u32)
+        });
+
+        // Dispatch transmission
+        reg_write!(udma.spim_cmd_cfg(), |w| unsafe {
+            w.datasize().bits(DataSize::Word as u8).en().set_bit()
+        });
+    }
+
+    /// Program the TX channel with `buf` and start it
+    ///
+    /// The channel will wait for the matching TX_DATA or FULL_DUPL command.
+    /// Make sure the transfer has finished before `buf` goes out of scope.
+    ///
+    /// # Panics
+    ///
+    /// If `buf` is longer than [MAX_XFER_LEN], which the data command could
+    /// not cover
+    #[inline]
+    pub fn enqueue_tx(&mut self, buf: &[u8]) {
+        assert!(buf.len() <= MAX_XFER_LEN);
+        self.start_tx(buf.as_ptr(), buf.len());
+    }
+
+    /// Program the RX channel with `buf` and start it
+    ///
+    /// The channel will wait for the matching RX_DATA or FULL_DUPL command.
+    /// Make sure the transfer has finished before `buf` goes out of scope.
+    ///
+    /// # Panics
+    ///
+    /// If `buf` is longer than [MAX_XFER_LEN], which the data command could
+    /// not cover
+    #[inline]
+    pub fn enqueue_rx(&mut self, buf: &mut [u8]) {
+        assert!(buf.len() <= MAX_XFER_LEN);
+        self.start_rx(buf.as_mut_ptr(), buf.len());
+    }
+
+    #[inline]
+    pub(crate) fn start_tx(&mut self, ptr: *const u8, len: usize) {
+        trace_event!(SpimTxStart { len: len as u32 });
+        self.ungate();
+
+        let udma = &self.udma;
+
+        reg_write!(udma.spim_tx_saddr(), |w| unsafe { w.bits(ptr as u32) });
+        reg_write!(udma.spim_tx_size(), |w| unsafe { w.bits(len as u32) });
+        reg_write!(udma.spim_tx_cfg(), |w| unsafe {
+            w.datasize().bits(DataSize::Byte as u8).en().set_bit()
+        });
+    }
+
+    #[inline]
+    pub(crate) fn start_rx(&mut self, ptr: *mut u8, len: usize) {
+        trace_event!(SpimRxStart { len: len as u32 });
+        self.ungate();
+
+        let udma = &self.udma;
+
+        reg_write!(udma.spim_rx_saddr(), |w| unsafe { w.bits(ptr as u32) });
+        reg_write!(udma.spim_rx_size(), |w| unsafe { w.bits(len as u32) });
+        reg_write!(udma.spim_rx_cfg(), |w| unsafe {
+            w.datasize().bits(DataSize::Byte as u8).en().set_bit()
+        });
+    }
+
+    /// Returns true once both the TX and RX channels have finished
+    #[inline]
+    pub(crate) fn is_idle(&self) -> bool {
+        self.udma.spim_tx_saddr().read().bits() == 0 && self.udma.spim_rx_saddr().read().bits() == 0
+    }
+
+    #[inline]
+    fn wait_tx(&mut self) {
+        self.wait_complete(|spim| spim.udma.spim_tx_saddr().read().bits() == 0);
+        trace_event!(SpimDone);
+        self.pulse_completion();
+    }
+
+    #[inline]
+    fn wait_rx(&mut self) {
+        self.wait_complete(|spim| spim.udma.spim_rx_saddr().read().bits() == 0);
+        trace_event!(SpimDone);
+        self.pulse_completion();
+    }
+
+    /// Sa. [UdmaSpim::set_completion_gpio_pulse]
+    #[inline]
+    fn pulse_completion(&self) {
+        if let Some((pin, cycles)) = self.completion_pulse {
+            mask_u32(mmap::GPIO_OUT, 1 << pin);
+            riscv::asm::delay(cycles as u32);
+            unmask_u32(mmap::GPIO_OUT, 1 << pin);
+        }
+    }
+
+    /// Wait until `done` as chosen by [UdmaSpim::set_completion_mode]
+    fn wait_complete(&mut self, done: fn(&Self) -> bool) {
+        let grace = match self.completion {
+            CompletionMode::Polling => {
+                poll::wait(|| done(self));
+                return;
+            }
+            CompletionMode::Interrupt => None,
+            CompletionMode::InterruptWithPollFallback { grace_cycles } => Some(grace_cycles as u64),
+        };
+        // A stale event from an earlier transfer only costs another loop
+        while !done(self) {
+            if !event::wait(&EVENT, grace) && done(self) {
+                self.missed_events = self.missed_events.saturating_add(1);
+            }
+        }
+    }
+
+    /// Close the clock gate between transactions
+    ///
+    /// Shorthand for setting [PowerPolicy::AutoGate] with
+    /// [GATE_SETTLE_CYCLES].
+    //
+    // Savings have not been measured. The VP has no power model and does not
+    // map the uDMA control block with the clock gate, so gating only avoids
+    // clocking the idle SPIM logic on the ASIC.
+    #[inline]
+    pub fn idle_clock_gate(&mut self, enable: bool) {
+        let power = if enable {
+            PowerPolicy::AutoGate {
+                settle_cycles: GATE_SETTLE_CYCLES,
+            }
+        } else {
+            PowerPolicy::AlwaysOn
+        };
+        self.set_config(SpimConfig { power, ..self.cfg });
+    }
+
+    /// Open the clock gate if [PowerPolicy::AutoGate] has closed it
+    #[inline]
+    fn ungate(&mut self) {
+        if let PowerPolicy::AutoGate { settle_cycles } = self.cfg.power {
+            let cg = self.udma.ctrl_cfg_cg();
+            if cg.read().cg_spim().bit_is_clear() {
+                reg_modify!(cg, |_r, w| w.cg_spim().set_bit());
+                riscv::asm::delay(settle_cycles);
+            }
+        }
+    }
+
+    /// Hand the pads in `pads`, a mask of pad indices, over to GPIO and hold
+    /// them at `state`
+    ///
+    /// For leakage and EMC measurements between tests. The BSP does not know
+    /// which pads the board routes to the SPIM, hence the mask. Transfers
+    /// still in flight, e.g., from a [SpimQueue], are waited for, and the
+    /// clock gate is closed regardless of [PowerPolicy]. Until
+    /// [UdmaSpim::unpark], opening a window fails with [SpimError::Parked]
+    /// and a [SpimQueue] holds its transfers.
+    ///
+    /// The pads stay parked over [UdmaSpim::disable] and
+    /// [UdmaSpim::enable].
+    pub fn park(&mut self, pads: u32, state: PinState) -> Result<(), SpimError> {
+        if self.cs.is_some() {
+            return Err(SpimError::CsAlreadyAsserted);
+        }
+        if self.parked.is_some() {
+            return Err(SpimError::Parked);
+        }
+
+        self.ungate();
+        poll::wait(|| self.channels_idle());
+        soc_ctrl::pads_into_gpio(pads, state == PinState::Low);
+        reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().clear_bit());
+        self.parked = Some(pads);
+        Ok(())
+    }
+
+    /// Return the pads parked by [UdmaSpim::park] to the SPIM
+    ///
+    /// The configuration is replayed before the pads are switched back, so
+    /// SCK idles at the configured polarity from the start. The clock gate
+    /// is then left as [PowerPolicy] has it between transactions. Returns
+    /// false if the pads were not parked.
+    pub fn unpark(&mut self) -> bool {
+        let Some(pads) = self.parked.take() else {
+            return false;
+        };
+
+        if self.cfg.power == PowerPolicy::AlwaysOn {
+            reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().set_bit());
+        }
+        self.enqueue_cmd_single_word(self.cfg.cmd());
+        soc_ctrl::pads_release(pads);
+        self.gate_after_eot();
+        true
+    }
+
+    /// Whether the pads are parked, sa. [UdmaSpim::park]
+    #[inline]
+    pub fn is_parked(&self) -> bool {
+        self.parked.is_some()
+    }
+
+    /// Apply the current configuration and assert `cs`
+    #[inline]
+    pub fn sot(&mut self, cs: ChipSelect) -> Result<(), SpimError> {
+        if self.parked.is_some() {
+            return Err(SpimError::Parked);
+        }
+        match self.cs {
+            Some(asserted) if asserted == cs && !self.strict_cs => return Ok(()),
+            Some(_) => return Err(SpimError::CsAlreadyAsserted),
+            None => {}
+        }
+
+        self.wait_cs_hold();
+        let mut cmd = CommandBuf::<2>::new();
+        cmd.push_word(self.cfg.cmd())
+            .push_cmd(SPI_CMD_SOT, cs as u32);
+        self.enqueue_cmd_words(cmd.as_words());
+        self.cs = Some(cs);
+        Ok(())
+    }
+
+    /// Deassert chip select
+    #[inline]
+    pub fn eot(&mut self) -> Result<(), SpimError> {
+        if self.cs.is_none() {
+            return Err(SpimError::CsNotAsserted);
+        }
+
+        self.enqueue_cmd_single_word(SPI_CMD_EOT);
+        self.cs = None;
+        self.last_eot_time = mcycle::read64();
+
+        self.gate_after_eot();
+        Ok(())
+    }
+
+    /// Spin until chip select has been deasserted for
+    /// [UdmaSpim::set_cs_hold_cycles]
+    #[inline]
+    fn wait_cs_hold(&self) {
+        if self.min_deassert_cycles == 0 {
+            return;
+        }
+        while mcycle::read64().wrapping_sub(self.last_eot_time) < self.min_deassert_cycles as u64 {}
+    }
+
+    /// Close the clock gate after EOT if [PowerPolicy::AutoGate] is in use
+    #[inline]
+    fn gate_after_eot(&mut self) {
+        if let PowerPolicy::AutoGate { settle_cycles } = self.cfg.power {
+            riscv::asm::delay(settle_cycles);
+            reg_modify!(self.udma.ctrl_cfg_cg(), |_r, w| w.cg_spim().clear_bit());
+        }
+    }
+
+    /// Write `data` within the currently open chip select window
+    #[inline]
+    pub fn send(&mut self, data: &[u8]) -> Result<(), SpimError> {
+        check_buf(data)?;
+
+        self.enqueue_tx(data);
+        self.enqueue_cmd_single_word(data_cmd(SPI_CMD_TX_DATA, data.len()));
+        self.wait_tx();
+        Ok(())
+    }
+
+    /// Read into `buf` within the currently open chip select window
+    ///
+    /// MOSI is driven with [SpimConfig::tx_idle_byte] if set.
+    #[inline]
+    pub fn receive(&mut self, buf: &mut [u8]) -> Result<(), SpimError> {
+        check_buf(buf)?;
+
+        if self.cfg.tx_idle_byte.is_some() {
+            self.start_receive(buf);
+            self.wait_complete(Self::is_idle);
+            trace_event!(SpimDone);
+            return Ok(());
+        }
+
+        let len = buf.len();
+        self.enqueue_rx(buf);
+        self.enqueue_cmd_single_word(data_cmd(SPI_CMD_RX_DATA, len));
+        self.wait_rx();
+        Ok(())
+    }
+
+    /// Like [UdmaSpim::receive], giving up after `timeout_cycles`
+    ///
+    /// Completion is polled regardless of [CompletionMode]. On timeout, the
+    /// remaining RX size tells how many bytes arrived before the channels
+    /// are stopped and chip select is deasserted with an explicit EOT.
+    /// The bytes received so far stay in `buf`, e.g., for a caller to look
+    /// for an error token at `buf[received - 1]`.
+    pub fn receive_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout_cycles: u64,
+    ) -> Result<(), SpimError> {
+        check_buf(buf)?;
+
+        let len = buf.len();
+        self.start_receive(buf);
+        if poll::wait_timeout(|| self.is_idle(), timeout_cycles).is_ok() {
+            trace_event!(SpimDone);
+            return Ok(());
+        }
+        let left = self.udma.spim_rx_size().read().bits();
+        // Deasserts chip select with an EOT, sa. `stop_and_reset`
+        self.abort();
+        self.cs = None;
+        self.last_eot_time = mcycle::read64();
+        self.gate_after_eot();
+        Err(short_transfer(len, left))
+    }
+
+    /// Like [UdmaSpim::receive], returning the `mcycle` value at which the
+    /// RX channel was seen done
+    ///
+    /// Completion is polled regardless of [CompletionMode], and the time is
+    /// taken right after the poll that sees the channel finish. The
+    /// resolution is one iteration of the poll loop, a few tens of cycles,
+    /// plus whatever interrupts preempt it. SysCtrl has no timer capture to
+    /// latch the uDMA event in hardware. Work done by the caller afterwards
+    /// does not skew the timestamp.
+    pub fn receive_timestamped(&mut self, buf: &mut [u8]) -> Result<u64, SpimError> {
+        check_buf(buf)?;
+
+        self.start_receive(buf);
+        poll::wait(|| self.is_idle());
+        let timestamp = mcycle::read64();
+        trace_event!(SpimDone);
+        Ok(timestamp)
+    }
+
+    /// Start reading into a buffer already validated with [check_buf],
+    /// clocking out [SpimConfig::tx_idle_byte] if set
+    fn start_receive(&mut self, buf: &mut [u8]) {
+        let cmd = receive_cmd(self.cfg.tx_idle_byte, buf);
+        let len = buf.len();
+        let ptr = buf.as_mut_ptr();
+        self.start_rx(ptr, len);
+        if self.cfg.tx_idle_byte.is_some() {
+            // uDMA reads each TX byte before the RX byte at the same offset
+            // overwrites it, so the buffer is sent as it is filled
+            self.start_tx(ptr, len);
+        }
+        self.enqueue_cmd_single_word(cmd);
+    }
+
+    /// Full-duplex transfer within the currently open chip select window
+    #[inline]
+    pub fn transfer(&mut self, rx: &mut [u8], tx: &[u8]) -> Result<(), SpimError> {
+        if rx.len() != tx.len() {
+            return Err(SpimError::LengthMismatch);
+        }
+        check_buf(tx)?;
+        check_buf(rx)?;
+
+        self.enqueue_rx(rx);
+        self.enqueue_tx(tx);
+        self.enqueue_cmd_single_word(data_cmd(SPI_CMD_FULL_DUPL, tx.len()));
+        self.wait_complete(Self::is_idle);
+        trace_event!(SpimDone);
+        Ok(())
+    }
+
+    /// Full-duplex transfer of a buffer already validated with [check_buf]
+    #[inline]
+    fn transfer_in_place_unchecked(&mut self, buf: &mut [u8]) {
+        // uDMA reads the TX byte before writing the RX byte at the same
+        // offset, so the same buffer can be used for both channels.
+        let len = buf.len();
+        let ptr = buf.as_mut_ptr();
+        self.start_rx(ptr, len);
+        self.start_tx(ptr, len);
+        self.enqueue_cmd_single_word(data_cmd(SPI_CMD_FULL_DUPL, len));
+        self.wait_complete(Self::is_idle);
+        trace_event!(SpimDone);
+    }
+
+    /// Open a chip select window on `cs`, closed when the returned guard is
+    /// dropped
+    ///
+    /// If `cs` is already asserted and strict checking is off, the guard
+    /// joins the open window and leaves it open when dropped.
+    #[inline]
+    pub fn transaction(&mut self, cs: ChipSelect) -> Result<SpimTransaction<'_, 'u>, SpimError> {
+        let owns_cs = self.cs.is_none();
+        self.sot(cs)?;
+        Ok(SpimTransaction {
+            spim: self,
+            cs,
+            owns_cs,
+        })
+    }
+
+    /// Send `data` in frames of `frame_len` bytes, each in its own chip select
+    /// window on `cs`
+    ///
+    /// For devices that latch each frame on the rising edge of CS, e.g.,
+    /// MAX7219 chains. The SOT, TX_DATA and EOT of consecutive frames are
+    /// dispatched together, so the CS pulses are paced by the hardware rather
+    /// than by the CPU. `data` must be a whole number of frames.
+    ///
+    /// With a hold set by [UdmaSpim::set_cs_hold_cycles], each frame is sent
+    /// on its own and the next one waits out the hold instead, as between
+    /// transactions.
+    pub fn send_framed(
+        &mut self,
+        cs: ChipSelect,
+        data: &[u8],
+        frame_len: usize,
+    ) -> Result<(), SpimError> {
+        /// Frames per command buffer
+        const CHUNK_FRAMES: usize = 4;
+
+        check_buf(data)?;
+        if frame_len == 0 || !data.len().is_multiple_of(frame_len) {
+            return Err(SpimError::PartialFrame);
+        }
+        if self.parked.is_some() {
+            return Err(SpimError::Parked);
+        }
+        if self.cs.is_some() {
+            return Err(SpimError::CsAlreadyAsserted);
+        }
+
+        if self.min_deassert_cycles != 0 {
+            for frame in data.chunks_exact(frame_len) {
+                self.wait_cs_hold();
+                self.enqueue_tx(frame);
+                let mut cmd = CommandBuf::<4>::new();
+                cmd.push_word(self.cfg.cmd())
+                    .push_cmd(SPI_CMD_SOT, cs as u32)
+                    .push_word(data_cmd(SPI_CMD_TX_DATA, frame_len))
+                    .push_word(SPI_CMD_EOT);
+                self.enqueue_cmd_words(cmd.as_words());
+                self.wait_tx();
+                self.last_eot_time = mcycle::read64();
+            }
+            self.gate_after_eot();
+            return Ok(());
+        }
+
+        // Configuration is issued once, ahead of the first frame
+        let mut cmd = CommandBuf::<{ 1 + CHUNK_FRAMES * 3 }>::new();
+        cmd.push_word(self.cfg.cmd());
+
+        self.wait_cs_hold();
+        self.enqueue_tx(data);
+        let mut frames = data.len() / frame_len;
+        while frames > 0 {
+            let n = frames.min(CHUNK_FRAMES);
+            for _ in 0..n {
+                cmd.push_cmd(SPI_CMD_SOT, cs as u32)
+                    .push_word(data_cmd(SPI_CMD_TX_DATA, frame_len))
+                    .push_word(SPI_CMD_EOT);
+            }
+            self.enqueue_cmd_words(cmd.as_words());
+            cmd.clear();
+            frames -= n;
+        }
+        self.wait_tx();
+        self.last_eot_time = mcycle::read64();
+
+        self.gate_after_eot();
+        Ok(())
+    }
+
+    /// Run `ops` in order within a single chip select window on `cs`
+    pub fn transaction_ops(
+        &mut self,
+        cs: ChipSelect,
+        ops: &mut [Operation<'_>],
+    ) -> Result<(), SpimError> {
+        let mut t = self.transaction(cs)?;
+        for op in ops {
+            match op {
+                Operation::Write(data) => t.write(data)?,
+                Operation::Read(buf) => t.read(buf)?,
+                Operation::Transfer(rx, tx) => t.transfer(rx, tx)?,
+                Operation::TransferInPlace(buf) => t.transfer_in_place(buf)?,
+                Operation::DelayNs(ns) => delay::nanos(*ns),
+                Operation::CsPulse => t.pulse_cs(),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chip select window on the uDMA SPIM
+///
+/// Chip select is asserted on creation with [UdmaSpim::transaction] and
+/// deasserted when the guard is dropped.
+pub struct SpimTransaction<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+    /// Whether this guard asserted chip select and should deassert it
+    owns_cs: bool,
+}
+
+impl SpimTransaction<'_, '_> {
+    #[inline]
+    pub fn cs(&self) -> ChipSelect {
+        self.cs
+    }
+
+    #[inline]
+    pub fn write(&mut self, data: &[u8]) -> Result<(), SpimError> {
+        self.spim.send(data)
+    }
+
+    #[inline]
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<(), SpimError> {
+        self.spim.receive(buf)
+    }
+
+    /// Read with a timeout, sa. [UdmaSpim::receive_timeout]
+    ///
+    /// Chip select is deasserted when this returns
+    /// [SpimError::ShortTransfer], so the window cannot be continued.
+    #[inline]
+    pub fn read_timeout(&mut self, buf: &mut [u8], timeout_cycles: u64) -> Result<(), SpimError> {
+        self.spim.receive_timeout(buf, timeout_cycles)
+    }
+
+    /// Read, returning the completion time, sa.
+    /// [UdmaSpim::receive_timestamped]
+    #[inline]
+    pub fn read_timestamped(&mut self, buf: &mut [u8]) -> Result<u64, SpimError> {
+        self.spim.receive_timestamped(buf)
+    }
+
+    #[inline]
+    pub fn transfer(&mut self, rx: &mut [u8], tx: &[u8]) -> Result<(), SpimError> {
+        self.spim.transfer(rx, tx)
+    }
+
+    /// Write `data` as a continuation of the stream in this window
+    ///
+    /// Unlike [write](Self::write), `data` may be longer than
+    /// [MAX_XFER_LEN]. It is sent in segments, each with its own CFG and
+    /// TX_DATA command, and chip select stays asserted in between. Streams
+    /// larger than uDMA memory can be sent by refilling a buffer and calling
+    /// this again. The guard borrows the driver, so no other EOT can be
+    /// issued until it is dropped.
+    pub fn continue_tx(&mut self, data: &[u8]) -> Result<(), SpimError> {
+        // Reject the whole buffer up front rather than stop halfway
+        if data.is_empty() {
+            return Err(SpimError::InvalidBuffer);
+        }
+        for segment in data.chunks(MAX_XFER_LEN) {
+            check_buf(segment)?;
+        }
+
+        for segment in data.chunks(MAX_XFER_LEN) {
+            self.spim.enqueue_tx(segment);
+            let mut cmd = CommandBuf::<2>::new();
+            cmd.push_word(self.spim.cfg.cmd())
+                .push_word(data_cmd(SPI_CMD_TX_DATA, segment.len()));
+            self.spim.enqueue_cmd_words(cmd.as_words());
+            self.spim.wait_tx();
+        }
+        Ok(())
+    }
+
+    /// Read into `buf` as a continuation of the stream in this window
+    ///
+    /// Like [continue_tx](Self::continue_tx), `buf` may be longer than
+    /// [MAX_XFER_LEN]. It is received in segments, and chip select stays
+    /// asserted in between, e.g., for flash reads past 64 KiB.
+    pub fn continue_rx(&mut self, buf: &mut [u8]) -> Result<(), SpimError> {
+        if buf.is_empty() {
+            return Err(SpimError::InvalidBuffer);
+        }
+        for segment in buf.chunks(MAX_XFER_LEN) {
+            check_buf(segment)?;
+        }
+
+        for segment in buf.chunks_mut(MAX_XFER_LEN) {
+            self.spim.receive(segment)?;
+        }
+        Ok(())
+    }
+
+    /// Full-duplex transfer where the received data replaces `buf`
+    #[inline]
+    pub fn transfer_in_place(&mut self, buf: &mut [u8]) -> Result<(), SpimError> {
+        check_buf(buf)?;
+        self.spim.transfer_in_place_unchecked(buf);
+        Ok(())
+    }
+
+    /// Deassert chip select and reassert it with the same line and
+    /// configuration
+    ///
+    /// Some devices (e.g., ADCs) latch a command on the rising edge of CS and
+    /// expect the response in a separate CS window. With no hold set by
+    /// [UdmaSpim::set_cs_hold_cycles], EOT and SOT are issued in a single
+    /// command buffer so the gap is paced by the hardware. Otherwise SOT
+    /// waits out the hold as between transactions. Chip select stays
+    /// asserted as far as tracking is concerned.
+    #[inline]
+    pub fn pulse_cs(&mut self) {
+        if self.spim.min_deassert_cycles != 0 {
+            // Cannot fail, chip select is asserted within the window
+            let _ = self.spim.eot();
+            let _ = self.spim.sot(self.cs);
+            return;
+        }
+        let cmd = pulse_cs_cmd(self.spim.cfg.cmd(), self.cs);
+        self.spim.enqueue_cmd_words(cmd.as_words());
+    }
+}
+
+impl Drop for SpimTransaction<'_, '_> {
+    fn drop(&mut self) {
+        if self.owns_cs {
+            // Cannot fail, CS was asserted by this guard
+            let _ = self.spim.eot();
+        }
+    }
+}
+
+/// EOT, then `cfg` and SOT on `cs`, sa. [SpimTransaction::pulse_cs]
+fn pulse_cs_cmd(cfg: u32, cs: ChipSelect) -> CommandBuf<3> {
+    let mut cmd = CommandBuf::<3>::new();
+    cmd.push_word(SPI_CMD_EOT)
+        .push_word(cfg)
+        .push_cmd(SPI_CMD_SOT, cs as u32);
+    cmd
+}
+
+/// [SpimError::ShortTransfer] for a read of `expected` bytes that timed out
+/// with `left` bytes remaining in SPIM_RX_SIZE
+fn short_transfer(expected: usize, left: u32) -> SpimError {
+    SpimError::ShortTransfer {
+        expected,
+        received: expected - (left as usize).min(expected),
+    }
+}
+
+/// Encode a TX_DATA, RX_DATA or FULL_DUPL command for `len` bytes
+#[inline]
+pub(crate) const fn data_cmd(id: u32, len: usize) -> u32 {
+    command::data(id, len, BITS_PER_WORD)
+}
+
+/// Data command reading into `buf`
+///
+/// With `tx_idle_byte` set, `buf` is filled with it and the read is a
+/// full-duplex transfer sending `buf` back, so MOSI does not carry whatever
+/// was last in the TX path.
+fn receive_cmd(tx_idle_byte: Option<u8>, buf: &mut [u8]) -> u32 {
+    match tx_idle_byte {
+        Some(idle) => {
+            buf.fill(idle);
+            data_cmd(SPI_CMD_FULL_DUPL, buf.len())
+        }
+        None => data_cmd(SPI_CMD_RX_DATA, buf.len()),
+    }
+}
+
+/// Check that `buf` can be transferred with a single data command
+///
+/// The buffer must also lie within the memory visible to the uDMA. Addresses
+/// past the end of the window would wrap around instead of failing.
+/// Splitting a straddling transfer would not help, as there is no
+/// uDMA-visible memory on the other side of the boundary.
+#[inline]
+pub(crate) fn check_buf(buf: &[u8]) -> Result<(), SpimError> {
+    check_range(buf.as_ptr() as usize, buf.len())
+}
+
+/// [check_buf] for the `len` bytes from `start`
+#[inline]
+fn check_range(start: usize, len: usize) -> Result<(), SpimError> {
+    if len == 0 || len > MAX_XFER_LEN {
+        return Err(SpimError::InvalidBuffer);
+    }
+    let end = start.checked_add(len).ok_or(SpimError::InvalidBuffer)?;
+    if start < mmap::UDMA_MEM_START || end > mmap::UDMA_MEM_END {
+        return Err(SpimError::InvalidBuffer);
+    }
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn words_as_bytes(words: &[u32]) -> &[u8] {
+    // Safety: u32 has no padding and a stricter alignment than u8
+    unsafe {
+        core::slice::from_raw_parts(words.as_ptr() as *const u8, core::mem::size_of_val(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_cs_encoding() {
+        let cfg = SpimConfig {
+            clk_div: 4,
+            cpol: true,
+            ..SpimConfig::DEFAULT
+        };
+        let cmd = pulse_cs_cmd(cfg.cmd(), ChipSelect::Cs2);
+        assert_eq!(cmd.as_words(), &[0x9000_0000, 0x0000_0204, 0x1000_0002]);
+    }
+
+    /// Every placement is either accepted and entirely within the uDMA
+    /// window, or rejected
+    #[test]
+    fn check_range_bounds() {
+        use mmap::{UDMA_MEM_END as END, UDMA_MEM_START as START};
+
+        let fits = |start: usize, len: usize| {
+            let end = start as u128 + len as u128;
+            len != 0 && len <= MAX_XFER_LEN && start >= START && end <= END as u128
+        };
+        let check = |start: usize, len: usize| {
+            assert_eq!(
+                check_range(start, len).is_ok(),
+                fits(start, len),
+                "{start:#x} + {len:#x}"
+            );
+        };
+
+        // Around the edges of the window and of the length field, and
+        // addresses at which the end would wrap around
+        let starts = [0, START - 1, START, START + 1, END - 1, END, usize::MAX - 1];
+        let lens = [0, 1, 2, END - START - 1, END - START, MAX_XFER_LEN + 1];
+        for start in starts {
+            for len in lens {
+                check(start, len);
+                check(start.wrapping_sub(len), len);
+            }
+        }
+
+        // Pseudo-random placements near the window, xorshift32
+        let mut x = 0x1234_5678u32;
+        let mut next = || {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as usize
+        };
+        for _ in 0..100_000 {
+            let start = START - 0x100 + next() % (END - START + 0x200);
+            let len = next() % (MAX_XFER_LEN + 0x10);
+            check(start, len);
+        }
+    }
+
+    #[test]
+    fn short_transfer_from_residual() {
+        // A card aborting a 512-byte block after 180 bytes
+        assert_eq!(
+            short_transfer(512, 332),
+            SpimError::ShortTransfer {
+                expected: 512,
+                received: 180
+            }
+        );
+        // Nothing arrived, or a residual larger than the read
+        for left in [512, 0xffff_ffff] {
+            assert_eq!(
+                short_transfer(512, left),
+                SpimError::ShortTransfer {
+                    expected: 512,
+                    received: 0
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn error_wire_codes() {
+        let cases = [
+            (SpimError::InvalidBuffer, vec![0]),
+            (SpimError::LengthMismatch, vec![1]),
+            (SpimError::NotPresent, vec![2]),
+            (SpimError::CsAlreadyAsserted, vec![3]),
+            (SpimError::CsNotAsserted, vec![4]),
+            (SpimError::PartialFrame, vec![5]),
+            (SpimError::QueueFull, vec![6]),
+            (SpimError::Parked, vec![7]),
+            (
+                SpimError::ShortTransfer {
+                    expected: 512,
+                    received: 3,
+                },
+                vec![8, 0x00, 0x02, 0, 0, 3, 0, 0, 0],
+            ),
+            (SpimError::NotReady { sent: 0x10203 }, vec![9, 3, 2, 1, 0]),
+        ];
+        for (e, wire) in cases {
+            let mut buf = [0; SpimError::WIRE_LEN];
+            let n = e.to_wire(&mut buf);
+            assert_eq!(&buf[..n], &wire[..], "{:?}", e);
+            // Too short for the fields
+            assert_eq!(e.to_wire(&mut buf[..n - 1]), 0);
+        }
+    }
+
+    #[test]
+    fn receive_with_tx_idle_byte() {
+        let mut buf = [0x5a; 4];
+        assert_eq!(receive_cmd(Some(0xff), &mut buf), 0xc007_0003);
+        assert_eq!(buf, [0xff; 4]);
+        let mut buf = [0x5a; 512];
+        assert_eq!(receive_cmd(Some(0x00), &mut buf), 0xc007_01ff);
+        assert_eq!(buf, [0x00; 512]);
+    }
+
+    #[test]
+    fn receive_without_tx_idle_byte() {
+        // RX only, the buffer is left for the channel to fill
+        let mut buf = [0x5a; 3];
+        assert_eq!(receive_cmd(None, &mut buf), 0x7007_0002);
+        assert_eq!(buf, [0x5a; 3]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Find the fastest SPI clock a device works reliably at
+//!
+//! The workable clock varies from board to board and with temperature,
+//! especially on the FPGA prototype. [UdmaSpim::calibrate] starts at the
+//! configured divider, which is assumed to work, and speeds up one divider
+//! step at a time until the test fails.
+//!
+//! ```ignore
+//! let div = spim.config().clk_div;
+//! let sck_hz = spim.calibrate(PERIPH_HZ, CalibrationTest::ReadId { cs, expected: ID }, 16)?;
+//! sprintln!("SCK {} Hz (clk_div {} -> {})", sck_hz, div, spim.config().clk_div);
+//! ```
+use super::{ChipSelect, Enabled, SpimError, UdmaSpim};
+
+const CMD_READ: u8 = 0x03;
+const CMD_JEDEC_ID: u8 = 0x9f;
+
+/// Read-back chunk of [CalibrationTest::Verify]
+const VERIFY_CHUNK: usize = 64;
+
+/// Check run repeatedly at each clock divider
+pub enum CalibrationTest<'a> {
+    /// Read the JEDEC ID of a SPI NOR flash
+    ReadId { cs: ChipSelect, expected: [u8; 3] },
+    /// Read a region of a SPI NOR flash that holds known data, e.g., a
+    /// pattern programmed during production
+    Verify {
+        cs: ChipSelect,
+        addr: u32,
+        expected: &'a [u8],
+    },
+    /// Returns whether the device responded correctly
+    Custom(&'a mut dyn FnMut(&mut UdmaSpim<'_, Enabled>) -> Result<bool, SpimError>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CalError {
+    /// The test failed at the configured divider already
+    NoReliableSetting,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for CalError {
+    fn from(e: SpimError) -> Self {
+        CalError::Spim(e)
+    }
+}
+
+impl<'u> UdmaSpim<'u, Enabled> {
+    /// Sweep the clock divider from the configured one towards zero and keep
+    /// the fastest setting that passes `passes` consecutive runs of `test`,
+    /// slowed down by one step for margin
+    ///
+    /// The chosen divider is applied to the configuration, which is restored
+    /// on error. Returns the resulting SCK frequency for a peripheral clock
+    /// of `periph_hz`.
+    pub fn calibrate(
+        &mut self,
+        periph_hz: u32,
+        mut test: CalibrationTest<'_>,
+        passes: u32,
+    ) -> Result<u32, CalError> {
+        if let CalibrationTest::Verify { expected, .. } = test {
+            if expected.is_empty() {
+                return Err(SpimError::InvalidBuffer.into());
+            }
+        }
+
+        let start = self.cfg;
+        let mut fastest = None;
+        for clk_div in (0..=start.clk_div).rev() {
+            self.cfg = start.with_clk_div(clk_div);
+            match self.passes(&mut test, passes) {
+                Ok(true) => fastest = Some(clk_div),
+                Ok(false) => break,
+                Err(e) => {
+                    self.cfg = start;
+                    return Err(e.into());
+                }
+            }
+        }
+
+        let Some(fastest) = fastest else {
+            self.cfg = start;
+            return Err(CalError::NoReliableSetting);
+        };
+        let clk_div = fastest.saturating_add(1).min(start.clk_div);
+        self.cfg = start.with_clk_div(clk_div);
+        Ok(self.cfg.sck_hz(periph_hz))
+    }
+
+    fn passes(&mut self, test: &mut CalibrationTest<'_>, passes: u32) -> Result<bool, SpimError> {
+        for _ in 0..passes {
+            let ok = match test {
+                CalibrationTest::ReadId { cs, expected } => {
+                    let mut id = [0u8; 3];
+                    let mut t = self.transaction(*cs)?;
+                    t.write(&[CMD_JEDEC_ID])?;
+                    t.read(&mut id)?;
+                    id == *expected
+                }
+                CalibrationTest::Verify { cs, addr, expected } => {
+                    self.verify(*cs, *addr, expected)?
+                }
+                CalibrationTest::Custom(f) => f(self)?,
+            };
+            if !ok {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn verify(&mut self, cs: ChipSelect, addr: u32, expected: &[u8]) -> Result<bool, SpimError> {
+        let mut buf = [0u8; VERIFY_CHUNK];
+        let mut t = self.transaction(cs)?;
+        let [_, a2, a1, a0] = addr.to_be_bytes();
+        t.write(&[CMD_READ, a2, a1, a0])?;
+        for chunk in expected.chunks(VERIFY_CHUNK) {
+            let buf = &mut buf[..chunk.len()];
+            t.read(buf)?;
+            if buf != chunk {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,192 @@
+//! Many short chip select windows sent with few uDMA dispatches
+//!
+//! Sending each register write of, e.g., a display init sequence with
+//! [UdmaSpim::sot], [UdmaSpim::send] and [UdmaSpim::eot] costs a command
+//! dispatch per step and a TX transfer per write, each waited for. A
+//! [SpimCoalescer] collects the data of consecutive windows in one buffer and
+//! their SOT, TX_DATA and EOT commands in one command buffer, and dispatches
+//! both at once when either is full or on [SpimCoalescer::flush]:
+//!
+//! ```ignore
+//! let mut c = SpimCoalescer::<256>::new();
+//! for (reg, args) in INIT_SEQUENCE {
+//!     c.begin(&mut spim, ChipSelect::Cs0)?;
+//!     c.write(&mut spim, &[*reg])?;
+//!     c.write(&mut spim, args)?;
+//!     c.end()?;
+//! }
+//! c.flush(&mut spim)?;
+//! ```
+//!
+//! Each window still gets its own SOT and EOT, and data written in a window
+//! is always dispatched ahead of its EOT. A window that is open at a flush
+//! stays asserted, and goes on in the next dispatch. Within one dispatch,
+//! the gap between windows is that of the SPIM, without the hold of
+//! [UdmaSpim::set_cs_hold_cycles].
+use riscv::register::mcycle;
+
+use super::{
+    check_buf, data_cmd, ChipSelect, CommandBuf, Enabled, SpimError, UdmaSpim, MAX_XFER_LEN,
+    SPI_CMD_EOT, SPI_CMD_SOT, SPI_CMD_TX_DATA,
+};
+
+/// Windows collected per dispatch at most
+pub const MAX_WINDOWS: usize = 16;
+
+/// CFG, SOT, TX_DATA and EOT per window
+const CMD_WORDS: usize = 4 * MAX_WINDOWS;
+
+/// Collects up to `BUF` bytes of TX data for one dispatch, sa. [module
+/// documentation](self)
+///
+/// Must be placed in memory visible to the uDMA. Data written but not
+/// flushed when the coalescer is dropped is not sent.
+pub struct SpimCoalescer<const BUF: usize> {
+    data: [u8; BUF],
+    fill: usize,
+    /// Start of the data not yet covered by a TX_DATA command
+    segment_start: usize,
+    cmd: CommandBuf<CMD_WORDS>,
+    /// Chip select of the window opened by [SpimCoalescer::begin]
+    open: Option<ChipSelect>,
+    /// The last dispatch left a window open
+    asserted: bool,
+    /// `cmd` holds an EOT
+    ends_window: bool,
+}
+
+impl<const BUF: usize> Default for SpimCoalescer<BUF> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BUF: usize> SpimCoalescer<BUF> {
+    pub const fn new() -> Self {
+        const { assert!(BUF != 0 && BUF <= MAX_XFER_LEN) };
+        Self {
+            data: [0; BUF],
+            fill: 0,
+            segment_start: 0,
+            cmd: CommandBuf::new(),
+            open: None,
+            asserted: false,
+            ends_window: false,
+        }
+    }
+
+    /// Open a window on `cs`, flushing first if no more windows fit
+    pub fn begin(
+        &mut self,
+        spim: &mut UdmaSpim<'_, Enabled>,
+        cs: ChipSelect,
+    ) -> Result<(), SpimError> {
+        if self.open.is_some() {
+            return Err(SpimError::CsAlreadyAsserted);
+        }
+        // Room for CFG, SOT, TX_DATA and EOT
+        if self.cmd.len() + 4 > CMD_WORDS {
+            self.flush(spim)?;
+        }
+        self.cmd
+            .push_word(spim.cfg.cmd())
+            .push_cmd(SPI_CMD_SOT, cs as u32);
+        self.open = Some(cs);
+        Ok(())
+    }
+
+    /// Add `data` to the open window, flushing whenever the buffer fills up
+    pub fn write(
+        &mut self,
+        spim: &mut UdmaSpim<'_, Enabled>,
+        mut data: &[u8],
+    ) -> Result<(), SpimError> {
+        if self.open.is_none() {
+            return Err(SpimError::CsNotAsserted);
+        }
+        while !data.is_empty() {
+            let n = (BUF - self.fill).min(data.len());
+            let (head, rest) = data.split_at(n);
+            self.data[self.fill..self.fill + n].copy_from_slice(head);
+            self.fill += n;
+            data = rest;
+
+            if self.fill == BUF {
+                self.flush(spim)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the open window
+    ///
+    /// The EOT goes out with the next dispatch, after the window's data.
+    pub fn end(&mut self) -> Result<(), SpimError> {
+        if self.open.is_none() {
+            return Err(SpimError::CsNotAsserted);
+        }
+        self.close_segment();
+        self.cmd.push_word(SPI_CMD_EOT);
+        self.open = None;
+        self.ends_window = true;
+        Ok(())
+    }
+
+    /// Dispatch the collected data and commands, and wait for the data to go
+    /// out
+    ///
+    /// Fails with [SpimError::InvalidBuffer] if the coalescer is not in
+    /// memory visible to the uDMA, and with [SpimError::CsAlreadyAsserted]
+    /// if a window was opened on the driver itself.
+    pub fn flush(&mut self, spim: &mut UdmaSpim<'_, Enabled>) -> Result<(), SpimError> {
+        self.close_segment();
+        if self.cmd.is_empty() {
+            return Ok(());
+        }
+        if spim.parked.is_some() {
+            return Err(SpimError::Parked);
+        }
+        // The only window that may be asserted is one of ours
+        let continued = spim.cs.is_some();
+        if continued && !self.asserted {
+            return Err(SpimError::CsAlreadyAsserted);
+        }
+        if self.fill != 0 {
+            check_buf(&self.data[..self.fill])?;
+        }
+
+        if !continued {
+            spim.wait_cs_hold();
+        }
+        if self.fill != 0 {
+            spim.start_tx(self.data.as_ptr(), self.fill);
+        }
+        spim.enqueue_cmd_words(self.cmd.as_words());
+        if self.fill != 0 {
+            spim.wait_tx();
+        }
+
+        spim.cs = self.open;
+        self.asserted = self.open.is_some();
+        if self.ends_window {
+            spim.last_eot_time = mcycle::read64();
+            if self.open.is_none() {
+                spim.gate_after_eot();
+            }
+        }
+        self.fill = 0;
+        self.segment_start = 0;
+        self.cmd.clear();
+        self.ends_window = false;
+        Ok(())
+    }
+
+    /// Cover the data written since the last TX_DATA command with one
+    fn close_segment(&mut self) {
+        let len = self.fill - self.segment_start;
+        if len != 0 {
+            self.cmd.push_word(data_cmd(SPI_CMD_TX_DATA, len));
+            self.segment_start = self.fill;
+        }
+    }
+}
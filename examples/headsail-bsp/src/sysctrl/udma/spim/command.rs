@@ -0,0 +1,275 @@
+//! Encoding of SPIM command words
+//!
+//! Field layouts follow the PULP `udma_spim` HAL. The driver itself issues
+//! CFG, SOT, EOT and the data commands. SEND_CMD, SETUP_UCA and SETUP_UCS
+//! are provided for hand-built command buffers, and have not been verified
+//! on Headsail.
+use super::{
+    words_as_bytes, MAX_XFER_LEN, SPI_CMD_FULL_DUPL, SPI_CMD_RX_DATA, SPI_CMD_SEND_CMD,
+    SPI_CMD_SETUP_UCA, SPI_CMD_SETUP_UCS, SPI_CMD_TX_DATA,
+};
+use crate::sysctrl::mmap;
+
+/// uDMA channel set up by [SpiCommandBuilder::setup_uca] and
+/// [SpiCommandBuilder::setup_ucs]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum UcChannel {
+    Tx = 0,
+    Rx = 1,
+}
+
+/// Builds command words with their fields checked
+///
+/// The methods panic on values that do not fit their fields, at compile
+/// time when called in a const context:
+///
+/// ```ignore
+/// const CMD: [u32; 2] = [
+///     SpiCommandBuilder::send_cmd(0x9f),
+///     SpiCommandBuilder::rx_data(3, 8),
+/// ];
+/// ```
+pub struct SpiCommandBuilder;
+
+impl SpiCommandBuilder {
+    /// Send `len` words of `bits_per_word` bits from the TX channel
+    pub const fn tx_data(len: usize, bits_per_word: u8) -> u32 {
+        data(SPI_CMD_TX_DATA, len, bits_per_word)
+    }
+
+    /// Receive `len` words of `bits_per_word` bits to the RX channel
+    pub const fn rx_data(len: usize, bits_per_word: u8) -> u32 {
+        data(SPI_CMD_RX_DATA, len, bits_per_word)
+    }
+
+    /// Send and receive `len` words of `bits_per_word` bits at once
+    pub const fn full_duplex(len: usize, bits_per_word: u8) -> u32 {
+        data(SPI_CMD_FULL_DUPL, len, bits_per_word)
+    }
+
+    /// Clock out the 8-bit `value` from the command word itself, without a
+    /// TX transfer
+    pub const fn send_cmd(value: u8) -> u32 {
+        SPI_CMD_SEND_CMD | (7 << 16) | value as u32
+    }
+
+    /// Set the start address of `channel` to `addr`, in bytes
+    ///
+    /// The 21-bit address field holds the offset into L2, as the uDMA
+    /// addresses nothing else. The bits above it are the same for every
+    /// buffer in [mmap::UDMA_MEM_START]..[mmap::UDMA_MEM_END], so no address
+    /// information is lost, and a buffer cannot cross a boundary of the
+    /// field.
+    ///
+    /// # Panics
+    ///
+    /// If `addr` is not in the memory visible to the uDMA
+    pub const fn setup_uca(channel: UcChannel, addr: u32) -> u32 {
+        let addr = addr as usize;
+        assert!(addr >= mmap::UDMA_MEM_START && addr < mmap::UDMA_MEM_END);
+        SPI_CMD_SETUP_UCA | ((channel as u32) << 27) | (addr as u32 & 0x1f_ffff)
+    }
+
+    /// Set the transfer size of `channel` to `len` bytes
+    ///
+    /// Unlike the data commands, the size field is not biased by one.
+    ///
+    /// # Panics
+    ///
+    /// If `len` is zero or does not fit the 16-bit size field
+    pub const fn setup_ucs(channel: UcChannel, len: usize) -> u32 {
+        assert!(len != 0 && len <= 0xffff);
+        SPI_CMD_SETUP_UCS | ((channel as u32) << 27) | len as u32
+    }
+}
+
+/// Up to `CAP_WORDS` command words, collected for one dispatch with
+/// [UdmaSpim::enqueue_cmd_words](super::UdmaSpim::enqueue_cmd_words)
+///
+/// ```ignore
+/// let mut cmd = CommandBuf::<2>::new();
+/// cmd.push_word(SpiCommandBuilder::send_cmd(0x9f))
+///     .push_word(SpiCommandBuilder::rx_data(3, 8));
+/// spim.enqueue_cmd_words(cmd.as_words());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct CommandBuf<const CAP_WORDS: usize> {
+    words: [u32; CAP_WORDS],
+    len: usize,
+}
+
+impl<const CAP_WORDS: usize> Default for CommandBuf<CAP_WORDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CAP_WORDS: usize> CommandBuf<CAP_WORDS> {
+    pub const fn new() -> Self {
+        Self {
+            words: [0; CAP_WORDS],
+            len: 0,
+        }
+    }
+
+    /// Append a complete command word
+    ///
+    /// # Panics
+    ///
+    /// If the buffer already holds `CAP_WORDS` words
+    #[inline]
+    pub fn push_word(&mut self, word: u32) -> &mut Self {
+        assert!(self.len < CAP_WORDS, "CommandBuf is full");
+        self.words[self.len] = word;
+        self.len += 1;
+        self
+    }
+
+    /// Append command `cmd`, an ID in bits 31..=28, with the fields `arg`
+    ///
+    /// # Panics
+    ///
+    /// If `arg` overlaps the ID bits or the buffer is full
+    #[inline]
+    pub fn push_cmd(&mut self, cmd: u32, arg: u32) -> &mut Self {
+        assert!(arg & 0xf000_0000 == 0);
+        self.push_word(cmd | arg)
+    }
+
+    pub fn as_words(&self) -> &[u32] {
+        &self.words[..self.len]
+    }
+
+    /// Words in native byte order, as expected by the CMD channel
+    pub fn as_bytes(&self) -> &[u8] {
+        words_as_bytes(self.as_words())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Data command `id` of `len` words of `bits_per_word` bits
+///
+/// # Panics
+///
+/// If `len` is zero or over [MAX_XFER_LEN], or `bits_per_word` is not in
+/// 1..=32
+#[inline]
+pub(crate) const fn data(id: u32, len: usize, bits_per_word: u8) -> u32 {
+    assert!(len != 0 && len <= MAX_XFER_LEN);
+    assert!(bits_per_word != 0 && bits_per_word <= 32);
+    id | ((bits_per_word as u32 - 1) << 16) | (len as u32 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_data_commands() {
+        assert_eq!(SpiCommandBuilder::tx_data(4, 8), 0x6007_0003);
+        assert_eq!(SpiCommandBuilder::rx_data(3, 8), 0x7007_0002);
+        assert_eq!(
+            SpiCommandBuilder::full_duplex(MAX_XFER_LEN, 32),
+            0xc01f_ffff
+        );
+        assert_eq!(SpiCommandBuilder::tx_data(1, 1), 0x6000_0000);
+    }
+
+    #[test]
+    fn builder_send_and_setup() {
+        assert_eq!(SpiCommandBuilder::send_cmd(0x9f), 0x2007_009f);
+        assert_eq!(
+            SpiCommandBuilder::setup_uca(UcChannel::Rx, 0x1c00_1234),
+            0xd800_1234
+        );
+        assert_eq!(
+            SpiCommandBuilder::setup_uca(UcChannel::Tx, mmap::UDMA_MEM_END as u32 - 1),
+            0xd000_ffff
+        );
+        assert_eq!(
+            SpiCommandBuilder::setup_ucs(UcChannel::Tx, 0xffff),
+            0xe000_ffff
+        );
+        assert_eq!(SpiCommandBuilder::setup_ucs(UcChannel::Rx, 1), 0xe800_0001);
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_rejects_empty_data() {
+        SpiCommandBuilder::rx_data(0, 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_rejects_long_data() {
+        SpiCommandBuilder::tx_data(MAX_XFER_LEN + 1, 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_rejects_wide_words() {
+        SpiCommandBuilder::full_duplex(1, 33);
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_rejects_address_outside_l2() {
+        SpiCommandBuilder::setup_uca(UcChannel::Tx, mmap::UDMA_MEM_END as u32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn builder_rejects_oversized_ucs() {
+        SpiCommandBuilder::setup_ucs(UcChannel::Rx, 0x1_0000);
+    }
+
+    #[test]
+    fn data_command_fields() {
+        // Length and word size are both biased by one
+        assert_eq!(data(SPI_CMD_TX_DATA, 1, 8), 0x6007_0000);
+        assert_eq!(data(SPI_CMD_RX_DATA, 512, 8), 0x7007_01ff);
+        assert_eq!(data(SPI_CMD_FULL_DUPL, 2, 16), 0xc00f_0001);
+    }
+
+    #[test]
+    fn command_buf_collects_words() {
+        let mut cmd = CommandBuf::<3>::new();
+        assert!(cmd.is_empty());
+        cmd.push_word(0x2007_009f)
+            .push_cmd(SPI_CMD_RX_DATA, 0x0007_0002);
+        assert_eq!(cmd.len(), 2);
+        assert_eq!(cmd.as_words(), &[0x2007_009f, 0x7007_0002]);
+
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&0x2007_009f_u32.to_ne_bytes());
+        bytes[4..].copy_from_slice(&0x7007_0002_u32.to_ne_bytes());
+        assert_eq!(cmd.as_bytes(), &bytes);
+
+        cmd.clear();
+        assert!(cmd.as_words().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "CommandBuf is full")]
+    fn command_buf_rejects_overflow() {
+        CommandBuf::<1>::new().push_word(0).push_word(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn command_buf_rejects_arg_in_id_bits() {
+        CommandBuf::<1>::new().push_cmd(SPI_CMD_TX_DATA, 1 << 28);
+    }
+}
@@ -0,0 +1,115 @@
+//! Daisy-chained shift-register devices sharing one chip select
+//!
+//! Each device shifts the bits it receives on to the next one, so one long
+//! frame is clocked out per chip select window and every device latches its
+//! part when CS rises. The data for the device furthest from the SPIM goes
+//! out first.
+//!
+//! ```text
+//! MOSI -> device 0 -> device 1 -> ... -> device N-1 -> MISO
+//! frame:  [ device N-1 | ... | device 1 | device 0 ]
+//! ```
+//!
+//! ```ignore
+//! dma_static!(FRAME: [u8; 4 * 2]);
+//! let mut chain = DaisyChain::<4>::new(ChipSelect::Cs0, unsafe { FRAME.get_mut() })?;
+//! chain.device_mut(2).copy_from_slice(&[REG_DIGIT0, 0x7e]);
+//! chain.commit(&mut spim)?;
+//! ```
+use super::{check_buf, ChipSelect, Enabled, SpimError, UdmaSpim, MAX_XFER_LEN};
+
+/// Frame buffer of a chain of `DEVICES` devices, sa. [module
+/// documentation](self)
+pub struct DaisyChain<const DEVICES: usize> {
+    cs: ChipSelect,
+    frame: &'static mut [u8],
+}
+
+impl<const DEVICES: usize> DaisyChain<DEVICES> {
+    /// `frame` is split evenly between the devices
+    ///
+    /// Returns [SpimError::PartialFrame] if its length is not a multiple of
+    /// `DEVICES`.
+    pub fn new(cs: ChipSelect, frame: &'static mut [u8]) -> Result<Self, SpimError> {
+        if DEVICES == 0 || frame.is_empty() || !frame.len().is_multiple_of(DEVICES) {
+            return Err(SpimError::PartialFrame);
+        }
+        for segment in frame.chunks(MAX_XFER_LEN) {
+            check_buf(segment)?;
+        }
+        Ok(Self { cs, frame })
+    }
+
+    pub fn free(self) -> &'static mut [u8] {
+        self.frame
+    }
+
+    /// Number of bytes each device takes
+    #[inline]
+    pub fn device_len(&self) -> usize {
+        self.frame.len() / DEVICES
+    }
+
+    /// Bytes of device `idx`, counted from the one nearest to the SPIM
+    ///
+    /// # Panics
+    ///
+    /// If `idx >= DEVICES`
+    pub fn device(&self, idx: usize) -> &[u8] {
+        let range = self.range(idx);
+        &self.frame[range]
+    }
+
+    /// Mutable bytes of device `idx`, sa. [device](Self::device)
+    pub fn device_mut(&mut self, idx: usize) -> &mut [u8] {
+        let range = self.range(idx);
+        &mut self.frame[range]
+    }
+
+    /// Whole frame as clocked out
+    pub fn frame(&self) -> &[u8] {
+        self.frame
+    }
+
+    /// Send the whole frame in one chip select window
+    ///
+    /// Devices whose bytes did not change are rewritten with the same data,
+    /// as a shift register chain has no way to skip a device.
+    pub fn commit(&mut self, spim: &mut UdmaSpim<'_, Enabled>) -> Result<(), SpimError> {
+        let mut t = spim.transaction(self.cs)?;
+        t.continue_tx(self.frame)
+    }
+
+    /// Send the frame again and check that the same frame comes out of the
+    /// far end of the chain
+    ///
+    /// For chains whose last device is wired back to MISO. Succeeds with
+    /// `false` if a device did not hold the last committed frame, e.g.,
+    /// because of a broken link in the chain. `rx` must be as long as the
+    /// frame.
+    pub fn verify(
+        &mut self,
+        spim: &mut UdmaSpim<'_, Enabled>,
+        rx: &mut [u8],
+    ) -> Result<bool, SpimError> {
+        if rx.len() != self.frame.len() {
+            return Err(SpimError::LengthMismatch);
+        }
+        let mut t = spim.transaction(self.cs)?;
+        for (rx, tx) in rx
+            .chunks_mut(MAX_XFER_LEN)
+            .zip(self.frame.chunks(MAX_XFER_LEN))
+        {
+            t.transfer(rx, tx)?;
+        }
+        Ok(*rx == *self.frame)
+    }
+
+    fn range(&self, idx: usize) -> core::ops::Range<usize> {
+        assert!(idx < DEVICES);
+        let len = self.device_len();
+        // The first bytes out end up in the last device
+        let start = (DEVICES - 1 - idx) * len;
+        start..start + len
+    }
+}
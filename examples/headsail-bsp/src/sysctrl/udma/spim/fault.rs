@@ -0,0 +1,130 @@
+//! Recovery from uDMA faults in the middle of a chip select window
+//!
+//! A transfer that stops halfway leaves chip select asserted, and the device
+//! waiting for clocks that never come. The uDMA has no error interrupt, and
+//! the SPIM no way to end a window on its own when a channel fails, so faults
+//! are reported by whoever detects them, e.g., a watchdog or the SPIM
+//! interrupt handler finding a channel stuck. [DmaFaultHandler::handle]
+//! records the fault and, with [CsErrorAction::Deassert], stops the channels
+//! and deasserts chip select with an EOT on the CMD channel:
+//!
+//! ```ignore
+//! spim.set_cs_on_dma_error(CsErrorAction::Deassert);
+//!
+//! // Wherever the fault is detected
+//! DmaFaultHandler::handle(DmaFaultCode::Tx);
+//!
+//! // Main loop
+//! if let Some(code) = spim.take_dma_fault() {
+//!     // Chip select is deasserted, retry the transfer
+//! }
+//! ```
+//!
+//! A blocking transfer cut short by the handler returns as if it had
+//! finished, so check [UdmaSpim::take_dma_fault] after transfers that may
+//! fault. The SPIM is also reset through CTRL_CFG_RST, which the SVD
+//! documents as unimplemented, so the EOT is what deasserts chip select.
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use riscv::register::mcycle;
+
+use super::{stop_and_reset, Enabled, UdmaSpim};
+use crate::{pac, trace_event};
+
+/// Sa. [UdmaSpim::set_cs_on_dma_error]
+static DEASSERT: AtomicBool = AtomicBool::new(false);
+/// Last [DmaFaultCode] not yet taken, 0 for none, with [FAULT_RESET] if
+/// the SPIM was reset
+static FAULT: AtomicU8 = AtomicU8::new(0);
+const FAULT_RESET: u8 = 0x80;
+
+/// What [DmaFaultHandler::handle] does to the SPIM
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CsErrorAction {
+    /// Only record the fault, leaving chip select and the channels as they
+    /// are
+    #[default]
+    Keep,
+    /// Stop the channels and deassert chip select with an EOT
+    Deassert,
+}
+
+/// Channel in which the fault was detected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum DmaFaultCode {
+    Cmd = 1,
+    Tx = 2,
+    Rx = 3,
+    /// No single channel to blame, e.g., a transfer timed out
+    Other = 4,
+}
+
+impl DmaFaultCode {
+    fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            1 => Some(DmaFaultCode::Cmd),
+            2 => Some(DmaFaultCode::Tx),
+            3 => Some(DmaFaultCode::Rx),
+            4 => Some(DmaFaultCode::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Entry point for reporting faults from interrupt handlers, sa. [module
+/// documentation](self)
+pub struct DmaFaultHandler;
+
+impl DmaFaultHandler {
+    /// Record `code` for [UdmaSpim::take_dma_fault], and recover the SPIM as
+    /// chosen by [UdmaSpim::set_cs_on_dma_error]
+    ///
+    /// Only the last fault is kept. Takes the uDMA registers without going
+    /// through the driver, so the driver must not be in the middle of
+    /// programming a transfer on another context, e.g., by calling this
+    /// from a handler that preempts it. With [CsErrorAction::Deassert], this
+    /// spins until the CMD channel has consumed the EOT.
+    pub fn handle(code: DmaFaultCode) {
+        let mut raw = code as u8;
+        if DEASSERT.load(Ordering::Acquire) {
+            let sysctrl = unsafe { pac::Sysctrl::steal() };
+            stop_and_reset(sysctrl.udma());
+            raw |= FAULT_RESET;
+        }
+        FAULT.store(raw, Ordering::Release);
+    }
+}
+
+impl UdmaSpim<'_, Enabled> {
+    /// Choose what [DmaFaultHandler::handle] does to the SPIM
+    ///
+    /// Defaults to [CsErrorAction::Keep]. The choice is shared by all
+    /// handles of the SPIM.
+    #[inline]
+    pub fn set_cs_on_dma_error(&mut self, action: CsErrorAction) {
+        DEASSERT.store(action == CsErrorAction::Deassert, Ordering::Release);
+    }
+
+    /// The last fault reported with [DmaFaultHandler::handle], cleared on
+    /// return
+    ///
+    /// If the handler deasserted chip select, the driver forgets the window it
+    /// had open, so the next transaction starts with a fresh SOT.
+    pub fn take_dma_fault(&mut self) -> Option<DmaFaultCode> {
+        let raw = riscv::interrupt::free(|| {
+            let raw = FAULT.load(Ordering::Acquire);
+            FAULT.store(0, Ordering::Relaxed);
+            raw
+        });
+        let code = DmaFaultCode::from_raw(raw & !FAULT_RESET)?;
+        if raw & FAULT_RESET != 0 {
+            trace_event!(SpimDone);
+            self.cs = None;
+            self.last_eot_time = mcycle::read64();
+            self.gate_after_eot();
+        }
+        Some(code)
+    }
+}
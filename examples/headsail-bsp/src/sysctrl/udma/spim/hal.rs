@@ -0,0 +1,88 @@
+//! [SpiDevice] on one chip select line of the uDMA SPIM
+//!
+//! Drivers written against embedded-hal take a [SpimSpiDevice]:
+//!
+//! ```ignore
+//! let dev = SpimSpiDevice::new(&mut spim, ChipSelect::Cs0);
+//! let mut sensor = Bme280::new(dev);
+//! ```
+//!
+//! The [Operation](spi::Operation) of embedded-hal has no step that pulses
+//! chip select within a transaction. Devices that latch a command on the
+//! rising edge of CS, e.g., some ADCs, are driven with
+//! [SpimSpiDevice::transaction_ops] and [Operation::CsPulse] instead.
+use embedded_hal::spi::{self, ErrorKind, ErrorType, SpiDevice};
+
+use super::{ChipSelect, Enabled, Operation, SpimError, UdmaSpim};
+use crate::delay;
+
+impl spi::Error for SpimError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SpimError::CsAlreadyAsserted | SpimError::CsNotAsserted => ErrorKind::ChipSelectFault,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// [UdmaSpim] with chip select `cs`, sa. [module documentation](self)
+pub struct SpimSpiDevice<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+}
+
+impl<'s, 'u> SpimSpiDevice<'s, 'u> {
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect) -> Self {
+        Self { spim, cs }
+    }
+
+    pub fn free(self) -> &'s mut UdmaSpim<'u, Enabled> {
+        self.spim
+    }
+
+    /// Sa. [UdmaSpim::transaction_ops]
+    #[inline]
+    pub fn transaction_ops(&mut self, ops: &mut [Operation<'_>]) -> Result<(), SpimError> {
+        self.spim.transaction_ops(self.cs, ops)
+    }
+}
+
+impl ErrorType for SpimSpiDevice<'_, '_> {
+    type Error = SpimError;
+}
+
+impl SpiDevice for SpimSpiDevice<'_, '_> {
+    /// Run `operations` within one chip select window
+    ///
+    /// Buffers must be in memory visible to the uDMA. Empty buffers are
+    /// skipped. For a [Transfer](spi::Operation::Transfer) with buffers of
+    /// different lengths, the tail of the longer one is written or read on
+    /// its own, so MOSI carries [SpimConfig::tx_idle_byte](super::SpimConfig)
+    /// past the end of the write buffer.
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), SpimError> {
+        let mut t = self.spim.transaction(self.cs)?;
+        for op in operations {
+            match op {
+                spi::Operation::Write(data) if !data.is_empty() => t.write(data)?,
+                spi::Operation::Read(buf) if !buf.is_empty() => t.read(buf)?,
+                spi::Operation::Transfer(rx, tx) => {
+                    let n = rx.len().min(tx.len());
+                    if n != 0 {
+                        t.transfer(&mut rx[..n], &tx[..n])?;
+                    }
+                    if rx.len() > n {
+                        t.read(&mut rx[n..])?;
+                    } else if tx.len() > n {
+                        t.write(&tx[n..])?;
+                    }
+                }
+                spi::Operation::TransferInPlace(buf) if !buf.is_empty() => {
+                    t.transfer_in_place(buf)?
+                }
+                spi::Operation::DelayNs(ns) => delay::nanos(*ns),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
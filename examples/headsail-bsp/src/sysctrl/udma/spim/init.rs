@@ -0,0 +1,197 @@
+//! Device bring-up sequences as data
+//!
+//! An init sequence is a table of [InitStep]s. [InitRunner] works through it
+//! one step per [InitRunner::poll], so bring-up can run from an event loop
+//! next to other work. Delays and polls compare against `mcycle` instead of
+//! blocking. [InitRunner::run_blocking] runs the same table to completion.
+//!
+//! ```ignore
+//! const SENSOR_INIT: &[InitStep] = &[
+//!     InitStep::WriteReg(0x7e, 0xb6),
+//!     InitStep::DelayMs(2),
+//!     InitStep::PollUntil { reg: 0x03, mask: 0x10, val: 0x10, timeout_ms: 10 },
+//!     InitStep::Write(&[0x40, 0x28]),
+//! ];
+//!
+//! let mut regs = SpiRegisterMap::new(&mut spim, ChipSelect::Cs1, AddressFormat::default());
+//! let mut init = InitRunner::new(SENSOR_INIT);
+//! loop {
+//!     match init.poll(&mut regs) {
+//!         InitStatus::Pending => { /* other work */ }
+//!         InitStatus::Done => break,
+//!         InitStatus::Failed { step, cause } => panic!("step {}: {:?}", step, cause),
+//!     }
+//! }
+//! ```
+use riscv::register::mcycle;
+
+use super::{regmap::SpiRegisterMap, SpimError};
+use crate::delay;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitStep<'a> {
+    /// Send bytes as they are in one chip select window, e.g., a command
+    /// with its parameters
+    ///
+    /// The bytes are sent from where the table puts them. The SysCtrl memory
+    /// is all visible to the uDMA, so tables need no staging.
+    Write(&'a [u8]),
+    /// Write a one-byte register, sa. [SpiRegisterMap::write_buf]
+    WriteReg(u16, u8),
+    /// Wait before the next step
+    DelayMs(u32),
+    /// Read a one-byte register until `value & mask == val`
+    PollUntil {
+        reg: u16,
+        mask: u8,
+        val: u8,
+        timeout_ms: u32,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InitError {
+    /// A [InitStep::PollUntil] did not see its value in time
+    Timeout,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for InitError {
+    fn from(value: SpimError) -> Self {
+        InitError::Spim(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitStatus {
+    /// More steps to go, poll again
+    Pending,
+    /// All steps completed
+    Done,
+    /// Step at index `step` failed. The runner stays failed until
+    /// [InitRunner::restart].
+    Failed { step: usize, cause: InitError },
+}
+
+/// Runs an init sequence, sa. [module documentation](self)
+pub struct InitRunner<'a> {
+    steps: &'a [InitStep<'a>],
+    /// Index of the current step
+    step: usize,
+    /// `mcycle` at which the current delay or poll ends
+    deadline: Option<u64>,
+    failed: Option<InitError>,
+}
+
+impl<'a> InitRunner<'a> {
+    pub const fn new(steps: &'a [InitStep<'a>]) -> Self {
+        Self {
+            steps,
+            step: 0,
+            deadline: None,
+            failed: None,
+        }
+    }
+
+    /// Start over from the first step
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.deadline = None;
+        self.failed = None;
+    }
+
+    /// Index of the step the next [InitRunner::poll] works on
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Advance by at most one step
+    ///
+    /// Writes complete within the call. Delays and polls return
+    /// [InitStatus::Pending] until their time is up, reading the register
+    /// once per call in the case of [InitStep::PollUntil].
+    pub fn poll(&mut self, regs: &mut SpiRegisterMap) -> InitStatus {
+        if let Some(cause) = self.failed {
+            return InitStatus::Failed {
+                step: self.step,
+                cause,
+            };
+        }
+        let Some(&step) = self.steps.get(self.step) else {
+            return InitStatus::Done;
+        };
+
+        match self.run(step, regs) {
+            Ok(true) => {
+                self.step += 1;
+                self.deadline = None;
+                if self.step == self.steps.len() {
+                    InitStatus::Done
+                } else {
+                    InitStatus::Pending
+                }
+            }
+            Ok(false) => InitStatus::Pending,
+            Err(cause) => {
+                self.failed = Some(cause);
+                InitStatus::Failed {
+                    step: self.step,
+                    cause,
+                }
+            }
+        }
+    }
+
+    /// Poll until the sequence is done or fails
+    ///
+    /// Returns the index of the failed step together with the cause.
+    pub fn run_blocking(&mut self, regs: &mut SpiRegisterMap) -> Result<(), (usize, InitError)> {
+        loop {
+            match self.poll(regs) {
+                InitStatus::Pending => {}
+                InitStatus::Done => return Ok(()),
+                InitStatus::Failed { step, cause } => return Err((step, cause)),
+            }
+        }
+    }
+
+    /// Returns true once `step` is complete
+    fn run(&mut self, step: InitStep, regs: &mut SpiRegisterMap) -> Result<bool, InitError> {
+        match step {
+            InitStep::Write(data) => {
+                regs.write_raw(data)?;
+                Ok(true)
+            }
+            InitStep::WriteReg(addr, val) => {
+                regs.write_buf(addr, &[val])?;
+                Ok(true)
+            }
+            InitStep::DelayMs(ms) => Ok(self.expired(ms)),
+            InitStep::PollUntil {
+                reg,
+                mask,
+                val,
+                timeout_ms,
+            } => {
+                let timed_out = self.expired(timeout_ms);
+                let mut value = [0u8];
+                regs.read_buf(reg, &mut value)?;
+                match value[0] & mask == val {
+                    true => Ok(true),
+                    false if timed_out => Err(InitError::Timeout),
+                    false => Ok(false),
+                }
+            }
+        }
+    }
+
+    /// Whether `ms` have passed since the current step was first polled
+    fn expired(&mut self, ms: u32) -> bool {
+        let now = mcycle::read64();
+        let deadline = *self
+            .deadline
+            .get_or_insert_with(|| now + delay::us_to_cycles(ms.saturating_mul(1000)));
+        now >= deadline
+    }
+}
@@ -0,0 +1,254 @@
+//! Prioritized SPIM transfers dispatched one at a time
+//!
+//! Transfers are queued with [SpimQueue::push] and started by
+//! [SpimQueue::pump], typically from the SPIM interrupt handler, so that a
+//! backlog of low-priority transfers does not delay urgent ones by more than
+//! the transfer in flight.
+//!
+//! ```ignore
+//! QUEUE.push(SpimTransfer { cs: ChipSelect::Cs1, tx: log, rx: None, priority: 0 })?;
+//! QUEUE.push(SpimTransfer { cs: ChipSelect::Cs0, tx: cmd, rx: Some(sample), priority: 10 })?;
+//!
+//! // On SPIM DMA done
+//! if let QueueEvent::Dispatched { completed: Some(t) } | QueueEvent::Empty { completed: Some(t) } =
+//!     QUEUE.pump(&mut spim)
+//! {
+//!     // `t.rx` holds the received data
+//! }
+//! ```
+//!
+//! # Interleaving
+//!
+//! A long transfer delays everything behind it by its full length. With
+//! [SpimQueue::set_segment_len], transfers longer than the segment length
+//! are sent as several chip select windows of at most that many bytes, and
+//! a more urgent transfer pushed in the meantime goes out between two
+//! segments. This bounds the wait of urgent transfers to about one segment,
+//! e.g., 512 bytes take 410 us at 10 MHz SCK. The device must accept the
+//! data split over several windows, as a FIFO endpoint usually does.
+//!
+//! Only one transfer is segmented at a time. Transfers dispatched while it
+//! is suspended go in one window. Transfers of the same priority stay in
+//! order, and the segments of a transfer are never reordered.
+use super::{
+    check_buf, data_cmd, ChipSelect, CommandBuf, Enabled, SpimConfig, SpimError, UdmaSpim,
+    SPI_CMD_EOT, SPI_CMD_FULL_DUPL, SPI_CMD_SOT, SPI_CMD_TX_DATA,
+};
+
+use riscv::register::mcycle;
+
+use crate::trace_event;
+
+/// Transfer in its own chip select window
+#[derive(Debug)]
+pub struct SpimTransfer {
+    pub cs: ChipSelect,
+    pub tx: &'static [u8],
+    /// Receive buffer for a full-duplex transfer, of the same length as `tx`
+    pub rx: Option<&'static mut [u8]>,
+    /// Higher is more urgent. Transfers of equal priority are dispatched in
+    /// the order they were pushed.
+    pub priority: u8,
+}
+
+/// Result of [SpimQueue::pump]
+#[derive(Debug)]
+pub enum QueueEvent {
+    /// The most urgent pending transfer was started
+    Dispatched {
+        /// The transfer that finished before it, if any
+        completed: Option<SpimTransfer>,
+    },
+    /// No transfer is pending
+    Empty {
+        /// The transfer that just finished, if any
+        completed: Option<SpimTransfer>,
+    },
+    /// A transfer or segment is in flight, chip select is asserted outside of the
+    /// queue, or transfers are pending while the pads are parked, sa.
+    /// [UdmaSpim::park]
+    Busy,
+}
+
+/// Up to `DEPTH` pending transfers, sorted by priority
+pub struct SpimQueue<const DEPTH: usize> {
+    /// Ascending priority, so the next transfer is the last one
+    pending: [Option<SpimTransfer>; DEPTH],
+    len: usize,
+    /// Transfer being dispatched, and where its current segment ends
+    in_flight: Option<(SpimTransfer, usize)>,
+    /// Segmented transfer waiting for its next segment, and where that
+    /// starts
+    suspended: Option<(SpimTransfer, usize)>,
+    segment_len: Option<usize>,
+}
+
+impl<const DEPTH: usize> Default for SpimQueue<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize> SpimQueue<DEPTH> {
+    pub const fn new() -> Self {
+        Self {
+            pending: [const { None }; DEPTH],
+            len: 0,
+            in_flight: None,
+            suspended: None,
+            segment_len: None,
+        }
+    }
+
+    /// Send transfers longer than `len` in segments of `len` bytes, sa.
+    /// [interleaving](self#interleaving)
+    ///
+    /// `None`, the default, sends every transfer in one window. A change
+    /// takes effect from the next segment.
+    ///
+    /// # Panics
+    ///
+    /// If `len` is zero
+    pub fn set_segment_len(&mut self, len: Option<usize>) {
+        assert!(len != Some(0));
+        self.segment_len = len;
+    }
+
+    /// Number of pending transfers, not counting the one in flight
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether a dispatched transfer has not been returned by
+    /// [pump](Self::pump) yet, including one between segments
+    pub fn in_flight(&self) -> bool {
+        self.in_flight.is_some() || self.suspended.is_some()
+    }
+
+    /// Queue `transfer` behind all pending transfers of the same or higher
+    /// priority
+    ///
+    /// The transfer is handed back if its buffers are invalid or the queue
+    /// is full.
+    pub fn push(&mut self, transfer: SpimTransfer) -> Result<(), (SpimTransfer, SpimError)> {
+        if let Err(e) = check_transfer(transfer.tx, transfer.rx.as_deref()) {
+            return Err((transfer, e));
+        }
+        if self.len == DEPTH {
+            return Err((transfer, SpimError::QueueFull));
+        }
+
+        let idx = self.pending[..self.len]
+            .iter()
+            .position(|t| t.as_ref().is_some_and(|t| t.priority >= transfer.priority))
+            .unwrap_or(self.len);
+        self.pending[idx..=self.len].rotate_right(1);
+        self.pending[idx] = Some(transfer);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Collect the transfer in flight if it has finished and start the most
+    /// urgent pending one
+    ///
+    /// Does not block. Call whenever the SPIM raises its DMA done event, and
+    /// once after pushing to an idle queue.
+    pub fn pump(&mut self, spim: &mut UdmaSpim<'_, Enabled>) -> QueueEvent {
+        if spim.cs.is_some() || (self.in_flight.is_some() && !spim.is_idle()) {
+            return QueueEvent::Busy;
+        }
+        if spim.parked.is_some() && (self.len != 0 || self.suspended.is_some()) {
+            // Pending transfers wait for the pads
+            return QueueEvent::Busy;
+        }
+        let mut completed = None;
+        if let Some((t, end)) = self.in_flight.take() {
+            trace_event!(SpimDone);
+            // The EOT went out some time before, so the hold is longer if
+            // anything
+            spim.last_eot_time = mcycle::read64();
+            match end < t.tx.len() {
+                true => self.suspended = Some((t, end)),
+                false => completed = Some(t),
+            }
+        }
+
+        // The suspended transfer goes on unless something more urgent waits
+        let resume = match (&self.suspended, self.len) {
+            (Some(_), 0) => true,
+            (Some((t, _)), len) => self.pending[len - 1]
+                .as_ref()
+                .is_some_and(|next| next.priority <= t.priority),
+            (None, _) => false,
+        };
+        let (mut next, start_at) = if resume {
+            self.suspended.take().unwrap()
+        } else if self.len != 0 {
+            self.len -= 1;
+            (self.pending[self.len].take().unwrap(), 0)
+        } else {
+            if completed.is_some() {
+                spim.gate_after_eot();
+            }
+            return QueueEvent::Empty { completed };
+        };
+
+        let len = next.tx.len();
+        let end = match self.segment_len {
+            // Only one transfer is split at a time
+            Some(seg) if self.suspended.is_none() => len.min(start_at + seg),
+            _ => len,
+        };
+        let cfg = spim.cfg;
+        let rx = next.rx.as_deref_mut().map(|rx| &mut rx[start_at..end]);
+        start(spim, next.cs, cfg, &next.tx[start_at..end], rx);
+        self.in_flight = Some((next, end));
+        QueueEvent::Dispatched { completed }
+    }
+}
+
+/// Check the buffers of a transfer, `rx` being the receive buffer of a
+/// full-duplex one
+pub(super) fn check_transfer(tx: &[u8], rx: Option<&[u8]>) -> Result<(), SpimError> {
+    check_buf(tx)?;
+    if let Some(rx) = rx {
+        if rx.len() != tx.len() {
+            return Err(SpimError::LengthMismatch);
+        }
+        check_buf(rx)?;
+    }
+    Ok(())
+}
+
+/// Start `tx`, full-duplex into `rx` if given, with CFG from `cfg`, SOT on
+/// `cs`, data and EOT in one command buffer, so the window closes without
+/// the CPU
+pub(super) fn start(
+    spim: &mut UdmaSpim<'_, Enabled>,
+    cs: ChipSelect,
+    cfg: SpimConfig,
+    tx: &[u8],
+    rx: Option<&mut [u8]>,
+) {
+    spim.wait_cs_hold();
+    let len = tx.len();
+    let id = match rx {
+        Some(rx) => {
+            spim.start_rx(rx.as_mut_ptr(), len);
+            SPI_CMD_FULL_DUPL
+        }
+        None => SPI_CMD_TX_DATA,
+    };
+    spim.start_tx(tx.as_ptr(), len);
+
+    let mut cmd = CommandBuf::<4>::new();
+    cmd.push_word(cfg.cmd())
+        .push_cmd(SPI_CMD_SOT, cs as u32)
+        .push_word(data_cmd(id, len))
+        .push_word(SPI_CMD_EOT);
+    spim.enqueue_cmd_words(cmd.as_words());
+}
@@ -0,0 +1,58 @@
+//! Byte-by-byte transfers paced by a ready pin from the device
+//!
+//! Some slow devices signal on a separate pin when they can take the next
+//! byte. [UdmaSpim::send_with_ready_check] opens one chip select window per
+//! byte and starts it only once the pin is at the ready level:
+//!
+//! ```ignore
+//! let ready = gpio9.into_input();
+//! spim.send_with_ready_check(ChipSelect::Cs0, &frame, &ready, false, 1_000)?;
+//! ```
+//!
+//! The GPIO has no event output to the uDMA, so the pin cannot trigger the
+//! transfer by itself. It is polled by the CPU between bytes, and each byte
+//! is a separate SOT, TX and EOT.
+use super::{ChipSelect, Enabled, SpimError, UdmaSpim};
+use crate::{
+    delay, poll,
+    sysctrl::gpio::{Gpio, Input},
+};
+
+impl UdmaSpim<'_, Enabled> {
+    /// Send each byte of `data` in its own chip select window, after
+    /// `ready_pin` reads `polarity`
+    ///
+    /// The pin is sampled with chip select asserted, for devices that only
+    /// drive it while selected. Returns [SpimError::NotReady] with chip
+    /// select deasserted if the pin does not reach `polarity` within
+    /// `timeout_us` of asserting chip select. Cycles are converted using
+    /// [delay::set_core_hz]. `data` must be in memory visible to the uDMA.
+    ///
+    /// Each byte closes its own window, so chip select must not be asserted
+    /// already, also with strict chip select checks off. Returns
+    /// [SpimError::CsAlreadyAsserted] otherwise.
+    pub fn send_with_ready_check<const N: u32>(
+        &mut self,
+        cs: ChipSelect,
+        data: &[u8],
+        ready_pin: &Gpio<N, Input>,
+        polarity: bool,
+        timeout_us: u32,
+    ) -> Result<(), SpimError> {
+        if self.cs.is_some() {
+            return Err(SpimError::CsAlreadyAsserted);
+        }
+        let timeout = delay::us_to_cycles(timeout_us);
+        for sent in 0..data.len() {
+            self.sot(cs)?;
+            if poll::wait_timeout(|| ready_pin.is_high() == polarity, timeout).is_err() {
+                self.eot()?;
+                return Err(SpimError::NotReady { sent });
+            }
+            let res = self.send(&data[sent..sent + 1]);
+            self.eot()?;
+            res?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+//! Typed register access for SPI devices with multi-byte registers
+//!
+//! Each access is one chip select window. It holds the register address,
+//! with the read or write flag applied, followed by the value.
+//!
+//! Most sensors have a flat map of byte registers, with bit 7 of the address
+//! set for reads, as in the [default](AddressFormat::default) format. These
+//! have shorthands:
+//!
+//! ```ignore
+//! let mut regs = SpiRegisterMap::new(&mut spim, ChipSelect::Cs0, AddressFormat::default());
+//! let id = regs.read_reg(0xd0)?;
+//! regs.modify_reg(0xf4, |ctrl| ctrl | 0b11)?;
+//! let mut sample = [0u8; 6];
+//! regs.burst_read_regs(0xf7, &mut sample)?;
+//! ```
+use super::{ChipSelect, SpimError};
+use crate::sysctrl::udma::{Enabled, UdmaSpim};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// How the register address is sent in front of the value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressFormat {
+    /// 1 or 2 bytes, sent big-endian
+    pub addr_bytes: u8,
+    /// OR'ed into the address for reads, e.g., `0x80` on most sensors
+    pub read_flag: u16,
+    /// OR'ed into the address for writes
+    pub write_flag: u16,
+}
+
+impl Default for AddressFormat {
+    fn default() -> Self {
+        Self {
+            addr_bytes: 1,
+            read_flag: 0x80,
+            write_flag: 0,
+        }
+    }
+}
+
+/// Register of 1 to 4 bytes, declared once per device:
+///
+/// ```ignore
+/// const TEMP: Register = Register::new(0x05, 2, Endian::Big);
+/// let raw = regs.get(&TEMP)?;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Register {
+    pub addr: u16,
+    width: u8,
+    pub endian: Endian,
+}
+
+impl Register {
+    pub const fn new(addr: u16, width: u8, endian: Endian) -> Self {
+        assert!(
+            width >= 1 && width <= 4,
+            "register width must be 1 to 4 bytes"
+        );
+        Self {
+            addr,
+            width,
+            endian,
+        }
+    }
+
+    /// Width in bytes
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Largest value that fits in the register
+    pub const fn max(&self) -> u32 {
+        u32::MAX >> (32 - 8 * self.width as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RegisterError {
+    /// Value does not fit in the width of the register
+    ValueTooWide,
+    Spim(SpimError),
+}
+
+impl From<SpimError> for RegisterError {
+    fn from(value: SpimError) -> Self {
+        RegisterError::Spim(value)
+    }
+}
+
+/// Registers of the device on `cs`
+pub struct SpiRegisterMap<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    cs: ChipSelect,
+    format: AddressFormat,
+}
+
+impl<'s, 'u> SpiRegisterMap<'s, 'u> {
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, cs: ChipSelect, format: AddressFormat) -> Self {
+        Self { spim, cs, format }
+    }
+
+    /// Read `buf.len()` bytes starting from `addr`
+    pub fn read_buf(&mut self, addr: u16, buf: &mut [u8]) -> Result<(), SpimError> {
+        let (header, len) = header(&self.format, addr | self.format.read_flag);
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&header[..len])?;
+        t.read(buf)
+    }
+
+    /// Write `data` starting from `addr`
+    pub fn write_buf(&mut self, addr: u16, data: &[u8]) -> Result<(), SpimError> {
+        let (header, len) = header(&self.format, addr | self.format.write_flag);
+        let mut t = self.spim.transaction(self.cs)?;
+        t.write(&header[..len])?;
+        t.write(data)
+    }
+
+    /// Write `data` in one window without a register address, e.g., a
+    /// command byte with its parameters
+    pub fn write_raw(&mut self, data: &[u8]) -> Result<(), SpimError> {
+        self.spim.transaction(self.cs)?.write(data)
+    }
+
+    /// Read `reg` as an unsigned value
+    pub fn get(&mut self, reg: &Register) -> Result<u32, SpimError> {
+        get_with(reg, |addr, buf| self.read_buf(addr, buf))
+    }
+
+    /// Write `val` to `reg`, rejecting values wider than the register
+    pub fn set(&mut self, reg: &Register, val: u32) -> Result<(), RegisterError> {
+        set_with(reg, val, |addr, data| self.write_buf(addr, data))
+    }
+
+    /// Read the byte register at `addr`
+    pub fn read_reg(&mut self, addr: u8) -> Result<u8, SpimError> {
+        let mut val = [0u8];
+        self.read_buf(addr as u16, &mut val)?;
+        Ok(val[0])
+    }
+
+    /// Write `val` to the byte register at `addr`
+    pub fn write_reg(&mut self, addr: u8, val: u8) -> Result<(), SpimError> {
+        self.write_buf(addr as u16, &[val])
+    }
+
+    /// Read the byte register at `addr`, and write back what `f` makes of it
+    ///
+    /// Two windows, so not atomic with respect to the device.
+    pub fn modify_reg(&mut self, addr: u8, f: impl FnOnce(u8) -> u8) -> Result<(), SpimError> {
+        let val = self.read_reg(addr)?;
+        self.write_reg(addr, f(val))
+    }
+
+    /// Read `buf.len()` consecutive byte registers starting from `start`, in
+    /// one window
+    ///
+    /// Relies on the device incrementing the address during a read. Devices
+    /// that need a flag for it, e.g., bit 6 on ST sensors, take it as part of
+    /// [AddressFormat::read_flag].
+    pub fn burst_read_regs(&mut self, start: u8, buf: &mut [u8]) -> Result<(), SpimError> {
+        self.read_buf(start as u16, buf)
+    }
+
+    pub fn read_reg_u16_be(&mut self, addr: u16) -> Result<u16, SpimError> {
+        self.get(&Register::new(addr, 2, Endian::Big))
+            .map(|v| v as u16)
+    }
+
+    pub fn read_reg_u24_be(&mut self, addr: u16) -> Result<u32, SpimError> {
+        self.get(&Register::new(addr, 3, Endian::Big))
+    }
+
+    pub fn read_reg_u32_be(&mut self, addr: u16) -> Result<u32, SpimError> {
+        self.get(&Register::new(addr, 4, Endian::Big))
+    }
+
+    pub fn write_reg_u16_be(&mut self, addr: u16, val: u16) -> Result<(), SpimError> {
+        self.write_buf(addr, &val.to_be_bytes())
+    }
+
+    /// Only the low 24 bits of `val` are written
+    pub fn write_reg_u24_be(&mut self, addr: u16, val: u32) -> Result<(), SpimError> {
+        self.write_buf(addr, &val.to_be_bytes()[1..])
+    }
+
+    pub fn write_reg_u32_be(&mut self, addr: u16, val: u32) -> Result<(), SpimError> {
+        self.write_buf(addr, &val.to_be_bytes())
+    }
+}
+
+/// Address bytes in front of the value, and how many of them are used
+fn header(format: &AddressFormat, addr: u16) -> ([u8; 2], usize) {
+    let [hi, lo] = addr.to_be_bytes();
+    if format.addr_bytes == 1 {
+        ([lo, 0], 1)
+    } else {
+        ([hi, lo], 2)
+    }
+}
+
+/// [SpiRegisterMap::get] with the bytes of `reg` read by `read`
+fn get_with(
+    reg: &Register,
+    read: impl FnOnce(u16, &mut [u8]) -> Result<(), SpimError>,
+) -> Result<u32, SpimError> {
+    let mut buf = [0u8; 4];
+    let bytes = &mut buf[..reg.width as usize];
+    read(reg.addr, bytes)?;
+    Ok(match reg.endian {
+        Endian::Big => bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32),
+        Endian::Little => bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32),
+    })
+}
+
+/// [SpiRegisterMap::set] with the bytes of `reg` written by `write`
+fn set_with(
+    reg: &Register,
+    val: u32,
+    write: impl FnOnce(u16, &[u8]) -> Result<(), SpimError>,
+) -> Result<(), RegisterError> {
+    if val > reg.max() {
+        return Err(RegisterError::ValueTooWide);
+    }
+    let width = reg.width as usize;
+    let (buf, range) = match reg.endian {
+        Endian::Big => (val.to_be_bytes(), 4 - width..4),
+        Endian::Little => (val.to_le_bytes(), 0..width),
+    };
+    Ok(write(reg.addr, &buf[range])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device with a flat map of byte registers that auto-increments the
+    /// address, decoding the windows as [SpiRegisterMap] sends them
+    struct MockDevice {
+        format: AddressFormat,
+        regs: Vec<u8>,
+    }
+
+    impl MockDevice {
+        fn new(format: AddressFormat) -> Self {
+            Self {
+                format,
+                regs: vec![0; 0x1_0000],
+            }
+        }
+
+        /// Address sent on MOSI for `addr` with `flag`, as the device sees it
+        fn decode(&self, addr: u16, flag: u16) -> usize {
+            let (header, len) = header(&self.format, addr | flag);
+            let sent = match len {
+                1 => header[0] as u16,
+                _ => u16::from_be_bytes(header),
+            };
+            assert_eq!(sent & flag, flag, "flag lost in the header");
+            (sent & !flag) as usize
+        }
+
+        fn read(&mut self, addr: u16, buf: &mut [u8]) -> Result<(), SpimError> {
+            let at = self.decode(addr, self.format.read_flag);
+            buf.copy_from_slice(&self.regs[at..at + buf.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, addr: u16, data: &[u8]) -> Result<(), SpimError> {
+            let at = self.decode(addr, self.format.write_flag);
+            self.regs[at..at + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn get(&mut self, reg: &Register) -> u32 {
+            get_with(reg, |addr, buf| self.read(addr, buf)).unwrap()
+        }
+
+        fn set(&mut self, reg: &Register, val: u32) -> Result<(), RegisterError> {
+            set_with(reg, val, |addr, data| self.write(addr, data))
+        }
+    }
+
+    const WIDE: AddressFormat = AddressFormat {
+        addr_bytes: 2,
+        read_flag: 0x8000,
+        write_flag: 0x4000,
+    };
+
+    #[test]
+    fn big_endian_round_trip() {
+        for format in [AddressFormat::default(), WIDE] {
+            let mut dev = MockDevice::new(format);
+            for width in 1..=4 {
+                let reg = Register::new(0x10, width, Endian::Big);
+                for val in [0, 1, 0x5a, 0x1234, 0x12_3456, 0x1234_5678, reg.max()] {
+                    let val = val & reg.max();
+                    dev.set(&reg, val).unwrap();
+                    assert_eq!(dev.get(&reg), val, "width {width}, {val:#x}");
+                    // Most significant byte first, at the register address
+                    let bytes = &val.to_be_bytes()[4 - width as usize..];
+                    assert_eq!(&dev.regs[0x10..0x10 + width as usize], bytes);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn typed_widths() {
+        let mut dev = MockDevice::new(AddressFormat::default());
+        dev.regs[0x20..0x24].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        // As read by read_reg_u16_be, read_reg_u24_be and read_reg_u32_be
+        assert_eq!(dev.get(&Register::new(0x20, 2, Endian::Big)), 0xdead);
+        assert_eq!(dev.get(&Register::new(0x20, 3, Endian::Big)), 0xde_adbe);
+        assert_eq!(dev.get(&Register::new(0x20, 4, Endian::Big)), 0xdead_beef);
+        assert_eq!(
+            dev.get(&Register::new(0x20, 4, Endian::Little)),
+            0xefbe_adde
+        );
+    }
+
+    #[test]
+    fn little_endian_round_trip() {
+        let mut dev = MockDevice::new(WIDE);
+        let reg = Register::new(0x1ff, 3, Endian::Little);
+        dev.set(&reg, 0x12_3456).unwrap();
+        assert_eq!(&dev.regs[0x1ff..0x202], &[0x56, 0x34, 0x12]);
+        assert_eq!(dev.get(&reg), 0x12_3456);
+    }
+
+    #[test]
+    fn rejects_wide_values() {
+        let mut dev = MockDevice::new(AddressFormat::default());
+        let reg = Register::new(0x10, 2, Endian::Big);
+        assert_eq!(dev.set(&reg, 0x1_0000), Err(RegisterError::ValueTooWide));
+        assert!(dev.regs.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn header_formats() {
+        assert_eq!(header(&AddressFormat::default(), 0xd0), ([0xd0, 0], 1));
+        assert_eq!(header(&WIDE, 0x8123), ([0x81, 0x23], 2));
+    }
+}
@@ -0,0 +1,166 @@
+//! Transfers checked end to end by CRC, with the clock slowed down on
+//! failure
+//!
+//! Too fast an SCK shows up as corrupted reads before anything else, as
+//! MISO arrives a board-dependent delay after the clock edge. For devices
+//! that echo a checksum, [SpimReliableTransfer] detects corruption in either
+//! direction and retries at a lower clock, so a link finds its working
+//! speed without a separate [calibration](super::calibrate).
+//!
+//! # Protocol
+//!
+//! Within one chip select window, the host sends the payload followed by its
+//! [CRC-16](crate::crc::Crc16), big-endian. The device then answers with the
+//! CRC-16 of the bytes it received, and, for a full-duplex
+//! [transfer](SpimReliableTransfer::transfer), the CRC-16 of the bytes it
+//! sent:
+//!
+//! | MOSI               | MISO                                       |
+//! | :-                 | :-                                         |
+//! | payload, CRC       | response to the payload, don't care        |
+//! | don't care         | CRC of payload received                    |
+//! | don't care         | CRC of response sent (`transfer` only)     |
+//!
+//! ```ignore
+//! let mut link = SpimReliableTransfer::new(&mut spim, 3);
+//! link.write(ChipSelect::Cs0, &frame)?;
+//! if link.slowdowns() != 0 {
+//!     sprintln!("SCK slowed to clk_div {}", link.clk_div());
+//! }
+//! ```
+use super::{ChipSelect, Enabled, SpimError, UdmaSpim};
+use crate::crc::crc16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReliableError {
+    /// The echoed CRC did not match on any of the attempts, which ended
+    /// early if the divider could go no higher. The divider was restored.
+    Integrity {
+        attempts: u8,
+    },
+    Spim(SpimError),
+}
+
+impl From<SpimError> for ReliableError {
+    fn from(e: SpimError) -> Self {
+        ReliableError::Spim(e)
+    }
+}
+
+/// [UdmaSpim] with CRC-checked transfers, sa. [module documentation](self)
+///
+/// After a retry succeeds, the lower clock is kept in the driver's
+/// configuration, so later transfers, checked or not, start from the last
+/// divider that worked.
+pub struct SpimReliableTransfer<'s, 'u> {
+    spim: &'s mut UdmaSpim<'u, Enabled>,
+    retries: u8,
+    slowdowns: u32,
+}
+
+impl<'s, 'u> SpimReliableTransfer<'s, 'u> {
+    /// Retry failed transfers up to `retries` times, each at a clock at
+    /// least 10% slower than the attempt before
+    pub fn new(spim: &'s mut UdmaSpim<'u, Enabled>, retries: u8) -> Self {
+        Self {
+            spim,
+            retries,
+            slowdowns: 0,
+        }
+    }
+
+    pub fn free(self) -> &'s mut UdmaSpim<'u, Enabled> {
+        self.spim
+    }
+
+    /// Divider in use, sa. [SpimConfig::clk_div](super::SpimConfig::clk_div)
+    #[inline]
+    pub fn clk_div(&self) -> u8 {
+        self.spim.cfg.clk_div
+    }
+
+    /// Number of times a retry has lowered the clock for good
+    #[inline]
+    pub fn slowdowns(&self) -> u32 {
+        self.slowdowns
+    }
+
+    /// Send `data` and check the device's echo of its CRC
+    ///
+    /// `data` must be in memory visible to the uDMA, and so must the stack,
+    /// which holds the CRC.
+    pub fn write(&mut self, cs: ChipSelect, data: &[u8]) -> Result<(), ReliableError> {
+        let crc = crc16(data).to_be_bytes();
+        self.retry(|spim| {
+            let mut echo = [0u8; 2];
+            let mut t = spim.transaction(cs)?;
+            t.write(data)?;
+            t.write(&crc)?;
+            t.read(&mut echo)?;
+            Ok(echo == crc)
+        })
+    }
+
+    /// Send `tx` while receiving `rx`, checking both directions by the
+    /// device's echo
+    ///
+    /// On failure, `rx` holds the response of the last attempt.
+    pub fn transfer(
+        &mut self,
+        cs: ChipSelect,
+        rx: &mut [u8],
+        tx: &[u8],
+    ) -> Result<(), ReliableError> {
+        let crc = crc16(tx).to_be_bytes();
+        self.retry(|spim| {
+            let mut echo = [0u8; 4];
+            let mut t = spim.transaction(cs)?;
+            t.transfer(rx, tx)?;
+            t.write(&crc)?;
+            t.read(&mut echo)?;
+            Ok(echo[..2] == crc && echo[2..] == crc16(rx).to_be_bytes())
+        })
+    }
+
+    /// Run `attempt` until it reports a matching echo, slowing down the
+    /// clock between attempts
+    fn retry(
+        &mut self,
+        mut attempt: impl FnMut(&mut UdmaSpim<'u, Enabled>) -> Result<bool, SpimError>,
+    ) -> Result<(), ReliableError> {
+        let start = self.spim.cfg;
+        let mut attempts = 0;
+        for n in 0..=self.retries {
+            attempts = n.saturating_add(1);
+            match attempt(self.spim) {
+                Ok(true) => {
+                    if n != 0 {
+                        self.slowdowns += 1;
+                    }
+                    return Ok(());
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    self.spim.cfg = start;
+                    return Err(e.into());
+                }
+            }
+            let Some(clk_div) = slower(self.spim.cfg.clk_div) else {
+                break;
+            };
+            self.spim.cfg = self.spim.cfg.with_clk_div(clk_div);
+        }
+        self.spim.cfg = start;
+        Err(ReliableError::Integrity { attempts })
+    }
+}
+
+/// Smallest divider with an SCK at least 10% below that of `clk_div`, or
+/// `None` if the divider would overflow
+///
+/// SCK is proportional to 1 / (`clk_div` + 1).
+fn slower(clk_div: u8) -> Option<u8> {
+    let div = (clk_div as u32 + 1) * 10;
+    u8::try_from(div.div_ceil(9) - 1).ok()
+}
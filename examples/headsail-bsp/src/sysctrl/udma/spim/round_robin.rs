@@ -0,0 +1,573 @@
+//! Fair sharing of the SPIM between devices that each have their own chip
+//! select and configuration
+//!
+//! Each device holds at most one pending transfer. [SpimRoundRobin::poll]
+//! services the pending devices in turn, starting after the one serviced
+//! last, so a device that always has data to send cannot starve the others.
+//! Unlike a [SpimQueue](super::SpimQueue), each transfer goes out with the
+//! clock divider and mode of its device.
+//!
+//! A device can also be given a priority, and a chunk length that bounds how
+//! long it holds the bus. Transfers longer than the chunk go out one chunk
+//! per chip select window, so a device with a higher priority gets the bus
+//! between chunks. Each dispatch a pending device loses raises its priority
+//! by one for the next, so a device with a lower priority still gets the bus
+//! after at most as many dispatches as the difference. The longest wait of
+//! each device is kept in [SpimRoundRobin::max_wait_cycles].
+//!
+//! With a [RetryPolicy], a chunk that times out or is hit by a [DMA
+//! fault](super::fault) is aborted, and the whole transfer is sent again
+//! after a backoff. Only transfers requested with
+//! [SpimDevice::request_repeatable] are retried, e.g., reads and status
+//! polls, as the device may have acted on part of the first attempt. Others
+//! fail at once, as do repeatable ones out of attempts, and are collected
+//! with [SpimDevice::take_failed]. Retries are counted in
+//! [SpimRoundRobin::retries].
+//!
+//! ```ignore
+//! static ADC: SpimDevice = SpimDevice::new(ChipSelect::Cs0, SpimConfig::DEFAULT);
+//! static RADIO: SpimDevice =
+//!     SpimDevice::new(ChipSelect::Cs1, SpimConfig::DEFAULT.with_clk_div(1));
+//! // Bulk transfers that give way to the others every 512 bytes
+//! static LOGGER: SpimDevice =
+//!     SpimDevice::new(ChipSelect::Cs2, SpimConfig::DEFAULT).with_max_chunk(512);
+//!
+//! let mut rr = SpimRoundRobin::new([&ADC, &RADIO, &LOGGER]);
+//! ADC.request(DeviceTransfer { tx: ADC_READ, rx: Some(sample) })?;
+//! rr.poll(&mut spim);
+//!
+//! // On SPIM DMA done, and after each request to an idle bus
+//! rr.poll(&mut spim);
+//!
+//! // Wherever the ADC is handled
+//! if let Some(t) = ADC.take_completed() {
+//!     // `t.rx` holds the sample
+//! }
+//! ```
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use riscv::register::mcycle;
+
+use super::{
+    queue::{check_transfer, start},
+    ChipSelect, DmaFaultCode, Enabled, SpimConfig, SpimError, UdmaSpim,
+};
+use crate::trace_event;
+
+const IDLE: u8 = 0;
+const PENDING: u8 = 1;
+const IN_FLIGHT: u8 = 2;
+const DONE: u8 = 3;
+const FAILED: u8 = 4;
+
+/// Buffers of one transfer of a [SpimDevice]
+#[derive(Debug)]
+pub struct DeviceTransfer {
+    pub tx: &'static [u8],
+    /// Receive buffer for a full-duplex transfer, of the same length as `tx`
+    pub rx: Option<&'static mut [u8]>,
+}
+
+/// Recovery from transient faults of a [SpimDevice], sa. [module
+/// documentation](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// A chunk still in flight after this many core cycles is aborted
+    pub timeout_cycles: u32,
+    /// Times a repeatable transfer is sent again before it fails
+    pub attempts: u8,
+    /// Wait before the first retry, doubled for each further one
+    pub backoff_cycles: u32,
+}
+
+impl RetryPolicy {
+    /// Wait before the retry that follows `failed` failed attempts, or
+    /// `None` if the transfer is not retried
+    fn backoff(&self, repeatable: bool, failed: u8) -> Option<u64> {
+        (repeatable && failed <= self.attempts)
+            .then(|| (self.backoff_cycles as u64) << failed.saturating_sub(1).min(31))
+    }
+}
+
+/// Scheduling state of a transfer, owned like it
+#[derive(Clone, Copy)]
+struct Slot {
+    /// `mcycle` from which the device is ready for the bus
+    since: u64,
+    repeatable: bool,
+    /// Failed attempts of the transfer so far
+    failed: u8,
+    /// Fault of the last attempt, for [SpimDevice::take_failed]
+    fault: Option<DmaFaultCode>,
+}
+
+/// Device on the shared bus, with a slot for one transfer
+///
+/// [SpimDevice::request] and [SpimDevice::take_completed] must be called
+/// from one context per device, e.g., the main loop or the handler of the
+/// device's interrupt.
+pub struct SpimDevice {
+    pub cs: ChipSelect,
+    /// Clock divider and mode of the transfers of this device. The
+    /// configuration of the [UdmaSpim] is not changed.
+    pub cfg: SpimConfig,
+    /// Devices with a higher priority are serviced first. Defaults to 0.
+    pub priority: u8,
+    /// Longest chip select window of this device in bytes, 0 for none. The
+    /// device must accept its transfers split at these boundaries.
+    pub max_chunk: usize,
+    /// Off by default
+    pub retry: Option<RetryPolicy>,
+    /// Owner of `transfer` and `slot`: the device's user in IDLE, DONE and
+    /// FAILED, the scheduler in PENDING and IN_FLIGHT
+    state: AtomicU8,
+    transfer: UnsafeCell<Option<DeviceTransfer>>,
+    slot: UnsafeCell<Slot>,
+}
+
+// Safety: `transfer` and `slot` are only accessed by the side that `state`
+// hands them to
+unsafe impl Sync for SpimDevice {}
+
+impl SpimDevice {
+    pub const fn new(cs: ChipSelect, cfg: SpimConfig) -> Self {
+        Self {
+            cs,
+            cfg,
+            priority: 0,
+            max_chunk: 0,
+            retry: None,
+            state: AtomicU8::new(IDLE),
+            transfer: UnsafeCell::new(None),
+            slot: UnsafeCell::new(Slot {
+                since: 0,
+                repeatable: false,
+                failed: 0,
+                fault: None,
+            }),
+        }
+    }
+
+    pub const fn with_priority(self, priority: u8) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Split transfers into chip select windows of at most `max_chunk`
+    /// bytes, sa. [SpimDevice::max_chunk]. A chunk holds the bus for
+    /// `8 * max_chunk` SCK periods, sa. [SpimConfig::sck_hz].
+    pub const fn with_max_chunk(self, max_chunk: usize) -> Self {
+        Self { max_chunk, ..self }
+    }
+
+    /// Abort chunks that time out or fault, and retry repeatable transfers,
+    /// sa. [module documentation](self)
+    ///
+    /// [SpimRoundRobin::poll] takes DMA faults with
+    /// [UdmaSpim::take_dma_fault] while a transfer of this device is in
+    /// flight.
+    pub const fn with_retry(self, retry: RetryPolicy) -> Self {
+        Self {
+            retry: Some(retry),
+            ..self
+        }
+    }
+
+    /// Hand `transfer` to the scheduler, to be started by a later
+    /// [SpimRoundRobin::poll]
+    ///
+    /// The transfer is handed back with [SpimError::QueueFull] if the
+    /// previous one has not been collected with
+    /// [SpimDevice::take_completed] or [SpimDevice::take_failed], or with the
+    /// error of its buffers. It is not retried, sa.
+    /// [SpimDevice::request_repeatable].
+    pub fn request(&self, transfer: DeviceTransfer) -> Result<(), (DeviceTransfer, SpimError)> {
+        self.submit(transfer, false)
+    }
+
+    /// Like [SpimDevice::request], for a transfer that is safe to send
+    /// again, sa. [SpimDevice::with_retry]
+    ///
+    /// Reads and status polls usually are. A write is only if the device
+    /// tolerates receiving it twice, or receiving a part of it first.
+    pub fn request_repeatable(
+        &self,
+        transfer: DeviceTransfer,
+    ) -> Result<(), (DeviceTransfer, SpimError)> {
+        self.submit(transfer, true)
+    }
+
+    fn submit(
+        &self,
+        transfer: DeviceTransfer,
+        repeatable: bool,
+    ) -> Result<(), (DeviceTransfer, SpimError)> {
+        if self.state.load(Ordering::Acquire) != IDLE {
+            return Err((transfer, SpimError::QueueFull));
+        }
+        if let Err(e) = check_transfer(transfer.tx, transfer.rx.as_deref()) {
+            return Err((transfer, e));
+        }
+        // Safety: the scheduler does not touch the slot in IDLE
+        unsafe {
+            *self.transfer.get() = Some(transfer);
+            *self.slot.get() = Slot {
+                since: mcycle::read64(),
+                repeatable,
+                failed: 0,
+                fault: None,
+            };
+        }
+        self.state.store(PENDING, Ordering::Release);
+        Ok(())
+    }
+
+    /// Whether a transfer has been requested and not yet finished
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state.load(Ordering::Acquire), PENDING | IN_FLIGHT)
+    }
+
+    /// The finished transfer, which frees the slot for the next request
+    pub fn take_completed(&self) -> Option<DeviceTransfer> {
+        if self.state.load(Ordering::Acquire) != DONE {
+            return None;
+        }
+        // Safety: the scheduler does not touch the slot in DONE
+        let t = unsafe { (*self.transfer.get()).take() };
+        self.state.store(IDLE, Ordering::Release);
+        t
+    }
+
+    /// The transfer that failed, with the fault of its last attempt, which
+    /// frees the slot for the next request
+    ///
+    /// Only devices with a [RetryPolicy] fail. The receive buffer holds what
+    /// arrived before the last attempt was aborted.
+    pub fn take_failed(&self) -> Option<(DeviceTransfer, DmaFaultCode)> {
+        if self.state.load(Ordering::Acquire) != FAILED {
+            return None;
+        }
+        // Safety: the scheduler does not touch the slot in FAILED
+        let (t, slot) = unsafe { ((*self.transfer.get()).take(), *self.slot.get()) };
+        self.state.store(IDLE, Ordering::Release);
+        Some((t?, slot.fault.unwrap_or(DmaFaultCode::Other)))
+    }
+}
+
+/// Result of [SpimRoundRobin::poll], devices given by their index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundRobinEvent {
+    /// The transfer of `device` was started
+    Serviced {
+        device: usize,
+        /// Device whose transfer finished or failed before it, if any
+        completed: Option<usize>,
+    },
+    /// No device has a pending transfer, or all wait out a retry backoff
+    Idle {
+        /// Device whose transfer just finished or failed, if any
+        completed: Option<usize>,
+    },
+    /// A transfer is in flight, chip select is asserted outside of the
+    /// scheduler, or transfers are pending while the pads are parked, sa.
+    /// [UdmaSpim::park]
+    Busy,
+}
+
+/// Round-robin scheduler over `N` devices, sa. [module
+/// documentation](self)
+pub struct SpimRoundRobin<'s, const N: usize> {
+    devices: [&'s SpimDevice; N],
+    /// Index to check first on the next dispatch
+    next: usize,
+    /// Device and length of the chunk in flight
+    in_flight: Option<(usize, usize)>,
+    /// `mcycle` when the chunk in flight was started
+    started: u64,
+    /// Bytes of each device's transfer already sent
+    offsets: [usize; N],
+    /// Dispatches each device has lost since it was last serviced
+    waited: [u32; N],
+    max_wait: [u64; N],
+    retries: [u32; N],
+}
+
+impl<'s, const N: usize> SpimRoundRobin<'s, N> {
+    pub const fn new(devices: [&'s SpimDevice; N]) -> Self {
+        Self {
+            devices,
+            next: 0,
+            in_flight: None,
+            started: 0,
+            offsets: [0; N],
+            waited: [0; N],
+            max_wait: [0; N],
+            retries: [0; N],
+        }
+    }
+
+    /// Longest time in core cycles each device has waited for the bus, from
+    /// its request or the end of its previous chunk to the start of the next
+    #[inline]
+    pub fn max_wait_cycles(&self) -> [u64; N] {
+        self.max_wait
+    }
+
+    /// Transfers each device has sent again, sa. [RetryPolicy]
+    #[inline]
+    pub fn retries(&self) -> [u32; N] {
+        self.retries
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.max_wait = [0; N];
+        self.retries = [0; N];
+    }
+
+    /// Collect the chunk in flight if it has finished and start the next
+    /// chunk of the pending device with the highest priority
+    ///
+    /// Does not block. Call whenever the SPIM raises its DMA done event, and
+    /// once after a request while no transfer is in flight. With a
+    /// [RetryPolicy], also call periodically, as a timeout raises no event
+    /// and a retry waits out its backoff.
+    pub fn poll(&mut self, spim: &mut UdmaSpim<'_, Enabled>) -> RoundRobinEvent {
+        if spim.cs.is_some() {
+            return RoundRobinEvent::Busy;
+        }
+        if spim.parked.is_some() && (self.has_pending() || self.has_more_chunks()) {
+            return RoundRobinEvent::Busy;
+        }
+
+        let now = mcycle::read64();
+        let mut completed = None;
+        let collected = self.in_flight.is_some();
+        if let Some((idx, len)) = self.in_flight {
+            let fault = match self.devices[idx].retry {
+                Some(policy) => spim.take_dma_fault().or_else(|| {
+                    (!spim.is_idle() && now - self.started > policy.timeout_cycles as u64)
+                        .then_some(DmaFaultCode::Other)
+                }),
+                None => None,
+            };
+            if fault.is_none() && !spim.is_idle() {
+                return RoundRobinEvent::Busy;
+            }
+            self.in_flight = None;
+            completed = match fault {
+                Some(code) => self.fail(spim, idx, code, now),
+                None => self.finish(spim, idx, len, now),
+            };
+        }
+
+        let ranks: [_; N] = core::array::from_fn(|i| {
+            let dev = self.devices[i];
+            // Safety: the device's user does not touch the slot in PENDING
+            let ready = dev.state.load(Ordering::Acquire) == PENDING
+                && unsafe { (*dev.slot.get()).since } <= now;
+            ready.then(|| dev.priority as u32 + self.waited[i])
+        });
+        let Some(idx) = select(&ranks, self.next) else {
+            if collected {
+                spim.gate_after_eot();
+            }
+            return RoundRobinEvent::Idle { completed };
+        };
+        for (i, rank) in ranks.iter().enumerate() {
+            if rank.is_some() && i != idx {
+                self.waited[i] = self.waited[i].saturating_add(1);
+            }
+        }
+        self.waited[idx] = 0;
+
+        let dev = self.devices[idx];
+        // Safety: the device's user does not touch the slot in PENDING
+        let (t, since) = unsafe {
+            (
+                (*dev.transfer.get()).as_mut().unwrap(),
+                (*dev.slot.get()).since,
+            )
+        };
+        self.max_wait[idx] = self.max_wait[idx].max(now - since);
+        let off = self.offsets[idx];
+        let len = match dev.max_chunk {
+            0 => t.tx.len() - off,
+            max => (t.tx.len() - off).min(max),
+        };
+        let rx = t.rx.as_deref_mut().map(|rx| &mut rx[off..off + len]);
+        start(spim, dev.cs, dev.cfg, &t.tx[off..off + len], rx);
+        dev.state.store(IN_FLIGHT, Ordering::Release);
+        self.in_flight = Some((idx, len));
+        self.started = mcycle::read64();
+        self.next = (idx + 1) % N;
+        RoundRobinEvent::Serviced {
+            device: idx,
+            completed,
+        }
+    }
+
+    /// Account for the chunk of `len` bytes of `idx` that has finished.
+    /// Returns `idx` if that was the last.
+    fn finish(
+        &mut self,
+        spim: &mut UdmaSpim<'_, Enabled>,
+        idx: usize,
+        len: usize,
+        now: u64,
+    ) -> Option<usize> {
+        trace_event!(SpimDone);
+        spim.last_eot_time = now;
+        let dev = self.devices[idx];
+        self.offsets[idx] += len;
+        if self.has_remaining(idx) {
+            // Safety: the device's user does not touch the slot in IN_FLIGHT
+            unsafe { (*dev.slot.get()).since = now };
+            dev.state.store(PENDING, Ordering::Release);
+            return None;
+        }
+        self.offsets[idx] = 0;
+        dev.state.store(DONE, Ordering::Release);
+        Some(idx)
+    }
+
+    /// Abort the chunk of `idx` in flight, and schedule the transfer again
+    /// or fail it. Returns `idx` if it failed.
+    fn fail(
+        &mut self,
+        spim: &mut UdmaSpim<'_, Enabled>,
+        idx: usize,
+        code: DmaFaultCode,
+        now: u64,
+    ) -> Option<usize> {
+        // Deasserts chip select with an EOT, sa. `stop_and_reset`
+        spim.abort();
+        spim.last_eot_time = now;
+        self.offsets[idx] = 0;
+
+        let dev = self.devices[idx];
+        // Safety: the device's user does not touch the slot in IN_FLIGHT
+        let slot = unsafe { &mut *dev.slot.get() };
+        slot.failed = slot.failed.saturating_add(1);
+        slot.fault = Some(code);
+        let backoff = dev
+            .retry
+            .and_then(|policy| policy.backoff(slot.repeatable, slot.failed));
+        match backoff {
+            Some(backoff) => {
+                slot.since = now + backoff;
+                self.retries[idx] = self.retries[idx].saturating_add(1);
+                dev.state.store(PENDING, Ordering::Release);
+                None
+            }
+            None => {
+                dev.state.store(FAILED, Ordering::Release);
+                Some(idx)
+            }
+        }
+    }
+
+    fn has_pending(&self) -> bool {
+        self.devices
+            .iter()
+            .any(|dev| dev.state.load(Ordering::Acquire) == PENDING)
+    }
+
+    /// Whether the transfer in flight has chunks left after the current one
+    fn has_more_chunks(&self) -> bool {
+        self.in_flight.is_some_and(|(idx, len)| {
+            let dev = self.devices[idx];
+            // Safety: the device's user does not touch the slot in IN_FLIGHT
+            let total = unsafe { (*dev.transfer.get()).as_ref() }.map_or(0, |t| t.tx.len());
+            self.offsets[idx] + len < total
+        })
+    }
+
+    /// Whether the transfer of `idx` has bytes left to send
+    fn has_remaining(&self, idx: usize) -> bool {
+        let dev = self.devices[idx];
+        // Safety: only called while the scheduler owns the slot
+        let total = unsafe { (*dev.transfer.get()).as_ref() }.map_or(0, |t| t.tx.len());
+        self.offsets[idx] < total
+    }
+}
+
+/// Index of the pending device with the highest rank, the first from `next`
+/// among equals. Devices that are not pending have no rank.
+fn select<const N: usize>(ranks: &[Option<u32>; N], next: usize) -> Option<usize> {
+    let mut best: Option<(usize, u32)> = None;
+    for idx in (0..N).map(|i| (next + i) % N) {
+        match (ranks[idx], best) {
+            (Some(rank), Some((_, top))) if rank <= top => {}
+            (Some(rank), _) => best = Some((idx, rank)),
+            (None, _) => {}
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        timeout_cycles: 1000,
+        attempts: 3,
+        backoff_cycles: 100,
+    };
+
+    #[test]
+    fn retry_backs_off_exponentially() {
+        assert_eq!(POLICY.backoff(true, 1), Some(100));
+        assert_eq!(POLICY.backoff(true, 2), Some(200));
+        assert_eq!(POLICY.backoff(true, 3), Some(400));
+        // Out of attempts
+        assert_eq!(POLICY.backoff(true, 4), None);
+    }
+
+    #[test]
+    fn retry_refused_unless_repeatable() {
+        assert_eq!(POLICY.backoff(false, 1), None);
+        let none = RetryPolicy {
+            attempts: 0,
+            ..POLICY
+        };
+        assert_eq!(none.backoff(true, 1), None);
+    }
+
+    #[test]
+    fn select_rotates_among_equals() {
+        assert_eq!(select(&[Some(0), Some(0), Some(0)], 0), Some(0));
+        assert_eq!(select(&[Some(0), Some(0), Some(0)], 1), Some(1));
+        assert_eq!(select(&[Some(0), None, Some(0)], 1), Some(2));
+        assert_eq!(select(&[None, None, None], 2), None);
+    }
+
+    #[test]
+    fn select_prefers_rank() {
+        assert_eq!(select(&[Some(0), Some(2), Some(1)], 2), Some(1));
+        assert_eq!(select(&[Some(3), Some(0), Some(3)], 1), Some(2));
+    }
+
+    /// A device that always has data cannot starve one with a lower priority
+    /// for longer than the difference, with the aging done by `poll`
+    #[test]
+    fn aging_bounds_starvation() {
+        let priority = [5u32, 0];
+        let mut waited = [0u32; 2];
+        let mut next = 0;
+        let mut serviced = [0; 2];
+        for _ in 0..60 {
+            let ranks: [_; 2] = core::array::from_fn(|i| Some(priority[i] + waited[i]));
+            let idx = select(&ranks, next).unwrap();
+            waited[1 - idx] += 1;
+            waited[idx] = 0;
+            next = (idx + 1) % 2;
+            serviced[idx] += 1;
+            assert!(waited[1] <= 5);
+        }
+        assert_eq!(serviced, [50, 10]);
+    }
+}
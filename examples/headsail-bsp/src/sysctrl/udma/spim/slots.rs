@@ -0,0 +1,343 @@
+//! Packet reception into a ring of fixed-size slots
+//!
+//! For devices that deliver variable-length packets, e.g., a bulk endpoint
+//! on an FPGA companion. Packets are received straight into the slots, so
+//! nothing is moved around after reception. Each packet is read in its own
+//! chip select window, as a 2-byte little-endian length followed by that
+//! many bytes. A length of 0 means the device has no packet.
+//!
+//! The [SlotProducer] is pumped from the SPIM interrupt handler and starts
+//! the next read as soon as a packet is in. The [SlotConsumer] hands out the
+//! received packets in order, and a slot goes back to the ring when its
+//! [Packet] is dropped:
+//!
+//! ```ignore
+//! static mut RING: SpimSlotRing<8, 256> = SpimSlotRing::new();
+//!
+//! let (mut producer, mut consumer) =
+//!     unsafe { (*addr_of_mut!(RING)).split(ChipSelect::Cs0) }?;
+//! producer.pump(&mut spim);
+//!
+//! // On SPIM DMA done, and when the device signals a packet after Empty
+//! producer.pump(&mut spim);
+//!
+//! // In the main loop
+//! while let Some(packet) = consumer.next_packet() {
+//!     handle(&packet);
+//! }
+//! ```
+//!
+//! Packets that arrive while every slot holds an unconsumed packet are still
+//! read, so the device can go on, but are discarded and counted in
+//! [SlotStats::dropped].
+use core::{
+    cell::UnsafeCell,
+    ops::Deref,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use riscv::register::mcycle;
+
+use super::{
+    data_cmd, ChipSelect, CommandBuf, Enabled, SpimError, UdmaSpim, MAX_XFER_LEN, SPI_CMD_EOT,
+    SPI_CMD_RX_DATA, SPI_CMD_SOT,
+};
+use crate::sysctrl::mmap;
+
+/// Length of the header preceding each packet
+pub const HEADER_LEN: usize = 2;
+
+/// Ring of `N` slots of `SLOT` bytes, sa. [module documentation](self)
+///
+/// Must be placed in memory visible to the uDMA. `N` must be a power of
+/// two.
+pub struct SpimSlotRing<const N: usize, const SLOT: usize> {
+    slots: UnsafeCell<[[u8; SLOT]; N]>,
+    /// Target of packets that arrive while all slots are full
+    discard: UnsafeCell<[u8; SLOT]>,
+    header: UnsafeCell<[u8; HEADER_LEN]>,
+    lens: UnsafeCell<[u16; N]>,
+    /// Number of packets stored, wrapping, written by the producer only
+    head: AtomicU32,
+    /// Number of packets consumed, wrapping, written by the consumer only
+    tail: AtomicU32,
+    received: AtomicU32,
+    dropped: AtomicU32,
+    oversized: AtomicU32,
+}
+
+// Safety: the producer only writes slots the consumer has released, and
+// publishes them through `head` after writing
+unsafe impl<const N: usize, const SLOT: usize> Sync for SpimSlotRing<N, SLOT> {}
+
+impl<const N: usize, const SLOT: usize> Default for SpimSlotRing<N, SLOT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const SLOT: usize> SpimSlotRing<N, SLOT> {
+    const VALID: () = assert!(
+        N.is_power_of_two() && SLOT > 0 && SLOT <= MAX_XFER_LEN && SLOT <= u16::MAX as usize
+    );
+
+    pub const fn new() -> Self {
+        let () = Self::VALID;
+        Self {
+            slots: UnsafeCell::new([[0; SLOT]; N]),
+            discard: UnsafeCell::new([0; SLOT]),
+            header: UnsafeCell::new([0; HEADER_LEN]),
+            lens: UnsafeCell::new([0; N]),
+            head: AtomicU32::new(0),
+            tail: AtomicU32::new(0),
+            received: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+            oversized: AtomicU32::new(0),
+        }
+    }
+
+    /// Split into the halves for the interrupt handler and the application,
+    /// receiving from the device on `cs`
+    ///
+    /// Fails with [SpimError::InvalidBuffer] if the ring is not in memory
+    /// visible to the uDMA. Packets left over from an earlier split are
+    /// discarded.
+    pub fn split(
+        &mut self,
+        cs: ChipSelect,
+    ) -> Result<(SlotProducer<'_, N, SLOT>, SlotConsumer<'_, N, SLOT>), SpimError> {
+        let (start, end) = (
+            self as *const Self as usize,
+            (self as *const Self).wrapping_add(1) as usize,
+        );
+        if start < mmap::UDMA_MEM_START || end > mmap::UDMA_MEM_END {
+            return Err(SpimError::InvalidBuffer);
+        }
+        *self.tail.get_mut() = *self.head.get_mut();
+
+        let ring = &*self;
+        Ok((
+            SlotProducer {
+                ring,
+                cs,
+                phase: Phase::Idle,
+            },
+            SlotConsumer { ring },
+        ))
+    }
+
+    pub fn stats(&self) -> SlotStats {
+        SlotStats {
+            received: self.received.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            oversized: self.oversized.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Packet counts of a [SpimSlotRing], wrapping
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotStats {
+    /// Packets stored in a slot
+    pub received: u32,
+    /// Packets discarded as all slots were full
+    pub dropped: u32,
+    /// Packets announced longer than a slot, which are not read
+    pub oversized: u32,
+}
+
+/// Result of [SlotProducer::pump]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotEvent {
+    /// A packet of `len` bytes was stored, and the next read has started
+    Received { len: usize },
+    /// A packet of `len` bytes was discarded as all slots were full, and the
+    /// next read has started
+    Dropped { len: usize },
+    /// The device announced a packet longer than a slot. The window was
+    /// closed without reading it, and reception stops until the next pump.
+    Oversized { len: usize },
+    /// The device has no packet. Reception stops until the next pump, e.g.,
+    /// when the device signals that a packet is ready.
+    Empty,
+    /// A read is in flight, this call started one, or the SPIM is in use
+    /// outside of the ring or parked
+    Busy,
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    Idle,
+    Header,
+    /// Payload of `len` bytes going to `slot`, or discarded with `None`
+    Payload {
+        len: usize,
+        slot: Option<usize>,
+    },
+}
+
+/// Receiving half of a [SpimSlotRing], for the SPIM interrupt handler
+pub struct SlotProducer<'r, const N: usize, const SLOT: usize> {
+    ring: &'r SpimSlotRing<N, SLOT>,
+    cs: ChipSelect,
+    phase: Phase,
+}
+
+impl<const N: usize, const SLOT: usize> SlotProducer<'_, N, SLOT> {
+    /// Finish the step in flight if the RX channel is done and start the
+    /// next one
+    ///
+    /// Does not block. Call whenever the SPIM raises its DMA done event, and
+    /// once to start reception.
+    pub fn pump(&mut self, spim: &mut UdmaSpim<'_, Enabled>) -> SlotEvent {
+        let ring = self.ring;
+        match self.phase {
+            Phase::Idle => {
+                if spim.cs.is_some() || spim.parked.is_some() {
+                    return SlotEvent::Busy;
+                }
+                self.start_header(spim);
+                SlotEvent::Busy
+            }
+            _ if !spim.is_idle() => SlotEvent::Busy,
+            Phase::Header => {
+                // Safety: the RX channel is done with the header
+                let len = u16::from_le_bytes(unsafe { *ring.header.get() }) as usize;
+                if len == 0 || len > SLOT {
+                    self.phase = Phase::Idle;
+                    // Chip select is asserted by this producer
+                    let _ = spim.eot();
+                    if len == 0 {
+                        return SlotEvent::Empty;
+                    }
+                    bump(&ring.oversized);
+                    return SlotEvent::Oversized { len };
+                }
+
+                let head = ring.head.load(Ordering::Relaxed);
+                let full = head.wrapping_sub(ring.tail.load(Ordering::Acquire)) as usize == N;
+                let (slot, ptr) = if full {
+                    (None, ring.discard.get() as *mut u8)
+                } else {
+                    let idx = head as usize % N;
+                    // Safety: the consumer has released this slot
+                    (Some(idx), unsafe { (*ring.slots.get())[idx].as_mut_ptr() })
+                };
+                spim.start_rx(ptr, len);
+                let mut cmd = CommandBuf::<2>::new();
+                cmd.push_word(data_cmd(SPI_CMD_RX_DATA, len))
+                    .push_word(SPI_CMD_EOT);
+                spim.enqueue_cmd_words(cmd.as_words());
+                self.phase = Phase::Payload { len, slot };
+                SlotEvent::Busy
+            }
+            Phase::Payload { len, slot } => {
+                // The EOT went out with the payload
+                spim.cs = None;
+                spim.last_eot_time = mcycle::read64();
+
+                let event = match slot {
+                    Some(idx) => {
+                        // Safety: the slot is not published yet
+                        unsafe { (*ring.lens.get())[idx] = len as u16 };
+                        let head = ring.head.load(Ordering::Relaxed);
+                        ring.head.store(head.wrapping_add(1), Ordering::Release);
+                        bump(&ring.received);
+                        SlotEvent::Received { len }
+                    }
+                    None => {
+                        bump(&ring.dropped);
+                        SlotEvent::Dropped { len }
+                    }
+                };
+                self.start_header(spim);
+                event
+            }
+        }
+    }
+
+    /// Open a window and read the header of the next packet
+    fn start_header(&mut self, spim: &mut UdmaSpim<'_, Enabled>) {
+        spim.wait_cs_hold();
+        spim.start_rx(self.ring.header.get() as *mut u8, HEADER_LEN);
+        let mut cmd = CommandBuf::<3>::new();
+        cmd.push_word(spim.cfg.cmd())
+            .push_cmd(SPI_CMD_SOT, self.cs as u32)
+            .push_word(data_cmd(SPI_CMD_RX_DATA, HEADER_LEN));
+        spim.enqueue_cmd_words(cmd.as_words());
+        spim.cs = Some(self.cs);
+        self.phase = Phase::Header;
+    }
+}
+
+/// Consuming half of a [SpimSlotRing], for the application
+pub struct SlotConsumer<'r, const N: usize, const SLOT: usize> {
+    ring: &'r SpimSlotRing<N, SLOT>,
+}
+
+impl<const N: usize, const SLOT: usize> SlotConsumer<'_, N, SLOT> {
+    /// Oldest received packet, if any
+    pub fn next_packet(&mut self) -> Option<Packet<'_, N, SLOT>> {
+        let ring = self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        if ring.head.load(Ordering::Acquire) == tail {
+            return None;
+        }
+        let idx = tail as usize % N;
+        // Safety: the producer does not write published slots until they are
+        // released
+        let (data, len) = unsafe { (&(*ring.slots.get())[idx], (*ring.lens.get())[idx]) };
+        Some(Packet {
+            ring,
+            data: &data[..len as usize],
+        })
+    }
+
+    /// Number of received packets not yet consumed
+    pub fn len(&self) -> usize {
+        let ring = self.ring;
+        ring.head
+            .load(Ordering::Acquire)
+            .wrapping_sub(ring.tail.load(Ordering::Relaxed)) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> SlotStats {
+        self.ring.stats()
+    }
+}
+
+/// Received packet, returned to the ring when dropped
+pub struct Packet<'c, const N: usize, const SLOT: usize> {
+    ring: &'c SpimSlotRing<N, SLOT>,
+    data: &'c [u8],
+}
+
+impl<const N: usize, const SLOT: usize> Deref for Packet<'_, N, SLOT> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.data
+    }
+}
+
+impl<const N: usize, const SLOT: usize> Drop for Packet<'_, N, SLOT> {
+    fn drop(&mut self) {
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+        self.ring
+            .tail
+            .store(tail.wrapping_add(1), Ordering::Release);
+    }
+}
+
+/// Increment a counter only the producer writes
+#[inline]
+fn bump(counter: &AtomicU32) {
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(1),
+        Ordering::Relaxed,
+    );
+}
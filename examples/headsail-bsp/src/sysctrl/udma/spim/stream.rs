@@ -0,0 +1,112 @@
+//! Double-buffered writes of arbitrary length within one chip select window
+use super::{check_buf, data_cmd, SpimError, SpimTransaction, SPI_CMD_TX_DATA};
+
+/// Streams bytes over SPIM through two `N`-byte buffers
+///
+/// Once a buffer fills up, it is handed to the TX channel and writing
+/// continues into the other one. Stack use is constant, regardless of how
+/// much data is streamed. The buffers are borrowed rather than owned, so they
+/// cannot move while the uDMA reads them.
+///
+/// ```ignore
+/// dma_static!(BUF_A: [u8; 64]);
+/// dma_static!(BUF_B: [u8; 64]);
+///
+/// let bufs = unsafe { [BUF_A.get_mut(), BUF_B.get_mut()] };
+/// let t = spim.transaction(ChipSelect::Cs0)?;
+/// let mut w = SpimStreamWriter::new(t, bufs)?;
+/// for sample in samples {
+///     w.write(&sample.to_le_bytes())?;
+/// }
+/// w.flush()?;
+/// ```
+pub struct SpimStreamWriter<'s, 'u, const N: usize> {
+    bufs: [&'s mut [u8; N]; 2],
+    /// Buffer being filled
+    active: usize,
+    fill: usize,
+    in_flight: bool,
+    // Dropped last, so that the window stays open until the TX channel is
+    // done
+    t: SpimTransaction<'s, 'u>,
+}
+
+impl<'s, 'u, const N: usize> SpimStreamWriter<'s, 'u, N> {
+    /// Both buffers must lie in memory visible to the uDMA
+    pub fn new(t: SpimTransaction<'s, 'u>, bufs: [&'s mut [u8; N]; 2]) -> Result<Self, SpimError> {
+        for buf in &bufs {
+            check_buf(&buf[..])?;
+        }
+        Ok(Self {
+            bufs,
+            active: 0,
+            fill: 0,
+            in_flight: false,
+            t,
+        })
+    }
+
+    /// Queue `data` for transmission, sending each buffer as it fills up
+    pub fn write(&mut self, mut data: &[u8]) -> Result<(), SpimError> {
+        while !data.is_empty() {
+            let n = (N - self.fill).min(data.len());
+            let (head, rest) = data.split_at(n);
+            self.bufs[self.active][self.fill..self.fill + n].copy_from_slice(head);
+            self.fill += n;
+            data = rest;
+
+            if self.fill == N {
+                self.launch();
+            }
+        }
+        Ok(())
+    }
+
+    /// Send the partially filled buffer and wait for all queued data to go
+    /// out
+    pub fn flush(&mut self) -> Result<(), SpimError> {
+        if self.fill > 0 {
+            self.launch();
+        }
+        self.wait();
+        Ok(())
+    }
+
+    /// Hand the active buffer to the TX channel and switch to the other one
+    fn launch(&mut self) {
+        // Only one buffer can be queued on the channel at a time
+        self.wait();
+
+        let buf = &self.bufs[self.active][..self.fill];
+        self.t.spim.start_tx(buf.as_ptr(), buf.len());
+        self.t
+            .spim
+            .enqueue_cmd_single_word(data_cmd(SPI_CMD_TX_DATA, buf.len()));
+
+        self.in_flight = true;
+        self.active ^= 1;
+        self.fill = 0;
+    }
+
+    fn wait(&mut self) {
+        if self.in_flight {
+            self.t.spim.wait_tx();
+            self.in_flight = false;
+        }
+    }
+}
+
+impl<const N: usize> ufmt_write::uWrite for SpimStreamWriter<'_, '_, N> {
+    type Error = SpimError;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write(s.as_bytes())
+    }
+}
+
+impl<const N: usize> Drop for SpimStreamWriter<'_, '_, N> {
+    /// Data not yet flushed is discarded
+    fn drop(&mut self) {
+        self.wait();
+    }
+}
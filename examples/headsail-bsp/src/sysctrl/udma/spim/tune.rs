@@ -0,0 +1,131 @@
+//! Completion mode chosen from the sizes of recent transfers
+//!
+//! Spinning on the channel registers returns as soon as a transfer ends, but
+//! keeps the core busy for the whole transfer, while sleeping until the SPIM
+//! event costs a wake-up per transfer and frees the core in between. Short
+//! transfers favor [CompletionMode::Polling] and long ones
+//! [CompletionMode::Interrupt]. [SpimAutoTuner] keeps the sizes of the last
+//! `N` transfers and switches to the mode with the lowest estimated cost
+//! over them, as given by a table of [TuneEntry]s:
+//!
+//! ```ignore
+//! // At SCK = core clock / 4, polling costs the core 32 cycles per byte
+//! static TABLE: [TuneEntry; 2] = [
+//!     TuneEntry { mode: CompletionMode::Polling, overhead_cycles: 0, cycles_per_byte: 32 },
+//!     TuneEntry { mode: CompletionMode::Interrupt, overhead_cycles: 400, cycles_per_byte: 0 },
+//! ];
+//!
+//! let mut tuner = SpimAutoTuner::<16>::new(&TABLE);
+//! spim.send(buf)?;
+//! tuner.update(&mut spim, buf.len());
+//! ```
+//!
+//! The uDMA channels have no burst size to tune, so the completion mode is
+//! the only choice made. List only modes the application supports, e.g.,
+//! [CompletionMode::Interrupt] needs [on_event](super::on_event) to be
+//! called from the SPIM interrupt handler.
+use super::{CompletionMode, Enabled, UdmaSpim};
+
+/// Estimated cost of a transfer in one [CompletionMode], sa. [module
+/// documentation](self)
+///
+/// A transfer of `n` bytes is estimated at `overhead_cycles` +
+/// `cycles_per_byte` * `n`. The unit is up to the caller, as long as it is
+/// the same for all entries of a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TuneEntry {
+    pub mode: CompletionMode,
+    pub overhead_cycles: u32,
+    pub cycles_per_byte: u32,
+}
+
+impl TuneEntry {
+    /// Estimated cost of transfers totalling `bytes` bytes
+    fn cost(&self, transfers: usize, bytes: u64) -> u64 {
+        self.overhead_cycles as u64 * transfers as u64 + self.cycles_per_byte as u64 * bytes
+    }
+}
+
+/// Chooses the [CompletionMode] of a [UdmaSpim] over a window of `N`
+/// transfers, sa. [module documentation](self)
+pub struct SpimAutoTuner<const N: usize> {
+    table: &'static [TuneEntry],
+    /// Circular buffer of the last transfer sizes
+    sizes: [u32; N],
+    next: usize,
+    filled: usize,
+    /// Index into `table` of the mode last set
+    current: Option<usize>,
+    switches: u32,
+}
+
+impl<const N: usize> SpimAutoTuner<N> {
+    /// # Panics
+    ///
+    /// If `table` is empty
+    pub const fn new(table: &'static [TuneEntry]) -> Self {
+        const { assert!(N != 0, "window must hold at least one transfer") };
+        assert!(!table.is_empty());
+        Self {
+            table,
+            sizes: [0; N],
+            next: 0,
+            filled: 0,
+            current: None,
+            switches: 0,
+        }
+    }
+
+    /// Record a transfer of `last_size` bytes, and set the mode with the
+    /// lowest estimated cost over the window on `spim`
+    ///
+    /// Call after each blocking transfer. On a tie, the mode in use is kept.
+    /// Returns the mode in use.
+    pub fn update(&mut self, spim: &mut UdmaSpim<'_, Enabled>, last_size: usize) -> CompletionMode {
+        self.sizes[self.next] = last_size.min(u32::MAX as usize) as u32;
+        self.next = (self.next + 1) % N;
+        self.filled = (self.filled + 1).min(N);
+
+        let bytes: u64 = self.sizes[..self.filled].iter().map(|&s| s as u64).sum();
+        let cost = |i: usize| self.table[i].cost(self.filled, bytes);
+        let mut best = self.current.unwrap_or(0);
+        for i in 0..self.table.len() {
+            if cost(i) < cost(best) {
+                best = i;
+            }
+        }
+
+        let mode = self.table[best].mode;
+        if self.current != Some(best) {
+            if self.current.is_some() {
+                self.switches = self.switches.saturating_add(1);
+            }
+            self.current = Some(best);
+        }
+        if spim.completion_mode() != mode {
+            spim.set_completion_mode(mode);
+        }
+        mode
+    }
+
+    /// Mean transfer size over the window, 0 before the first update
+    pub fn mean_size(&self) -> u32 {
+        if self.filled == 0 {
+            return 0;
+        }
+        let bytes: u64 = self.sizes[..self.filled].iter().map(|&s| s as u64).sum();
+        (bytes / self.filled as u64) as u32
+    }
+
+    /// Number of times the tuner has changed its choice of mode
+    #[inline]
+    pub fn switches(&self) -> u32 {
+        self.switches
+    }
+
+    /// Forget the recorded sizes, e.g., when the workload changes
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.filled = 0;
+    }
+}
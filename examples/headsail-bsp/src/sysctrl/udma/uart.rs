@@ -1,14 +1,85 @@
+pub mod cdc;
+pub mod cobs;
+#[cfg(feature = "sync")]
+pub mod console;
+pub mod newline;
+pub mod ring;
+pub mod slip;
+pub mod split;
+pub mod timing;
+
+#[cfg(feature = "sync")]
+pub use console::UartConsole;
+pub use newline::UartNormalizer;
+pub use ring::UartRxRing;
+pub use split::{UartRx, UartTx};
+pub use timing::RxByteTimer;
+
 use core::marker::PhantomData;
 
 use super::{Disabled, Enabled};
-use crate::pac;
+use crate::{
+    delay,
+    mmio::{reg_modify, reg_write},
+    pac, poll, poll_bit_clear, poll_eq,
+    sysctrl::{
+        gpio::{Gpio, Output},
+        mmap,
+    },
+    trace_event,
+};
+
+/// Largest buffer the 20-bit `UART_TX_SIZE` and `UART_RX_SIZE` registers can
+/// describe
+pub const MAX_XFER_LEN: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UartError {
+    /// Buffer is empty, longer than [MAX_XFER_LEN] or outside of memory
+    /// visible to the uDMA
+    InvalidBuffer,
+    /// The receive FIFO overflowed while a DMA reception was running, so
+    /// bytes are missing between the `received` bytes in the buffer
+    Overrun { received: usize },
+}
+
+pub(crate) fn check_buf(buf: &[u8]) -> Result<(), UartError> {
+    let (start, len) = (buf.as_ptr() as usize, buf.len());
+    if len == 0 || len > MAX_XFER_LEN {
+        return Err(UartError::InvalidBuffer);
+    }
+    if start < mmap::UDMA_MEM_START || start + len > mmap::UDMA_MEM_END {
+        return Err(UartError::InvalidBuffer);
+    }
+    Ok(())
+}
+
+/// Back-off of [UdmaUart::write_blocking] while the TX channel is above
+/// its high-water mark
+const TX_BACKOFF_US: u32 = 100;
 
 /// Obtain an instance by calling [Udma::split]
 pub struct UdmaUart<'u, UdmaPeriphState>(
     pub(crate) &'u pac::sysctrl::Udma,
     pub(crate) PhantomData<UdmaPeriphState>,
+    pub(crate) WaterMarks,
 );
 
+/// Thresholds of [UdmaUart::write_blocking] and [UdmaUart::read_dma_idle]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WaterMarks {
+    tx_high: u8,
+    rx_low: u8,
+}
+
+impl WaterMarks {
+    pub(crate) const DEFAULT: Self = Self {
+        tx_high: 0,
+        rx_low: 0,
+    };
+}
+
 type UartSetupW = pac::sysctrl::udma::uart_setup::W;
 
 impl<'u> UdmaUart<'u, Disabled> {
@@ -20,26 +91,57 @@ impl<'u> UdmaUart<'u, Disabled> {
         let udma = &self.0;
 
         // Turn on the clock gates for UART
-        udma.ctrl_cfg_cg().modify(|_r, w| w.cg_uart().set_bit());
+        reg_modify!(udma.ctrl_cfg_cg(), |_r, w| w.cg_uart().set_bit());
 
         // Setup UART
-        udma.uart_setup().write(|w| unsafe { w.bits(0) });
-        udma.uart_setup().write(setup_spec);
+        reg_write!(udma.uart_setup(), |w| unsafe { w.bits(0) });
+        reg_write!(udma.uart_setup(), setup_spec);
 
         #[cfg(feature = "panic-sysctrl-uart")]
         unsafe {
             crate::ufmt_panic::PANIC_UART_IS_INIT = true
         };
 
-        UdmaUart::<Enabled>(self.0, PhantomData)
+        UdmaUart::<Enabled>(self.0, PhantomData, self.2)
+    }
+}
+
+impl<S> UdmaUart<'_, S> {
+    /// Write the UART channel, setup and status registers and the uDMA clock
+    /// gates to `w`
+    ///
+    /// `UART_DATA` and `UART_ERROR` are left out, as reading them consumes the
+    /// received byte and clears the error flags.
+    #[cfg(feature = "debug-registers")]
+    pub fn dump_registers<W>(&self, w: &mut W) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        let udma = self.0;
+        super::dump_registers(
+            w,
+            &[
+                ("uart_tx_saddr", udma.uart_tx_saddr().read().bits()),
+                ("uart_tx_size", udma.uart_tx_size().read().bits()),
+                ("uart_tx_cfg", udma.uart_tx_cfg().read().bits()),
+                ("uart_rx_saddr", udma.uart_rx_saddr().read().bits()),
+                ("uart_rx_size", udma.uart_rx_size().read().bits()),
+                ("uart_rx_cfg", udma.uart_rx_cfg().read().bits()),
+                ("uart_status", udma.uart_status().read().bits()),
+                ("uart_setup", udma.uart_setup().read().bits()),
+                ("uart_irq_en", udma.uart_irq_en().read().bits()),
+                ("uart_valid", udma.uart_valid().read().bits()),
+                ("ctrl_cfg_cg", udma.ctrl_cfg_cg().read().bits()),
+            ],
+        )
     }
 }
 
 impl<'u> UdmaUart<'u, Enabled> {
     #[inline]
     pub fn disable(self) -> UdmaUart<'u, Disabled> {
-        self.0.ctrl_cfg_cg().modify(|_r, w| w.cg_uart().clear_bit());
-        UdmaUart::<Disabled>(self.0, PhantomData)
+        reg_modify!(self.0.ctrl_cfg_cg(), |_r, w| w.cg_uart().clear_bit());
+        UdmaUart::<Disabled>(self.0, PhantomData, self.2)
     }
 
     /// # Safety
@@ -47,18 +149,39 @@ impl<'u> UdmaUart<'u, Enabled> {
     /// This will not configure the UART in any way.
     #[inline]
     pub unsafe fn steal(udma: &'static pac::sysctrl::Udma) -> Self {
-        Self(udma, PhantomData)
+        Self(udma, PhantomData, WaterMarks::DEFAULT)
+    }
+
+    /// Access the uDMA registers directly, e.g., to set a bit the driver does
+    /// not model
+    ///
+    /// The driver keeps no register state of its own, only the thresholds
+    /// set with [UdmaUart::set_tx_high_water_mark] and
+    /// [UdmaUart::set_rx_low_water_mark], so only the UART clock
+    /// gate, which the `Enabled` state relies on, is reopened after `f`. The
+    /// access is recorded in the [trace](crate::trace) log.
+    pub fn with_raw<R>(&mut self, f: impl FnOnce(&pac::sysctrl::Udma) -> R) -> R {
+        trace_event!(RawAccess { periph: "uart" });
+        let ret = f(self.0);
+        reg_modify!(self.0.ctrl_cfg_cg(), |_r, w| w.cg_uart().set_bit());
+        ret
     }
 
     #[inline]
     pub fn write(&mut self, buf: &[u8]) {
+        trace_event!(UartTx {
+            len: buf.len() as u32
+        });
         let udma = &self.0;
 
+        // A transfer from [UdmaUart::write_blocking] may still be running
+        poll_eq!(udma.uart_tx_saddr().read().bits(), 0);
+
         // Write buffer location & len
-        udma.uart_tx_saddr()
-            .write(|w| unsafe { w.bits(buf.as_ptr() as u32) });
-        udma.uart_tx_size()
-            .write(|w| unsafe { w.bits(buf.len() as u32) });
+        reg_write!(udma.uart_tx_saddr(), |w| unsafe {
+            w.bits(buf.as_ptr() as u32)
+        });
+        reg_write!(udma.uart_tx_size(), |w| unsafe { w.bits(buf.len() as u32) });
 
         // Dispatch transmission
         udma.uart_tx_cfg().write(
@@ -67,13 +190,224 @@ impl<'u> UdmaUart<'u, Enabled> {
         );
 
         // Poll until finished (prevents `buf` leakage)
-        while udma.uart_tx_saddr().read().bits() != 0 {}
+        poll_eq!(udma.uart_tx_saddr().read().bits(), 0);
     }
 
     #[inline]
     pub fn write_str(&mut self, s: &str) {
         self.write(s.as_bytes());
     }
+
+    /// Bytes of the running TX transfer not yet handed to the UART, up to
+    /// 255
+    ///
+    /// The UART has no FIFO level register. Its TX FIFO is fed by the uDMA
+    /// channel, so what is queued for transmission is what `UART_TX_SIZE`
+    /// has left to count down, plus the few bytes in flight in the FIFO.
+    #[inline]
+    pub fn tx_fifo_level(&self) -> u8 {
+        let udma = self.0;
+        if udma.uart_tx_saddr().read().bits() == 0 {
+            return 0;
+        }
+        udma.uart_tx_size().read().bits().min(u8::MAX as u32) as u8
+    }
+
+    /// Received bytes waiting to be read with [UdmaUart::try_read_byte],
+    /// which is at most one
+    ///
+    /// Only meaningful with `polling_en`, as the uDMA otherwise drains the
+    /// RX FIFO into memory as bytes arrive.
+    #[inline]
+    pub fn rx_fifo_level(&self) -> u8 {
+        self.0.uart_valid().read().ready().bit_is_set() as u8
+    }
+
+    /// Let [UdmaUart::write_blocking] return once at most `bytes` are left
+    /// to send. Zero, the default, waits for the whole transfer.
+    #[inline]
+    pub fn set_tx_high_water_mark(&mut self, bytes: u8) {
+        self.2.tx_high = bytes;
+    }
+
+    /// Keep [UdmaUart::read_dma_idle] receiving over idle gaps until at least
+    /// `bytes` have arrived, so that short bursts are collected into one
+    /// buffer instead of being handed over one by one. Zero, the default,
+    /// ends the reception at the first gap.
+    #[inline]
+    pub fn set_rx_low_water_mark(&mut self, bytes: u8) {
+        self.2.rx_low = bytes;
+    }
+
+    /// Transmit `buf`, returning once no more than the high-water mark is
+    /// left to send
+    ///
+    /// Unlike [UdmaUart::write], the CPU is not kept spinning on the
+    /// channel: above the mark, it backs off for [TX_BACKOFF_US] between
+    /// checks, and below it, it goes on while the tail drains. The buffer
+    /// is `'static` because the channel may still read it afterwards. The
+    /// next write waits for the transfer to finish.
+    pub fn write_blocking(&mut self, buf: &'static [u8]) -> Result<(), UartError> {
+        check_buf(buf)?;
+        trace_event!(UartTx {
+            len: buf.len() as u32
+        });
+        let udma = &self.0;
+
+        poll_eq!(udma.uart_tx_saddr().read().bits(), 0);
+        reg_write!(udma.uart_tx_saddr(), |w| unsafe {
+            w.bits(buf.as_ptr() as u32)
+        });
+        reg_write!(udma.uart_tx_size(), |w| unsafe { w.bits(buf.len() as u32) });
+        udma.uart_tx_cfg().write(|w| w.en().set_bit());
+
+        while self.tx_fifo_level() > self.2.tx_high {
+            delay::micros(TX_BACKOFF_US);
+        }
+        Ok(())
+    }
+
+    /// Received byte, if one is waiting
+    ///
+    /// Requires `polling_en` and `rx_ena` in [UdmaUart::enable]. Reading
+    /// the byte clears `UART_VALID`.
+    #[inline]
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        let udma = &self.0;
+        udma.uart_valid()
+            .read()
+            .ready()
+            .bit_is_set()
+            .then(|| udma.uart_data().read().bits() as u8)
+    }
+
+    /// Wait for the next received byte, sa. [UdmaUart::try_read_byte]
+    #[inline]
+    pub fn read_byte(&mut self) -> u8 {
+        loop {
+            if let Some(byte) = self.try_read_byte() {
+                return byte;
+            }
+        }
+    }
+
+    /// Receive `buf.len()` bytes over the RX channel
+    ///
+    /// Requires `rx_ena` without `polling_en` in [UdmaUart::enable]. For
+    /// frames of varying length, use [UdmaUart::read_dma_idle], or
+    /// [UartRxRing] for continuous reception.
+    pub fn read_dma(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
+        self.rx_dma(buf, None)
+    }
+
+    /// Like [UdmaUart::read_dma], but also stop once no byte has arrived for
+    /// `idle_cycles` `mcycle` cycles
+    ///
+    /// The UART has no idle-line detection, so the gap is timed in software
+    /// from the progress of the channel. A byte arriving while the channel is
+    /// being stopped stays in the FIFO. Returns the number of bytes received,
+    /// which is zero if the line stays idle from the start. With a low-water
+    /// mark, sa. [UdmaUart::set_rx_low_water_mark], gaps are waited out until
+    /// the mark is reached.
+    pub fn read_dma_idle(&mut self, buf: &mut [u8], idle_cycles: u64) -> Result<usize, UartError> {
+        self.rx_dma(buf, Some(idle_cycles))
+    }
+
+    fn rx_dma(&mut self, buf: &mut [u8], idle_cycles: Option<u64>) -> Result<usize, UartError> {
+        check_buf(buf)?;
+        let udma = self.0;
+        let len = buf.len();
+
+        // Reading clears the flags left over from earlier receptions
+        let _ = udma.uart_error().read();
+        reg_write!(udma.uart_rx_saddr(), |w| unsafe {
+            w.bits(buf.as_mut_ptr() as u32)
+        });
+        reg_write!(udma.uart_rx_size(), |w| unsafe { w.bits(len as u32) });
+        reg_write!(udma.uart_rx_cfg(), |w| w.en().set_bit());
+
+        // `UART_RX_SIZE` counts down the bytes left
+        let received = || len - (udma.uart_rx_size().read().bits() as usize).min(len);
+        let done = || udma.uart_rx_saddr().read().bits() == 0;
+        let received = match idle_cycles {
+            None => {
+                poll_eq!(udma.uart_rx_saddr().read().bits(), 0);
+                len
+            }
+            Some(idle) => {
+                let rx_low = (self.2.rx_low as usize).min(len);
+                let mut seen = 0;
+                loop {
+                    let progress = poll::wait_timeout(|| done() || received() != seen, idle);
+                    if done() {
+                        break len;
+                    }
+                    seen = received();
+                    if progress.is_err() && seen >= rx_low {
+                        reg_write!(udma.uart_rx_cfg(), |w| w.clr().set_bit());
+                        break seen;
+                    }
+                }
+            }
+        };
+        trace_event!(UartRx {
+            len: received as u32
+        });
+
+        if udma.uart_error().read().rx_err_overflow().bit_is_set() {
+            return Err(UartError::Overrun { received });
+        }
+        Ok(received)
+    }
+}
+
+/// UART with a driver enable (DE) pin for RS-485 and other half-duplex
+/// transceivers
+///
+/// DE is driven high while transmitting and low otherwise.
+pub struct UdmaUartHalfDuplex<'u, const DE: u32> {
+    uart: UdmaUart<'u, Enabled>,
+    de: Gpio<DE, Output>,
+    /// Duration of one bit on the line
+    bit_ns: u32,
+}
+
+impl<'u, const DE: u32> UdmaUartHalfDuplex<'u, DE> {
+    /// `baud` must match the divider configured in [UdmaUart::enable]. It is
+    /// used to hold DE through the stop bit(s) of the last frame.
+    pub fn new(uart: UdmaUart<'u, Enabled>, mut de: Gpio<DE, Output>, baud: u32) -> Self {
+        de.set_low();
+        Self {
+            uart,
+            de,
+            bit_ns: 1_000_000_000 / baud.max(1),
+        }
+    }
+
+    pub fn free(self) -> (UdmaUart<'u, Enabled>, Gpio<DE, Output>) {
+        (self.uart, self.de)
+    }
+
+    /// Transmit `data` with DE asserted, releasing the bus only after the last
+    /// stop bit has left the shifter
+    ///
+    /// The TX channel completes when the last byte is loaded into the FIFO,
+    /// so DE is held until `UART_STATUS.TX_BUSY` also clears.
+    pub fn send_dma(&mut self, data: &[u8]) -> Result<(), UartError> {
+        check_buf(data)?;
+
+        let udma: &'u pac::sysctrl::Udma = self.uart.0;
+        let stop_bits = udma.uart_setup().read().stop_bits().bit_is_set() as u32 + 1;
+
+        self.de.set_high();
+        self.uart.write(data);
+        poll_bit_clear!(udma.uart_status(), tx_busy);
+        // TX_BUSY may drop as the stop bit starts rather than when it ends
+        delay::nanos(self.bit_ns * stop_bits);
+        self.de.set_low();
+
+        Ok(())
+    }
 }
 
 impl<'a> ufmt_write::uWrite for UdmaUart<'a, Enabled> {
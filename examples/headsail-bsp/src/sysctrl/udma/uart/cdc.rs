@@ -0,0 +1,223 @@
+//! USB CDC ACM line coding for the uDMA UART
+//!
+//! Forward the class-specific control requests of a CDC ACM interface to
+//! [UartCdcBridge::process_cdc_message], and the UART follows the settings
+//! picked in the host's serial terminal. Each message is the 8-byte setup
+//! packet, followed by the data stage for host-to-device requests.
+use super::UdmaUart;
+use crate::{mmio::reg_modify, sysctrl::udma::Enabled};
+
+const REQUEST_TYPE_CLASS_OUT: u8 = 0x21;
+const REQUEST_TYPE_CLASS_IN: u8 = 0xa1;
+
+const SET_LINE_CODING: u8 = 0x20;
+const GET_LINE_CODING: u8 = 0x21;
+const SET_CONTROL_LINE_STATE: u8 = 0x22;
+
+const SETUP_LEN: usize = 8;
+const LINE_CODING_LEN: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StopBits {
+    One = 0,
+    OneAndHalf = 1,
+    Two = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Parity {
+    None = 0,
+    Odd = 1,
+    Even = 2,
+    Mark = 3,
+    Space = 4,
+}
+
+/// CDC ACM `LineCoding` structure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCoding {
+    pub baud: u32,
+    pub stop_bits: StopBits,
+    pub parity: Parity,
+    /// 5, 6, 7, 8 or 16
+    pub data_bits: u8,
+}
+
+impl LineCoding {
+    pub fn from_bytes(bytes: &[u8; LINE_CODING_LEN]) -> Option<Self> {
+        let stop_bits = match bytes[4] {
+            0 => StopBits::One,
+            1 => StopBits::OneAndHalf,
+            2 => StopBits::Two,
+            _ => return None,
+        };
+        let parity = match bytes[5] {
+            0 => Parity::None,
+            1 => Parity::Odd,
+            2 => Parity::Even,
+            3 => Parity::Mark,
+            4 => Parity::Space,
+            _ => return None,
+        };
+        Some(Self {
+            baud: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            stop_bits,
+            parity,
+            data_bits: bytes[6],
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; LINE_CODING_LEN] {
+        let [b0, b1, b2, b3] = self.baud.to_le_bytes();
+        [
+            b0,
+            b1,
+            b2,
+            b3,
+            self.stop_bits as u8,
+            self.parity as u8,
+            self.data_bits,
+        ]
+    }
+}
+
+/// Reply to a CDC ACM request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CdcResponse {
+    /// Data stage of GET_LINE_CODING
+    LineCoding([u8; LINE_CODING_LEN]),
+    /// Complete the status stage
+    Ack,
+    /// Request is malformed or asks for something the UART can't do
+    Stall,
+}
+
+/// [UdmaUart] configured through CDC ACM control requests
+pub struct UartCdcBridge<'u> {
+    uart: UdmaUart<'u, Enabled>,
+    periph_hz: u32,
+    coding: LineCoding,
+    dtr: bool,
+    rts: bool,
+}
+
+impl<'u> UartCdcBridge<'u> {
+    /// `periph_hz` is the uDMA peripheral clock used to derive the divider.
+    /// The UART is reconfigured to `coding` right away.
+    ///
+    /// Returns `None` if the UART cannot implement `coding`.
+    pub fn new(uart: UdmaUart<'u, Enabled>, periph_hz: u32, coding: LineCoding) -> Option<Self> {
+        let mut bridge = Self {
+            uart,
+            periph_hz,
+            coding,
+            dtr: false,
+            rts: false,
+        };
+        bridge.apply(coding).then_some(bridge)
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    pub fn uart(&mut self) -> &mut UdmaUart<'u, Enabled> {
+        &mut self.uart
+    }
+
+    pub fn line_coding(&self) -> LineCoding {
+        self.coding
+    }
+
+    /// Data terminal ready, i.e., the host has the port open
+    pub fn dtr(&self) -> bool {
+        self.dtr
+    }
+
+    pub fn rts(&self) -> bool {
+        self.rts
+    }
+
+    /// Handle a CDC ACM class request
+    ///
+    /// Returns `None` if `msg` is not one of SET_LINE_CODING, GET_LINE_CODING
+    /// or SET_CONTROL_LINE_STATE, so the caller can handle it otherwise.
+    pub fn process_cdc_message(&mut self, msg: &[u8]) -> Option<CdcResponse> {
+        if msg.len() < SETUP_LEN {
+            return None;
+        }
+        let (setup, data) = msg.split_at(SETUP_LEN);
+        let (request_type, request) = (setup[0], setup[1]);
+        let value = u16::from_le_bytes([setup[2], setup[3]]);
+        let length = u16::from_le_bytes([setup[6], setup[7]]) as usize;
+
+        Some(match (request_type, request) {
+            (REQUEST_TYPE_CLASS_OUT, SET_LINE_CODING) => {
+                let Ok(bytes) = <&[u8; LINE_CODING_LEN]>::try_from(data) else {
+                    return Some(CdcResponse::Stall);
+                };
+                match LineCoding::from_bytes(bytes) {
+                    Some(coding) if self.apply(coding) => CdcResponse::Ack,
+                    _ => CdcResponse::Stall,
+                }
+            }
+            (REQUEST_TYPE_CLASS_IN, GET_LINE_CODING) if length >= LINE_CODING_LEN => {
+                CdcResponse::LineCoding(self.coding.to_bytes())
+            }
+            (REQUEST_TYPE_CLASS_IN, GET_LINE_CODING) => CdcResponse::Stall,
+            (REQUEST_TYPE_CLASS_OUT, SET_CONTROL_LINE_STATE) => {
+                self.dtr = value & 0b01 != 0;
+                self.rts = value & 0b10 != 0;
+                CdcResponse::Ack
+            }
+            _ => return None,
+        })
+    }
+
+    /// Reconfigure the UART for `coding`, returning false if it is not
+    /// supported
+    ///
+    /// The uDMA UART has a single parity enable, which is mapped to even
+    /// parity here. 1.5 stop bits and 16 data bits are not supported.
+    fn apply(&mut self, coding: LineCoding) -> bool {
+        let stop_bits = match coding.stop_bits {
+            StopBits::One => false,
+            StopBits::Two => true,
+            StopBits::OneAndHalf => return false,
+        };
+        let parity = match coding.parity {
+            Parity::None => false,
+            Parity::Even => true,
+            _ => return false,
+        };
+        let bit_length = match coding.data_bits {
+            5..=8 => coding.data_bits - 5,
+            _ => return false,
+        };
+        let Some(clk_div) = self.periph_hz.checked_div(coding.baud) else {
+            return false;
+        };
+        let Ok(clk_div) = u16::try_from(clk_div) else {
+            return false;
+        };
+        if clk_div == 0 {
+            return false;
+        }
+
+        // TX is synchronous, so nothing is in flight while reconfiguring
+        reg_modify!(self.uart.0.uart_setup(), |_r, w| unsafe {
+            w.parity_ena()
+                .bit(parity)
+                .bit_length()
+                .bits(bit_length)
+                .stop_bits()
+                .bit(stop_bits)
+                .clkdiv()
+                .bits(clk_div)
+        });
+        self.coding = coding;
+        true
+    }
+}
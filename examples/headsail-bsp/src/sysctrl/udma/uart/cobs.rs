@@ -0,0 +1,166 @@
+//! COBS framing for binary messages over the uDMA UART
+//!
+//! Consistent Overhead Byte Stuffing removes all zero bytes from a payload at
+//! a cost of one byte per 254, so that a zero byte can delimit frames. A
+//! receiver joining mid-stream resynchronizes at the next delimiter.
+//!
+//! ```ignore
+//! let mut tx = CobsEncoder::new(uart);
+//! tx.send_frame(&[0x11, 0x00, 0x22])?; // 02 11 02 22 00 on the wire
+//!
+//! let mut rx = CobsDecoder::new(tx.free());
+//! let mut buf = [0; 64];
+//! let len = rx.receive_frame(&mut buf)?;
+//! ```
+use super::{UartError, UdmaUart};
+use crate::sysctrl::{mmap, udma::Enabled};
+
+const DELIMITER: u8 = 0x00;
+/// Longest run of non-zero bytes a single code byte can describe
+const MAX_RUN: usize = 254;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CobsError {
+    /// The decoded frame does not fit the receive buffer. The rest of the
+    /// frame was discarded.
+    FrameTooLong,
+    /// A delimiter arrived in the middle of a code block
+    InvalidEncoding,
+    Uart(UartError),
+}
+
+impl From<UartError> for CobsError {
+    fn from(e: UartError) -> Self {
+        CobsError::Uart(e)
+    }
+}
+
+/// Sends COBS frames, each terminated by a zero byte
+pub struct CobsEncoder<'u> {
+    uart: UdmaUart<'u, Enabled>,
+}
+
+impl<'u> CobsEncoder<'u> {
+    pub fn new(uart: UdmaUart<'u, Enabled>) -> Self {
+        Self { uart }
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    /// Encode `data` and transmit it followed by the delimiter
+    ///
+    /// Each code block is staged on the stack and written with one uDMA
+    /// transfer, so `data` can be anywhere in memory. Fails with
+    /// [UartError::InvalidBuffer] if the stack is not visible to the uDMA.
+    pub fn send_frame(&mut self, data: &[u8]) -> Result<(), CobsError> {
+        // Code byte, run and the delimiter after the last block
+        let mut block = [0u8; MAX_RUN + 2];
+        let start = block.as_ptr() as usize;
+        if start < mmap::UDMA_MEM_START || start + block.len() > mmap::UDMA_MEM_END {
+            return Err(UartError::InvalidBuffer.into());
+        }
+
+        let mut rest = data;
+        loop {
+            let run = rest
+                .iter()
+                .take(MAX_RUN)
+                .position(|&b| b == DELIMITER)
+                .unwrap_or(rest.len().min(MAX_RUN));
+            block[0] = run as u8 + 1;
+            block[1..=run].copy_from_slice(&rest[..run]);
+            let mut len = run + 1;
+
+            // A full block carries no implicit zero, so a zero right after it
+            // starts the next block
+            let done = if run < MAX_RUN && rest.get(run) == Some(&DELIMITER) {
+                rest = &rest[run + 1..];
+                false
+            } else {
+                rest = &rest[run..];
+                rest.is_empty()
+            };
+            if done {
+                block[len] = DELIMITER;
+                len += 1;
+            }
+            self.uart.write(&block[..len]);
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Receives COBS frames sent by [CobsEncoder] or any other standard encoder
+///
+/// Requires RX polling to be enabled, sa. [UdmaUart::try_read_byte].
+pub struct CobsDecoder<'u> {
+    uart: UdmaUart<'u, Enabled>,
+}
+
+impl<'u> CobsDecoder<'u> {
+    pub fn new(uart: UdmaUart<'u, Enabled>) -> Self {
+        Self { uart }
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    /// Wait for the next frame and decode it into `buf`
+    ///
+    /// Returns the length of the decoded frame. Delimiters without a frame in
+    /// between are skipped. On error, the bytes up to and including the
+    /// next delimiter are consumed, so the following call starts at a frame
+    /// boundary.
+    pub fn receive_frame<const N: usize>(&mut self, buf: &mut [u8; N]) -> Result<usize, CobsError> {
+        let mut len = 0;
+        let mut overflow = false;
+        // Code byte of the current block, `0` before the first one
+        let mut code = 0u8;
+        // Bytes left in the current block
+        let mut remaining = 0u8;
+
+        loop {
+            let byte = self.uart.read_byte();
+            if byte == DELIMITER {
+                if code == 0 {
+                    continue;
+                }
+                if remaining != 0 {
+                    return Err(CobsError::InvalidEncoding);
+                }
+                if overflow {
+                    return Err(CobsError::FrameTooLong);
+                }
+                return Ok(len);
+            }
+
+            let data = if remaining == 0 {
+                // Every block but a full one ends in an implicit zero, which
+                // is dropped after the last block
+                let zero = code != 0 && code != 0xff;
+                code = byte;
+                remaining = byte - 1;
+                if !zero {
+                    continue;
+                }
+                0
+            } else {
+                remaining -= 1;
+                byte
+            };
+            match buf.get_mut(len) {
+                Some(b) => {
+                    *b = data;
+                    len += 1;
+                }
+                None => overflow = true,
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Console shared by tasks of any priority, e.g., in RTIC applications
+//!
+//! Each message is formatted into a [LineBuf] on the stack of the caller,
+//! and the critical section is only entered to send the finished line. Lines
+//! from different tasks are therefore never interleaved, and formatting does
+//! not delay interrupts.
+//!
+//! ```ignore
+//! static CONSOLE: UartConsole<'static> = UartConsole::new(Some(|| mcycle::read64()));
+//!
+//! CONSOLE.attach(udma.split().unwrap().uart.unwrap().enable(setup));
+//! CONSOLE.set_level(Level::Info);
+//!
+//! // In any task
+//! console_log!(CONSOLE, Level::Warn, "rx overrun after {} bytes", n);
+//! console_println!(CONSOLE, "tick {}", tick);
+//! ```
+//!
+//! With a timestamp source, lines start with its value in brackets, e.g.,
+//! `[123456] W rx overrun after 12 bytes`. Lines longer than [LINE_LEN]
+//! are cut short and end in [TRUNCATION_MARKER].
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use super::UdmaUart;
+use crate::{
+    fmt::{LineBuf, TRUNCATION_MARKER},
+    sync::BspMutex,
+    sysctrl::udma::Enabled,
+};
+
+/// Longest line, including the prefix, excluding the line ending
+pub const LINE_LEN: usize = 128;
+
+/// Line assembled by the console macros
+pub type Line = LineBuf<LINE_LEN>;
+
+/// Severity of a message, from most to least severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    /// Letter shown in the line prefix
+    pub const fn tag(&self) -> &'static str {
+        match self {
+            Level::Error => "E",
+            Level::Warn => "W",
+            Level::Info => "I",
+            Level::Debug => "D",
+            Level::Trace => "T",
+        }
+    }
+
+    const fn from_u8(n: u8) -> Self {
+        match n {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+/// UART console shared between tasks, sa. [module documentation](self)
+///
+/// Messages sent before [UartConsole::attach] are dropped.
+pub struct UartConsole<'u> {
+    uart: BspMutex<Option<UdmaUart<'u, Enabled>>>,
+    /// Least severe [Level] printed
+    level: AtomicU8,
+    timestamp: Option<fn() -> u64>,
+}
+
+// Safety: the UART is only reached through the mutex. Its register block
+// is not `Sync`, but SysCtrl has a single hart, on which the critical
+// section excludes all other access.
+unsafe impl Sync for UartConsole<'_> {}
+
+impl<'u> UartConsole<'u> {
+    /// Console printing messages up to [Level::Info], prefixed with the
+    /// value of `timestamp` if given, e.g., a CLINT `mtime` reading
+    pub const fn new(timestamp: Option<fn() -> u64>) -> Self {
+        Self {
+            uart: BspMutex::new(None),
+            level: AtomicU8::new(Level::Info as u8),
+            timestamp,
+        }
+    }
+
+    /// Send messages to `uart`, returning the previously attached UART
+    pub fn attach(&self, uart: UdmaUart<'u, Enabled>) -> Option<UdmaUart<'u, Enabled>> {
+        self.uart.lock(|slot| slot.replace(uart))
+    }
+
+    /// Stop printing and return the UART, e.g., to disable it
+    pub fn detach(&self) -> Option<UdmaUart<'u, Enabled>> {
+        self.uart.lock(|slot| slot.take())
+    }
+
+    /// Print messages of `level` and more severe ones
+    pub fn set_level(&self, level: Level) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn level(&self) -> Level {
+        Level::from_u8(self.level.load(Ordering::Relaxed))
+    }
+
+    /// Whether messages of `level` are printed
+    #[inline]
+    pub fn enabled(&self, level: Level) -> bool {
+        level as u8 <= self.level.load(Ordering::Relaxed)
+    }
+
+    /// Start `line` with the timestamp and the tag of `level`, if any
+    #[doc(hidden)]
+    pub fn begin_line(&self, line: &mut Line, level: Option<Level>) {
+        if let Some(timestamp) = self.timestamp {
+            let _ = ufmt::uwrite!(line, "[{}] ", timestamp());
+        }
+        if let Some(level) = level {
+            line.str(level.tag()).str(" ");
+        }
+    }
+
+    /// Send `line` with a line ending
+    pub fn emit(&self, line: &Line) {
+        let mut marker = LineBuf::<{ TRUNCATION_MARKER.len() }>::new();
+        if line.truncated() {
+            marker.str(TRUNCATION_MARKER);
+        }
+        self.uart.lock(|uart| {
+            if let Some(uart) = uart {
+                uart.write(line.as_bytes());
+                if !marker.as_bytes().is_empty() {
+                    uart.write(marker.as_bytes());
+                }
+                uart.write_str("\r\n");
+            }
+        });
+    }
+}
+
+/// Print a line at [Level] `$level` on a
+/// [UartConsole](crate::sysctrl::udma::uart::console::UartConsole), if the
+/// console's level allows it. The arguments are those of `ufmt::uwrite!`.
+#[macro_export]
+macro_rules! console_log {
+    ($console:expr, $level:expr, $($tt:tt)*) => {{
+        let console: &$crate::sysctrl::udma::uart::console::UartConsole = &$console;
+        let level: $crate::sysctrl::udma::uart::console::Level = $level;
+        if console.enabled(level) {
+            let mut line = $crate::sysctrl::udma::uart::console::Line::new();
+            console.begin_line(&mut line, Some(level));
+            let _ = $crate::ufmt::uwrite!(line, $($tt)*);
+            console.emit(&line);
+        }
+    }};
+}
+
+/// Print a line on a
+/// [UartConsole](crate::sysctrl::udma::uart::console::UartConsole)
+/// regardless of its level, without a level tag
+#[macro_export]
+macro_rules! console_println {
+    ($console:expr, $($tt:tt)*) => {{
+        let console: &$crate::sysctrl::udma::uart::console::UartConsole = &$console;
+        let mut line = $crate::sysctrl::udma::uart::console::Line::new();
+        console.begin_line(&mut line, None);
+        let _ = $crate::ufmt::uwrite!(line, $($tt)*);
+        console.emit(&line);
+    }};
+}
@@ -0,0 +1,135 @@
+//! Line ending normalization for terminals and line-based protocols
+//!
+//! Terminals send Enter as CR, or as CRLF on Windows, and expect CRLF at the
+//! end of each line they display. [UartNormalizer] turns every received line
+//! ending into a single LF, and every LF sent into CRLF, so that parsers of
+//! AT commands or shell input only ever see LF:
+//!
+//! ```ignore
+//! let mut term = UartNormalizer::new(uart);
+//! uwriteln!(term, "ready")?; // "ready\r\n" on the wire
+//! loop {
+//!     match term.read_byte() {
+//!         b'\n' => handle(&line),
+//!         byte => line.push(byte),
+//!     }
+//! }
+//! ```
+use super::{UartError, UdmaUart};
+use crate::sysctrl::{mmap, udma::Enabled};
+
+/// Bytes staged per uDMA transfer by [UartNormalizer::write]
+const STAGING_LEN: usize = 64;
+
+/// [UdmaUart] with CR and CRLF received as LF, and LF sent as CRLF
+pub struct UartNormalizer<'u> {
+    uart: UdmaUart<'u, Enabled>,
+    rx: bool,
+    tx: bool,
+    /// The last byte received was a CR, delivered as LF
+    rx_after_cr: bool,
+    /// The last byte sent was a CR
+    tx_after_cr: bool,
+}
+
+impl<'u> UartNormalizer<'u> {
+    /// Wrap `uart`, normalizing both directions
+    pub fn new(uart: UdmaUart<'u, Enabled>) -> Self {
+        Self {
+            uart,
+            rx: true,
+            tx: true,
+            rx_after_cr: false,
+            tx_after_cr: false,
+        }
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    /// Whether to normalize received and sent line endings
+    ///
+    /// With both off, bytes pass through unchanged.
+    pub fn set_crlf_normalize(&mut self, rx: bool, tx: bool) {
+        self.rx = rx;
+        self.tx = tx;
+        self.rx_after_cr = false;
+        self.tx_after_cr = false;
+    }
+
+    /// Received byte, if one is waiting, sa. [UdmaUart::try_read_byte]
+    ///
+    /// A CR is returned as LF right away, and an LF right after it is
+    /// dropped, so a line ending is seen as soon as its first byte arrives.
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        loop {
+            let byte = self.uart.try_read_byte()?;
+            if !self.rx {
+                return Some(byte);
+            }
+            let after_cr = core::mem::replace(&mut self.rx_after_cr, byte == b'\r');
+            match byte {
+                b'\r' => return Some(b'\n'),
+                b'\n' if after_cr => continue,
+                _ => return Some(byte),
+            }
+        }
+    }
+
+    /// Wait for the next received byte, sa. [UartNormalizer::try_read_byte]
+    pub fn read_byte(&mut self) -> u8 {
+        loop {
+            if let Some(byte) = self.try_read_byte() {
+                return byte;
+            }
+        }
+    }
+
+    /// Transmit `data`, with each LF not preceded by CR sent as CRLF
+    ///
+    /// The converted bytes are staged on the stack and written with one uDMA
+    /// transfer per [STAGING_LEN] bytes, so `data` can be anywhere in memory.
+    /// Fails with [UartError::InvalidBuffer] if the stack is not visible to
+    /// the uDMA.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), UartError> {
+        if !self.tx {
+            self.uart.write(data);
+            return Ok(());
+        }
+
+        let mut staging = [0u8; STAGING_LEN];
+        let start = staging.as_ptr() as usize;
+        if start < mmap::UDMA_MEM_START || start + STAGING_LEN > mmap::UDMA_MEM_END {
+            return Err(UartError::InvalidBuffer);
+        }
+
+        let mut len = 0;
+        for &byte in data {
+            // Room for CRLF
+            if len + 2 > STAGING_LEN {
+                self.uart.write(&staging[..len]);
+                len = 0;
+            }
+            if byte == b'\n' && !self.tx_after_cr {
+                staging[len] = b'\r';
+                len += 1;
+            }
+            staging[len] = byte;
+            len += 1;
+            self.tx_after_cr = byte == b'\r';
+        }
+        if len != 0 {
+            self.uart.write(&staging[..len]);
+        }
+        Ok(())
+    }
+}
+
+impl ufmt_write::uWrite for UartNormalizer<'_> {
+    type Error = UartError;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write(s.as_bytes())
+    }
+}
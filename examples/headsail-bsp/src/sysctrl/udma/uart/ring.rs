@@ -0,0 +1,137 @@
+//! Continuous reception into a ring buffer over the uDMA UART RX channel
+//!
+//! The RX channel runs in continuous mode, so the uDMA wraps around to the
+//! start of the buffer once it fills up. Received bytes are read in place,
+//! e.g., by a deframer:
+//!
+//! ```ignore
+//! dma_static!(RING: [u8; 512]);
+//!
+//! let mut ring = UartRxRing::new(&mut uart, unsafe { RING.get_mut() })?;
+//! let mut dec = slip::Decoder::new();
+//! loop {
+//!     ring.drain(|byte| {
+//!         if let Some(Ok(len)) = dec.push(byte, &mut frame) {
+//!             handle(&frame[..len]);
+//!         }
+//!     });
+//! }
+//! ```
+use core::marker::PhantomData;
+
+use super::{check_buf, UartError, UdmaUart};
+use crate::{mmio::reg_write, sysctrl::udma::Enabled};
+
+/// Ring of received bytes, sa. [module documentation](self)
+///
+/// The channel keeps writing whether or not the bytes have been read, and
+/// the hardware does not report when it laps the reader. Drain the ring
+/// before `len - 1` unread bytes pile up. Past that, unread bytes are
+/// overwritten and [UartRxRing::available] is wrong by a multiple of the
+/// length. The channel is stopped when the ring is dropped.
+pub struct UartRxRing<'r, 'u> {
+    uart: &'r mut UdmaUart<'u, Enabled>,
+    // Written by the uDMA, so not held as a reference
+    ptr: *const u8,
+    len: usize,
+    /// Next byte to read
+    tail: usize,
+    _buf: PhantomData<&'r mut [u8]>,
+}
+
+impl<'r, 'u> UartRxRing<'r, 'u> {
+    /// Start continuous reception into `buf`
+    ///
+    /// `buf` must lie in memory visible to the uDMA and hold at least two
+    /// bytes. Requires `rx_ena` without `polling_en` in [UdmaUart::enable].
+    pub fn new(uart: &'r mut UdmaUart<'u, Enabled>, buf: &'r mut [u8]) -> Result<Self, UartError> {
+        check_buf(buf)?;
+        if buf.len() < 2 {
+            return Err(UartError::InvalidBuffer);
+        }
+
+        let udma = uart.0;
+        // Reading clears the flags left over from earlier receptions
+        let _ = udma.uart_error().read();
+        reg_write!(udma.uart_rx_saddr(), |w| unsafe {
+            w.bits(buf.as_mut_ptr() as u32)
+        });
+        reg_write!(udma.uart_rx_size(), |w| unsafe { w.bits(buf.len() as u32) });
+        reg_write!(udma.uart_rx_cfg(), |w| {
+            w.continous().set_bit();
+            w.en().set_bit()
+        });
+
+        Ok(Self {
+            uart,
+            ptr: buf.as_ptr(),
+            len: buf.len(),
+            tail: 0,
+            _buf: PhantomData,
+        })
+    }
+
+    /// Offset the uDMA writes next
+    fn head(&self) -> usize {
+        // `UART_RX_SIZE` counts down the bytes left until the wrap
+        let left = self.uart.0.uart_rx_size().read().bits() as usize;
+        (self.len - left.min(self.len)) % self.len
+    }
+
+    /// Number of bytes received but not yet consumed
+    pub fn available(&self) -> usize {
+        (self.head() + self.len - self.tail) % self.len
+    }
+
+    /// Unread bytes, oldest first, in two parts when they wrap around the end
+    /// of the buffer
+    pub fn peek(&self) -> (&[u8], &[u8]) {
+        let head = self.head();
+        // SAFETY: the uDMA does not write `tail..head` until it is consumed,
+        // provided the ring is drained in time
+        let buf = unsafe { core::slice::from_raw_parts(self.ptr, self.len) };
+        if head >= self.tail {
+            (&buf[self.tail..head], &[])
+        } else {
+            (&buf[self.tail..], &buf[..head])
+        }
+    }
+
+    /// Release the `n` oldest bytes back to the uDMA
+    ///
+    /// # Panics
+    ///
+    /// If `n` is more than [UartRxRing::available]
+    pub fn consume(&mut self, n: usize) {
+        assert!(n <= self.available());
+        self.tail = (self.tail + n) % self.len;
+    }
+
+    /// Pass each unread byte to `f` and consume them, returning how many
+    /// there were
+    pub fn drain<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(u8),
+    {
+        let (a, b) = self.peek();
+        let n = a.len() + b.len();
+        a.iter().chain(b).for_each(|&byte| f(byte));
+        self.consume(n);
+        n
+    }
+
+    /// Whether the receive FIFO has overflowed since the last call
+    ///
+    /// This catches bytes lost before the uDMA, not unread bytes overwritten
+    /// in the ring.
+    pub fn overrun(&mut self) -> bool {
+        let udma = self.uart.0;
+        udma.uart_error().read().rx_err_overflow().bit_is_set()
+    }
+}
+
+impl Drop for UartRxRing<'_, '_> {
+    fn drop(&mut self) {
+        reg_write!(self.uart.0.uart_rx_cfg(), |w| w.clr().set_bit());
+    }
+}
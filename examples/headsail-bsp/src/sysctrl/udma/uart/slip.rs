@@ -0,0 +1,110 @@
+//! SLIP framing for packets over the uDMA UART, sa. [crate::slip]
+//!
+//! ```ignore
+//! let mut tx = SlipEncoder::new(uart);
+//! tx.send_packet(&[0x45, 0xc0, 0x01])?; // c0 45 db dc 01 c0 on the wire
+//!
+//! let mut rx = SlipDecoder::new(tx.free());
+//! let mut buf = [0; 1500];
+//! let len = rx.receive_packet(&mut buf)?;
+//! ```
+use super::{UartError, UdmaUart};
+use crate::{
+    slip::{self, DecodeError},
+    sysctrl::{mmap, udma::Enabled},
+};
+
+/// Bytes staged per uDMA transfer by [SlipEncoder::send_packet]
+const STAGING_LEN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SlipError {
+    /// The packet does not fit the receive buffer. The rest of the packet
+    /// was discarded.
+    PacketTooLong,
+    /// An escape byte was followed by an invalid code
+    InvalidEscape,
+    Uart(UartError),
+}
+
+impl From<UartError> for SlipError {
+    fn from(e: UartError) -> Self {
+        SlipError::Uart(e)
+    }
+}
+
+impl From<DecodeError> for SlipError {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::Overflow => SlipError::PacketTooLong,
+            DecodeError::Framing => SlipError::InvalidEscape,
+        }
+    }
+}
+
+/// Sends SLIP packets, each between two delimiters
+pub struct SlipEncoder<'u> {
+    uart: UdmaUart<'u, Enabled>,
+}
+
+impl<'u> SlipEncoder<'u> {
+    pub fn new(uart: UdmaUart<'u, Enabled>) -> Self {
+        Self { uart }
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    /// Escape `data` and transmit it as one packet
+    ///
+    /// The escaped bytes are staged on the stack and written with one uDMA
+    /// transfer per [STAGING_LEN] bytes, so `data` can be anywhere in memory.
+    /// Fails with [UartError::InvalidBuffer] if the stack is not visible to
+    /// the uDMA.
+    pub fn send_packet(&mut self, data: &[u8]) -> Result<(), SlipError> {
+        let mut encoder = slip::Encoder::<STAGING_LEN>::new();
+        let buf = encoder.buffer();
+        let start = buf.as_ptr() as usize;
+        if start < mmap::UDMA_MEM_START || start + buf.len() > mmap::UDMA_MEM_END {
+            return Err(UartError::InvalidBuffer.into());
+        }
+
+        let uart = &mut self.uart;
+        encoder.encode(&[data], |bytes| uart.write(bytes));
+        Ok(())
+    }
+}
+
+/// Receives SLIP packets sent by [SlipEncoder] or any other RFC 1055 sender
+///
+/// Requires RX polling to be enabled, sa. [UdmaUart::try_read_byte].
+pub struct SlipDecoder<'u> {
+    uart: UdmaUart<'u, Enabled>,
+}
+
+impl<'u> SlipDecoder<'u> {
+    pub fn new(uart: UdmaUart<'u, Enabled>) -> Self {
+        Self { uart }
+    }
+
+    pub fn free(self) -> UdmaUart<'u, Enabled> {
+        self.uart
+    }
+
+    /// Wait for the next packet and unescape it into `buf`
+    ///
+    /// Returns the length of the packet. Delimiters without a packet in
+    /// between are skipped. On error, the rest of the packet is consumed, so
+    /// the following call starts at a packet boundary.
+    pub fn receive_packet(&mut self, buf: &mut [u8]) -> Result<usize, SlipError> {
+        let mut decoder = slip::Decoder::new();
+        loop {
+            let byte = self.uart.read_byte();
+            if let Some(res) = decoder.push(byte, buf) {
+                return Ok(res?);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Independent TX and RX halves of the uDMA UART
+//!
+//! The TX and RX channels have registers of their own, so the halves from
+//! [UdmaUart::split] can be used from different contexts without a mutex,
+//! e.g., TX from the main loop and RX from the UART interrupt handler:
+//!
+//! ```ignore
+//! let (mut tx, mut rx) = uart.split();
+//! // Hand `rx` over to the interrupt handler
+//! uwriteln!(tx, "ready")?;
+//! ```
+use super::{UartError, UdmaUart, WaterMarks};
+use crate::sysctrl::udma::Enabled;
+
+/// Transmit half of a [UdmaUart], sa. [module documentation](self)
+pub struct UartTx<'u>(UdmaUart<'u, Enabled>);
+
+/// Receive half of a [UdmaUart], sa. [module documentation](self)
+pub struct UartRx<'u>(UdmaUart<'u, Enabled>);
+
+// Safety: each half only accesses the registers of its own channel, and
+// `UART_VALID`, `UART_DATA` and `UART_ERROR` only belong to RX
+unsafe impl Send for UartTx<'_> {}
+unsafe impl Send for UartRx<'_> {}
+
+impl<'u> UdmaUart<'u, Enabled> {
+    /// Split into halves that can be moved to different contexts
+    ///
+    /// Each half keeps its own water mark, sa.
+    /// [UdmaUart::set_tx_high_water_mark] and
+    /// [UdmaUart::set_rx_low_water_mark].
+    pub fn split(self) -> (UartTx<'u>, UartRx<'u>) {
+        let rx = UdmaUart(self.0, self.1, self.2);
+        (UartTx(self), UartRx(rx))
+    }
+
+    /// Join the halves of [UdmaUart::split], e.g., to disable the UART
+    pub fn unsplit(tx: UartTx<'u>, rx: UartRx<'u>) -> Self {
+        let marks = WaterMarks {
+            tx_high: tx.0 .2.tx_high,
+            rx_low: rx.0 .2.rx_low,
+        };
+        UdmaUart(tx.0 .0, tx.0 .1, marks)
+    }
+}
+
+impl UartTx<'_> {
+    /// Sa. [UdmaUart::write]
+    #[inline]
+    pub fn write(&mut self, buf: &[u8]) {
+        self.0.write(buf);
+    }
+
+    #[inline]
+    pub fn write_str(&mut self, s: &str) {
+        self.0.write_str(s);
+    }
+
+    /// Sa. [UdmaUart::write_blocking]
+    #[inline]
+    pub fn write_blocking(&mut self, buf: &'static [u8]) -> Result<(), UartError> {
+        self.0.write_blocking(buf)
+    }
+
+    /// Sa. [UdmaUart::tx_fifo_level]
+    #[inline]
+    pub fn tx_fifo_level(&self) -> u8 {
+        self.0.tx_fifo_level()
+    }
+
+    /// Sa. [UdmaUart::set_tx_high_water_mark]
+    #[inline]
+    pub fn set_tx_high_water_mark(&mut self, bytes: u8) {
+        self.0.set_tx_high_water_mark(bytes);
+    }
+}
+
+impl UartRx<'_> {
+    /// Sa. [UdmaUart::try_read_byte]
+    #[inline]
+    pub fn try_read_byte(&mut self) -> Option<u8> {
+        self.0.try_read_byte()
+    }
+
+    /// Sa. [UdmaUart::read_byte]
+    #[inline]
+    pub fn read_byte(&mut self) -> u8 {
+        self.0.read_byte()
+    }
+
+    /// Sa. [UdmaUart::read_dma]
+    #[inline]
+    pub fn read_dma(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
+        self.0.read_dma(buf)
+    }
+
+    /// Sa. [UdmaUart::read_dma_idle]
+    #[inline]
+    pub fn read_dma_idle(&mut self, buf: &mut [u8], idle_cycles: u64) -> Result<usize, UartError> {
+        self.0.read_dma_idle(buf, idle_cycles)
+    }
+
+    /// Sa. [UdmaUart::rx_fifo_level]
+    #[inline]
+    pub fn rx_fifo_level(&self) -> u8 {
+        self.0.rx_fifo_level()
+    }
+
+    /// Sa. [UdmaUart::set_rx_low_water_mark]
+    #[inline]
+    pub fn set_rx_low_water_mark(&mut self, bytes: u8) {
+        self.0.set_rx_low_water_mark(bytes);
+    }
+}
+
+impl ufmt_write::uWrite for UartTx<'_> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write(s.as_bytes());
+        Ok(())
+    }
+}
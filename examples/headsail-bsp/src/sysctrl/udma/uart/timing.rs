@@ -0,0 +1,97 @@
+//! Arrival times of received bytes, e.g., for Modbus RTU framing
+//!
+//! SysCtrl has no timer capture, and the uDMA cannot stamp bytes it writes
+//! to memory, so bytes are read by polling and stamped with `mcycle` as they
+//! are picked up. The resolution is one poll of [RxByteTimer::poll], as long
+//! as the caller polls more often than bytes arrive.
+//!
+//! ```ignore
+//! let mut timer = RxByteTimer::<256>::new();
+//! let t35 = modbus_t35_cycles(BAUD);
+//! loop {
+//!     if let Some(byte) = timer.poll(&mut uart) {
+//!         frame.push(byte);
+//!     } else if timer.idle_gap().is_some_and(|gap| gap >= t35) && !frame.is_empty() {
+//!         handle(&frame);
+//!         frame.clear();
+//!         timer.clear();
+//!     }
+//! }
+//! ```
+use riscv::register::mcycle;
+
+use super::UdmaUart;
+use crate::{delay, sysctrl::udma::Enabled};
+
+/// Stamps received bytes with `mcycle`, keeping the first `N` since the last
+/// [RxByteTimer::clear]
+pub struct RxByteTimer<const N: usize> {
+    stamps: [u64; N],
+    len: usize,
+    last: Option<u64>,
+}
+
+impl<const N: usize> Default for RxByteTimer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RxByteTimer<N> {
+    pub const fn new() -> Self {
+        Self {
+            stamps: [0; N],
+            len: 0,
+            last: None,
+        }
+    }
+
+    /// Read a byte if one is waiting and note when it was seen
+    ///
+    /// Requires `polling_en` and `rx_ena` in [UdmaUart::enable], sa.
+    /// [UdmaUart::try_read_byte].
+    #[inline]
+    pub fn poll(&mut self, uart: &mut UdmaUart<'_, Enabled>) -> Option<u8> {
+        let byte = uart.try_read_byte()?;
+        let now = mcycle::read64();
+        if let Some(stamp) = self.stamps.get_mut(self.len) {
+            *stamp = now;
+            self.len += 1;
+        }
+        self.last = Some(now);
+        Some(byte)
+    }
+
+    /// Copy the `mcycle` stamps of the bytes received since the last
+    /// [RxByteTimer::clear] into `out`, returning how many were copied
+    ///
+    /// Bytes past the first `N` are not stamped.
+    pub fn byte_timestamps(&self, out: &mut [u64]) -> usize {
+        let n = self.len.min(out.len());
+        out[..n].copy_from_slice(&self.stamps[..n]);
+        n
+    }
+
+    /// Cycles since the last byte was received, or `None` if none has been
+    pub fn idle_gap(&self) -> Option<u64> {
+        self.last.map(|last| mcycle::read64().wrapping_sub(last))
+    }
+
+    /// Forget the stamps, e.g., at the end of a frame. [RxByteTimer::idle_gap]
+    /// keeps counting from the last byte.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Modbus RTU inter-frame silence of 3.5 characters in `mcycle` cycles
+///
+/// A character is 11 bits. Above 19200 baud, the specification fixes the
+/// silence at 1750 us instead.
+pub fn modbus_t35_cycles(baud: u32) -> u64 {
+    if baud > 19_200 {
+        return delay::us_to_cycles(1750);
+    }
+    // 3.5 * 11 bits
+    delay::us_to_cycles((38_500_000 / baud.max(1) as u64) as u32)
+}
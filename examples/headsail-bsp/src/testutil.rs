@@ -0,0 +1,145 @@
+//! Reproducible test data for loopback and storage tests, and statistics for
+//! timing tests
+//!
+//! Buffers are generated from a seed, so a test can verify received data by
+//! regenerating the expected stream instead of keeping a copy of it around.
+//! On mismatch, the first offending offset is reported.
+
+/// xoshiro128** pseudo-random number generator
+///
+/// Not suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct Xoshiro128 {
+    s: [u32; 4],
+}
+
+impl Xoshiro128 {
+    /// Create a generator from `seed`
+    ///
+    /// The seed is expanded with SplitMix32, so any value, including zero, is
+    /// valid.
+    pub const fn new(seed: u32) -> Self {
+        let mut x = seed;
+        let mut s = [0; 4];
+        let mut idx = 0;
+        while idx < 4 {
+            x = x.wrapping_add(0x9e37_79b9);
+            let mut z = x;
+            z = (z ^ (z >> 16)).wrapping_mul(0x85eb_ca6b);
+            z = (z ^ (z >> 13)).wrapping_mul(0xc2b2_ae35);
+            s[idx] = z ^ (z >> 16);
+            idx += 1;
+        }
+        Self { s }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 9;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+
+        result
+    }
+
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// First byte that differs from the expected pattern
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirstMismatch {
+    /// Offset into the verified buffer
+    pub index: usize,
+    pub expected: u8,
+    pub got: u8,
+}
+
+/// Fill `buf` with a pseudo-random stream determined by `seed`
+pub fn fill_pattern(buf: &mut [u8], seed: u32) {
+    Xoshiro128::new(seed).fill_bytes(buf);
+}
+
+/// Check that `buf` holds the stream written by [fill_pattern] with `seed`
+pub fn verify_pattern(buf: &[u8], seed: u32) -> Result<(), FirstMismatch> {
+    let mut rng = Xoshiro128::new(seed);
+    verify_with(buf, |_| rng.next_u32())
+}
+
+/// Fill `buf` with 32-bit little-endian words holding their own byte offset,
+/// XORed with `seed`
+///
+/// Data that lands at the wrong offset is evident from its value, which makes
+/// this pattern useful for chasing addressing bugs.
+pub fn fill_address_pattern(buf: &mut [u8], seed: u32) {
+    for (idx, chunk) in buf.chunks_mut(4).enumerate() {
+        let bytes = address_word(idx, seed).to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+/// Check that `buf` holds the pattern written by [fill_address_pattern] with
+/// `seed`
+pub fn verify_address_pattern(buf: &[u8], seed: u32) -> Result<(), FirstMismatch> {
+    verify_with(buf, |chunk_idx| address_word(chunk_idx, seed))
+}
+
+#[inline]
+fn address_word(chunk_idx: usize, seed: u32) -> u32 {
+    ((chunk_idx * 4) as u32) ^ seed
+}
+
+/// Compare `buf` against words produced by `expected_word`, called once per
+/// 4-byte chunk in order
+fn verify_with<F>(buf: &[u8], mut expected_word: F) -> Result<(), FirstMismatch>
+where
+    F: FnMut(usize) -> u32,
+{
+    for (chunk_idx, chunk) in buf.chunks(4).enumerate() {
+        let expected = expected_word(chunk_idx).to_le_bytes();
+        for (byte_idx, (&got, &expected)) in chunk.iter().zip(expected.iter()).enumerate() {
+            if got != expected {
+                return Err(FirstMismatch {
+                    index: chunk_idx * 4 + byte_idx,
+                    expected,
+                    got,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Summary of latency samples, e.g., in `mcycle` cycles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub min: u32,
+    /// Upper median for an even number of samples
+    pub median: u32,
+    pub max: u32,
+    pub samples: usize,
+}
+
+impl LatencyStats {
+    /// Summarize `samples`, which are sorted in place. Returns `None` if
+    /// there are no samples.
+    pub fn from_samples(samples: &mut [u32]) -> Option<Self> {
+        samples.sort_unstable();
+        Some(Self {
+            min: *samples.first()?,
+            median: samples[samples.len() / 2],
+            max: *samples.last()?,
+            samples: samples.len(),
+        })
+    }
+}
@@ -0,0 +1,138 @@
+//! Ring of time-stamped driver events for post-mortem analysis
+//!
+//! Drivers record what they start and finish with [push], from thread or
+//! interrupt context. Only the last [TRACE_LEN] events are kept. [dump] prints
+//! them oldest first and is called by the panic handlers, so a panic report
+//! ends with what the drivers were doing right before it.
+//!
+//! ```text
+//! trace: 3 events
+//!   12000 +0 SpimTxStart len=4
+//!   12210 +210 SpimCmd words=1
+//!   12980 +770 SpimDone
+//! ```
+//!
+//! Timestamps are the low 32 bits of `mcycle`. Recording costs a timestamp
+//! read and a store with interrupts masked, formatting happens only in
+//! [dump].
+use core::ptr::addr_of_mut;
+
+use riscv::register::mcycle;
+use ufmt::{uDisplay, uWrite, uwrite};
+
+/// Number of events kept
+pub const TRACE_LEN: usize = 64;
+
+/// Event recorded by a driver or the application
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// SPIM TX channel started with `len` bytes
+    SpimTxStart { len: u32 },
+    /// SPIM RX channel started with `len` bytes
+    SpimRxStart { len: u32 },
+    /// Command words dispatched to the SPIM CMD channel
+    SpimCmd { words: u32 },
+    /// SPIM data channels finished
+    SpimDone,
+    /// uDMA UART transmission of `len` bytes
+    UartTx { len: u32 },
+    /// uDMA UART reception ended with `len` bytes received
+    UartRx { len: u32 },
+    /// Record at the start of an interrupt handler
+    IrqEnter { n: u8 },
+    /// Record at the end of an interrupt handler
+    IrqExit { n: u8 },
+    /// Registers of `periph` accessed around its driver, e.g., with
+    /// [UdmaSpim::with_raw](crate::sysctrl::udma::UdmaSpim::with_raw)
+    RawAccess { periph: &'static str },
+    /// Application-defined marker
+    Mark(u32),
+}
+
+impl uDisplay for Event {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        match *self {
+            Event::SpimTxStart { len } => uwrite!(f, "SpimTxStart len={}", len),
+            Event::SpimRxStart { len } => uwrite!(f, "SpimRxStart len={}", len),
+            Event::SpimCmd { words } => uwrite!(f, "SpimCmd words={}", words),
+            Event::SpimDone => f.write_str("SpimDone"),
+            Event::UartTx { len } => uwrite!(f, "UartTx len={}", len),
+            Event::UartRx { len } => uwrite!(f, "UartRx len={}", len),
+            Event::IrqEnter { n } => uwrite!(f, "IrqEnter n={}", n),
+            Event::IrqExit { n } => uwrite!(f, "IrqExit n={}", n),
+            Event::RawAccess { periph } => uwrite!(f, "RawAccess periph={}", periph),
+            Event::Mark(tag) => uwrite!(f, "Mark {}", tag),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    cycles: u32,
+    event: Event,
+}
+
+static mut RING: [Entry; TRACE_LEN] = [Entry {
+    cycles: 0,
+    event: Event::Mark(0),
+}; TRACE_LEN];
+/// Number of events pushed since reset, wrapping
+static mut PUSHED: usize = 0;
+/// Set while dumping, so the output driver does not overwrite what is being
+/// printed
+static mut PAUSED: bool = false;
+
+/// Record `event`
+#[inline]
+pub fn push(event: Event) {
+    let cycles = mcycle::read() as u32;
+    // Not all targets support atomic read-modify-write, so exclude interrupts
+    // instead
+    riscv::interrupt::free(|| unsafe {
+        if PAUSED {
+            return;
+        }
+        let idx = PUSHED % TRACE_LEN;
+        (*addr_of_mut!(RING))[idx] = Entry { cycles, event };
+        PUSHED = PUSHED.wrapping_add(1);
+    });
+}
+
+/// Print the recorded events oldest first
+///
+/// Events pushed while dumping, e.g., by the UART driver printing the dump,
+/// are not recorded.
+pub fn dump<W: uWrite + ?Sized>(w: &mut W) -> Result<(), W::Error> {
+    let (pushed, was_paused) = riscv::interrupt::free(|| unsafe {
+        let was_paused = PAUSED;
+        PAUSED = true;
+        (PUSHED, was_paused)
+    });
+
+    let result = dump_entries(w, pushed);
+    unsafe { PAUSED = was_paused };
+    result
+}
+
+fn dump_entries<W: uWrite + ?Sized>(w: &mut W, pushed: usize) -> Result<(), W::Error> {
+    let count = pushed.min(TRACE_LEN);
+    let first = pushed.wrapping_sub(count);
+
+    uwrite!(w, "trace: {} events\n", count)?;
+    let mut prev = None;
+    for i in 0..count {
+        let entry = unsafe { (*addr_of_mut!(RING))[first.wrapping_add(i) % TRACE_LEN] };
+        let delta = prev.map_or(0, |p: u32| entry.cycles.wrapping_sub(p));
+        uwrite!(w, "  {} +{} {}\n", entry.cycles, delta, entry.event)?;
+        prev = Some(entry.cycles);
+    }
+    Ok(())
+}
+
+/// Discard all recorded events
+pub fn clear() {
+    riscv::interrupt::free(|| unsafe { PUSHED = 0 });
+}
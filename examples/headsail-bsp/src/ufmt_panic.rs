@@ -28,7 +28,7 @@ impl uDisplay for PanicInfoWrapper<'_> {
             // Printing parameters would make us depend on core::fmt.
             f.write_str("cause lost")?;
         }
-        Ok(())
+        uwrite!(f, "\n{}", crate::version())
     }
 }
 
@@ -46,6 +46,17 @@ fn panic(info: &PanicInfo) -> ! {
         PanicInfoWrapper(info)
     )
     .unwrap();
+    #[cfg(feature = "trace")]
+    {
+        let mut uart = unsafe { crate::apb_uart::ApbUart0::instance() };
+        uwrite!(uart, "\n").unwrap();
+        crate::trace::dump(&mut uart).unwrap();
+    }
+    #[cfg(feature = "dma-canary")]
+    if let Err(e) = crate::dma::check_all() {
+        let mut uart = unsafe { crate::apb_uart::ApbUart0::instance() };
+        uwrite!(uart, "\n{}", e).unwrap();
+    }
 
     loop {}
 }
@@ -61,8 +72,14 @@ fn panic(info: &PanicInfo) -> ! {
         let (soc_freq, baud) = (30_000_000, 9600_u32);
         let clk_div: u16 = (soc_freq / baud) as u16;
 
-        let udma = crate::sysctrl::udma::Udma(udma);
-        udma.split().uart.enable(|w| {
+        // Without the layout check and the probe of Udma::split, so that the
+        // message is printed even when they would refuse the driver
+        let uart = crate::sysctrl::udma::UdmaUart::<crate::sysctrl::udma::Disabled>(
+            udma,
+            core::marker::PhantomData,
+            crate::sysctrl::udma::uart::WaterMarks::DEFAULT,
+        );
+        uart.enable(|w| {
             unsafe {
                 w
                     // Use this if using parity bit
@@ -85,6 +102,15 @@ fn panic(info: &PanicInfo) -> ! {
         unsafe { crate::sysctrl::udma::UdmaUart::steal(udma) }
     };
     ufmt::uwrite!(serial, "{}", PanicInfoWrapper(info)).unwrap();
+    #[cfg(feature = "trace")]
+    {
+        uwrite!(serial, "\n").unwrap();
+        crate::trace::dump(&mut serial).unwrap();
+    }
+    #[cfg(feature = "dma-canary")]
+    if let Err(e) = crate::dma::check_all() {
+        uwrite!(serial, "\n{}", e).unwrap();
+    }
 
     loop {}
 }
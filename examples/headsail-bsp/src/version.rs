@@ -0,0 +1,114 @@
+//! Identification of the BSP build
+//!
+//! Print [version] at start-up so that logs and bug reports tell which build
+//! and features they came from:
+//!
+//! ```text
+//! headsail-bsp 0.1.0 (3144664-dirty) on SysCtrl [rt,spim,sysctrl,udma-uart] pac 0.1.1
+//! ```
+use ufmt::{uDisplay, uWrite, uwrite};
+
+/// Core the BSP is built for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Core {
+    Hpc = 0,
+    SysCtrl = 1,
+    /// Neither of the Headsail targets, e.g., a host build
+    Other = 0xff,
+}
+
+impl Core {
+    const fn current() -> Self {
+        if cfg!(target_arch = "riscv64") {
+            Core::Hpc
+        } else if cfg!(target_arch = "riscv32") {
+            Core::SysCtrl
+        } else {
+            Core::Other
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Core::Hpc => "HPC",
+            Core::SysCtrl => "SysCtrl",
+            Core::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Version {
+    /// Version of the headsail-bsp crate
+    pub crate_version: &'static str,
+    /// `git describe` of the checkout, or "unknown" when built outside of one
+    pub git_describe: &'static str,
+    /// Enabled Cargo features, including the implied ones, sorted and
+    /// comma-separated
+    pub features: &'static str,
+    pub core: Core,
+    /// Version of the SysCtrl PAC the uDMA drivers are written against, sa.
+    /// [PAC_VERSION](crate::sysctrl::udma::PAC_VERSION). Empty without the
+    /// `sysctrl-pac` feature.
+    pub pac_version: &'static str,
+}
+
+/// Version of the running BSP build
+pub const fn version() -> Version {
+    Version {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        git_describe: env!("HEADSAIL_BSP_GIT_DESCRIBE"),
+        features: env!("HEADSAIL_BSP_FEATURES"),
+        core: Core::current(),
+        #[cfg(feature = "sysctrl-pac")]
+        pac_version: crate::sysctrl::udma::PAC_VERSION,
+        #[cfg(not(feature = "sysctrl-pac"))]
+        pac_version: "",
+    }
+}
+
+impl Version {
+    /// Write `core: u8` followed by [Version::crate_version],
+    /// [Version::git_describe], [Version::features] and
+    /// [Version::pac_version], each terminated by a zero byte
+    ///
+    /// Returns the number of bytes written, or `None` if `buf` is too short.
+    pub fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        let (core, rest) = buf.split_first_mut()?;
+        *core = self.core as u8;
+        let mut len = 0;
+        for s in [
+            self.crate_version,
+            self.git_describe,
+            self.features,
+            self.pac_version,
+        ] {
+            let field = rest.get_mut(len..len + s.len() + 1)?;
+            field[..s.len()].copy_from_slice(s.as_bytes());
+            field[s.len()] = 0;
+            len += s.len() + 1;
+        }
+        Some(1 + len)
+    }
+}
+
+impl uDisplay for Version {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        uwrite!(
+            f,
+            "headsail-bsp {} ({}) on {} [{}]",
+            self.crate_version,
+            self.git_describe,
+            self.core.name(),
+            self.features
+        )?;
+        if !self.pac_version.is_empty() {
+            uwrite!(f, " pac {}", self.pac_version)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! Feed a watchdog only while every critical task is alive
+//!
+//! Feeding the watchdog from one periodic task keeps the system alive even
+//! when the other tasks are stuck. With a [WatchdogRefresher], each critical
+//! task checks in to its own slot, and the watchdog is fed only once all of
+//! them have checked in since the last feed.
+//!
+//! In an RTIC application, the refresher is a shared resource:
+//!
+//! ```ignore
+//! #[shared]
+//! struct Shared {
+//!     wdt: WatchdogRefresher<2>,
+//! }
+//!
+//! #[task(shared = [wdt])]
+//! async fn sensor(mut cx: sensor::Context) {
+//!     loop {
+//!         // ...
+//!         cx.shared.wdt.lock(|wdt| wdt.check_in(0));
+//!     }
+//! }
+//!
+//! #[task(shared = [wdt])]
+//! async fn feeder(mut cx: feeder::Context) {
+//!     loop {
+//!         cx.shared.wdt.lock(|wdt| wdt.feed(|| kick_watchdog()));
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! The BSP has no watchdog driver, so feeding is left to the closure passed
+//! to [feed](WatchdogRefresher::feed).
+
+/// Check-in slots of `N` tasks, sa. [module documentation](self)
+pub struct WatchdogRefresher<const N: usize> {
+    checked_in: [bool; N],
+    /// Feeds skipped because a task had not checked in, saturating
+    missed: u32,
+}
+
+impl<const N: usize> Default for WatchdogRefresher<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> WatchdogRefresher<N> {
+    pub const fn new() -> Self {
+        Self {
+            checked_in: [false; N],
+            missed: 0,
+        }
+    }
+
+    /// Mark the task owning `slot` as healthy until the next feed
+    ///
+    /// # Panics
+    ///
+    /// If `slot >= N`
+    pub fn check_in(&mut self, slot: usize) {
+        self.checked_in[slot] = true;
+    }
+
+    /// Whether every slot has checked in since the last feed
+    pub fn all_checked_in(&self) -> bool {
+        self.checked_in.iter().all(|&c| c)
+    }
+
+    /// Call `feed` if every slot has checked in and clear the slots
+    ///
+    /// Returns whether the watchdog was fed. The slots are left as they are
+    /// if it was not, so the tasks that did check in need not do it again.
+    pub fn feed(&mut self, feed: impl FnOnce()) -> bool {
+        if !self.all_checked_in() {
+            self.missed = self.missed.saturating_add(1);
+            return false;
+        }
+        feed();
+        self.checked_in = [false; N];
+        true
+    }
+
+    /// First slot that has not checked in since the last feed, e.g., for
+    /// reporting the stuck task before the watchdog fires
+    pub fn first_missing(&self) -> Option<usize> {
+        self.checked_in.iter().position(|&c| !c)
+    }
+
+    /// Number of calls to [feed](Self::feed) that did not feed
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+}
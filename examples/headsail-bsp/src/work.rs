@@ -0,0 +1,105 @@
+//! Deferred work, so that interrupt handlers stay short
+//!
+//! A handler does the time-critical part, e.g., acknowledging the peripheral,
+//! and hands the rest to [defer]. The application calls [run_pending] from
+//! its main loop, which runs the deferred work in the order it was queued,
+//! with interrupts enabled.
+//!
+//! ```ignore
+//! fn layer_done(layer: usize) {
+//!     start_layer(layer + 1);
+//! }
+//!
+//! // In the DLA interrupt handler
+//! work::defer(layer_done, layer).ok();
+//!
+//! loop {
+//!     work::run_pending();
+//!     riscv::asm::wfi();
+//! }
+//! ```
+//!
+//! Deferred work waits for the main loop to come around, so its latency is
+//! that of the longest main loop iteration, not the interrupt latency. Work
+//! that cannot wait that long belongs in the handler. Queuing and dequeuing
+//! mask interrupts for a few instructions only.
+use core::ptr::addr_of_mut;
+
+/// Number of entries that can be pending at once
+pub const WORK_QUEUE_LEN: usize = 16;
+
+/// Work item, called with the context it was deferred with
+pub type WorkFn = fn(usize);
+
+/// [defer] found the queue full and dropped the work
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkQueueFull;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    work: WorkFn,
+    ctx: usize,
+}
+
+fn nop(_: usize) {}
+
+static mut QUEUE: [Entry; WORK_QUEUE_LEN] = [Entry { work: nop, ctx: 0 }; WORK_QUEUE_LEN];
+/// Index of the oldest entry
+static mut HEAD: usize = 0;
+static mut LEN: usize = 0;
+/// Entries dropped because the queue was full, saturating
+static mut OVERFLOWS: u32 = 0;
+
+/// Queue `work(ctx)` to be run by [run_pending]
+///
+/// Can be called from interrupt handlers and from the main loop. The work is
+/// dropped and counted in [overflows] if [WORK_QUEUE_LEN] entries are already
+/// pending.
+pub fn defer(work: WorkFn, ctx: usize) -> Result<(), WorkQueueFull> {
+    riscv::interrupt::free(|| unsafe {
+        if LEN == WORK_QUEUE_LEN {
+            OVERFLOWS = OVERFLOWS.saturating_add(1);
+            return Err(WorkQueueFull);
+        }
+        let queue = &mut *addr_of_mut!(QUEUE);
+        queue[(HEAD + LEN) % WORK_QUEUE_LEN] = Entry { work, ctx };
+        LEN += 1;
+        Ok(())
+    })
+}
+
+/// Run deferred work until the queue is empty, including work deferred while
+/// draining
+///
+/// Returns the number of work items run. Must not be called from an
+/// interrupt handler.
+pub fn run_pending() -> usize {
+    let mut ran = 0;
+    while let Some(entry) = pop() {
+        (entry.work)(entry.ctx);
+        ran += 1;
+    }
+    ran
+}
+
+/// Number of entries waiting for [run_pending]
+pub fn pending() -> usize {
+    riscv::interrupt::free(|| unsafe { LEN })
+}
+
+/// Number of entries dropped by [defer] since reset, saturating
+pub fn overflows() -> u32 {
+    riscv::interrupt::free(|| unsafe { OVERFLOWS })
+}
+
+fn pop() -> Option<Entry> {
+    riscv::interrupt::free(|| unsafe {
+        if LEN == 0 {
+            return None;
+        }
+        let entry = (*addr_of_mut!(QUEUE))[HEAD];
+        HEAD = (HEAD + 1) % WORK_QUEUE_LEN;
+        LEN -= 1;
+        Some(entry)
+    })
+}
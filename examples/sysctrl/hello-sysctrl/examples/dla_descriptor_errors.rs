@@ -0,0 +1,107 @@
+//! Check that DLA layer descriptors with fields out of range for the DLA are
+//! rejected before use, with the offending field reported.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dla::{LayerDescriptor, ParseError, Region, MAGIC, VERSION},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// 8x8 single-channel input, one 3x3 kernel
+const VALID: LayerDescriptor = LayerDescriptor {
+    magic: MAGIC,
+    version: VERSION,
+    size: LayerDescriptor::SIZE as u16,
+    input: Region { offset: 0, len: 64 },
+    weights: Region { offset: 64, len: 9 },
+    bias: Region { offset: 0, len: 0 },
+    output: Region {
+        offset: 128,
+        len: 36,
+    },
+    width: 8,
+    height: 8,
+    channels: 1,
+    kernels: 1,
+    kernel_width: 3,
+    kernel_height: 3,
+    stride_x: 1,
+    stride_y: 1,
+    pad_top: 0,
+    pad_right: 0,
+    pad_bottom: 0,
+    pad_left: 0,
+    pad_value: 0,
+    mac_clip: 0,
+    pp_clip: 0,
+    flags: 0,
+    reserved: 0,
+};
+
+fn parse(desc: &LayerDescriptor) -> Result<(), ParseError> {
+    // SAFETY: the descriptor has no padding
+    let bytes = unsafe {
+        core::slice::from_raw_parts(desc as *const _ as *const u8, LayerDescriptor::SIZE)
+    };
+    LayerDescriptor::from_bytes(bytes).map(|_| ())
+}
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut ok = parse(&VALID).is_ok();
+
+    let kernel_too_large = LayerDescriptor {
+        kernel_width: 16,
+        ..VALID
+    };
+    let kernel_past_input = LayerDescriptor {
+        width: 2,
+        input: Region { offset: 0, len: 16 },
+        ..VALID
+    };
+    let zero_stride = LayerDescriptor {
+        stride_y: 0,
+        ..VALID
+    };
+    let unknown_flag = LayerDescriptor {
+        flags: 1 << 7,
+        ..VALID
+    };
+    let cases = [
+        (kernel_too_large, "kernel_width", 16, 15),
+        (kernel_past_input, "kernel_width", 3, 2),
+        (zero_stride, "stride_y", 0, 16),
+        (unknown_flag, "flags", 1 << 7, 0b111),
+    ];
+    for (desc, field, value, limit) in cases {
+        let res = parse(&desc);
+        sprintln!("{}: {}", field, res.is_err() as u8);
+        ok &= res
+            == Err(ParseError::InvalidConfig {
+                field,
+                value,
+                limit,
+            });
+    }
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,59 @@
+//! Overrun a DMA buffer by one byte and check that the canary after it
+//! reports the damage. The overrun is done by the CPU, standing in for a
+//! transfer with an off-by-one length.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma::{self, CanarySide},
+    dma_static,
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const LEN: usize = 64;
+
+dma_static!(BUF: [u8; LEN]);
+dma_static!(OTHER: [u8; 16]);
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    unsafe { dma::init() };
+    let mut ok = dma::check_all().is_ok();
+
+    // Filling the whole buffer leaves the canaries alone
+    unsafe { BUF.get_mut() }.fill(0);
+    ok &= BUF.check().is_ok() && OTHER.check().is_ok();
+
+    unsafe { BUF.as_mut_ptr().add(LEN).write_volatile(0x5a) };
+    match dma::check_all() {
+        Err(e) => {
+            sprintln!("{}", e);
+            ok &= e.buf == BUF.as_mut_ptr() as usize
+                && e.side == CanarySide::After
+                && e.offset == 0
+                && e.written == 0x5a
+                && e.count == 1;
+        }
+        Ok(()) => ok = false,
+    }
+    ok &= OTHER.check().is_ok();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,59 @@
+//! Time the software Q15 FIR and dot product for a range of filter lengths.
+//!
+//! Compare the cycles per output sample with the uDMA filter channel to find
+//! the filter length above which offloading pays off.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    fixedpoint::{q15_fir, q15_vector_dot, Q15},
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const MAX_TAPS: usize = 64;
+const BLOCK_LEN: usize = 256;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    // Moving average, so the output of a constant input is known
+    let mut coeffs = [Q15::ZERO; MAX_TAPS];
+    let mut input = [Q15::ZERO; BLOCK_LEN + MAX_TAPS - 1];
+    let mut output = [Q15::ZERO; BLOCK_LEN];
+    input.fill(Q15::from_f32(0.5));
+
+    let mut taps = 4;
+    while taps <= MAX_TAPS {
+        coeffs[..taps].fill(Q15((Q15::MAX.0 as usize / taps) as i16));
+
+        let start = mcycle::read64();
+        q15_fir(&coeffs[..taps], &input[..BLOCK_LEN + taps - 1], &mut output);
+        let fir_cycles = (mcycle::read64() - start) as u32;
+
+        let start = mcycle::read64();
+        let dot = q15_vector_dot(&coeffs[..taps], &input[..taps]);
+        let dot_cycles = (mcycle::read64() - start) as u32;
+
+        sprintln!(
+            "{} taps: fir {} cycles/sample, dot {} cycles, out {} dot {}",
+            taps,
+            fir_cycles / BLOCK_LEN as u32,
+            dot_cycles,
+            output[0].0,
+            dot.0
+        );
+        taps *= 2;
+    }
+
+    loop {}
+}
@@ -0,0 +1,50 @@
+//! Format integers into a LineBuf and compare with the expected renderings.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{fmt::LineBuf, rt::entry, sysctrl::soc_ctrl};
+use hello_sysctrl::{print_example_name, sprintln};
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut ok = true;
+    let mut line = LineBuf::<64>::new();
+
+    line.str("tick ").u32(4_294_967_295).str(" busy ").u32(0);
+    ok &= line.as_bytes() == b"tick 4294967295 busy 0";
+
+    line.clear();
+    line.i32(i32::MIN).str(" ").i32(-7).str(" ").i32(12);
+    ok &= line.as_bytes() == b"-2147483648 -7 12";
+
+    line.clear();
+    line.hex(0xab, 4)
+        .str(" ")
+        .hex(0x1234_5678, 2)
+        .str(" ")
+        .hex(0, 0);
+    ok &= line.as_bytes() == b"00ab 12345678 0";
+    ok &= !line.truncated();
+
+    let mut short = LineBuf::<4>::new();
+    short.str("spim").u32(1);
+    ok &= short.as_bytes() == b"spim" && short.truncated();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
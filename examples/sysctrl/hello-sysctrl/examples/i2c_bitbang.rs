@@ -0,0 +1,41 @@
+//! Read the chip ID of a BME280 at 0x76 over bit-banged I2C on GPIO pads 10
+//! (SDA) and 11 (SCL). Both lines need an external pull-up.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay::Delay,
+    i2c::{BitBangI2c, I2cRegisterMap},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const BME280_ADDR: u8 = 0x76;
+const REG_ID: u8 = 0xd0;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let sda = pads.p10.into_gpio().into_open_drain();
+    let scl = pads.p11.into_gpio().into_open_drain();
+
+    let mut delay = Delay;
+    let mut bus = BitBangI2c::new(sda, scl, &mut delay, 100_000).unwrap();
+    let mut regs = I2cRegisterMap::<_>::new(&mut bus, BME280_ADDR);
+    match regs.read_u8(REG_ID) {
+        // 0x60 for BME280, 0x58 for BMP280
+        Ok(id) => sprintln!("chip id {}", id),
+        Err(_) => sprintln!("no response from {}", BME280_ADDR),
+    }
+
+    loop {}
+}
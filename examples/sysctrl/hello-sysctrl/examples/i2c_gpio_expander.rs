@@ -0,0 +1,58 @@
+//! Toggle pin 0 of a PCF8574 at 0x20 and read it back, over bit-banged I2C on
+//! GPIO pads 10 (SDA) and 11 (SCL). Both lines need an external pull-up, and
+//! pin 0 must be unloaded.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use embedded_hal::digital::{InputPin, OutputPin, StatefulOutputPin};
+use headsail_bsp::{
+    delay::Delay,
+    i2c::{gpio_expander::Expander, BitBangI2c, GpioExpander},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const PCF8574_ADDR: u8 = 0x20;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let sda = pads.p10.into_gpio().into_open_drain();
+    let scl = pads.p11.into_gpio().into_open_drain();
+
+    let mut delay = Delay;
+    let mut bus = BitBangI2c::new(sda, scl, &mut delay, 100_000).unwrap();
+    let mut exp = GpioExpander::new(&mut bus, Expander::Pcf8574, PCF8574_ADDR);
+
+    let mut ok = exp.set_outputs(0b1).is_ok();
+    for high in [false, true, false] {
+        let mut pin = exp.pin::<0>();
+        ok &= pin.set_state(high.into()).is_ok();
+        ok &= pin.is_set_high() == Ok(high);
+        ok &= pin.is_high() == Ok(high);
+    }
+    // Inputs read high through the weak pull-ups, unless driven externally
+    match exp.read_port() {
+        Ok(port) => sprintln!("port {}", port),
+        Err(_) => ok = false,
+    }
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,66 @@
+//! Write to a device at 0x50 over bit-banged I2C on GPIO pads 10 (SDA) and 11
+//! (SCL), sharing the bus with another master. Both lines need an external
+//! pull-up. Without another master, every write goes through on the first
+//! attempt.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+use headsail_bsp::{
+    delay::Delay,
+    i2c::{BitBangI2c, I2cError},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const DEV_ADDR: u8 = 0x50;
+const WRITES: u32 = 20;
+const MAX_BACKOFF_US: u32 = 10_000;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let sda = pads.p10.into_gpio().into_open_drain();
+    let scl = pads.p11.into_gpio().into_open_drain();
+
+    let mut bus_delay = Delay;
+    let mut bus = BitBangI2c::new(sda, scl, &mut bus_delay, 100_000).unwrap();
+    bus.enable_multi_master();
+
+    let mut delay = Delay;
+    let (mut retries, mut errors) = (0, 0);
+    for i in 0..WRITES {
+        let mut backoff_us = 10;
+        let res = loop {
+            if !bus.is_bus_busy() {
+                match bus.write(DEV_ADDR, &[0x00, i as u8]) {
+                    Err(I2cError::ArbitrationLost) => {}
+                    res => break res,
+                }
+            }
+            retries += 1;
+            delay.delay_us(backoff_us);
+            backoff_us = (backoff_us * 2).min(MAX_BACKOFF_US);
+        };
+        if res.is_err() {
+            errors += 1;
+        }
+    }
+    sprintln!("{} writes, {} retries, {} errors", WRITES, retries, errors);
+
+    if errors == 0 {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {}
+}
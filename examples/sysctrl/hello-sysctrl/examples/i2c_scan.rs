@@ -0,0 +1,36 @@
+//! Scan bit-banged I2C on GPIO pads 10 (SDA) and 11 (SCL) and print the
+//! devices that answer, as a grid like `i2cdetect`. Both lines need an
+//! external pull-up.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay::Delay,
+    i2c::{self, BitBangI2c},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprint, sprintln};
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let sda = pads.p10.into_gpio().into_open_drain();
+    let scl = pads.p11.into_gpio().into_open_drain();
+
+    let mut delay = Delay;
+    let mut bus = BitBangI2c::new(sda, scl, &mut delay, 100_000).unwrap();
+    let found = i2c::scan(&mut bus);
+    sprint!("{}", found);
+    sprintln!("{} devices", found.count());
+
+    loop {}
+}
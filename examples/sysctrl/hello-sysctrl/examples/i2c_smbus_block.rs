@@ -0,0 +1,51 @@
+//! Read the manufacturer name of a smart battery at 0x0b with an SMBus block
+//! read with PEC, over bit-banged I2C on GPIO pads 10 (SDA) and 11 (SCL).
+//! Both lines need an external pull-up.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay::Delay,
+    i2c::{BitBangI2c, I2cError, I2cRegisterMap, Pec},
+    rt::entry,
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprint, sprintln};
+
+const BATTERY_ADDR: u8 = 0x0b;
+const CMD_MANUFACTURER_NAME: u8 = 0x20;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let sda = pads.p10.into_gpio().into_open_drain();
+    let scl = pads.p11.into_gpio().into_open_drain();
+
+    let mut delay = Delay;
+    let mut bus = BitBangI2c::new(sda, scl, &mut delay, 100_000).unwrap();
+    let mut battery = I2cRegisterMap::<_>::new(&mut bus, BATTERY_ADDR);
+    let mut name = [0u8; 20];
+    match battery.block_read(CMD_MANUFACTURER_NAME, &mut name, Pec::Enabled) {
+        Ok(len) => {
+            sprint!("manufacturer ");
+            for &c in &name[..len] {
+                sprint!("{}", c as char);
+            }
+            sprintln!();
+        }
+        Err(I2cError::AddressNack) => sprintln!("no device at {}", BATTERY_ADDR),
+        Err(I2cError::DataNack) => sprintln!("command {} refused", CMD_MANUFACTURER_NAME),
+        Err(I2cError::Pec) => sprintln!("PEC mismatch"),
+        Err(_) => sprintln!("bus error"),
+    }
+
+    loop {}
+}
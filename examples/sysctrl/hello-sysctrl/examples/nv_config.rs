@@ -0,0 +1,86 @@
+//! Save a configuration to SPI NOR flash on CS0 several times, alternating
+//! between its two copies, and load it back. Erases the two sectors at
+//! 0xff_e000. Run twice to see the configuration survive a reset.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    storage::{NvConfig, NvConfigError},
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::SpiFlash,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+/// Last two sectors
+const NV_BASE: u32 = FLASH_CAPACITY - 2 * 4096;
+const NV_VERSION: u16 = 1;
+
+/// Boot count, and three words of calibration
+type Config = [u32; 4];
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut flash = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY);
+    let mut nv = NvConfig::<Config>::new(NV_BASE, NV_VERSION);
+
+    let mut config = match nv.load(&mut flash) {
+        Ok(config) => {
+            sprintln!("loaded, boot count {}", config[0]);
+            config
+        }
+        Err(NvConfigError::NotFound) => {
+            sprintln!("no configuration yet");
+            [0, 100, 200, 300]
+        }
+        Err(_) => {
+            sprintln!("no valid copy, starting over");
+            [0, 100, 200, 300]
+        }
+    };
+
+    let mut ok = true;
+    for _ in 0..3 {
+        config[0] += 1;
+        ok &= nv.save(&mut flash, &config).is_ok();
+        ok &= nv.load(&mut flash) == Ok(config);
+    }
+    sprintln!("boot count {}, seq {}", config[0], nv.seq().unwrap_or(0));
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
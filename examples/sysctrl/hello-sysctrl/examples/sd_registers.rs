@@ -0,0 +1,74 @@
+//! Decode reference CID and CSD register dumps and compare with the expected
+//! fields.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    rt::entry,
+    sd::{Cid, Csd, CsdVersion},
+    sysctrl::soc_ctrl,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// 8 GB high capacity card
+const CSD_V2: [u8; 16] = [
+    0x40, 0x0e, 0x00, 0x32, 0x5b, 0x59, 0x00, 0x00, 0x3b, 0x37, 0x7f, 0x80, 0x02, 0x40, 0x40, 0x7b,
+];
+/// 2 GB standard capacity card with 1024-byte read blocks
+const CSD_V1: [u8; 16] = [
+    0x00, 0x26, 0x00, 0x32, 0x5f, 0x5a, 0x83, 0xbf, 0xff, 0xff, 0xff, 0x80, 0x12, 0x80, 0x40, 0xf1,
+];
+const CID: [u8; 16] = [
+    0x03, 0x53, 0x44, 0x53, 0x55, 0x30, 0x38, 0x47, 0x80, 0x12, 0x34, 0x56, 0x78, 0x00, 0xdb, 0xc1,
+];
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut ok = true;
+
+    let csd = Csd(CSD_V2);
+    ok &= csd.crc_ok();
+    ok &= csd.version() == Some(CsdVersion::V2);
+    ok &= csd.c_size() == Some(15159) && csd.c_size_mult().is_none();
+    ok &= csd.capacity_bytes() == Some(7_948_206_080);
+    ok &= csd.max_transfer_rate() == Some(25_000_000);
+    ok &= csd.ccc() == 0x5b5 && csd.read_bl_len() == 9 && csd.write_bl_len() == 9;
+    ok &= csd.sector_size() == 0x7f && csd.erase_blk_en() && csd.copy();
+
+    let csd = Csd(CSD_V1);
+    ok &= csd.crc_ok();
+    ok &= csd.version() == Some(CsdVersion::V1);
+    ok &= csd.c_size() == Some(3839) && csd.c_size_mult() == Some(7);
+    ok &= csd.read_bl_len() == 10 && csd.read_bl_partial();
+    ok &= csd.capacity_bytes() == Some(2_013_265_920);
+    ok &= csd.r2w_factor() == 4;
+
+    let cid = Cid(CID);
+    ok &= cid.crc_ok();
+    ok &= cid.manufacturer_id() == 0x03 && &cid.oem_id() == b"SD";
+    ok &= &cid.product_name() == b"SU08G" && cid.product_revision() == (8, 0);
+    ok &= cid.serial_number() == 0x1234_5678;
+    ok &= cid.manufacturing_date() == (2013, 11);
+
+    let mut corrupt = CSD_V2;
+    corrupt[5] ^= 1;
+    ok &= !Csd(corrupt).crc_ok();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,81 @@
+//! Send CAN frames through an MCP2515 on CS1 in loopback mode and check that
+//! they are received back, with and without an acceptance filter. The
+//! MCP2515 is assumed to run from an 8 MHz crystal.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_can::{CanBitrate, CanFrame, CanId, Mcp2515},
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const OSC_HZ: u32 = 8_000_000;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut can = Mcp2515::new(&mut spim, ChipSelect::Cs1, OSC_HZ);
+    can.init(CanBitrate::Kbps500).unwrap();
+    can.set_loopback(true).unwrap();
+
+    let data = [0, 1, 2, 3, 4, 5, 6, 7];
+    let mut ok = true;
+    for id in [CanId::Standard(0x123), CanId::Extended(0x1234_5678)] {
+        can.send_frame(id, &data).unwrap();
+        let frame = poll_frame(&mut can);
+        ok &= frame.is_some_and(|f| f.id == id && f.data() == data);
+    }
+
+    // Buffer 0 only takes 0x120..=0x12f, the rest goes to buffer 1, which
+    // still accepts everything
+    can.set_filter(0, CanId::Standard(0x120), CanId::Standard(0x7f0))
+        .unwrap();
+    can.set_filter(1, CanId::Standard(0x120), CanId::Standard(0x7f0))
+        .unwrap();
+    for id in [CanId::Standard(0x12a), CanId::Standard(0x200)] {
+        can.send_frame(id, &data).unwrap();
+        ok &= poll_frame(&mut can).is_some_and(|f| f.id == id);
+    }
+    sprintln!("frames matched: {}", ok as u8);
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
+
+fn poll_frame(can: &mut Mcp2515<'_, '_>) -> Option<CanFrame> {
+    (0..10_000).find_map(|_| can.receive_frame())
+}
@@ -0,0 +1,96 @@
+//! Program a page with ECC into SPI NOR flash on CS0, then clear one and two
+//! of its bits behind the ECC's back. One flipped bit is corrected on read,
+//! two are reported as uncorrectable.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::{CorrectedCount, FlashError, SpiFlash, PAGE_SIZE},
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+    testutil::fill_pattern,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+/// ECC of the first MiB, 16 KiB from here on
+const ECC_AREA: u32 = 1024 * 1024;
+const PAGE_ADDR: u32 = 0;
+
+dma_static!(PAGE: [u8; PAGE_SIZE]);
+dma_static!(READ: [u8; PAGE_SIZE]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut flash = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY);
+    flash.set_ecc_area(ECC_AREA).unwrap();
+
+    unsafe { dma::init() };
+    let (page, read) = unsafe { (PAGE.get_mut(), READ.get_mut()) };
+    fill_pattern(page, 0);
+
+    flash.erase_sector(PAGE_ADDR).unwrap();
+    flash.erase_sector(ECC_AREA).unwrap();
+    flash.write_page_with_ecc(PAGE_ADDR, page).unwrap();
+
+    let mut ok = flash.read_page_with_ecc_correction(PAGE_ADDR, read) == Ok(CorrectedCount(0));
+    ok &= read == page;
+
+    // Programming can only clear bits, so clear set ones
+    let flip = |flash: &mut SpiFlash, nth: usize| {
+        let (idx, byte) = page
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| b != 0)
+            .nth(nth)
+            .unwrap();
+        let cleared = byte & (byte - 1);
+        flash
+            .page_program(PAGE_ADDR + idx as u32, &[cleared])
+            .unwrap();
+    };
+
+    flip(&mut flash, 3);
+    let res = flash.read_page_with_ecc_correction(PAGE_ADDR, read);
+    sprintln!("one flipped bit: corrected {}", res.map_or(0, |c| c.0));
+    ok &= res == Ok(CorrectedCount(1)) && read == page;
+
+    flip(&mut flash, 100);
+    let res = flash.read_page_with_ecc_correction(PAGE_ADDR, read);
+    ok &= res == Err(FlashError::EccUncorrectable { addr: PAGE_ADDR });
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,69 @@
+//! Put SPI NOR flash on CS0 into deep power-down and check that the next
+//! access releases it without the application doing so.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::SpiFlash,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut flash = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY);
+
+    // State after reset is unknown, releasing is harmless either way
+    flash.release_power_down().unwrap();
+    let id = flash.jedec_id().unwrap();
+    sprintln!("JEDEC ID {} {} {}", id[0], id[1], id[2]);
+
+    let mut ok = id != [0; 3] && id != [0xff; 3];
+    flash.deep_power_down().unwrap();
+    ok &= flash.is_powered_down();
+    ok &= flash.jedec_id() == Ok(id);
+    ok &= !flash.is_powered_down();
+
+    // As after switching the supply back on
+    flash.mark_powered_down();
+    ok &= flash.read_status1().is_ok() && !flash.is_powered_down();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
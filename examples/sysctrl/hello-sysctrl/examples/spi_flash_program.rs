@@ -0,0 +1,124 @@
+//! Program a 256 KiB image into SPI NOR flash on CS0, first one blocking page
+//! at a time, then preparing each page while the previous one programs, and
+//! finally verifying each page as it is programmed.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::{FlashError, SpiFlash, PAGE_SIZE, SECTOR_SIZE},
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const IMAGE_LEN: u32 = 256 * 1024;
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+
+dma_static!(PAGE: [u8; PAGE_SIZE]);
+
+fn erase_image(flash: &mut SpiFlash) {
+    for addr in (0..IMAGE_LEN).step_by(SECTOR_SIZE) {
+        flash.erase_sector(addr).unwrap();
+    }
+}
+
+fn verify_image(flash: &mut SpiFlash, page: &mut [u8]) -> u32 {
+    let mut errors = 0;
+    for (idx, addr) in (0..IMAGE_LEN).step_by(PAGE_SIZE).enumerate() {
+        flash.read(addr, page).unwrap();
+        if verify_pattern(page, idx as u32).is_err() {
+            errors += 1;
+        }
+    }
+    errors
+}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut flash = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY);
+
+    unsafe { dma::init() };
+    let page = unsafe { PAGE.get_mut() };
+
+    erase_image(&mut flash);
+    let start = mcycle::read64();
+    for (idx, addr) in (0..IMAGE_LEN).step_by(PAGE_SIZE).enumerate() {
+        fill_pattern(page, idx as u32);
+        flash.page_program(addr, page).unwrap();
+    }
+    let blocking = mcycle::read64() - start;
+    sprintln!(
+        "blocking: {} cycles, {} bad pages",
+        blocking,
+        verify_image(&mut flash, page)
+    );
+
+    erase_image(&mut flash);
+    let start = mcycle::read64();
+    fill_pattern(page, 0);
+    for (idx, addr) in (0..IMAGE_LEN).step_by(PAGE_SIZE).enumerate() {
+        // The page buffer is free again once the data has been sent
+        let op = flash.begin_page_program(addr, page).unwrap();
+        fill_pattern(page, idx as u32 + 1);
+        op.wait().unwrap();
+    }
+    let split = mcycle::read64() - start;
+    sprintln!(
+        "split-phase: {} cycles, {} bad pages",
+        split,
+        verify_image(&mut flash, page)
+    );
+    sprintln!("saved {} cycles", blocking.saturating_sub(split));
+
+    erase_image(&mut flash);
+    let start = mcycle::read64();
+    for (idx, addr) in (0..IMAGE_LEN).step_by(PAGE_SIZE).enumerate() {
+        fill_pattern(page, idx as u32);
+        flash.page_program_verified(addr, page, 3).unwrap();
+    }
+    sprintln!(
+        "verified: {} cycles, {} retries",
+        mcycle::read64() - start,
+        flash.verify_retries()
+    );
+    // Page 0 holds pattern 0, not 1
+    fill_pattern(page, 1);
+    let res = flash.verify_region(0, page.iter().copied());
+    if matches!(res, Err(FlashError::VerifyFailed { addr: 0, .. })) {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL] mismatch not detected");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,97 @@
+//! Let SpimAutoTuner pick the completion mode for short and long transfers.
+//! The long transfers are only recorded, not sent, as the example has no
+//! SPIM interrupt handler to wake up the interrupt mode.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, CompletionMode, SpimAutoTuner, SpimConfig, TuneEntry},
+            Udma,
+        },
+    },
+    testutil::fill_pattern,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// Rough costs in core cycles spent waiting, SCK = core clock / 4
+static TABLE: [TuneEntry; 2] = [
+    TuneEntry {
+        mode: CompletionMode::Polling,
+        overhead_cycles: 0,
+        cycles_per_byte: 32,
+    },
+    TuneEntry {
+        mode: CompletionMode::Interrupt,
+        overhead_cycles: 400,
+        cycles_per_byte: 0,
+    },
+];
+
+const WINDOW: usize = 8;
+
+dma_static!(TX_BUF: [u8; 4]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let tx = unsafe { TX_BUF.get_mut() };
+    fill_pattern(tx, 0);
+    let mut tuner = SpimAutoTuner::<WINDOW>::new(&TABLE);
+
+    let mut ok = true;
+    for _ in 0..WINDOW {
+        ok &= spim
+            .transaction(ChipSelect::Cs0)
+            .and_then(|mut t| t.write(tx))
+            .is_ok();
+        ok &= tuner.update(&mut spim, tx.len()) == CompletionMode::Polling;
+    }
+    sprintln!("short transfers, mean {} bytes", tuner.mean_size());
+
+    for _ in 0..WINDOW {
+        tuner.update(&mut spim, 1024);
+    }
+    ok &= spim.completion_mode() == CompletionMode::Interrupt;
+    sprintln!("long transfers, mean {} bytes", tuner.mean_size());
+
+    for _ in 0..WINDOW {
+        tuner.update(&mut spim, tx.len());
+    }
+    ok &= spim.completion_mode() == CompletionMode::Polling;
+    sprintln!("mode switched {} times, expected 2", tuner.switches());
+    ok &= tuner.switches() == 2;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,73 @@
+//! Run SPIM loopback transfers with the clock gate closed between
+//! transactions. Connect MOSI to MISO.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, PowerPolicy, SpimConfig},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const GATE_CYCLES: u32 = 1000;
+
+dma_static!(TX_BUF: [u8; 64]);
+dma_static!(RX_BUF: [u8; 64]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_power(PowerPolicy::AutoGate { settle_cycles: 100 }))
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+    let mut errors = 0;
+    for seed in 0..GATE_CYCLES {
+        fill_pattern(tx, seed);
+        spim.transaction(ChipSelect::Cs0)
+            .unwrap()
+            .transfer(rx, tx)
+            .unwrap();
+
+        if let Err(e) = verify_pattern(rx, seed) {
+            sprintln!(
+                "cycle {}: first mismatch at {}, expected {:#x}, got {:#x}",
+                seed,
+                e.index,
+                e.expected,
+                e.got
+            );
+            errors += 1;
+        }
+    }
+    sprintln!("{} gate cycles, {} errors", GATE_CYCLES, errors);
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,66 @@
+//! Find the fastest SPI clock the NOR flash on CS0 reads its JEDEC ID
+//! reliably at, starting from a slow divider.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::SpiFlash,
+        udma::{
+            spim::{CalibrationTest, ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const PERIPH_HZ: u32 = 30_000_000;
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+const START_CLK_DIV: u8 = 0x20;
+const PASSES: u32 = 64;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_clk_div(START_CLK_DIV))
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    let id = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY)
+        .jedec_id()
+        .unwrap();
+    let test = CalibrationTest::ReadId {
+        cs: ChipSelect::Cs0,
+        expected: id,
+    };
+    match spim.calibrate(PERIPH_HZ, test, PASSES) {
+        Ok(sck_hz) => sprintln!(
+            "[PASS] SCK {} Hz, clk_div {}",
+            sck_hz,
+            spim.config().clk_div
+        ),
+        Err(_) => sprintln!("[FAIL] no reliable clock divider"),
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
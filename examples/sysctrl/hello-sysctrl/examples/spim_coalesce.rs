@@ -0,0 +1,96 @@
+//! Send a display-style init sequence of 48 register writes on CS0, each in
+//! its own chip select window, once window by window and once through a
+//! `SpimCoalescer`. The coalesced run must take fewer cycles.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::ptr::addr_of_mut;
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimCoalescer, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const WRITES: usize = 48;
+/// Arguments per register write, after the register byte
+const ARGS: usize = 3;
+
+dma_static!(WINDOW: [u8; 1 + ARGS]);
+
+static mut COALESCER: SpimCoalescer<256> = SpimCoalescer::new();
+
+fn write_for(i: usize) -> [u8; 1 + ARGS] {
+    let i = i as u8;
+    [0x80 | i, i, i ^ 0xff, 0x5a]
+}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    unsafe { dma::init() };
+    let window = unsafe { WINDOW.get_mut() };
+
+    let start = mcycle::read64();
+    for i in 0..WRITES {
+        *window = write_for(i);
+        let mut t = spim.transaction(ChipSelect::Cs0).unwrap();
+        t.write(window).unwrap();
+    }
+    let plain = mcycle::read64() - start;
+
+    let c = unsafe { &mut *addr_of_mut!(COALESCER) };
+    let start = mcycle::read64();
+    let mut ok = true;
+    for i in 0..WRITES {
+        let w = write_for(i);
+        ok &= c.begin(&mut spim, ChipSelect::Cs0).is_ok();
+        ok &= c.write(&mut spim, &w[..1]).is_ok();
+        ok &= c.write(&mut spim, &w[1..]).is_ok();
+        ok &= c.end().is_ok();
+    }
+    ok &= c.flush(&mut spim).is_ok();
+    let coalesced = mcycle::read64() - start;
+
+    sprintln!(
+        "window by window {} cycles, coalesced {} cycles",
+        plain,
+        coalesced
+    );
+    ok &= coalesced < plain && spim.asserted_cs().is_none();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,64 @@
+//! Compare SpiCommandBuilder and CommandBuf output with hand-encoded command
+//! words.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::spim::{CommandBuf, SpiCommandBuilder as Cmd, UcChannel},
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let cases = [
+        ("tx_data", Cmd::tx_data(4, 8), 0x6007_0003),
+        ("rx_data", Cmd::rx_data(3, 8), 0x7007_0002),
+        ("full_duplex", Cmd::full_duplex(1, 32), 0xc01f_0000),
+        ("send_cmd", Cmd::send_cmd(0x9f), 0x2007_009f),
+        (
+            "setup_uca",
+            Cmd::setup_uca(UcChannel::Rx, 0x1c00_1000),
+            0xd800_1000,
+        ),
+        ("setup_ucs", Cmd::setup_ucs(UcChannel::Tx, 256), 0xe000_0100),
+    ];
+
+    let mut ok = true;
+    for (name, got, expected) in cases {
+        if got != expected {
+            sprintln!("{}: {:#x} != {:#x}", name, got, expected);
+            ok = false;
+        }
+    }
+
+    let mut buf = CommandBuf::<3>::new();
+    buf.push_word(Cmd::send_cmd(0x9f))
+        .push_cmd(1 << 28, 2)
+        .push_word(Cmd::rx_data(3, 8));
+    ok &= buf.as_words() == [0x2007_009f, 0x1000_0002, 0x7007_0002];
+    ok &= buf.as_bytes().len() == 12 && buf.as_bytes()[..4] == 0x2007_009f_u32.to_ne_bytes();
+    buf.clear();
+    ok &= buf.is_empty() && buf.as_bytes().is_empty();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,73 @@
+//! Pulse pad 9 at the end of each SPIM transfer. Watch pad 9 and SCK on a
+//! scope, triggering on the rising edge of pad 9.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+    testutil::fill_pattern,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// About 10 us at 30 MHz
+const PULSE_CYCLES: u16 = 300;
+
+dma_static!(TX_BUF: [u8; 64]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let mut trigger = pads.p9.into_gpio().into_output();
+    trigger.set_low();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    spim.set_completion_gpio_pulse(9, PULSE_CYCLES);
+
+    unsafe { dma::init() };
+    let tx = unsafe { TX_BUF.get_mut() };
+    fill_pattern(tx, 0);
+    let mut ok = true;
+    for _ in 0..10 {
+        ok &= spim
+            .transaction(ChipSelect::Cs0)
+            .and_then(|mut t| t.write(tx))
+            .is_ok();
+    }
+    spim.clear_completion_gpio_pulse();
+    sprintln!("sent 10 transfers, expect 10 pulses on pad 9");
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
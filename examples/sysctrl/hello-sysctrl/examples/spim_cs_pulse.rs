@@ -0,0 +1,60 @@
+//! Read a conversion from an SPI ADC that latches its command on the rising
+//! edge of CS and returns the result in a separate CS window.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln, NOPS_PER_SEC};
+
+/// Start a conversion on channel 0
+const CMD_CONVERT_CH0: u8 = 0x80;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    loop {
+        let mut result = [0u8; 2];
+        {
+            let mut t = spim.transaction(ChipSelect::Cs0).unwrap();
+            t.write(&[CMD_CONVERT_CH0]).unwrap();
+            t.pulse_cs();
+            t.read(&mut result).unwrap();
+        }
+        sprintln!("ch0: {}", u16::from_be_bytes(result));
+
+        for _ in 0..NOPS_PER_SEC {
+            unsafe { asm!("nop") };
+        }
+    }
+}
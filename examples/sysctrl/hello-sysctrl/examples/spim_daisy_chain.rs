@@ -0,0 +1,101 @@
+//! Scroll a column across a 32x8 LED matrix made of four daisy-chained
+//! MAX7219 drivers on CS0, and check the chain by reading the frame back from
+//! its far end.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::arch::asm;
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, DaisyChain, SpimConfig, UdmaSpim},
+            Enabled, Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln, NOPS_PER_SEC};
+
+const DEVICES: usize = 4;
+
+const REG_DIGIT0: u8 = 0x01;
+const REG_DECODE_MODE: u8 = 0x09;
+const REG_INTENSITY: u8 = 0x0a;
+const REG_SCAN_LIMIT: u8 = 0x0b;
+const REG_SHUTDOWN: u8 = 0x0c;
+const REG_DISPLAY_TEST: u8 = 0x0f;
+
+// Register address and data per device
+dma_static!(FRAME: [u8; DEVICES * 2]);
+dma_static!(READ_BACK: [u8; DEVICES * 2]);
+
+/// Write `data` to register `reg` of every device
+fn write_all(chain: &mut DaisyChain<DEVICES>, spim: &mut UdmaSpim<'_, Enabled>, reg: u8, data: u8) {
+    for idx in 0..DEVICES {
+        chain.device_mut(idx).copy_from_slice(&[reg, data]);
+    }
+    chain.commit(spim).unwrap();
+}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let mut chain =
+        DaisyChain::<DEVICES>::new(ChipSelect::Cs0, unsafe { FRAME.get_mut() }).unwrap();
+    let read_back = unsafe { READ_BACK.get_mut() };
+
+    write_all(&mut chain, &mut spim, REG_DISPLAY_TEST, 0);
+    write_all(&mut chain, &mut spim, REG_DECODE_MODE, 0);
+    write_all(&mut chain, &mut spim, REG_SCAN_LIMIT, 7);
+    write_all(&mut chain, &mut spim, REG_INTENSITY, 4);
+    write_all(&mut chain, &mut spim, REG_SHUTDOWN, 1);
+
+    match chain.verify(&mut spim, read_back) {
+        Ok(true) => sprintln!("[PASS] chain read back"),
+        Ok(false) => sprintln!("[FAIL] chain read back mismatch"),
+        Err(_) => sprintln!("[FAIL] chain read back error"),
+    }
+
+    let mut col = 0;
+    loop {
+        // Each device drives eight columns. All devices are rewritten on
+        // every update, the ones without the lit column with blank digits.
+        for digit in 0..8u8 {
+            for idx in 0..DEVICES {
+                let lit = col / 8 == idx && col % 8 == digit as usize;
+                chain
+                    .device_mut(idx)
+                    .copy_from_slice(&[REG_DIGIT0 + digit, if lit { 0xff } else { 0 }]);
+            }
+            chain.commit(&mut spim).unwrap();
+        }
+        col = (col + 1) % (8 * DEVICES);
+
+        for _ in 0..NOPS_PER_SEC / 16 {
+            unsafe { asm!("nop") };
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Disable the SPIM in the middle of a background transfer, re-enable it and
+//! check that a loopback transfer still works. Connect MOSI to MISO.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, DisablePolicy, SpimConfig, SpimQueue, SpimTransfer},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const LEN: usize = 1021;
+
+dma_static!(BACKGROUND: [u8; 4096]);
+dma_static!(TX_BUF: [u8; LEN]);
+dma_static!(RX_BUF: [u8; LEN]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    // Slow clock, so the background transfer is still running when aborted
+    let cfg = SpimConfig::default().with_clk_div(0xff);
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(cfg)
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let mut queue = SpimQueue::<1>::new();
+    queue
+        .push(SpimTransfer {
+            cs: ChipSelect::Cs1,
+            tx: unsafe { BACKGROUND.get_mut() },
+            rx: None,
+            priority: 0,
+        })
+        .map_err(|(_, e)| e)
+        .unwrap();
+    queue.pump(&mut spim);
+
+    let (spim, aborted) = spim.disable(DisablePolicy::Abort);
+    sprintln!("aborted {} transfers", aborted);
+
+    let mut spim = spim
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+    fill_pattern(tx, 0);
+    spim.transaction(ChipSelect::Cs0)
+        .unwrap()
+        .transfer(rx, tx)
+        .unwrap();
+
+    match (aborted, verify_pattern(rx, 0)) {
+        (1.., Ok(())) => sprintln!("[PASS]"),
+        (0, _) => sprintln!("[FAIL] background transfer finished before disable"),
+        (_, Err(e)) => sprintln!("[FAIL] first mismatch at {}", e.index),
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,77 @@
+//! Recover from a uDMA fault reported in the middle of a chip select window.
+//! The fault is reported by the example itself, in place of a watchdog or
+//! interrupt handler.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, CsErrorAction, DmaFaultCode, DmaFaultHandler, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+dma_static!(TX_BUF: [u8; 4]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut ok = true;
+
+    // Keep: the fault is only recorded
+    spim.sot(ChipSelect::Cs0).unwrap();
+    DmaFaultHandler::handle(DmaFaultCode::Rx);
+    ok &= spim.take_dma_fault() == Some(DmaFaultCode::Rx);
+    ok &= spim.asserted_cs() == Some(ChipSelect::Cs0);
+    spim.eot().unwrap();
+
+    // Deassert: the SPIM is reset and the window forgotten
+    spim.set_cs_on_dma_error(CsErrorAction::Deassert);
+    spim.sot(ChipSelect::Cs0).unwrap();
+    DmaFaultHandler::handle(DmaFaultCode::Tx);
+    ok &= spim.take_dma_fault() == Some(DmaFaultCode::Tx);
+    ok &= spim.asserted_cs().is_none();
+    ok &= spim.take_dma_fault().is_none();
+
+    // The SPIM works again after the reset
+    unsafe { dma::init() };
+    let tx = unsafe { TX_BUF.get_mut() };
+    let res = spim
+        .transaction(ChipSelect::Cs0)
+        .and_then(|mut t| t.write(tx));
+    sprintln!("transfer after reset ok: {}", res.is_ok() as u8);
+    ok &= res.is_ok();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
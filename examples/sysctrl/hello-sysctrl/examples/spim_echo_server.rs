@@ -0,0 +1,118 @@
+//! SPIM echo server for soak testing. Connect MOSI to MISO.
+//!
+//! Each round receives a block of test data and echoes it back verbatim
+//! through the double-buffered stream writer. Throughput and errors are
+//! printed once per second.
+//!
+//! With `-Fvp`, the example instead runs `ROUNDS` short rounds against the
+//! stub SPI device of `scripts/robot/spim_echo_server.robot`, which only
+//! returns the bytes the test queues. The address pattern is used there, as
+//! the test can reproduce it, and the result is printed as [PASS] or [FAIL].
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+#[cfg(feature = "vp")]
+use headsail_bsp::testutil::{
+    fill_address_pattern as fill_pattern, verify_address_pattern as verify_pattern,
+};
+#[cfg(not(feature = "vp"))]
+use headsail_bsp::testutil::{fill_pattern, verify_pattern};
+use headsail_bsp::{
+    delay, dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig, SpimStreamWriter},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+#[cfg(not(feature = "vp"))]
+const BLOCK_LEN: usize = 1024;
+#[cfg(feature = "vp")]
+const BLOCK_LEN: usize = 8;
+/// Rounds to run on the VP
+#[cfg(feature = "vp")]
+const ROUNDS: u32 = 2;
+const STREAM_BUF_LEN: usize = 128;
+
+dma_static!(TX_BUF: [u8; BLOCK_LEN]);
+dma_static!(RX_BUF: [u8; BLOCK_LEN]);
+dma_static!(STREAM_A: [u8; STREAM_BUF_LEN]);
+dma_static!(STREAM_B: [u8; STREAM_BUF_LEN]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+
+    let cycles_per_report = delay::core_hz() as u64;
+    let mut last_report = mcycle::read64();
+    let (mut bytes, mut errors) = (0u32, 0u32);
+    let mut seed = 0u32;
+    loop {
+        // Receive
+        fill_pattern(tx, seed);
+        spim.transaction(ChipSelect::Cs0)
+            .unwrap()
+            .transfer(rx, tx)
+            .unwrap();
+        if verify_pattern(rx, seed).is_err() {
+            errors += 1;
+        }
+
+        // Echo
+        let bufs = unsafe { [STREAM_A.get_mut(), STREAM_B.get_mut()] };
+        let t = spim.transaction(ChipSelect::Cs0).unwrap();
+        let mut w = SpimStreamWriter::new(t, bufs).unwrap();
+        w.write(&rx[..]).unwrap();
+        w.flush().unwrap();
+        drop(w);
+        bytes += 2 * BLOCK_LEN as u32;
+
+        let now = mcycle::read64();
+        if now - last_report >= cycles_per_report {
+            let elapsed_ms = (now - last_report) * 1000 / cycles_per_report;
+            let bytes_per_s = bytes as u64 * 1000 / elapsed_ms.max(1);
+            sprintln!("{} B/s, {} errors", bytes_per_s, errors);
+            (bytes, errors, last_report) = (0, 0, now);
+        }
+        seed = seed.wrapping_add(1);
+
+        #[cfg(feature = "vp")]
+        if seed == ROUNDS {
+            if errors == 0 {
+                sprintln!("[PASS]");
+            } else {
+                sprintln!("[FAIL] {} errors", errors);
+            }
+            loop {
+                unsafe { core::arch::asm!("wfi") };
+            }
+        }
+    }
+}
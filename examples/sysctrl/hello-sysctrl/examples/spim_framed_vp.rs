@@ -0,0 +1,68 @@
+//! Send four 2-byte frames on CS0 with `send_framed`, once in one
+//! command buffer and once with a CS hold between frames, and check that the
+//! held run took at least the hold between each pair of frames. Counterpart
+//! of `scripts/robot/spim_framed_vp.robot`, which checks the bytes on a stub
+//! SPI device in the VP.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FRAME_LEN: usize = 2;
+const FRAMES: [u8; 4 * FRAME_LEN] = [0x10, 0x11, 0x20, 0x21, 0x30, 0x31, 0x40, 0x41];
+/// CS deassert time between frames
+const HOLD_CYCLES: u32 = 2_000;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    let data = FRAMES;
+    spim.send_framed(ChipSelect::Cs0, &data, FRAME_LEN).unwrap();
+
+    spim.set_cs_hold_cycles(HOLD_CYCLES);
+    let start = mcycle::read64();
+    spim.send_framed(ChipSelect::Cs0, &data, FRAME_LEN).unwrap();
+    let elapsed = mcycle::read64() - start;
+
+    // The first frame may go out at once, each later one waits out the hold
+    let min = (data.len() / FRAME_LEN - 1) as u64 * HOLD_CYCLES as u64;
+    if elapsed >= min {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL] {} cycles, expected at least {}", elapsed, min);
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
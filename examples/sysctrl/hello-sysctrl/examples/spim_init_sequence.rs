@@ -0,0 +1,101 @@
+//! Run an init sequence cooperatively and blocking. Connect MOSI to MISO, so
+//! register reads return the TX idle byte.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{
+                init::InitError,
+                regmap::{AddressFormat, SpiRegisterMap},
+                ChipSelect, InitRunner, InitStatus, InitStep, SpimConfig,
+            },
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const IDLE: u8 = 0x5a;
+
+const GOOD: &[InitStep] = &[
+    InitStep::WriteReg(0x7e, 0xb6),
+    InitStep::DelayMs(5),
+    InitStep::PollUntil {
+        reg: 0x03,
+        mask: 0xf0,
+        val: IDLE & 0xf0,
+        timeout_ms: 10,
+    },
+    InitStep::Write(&[0x40, 0x28, 0x11]),
+];
+
+const BAD: &[InitStep] = &[
+    InitStep::Write(&[0x01]),
+    InitStep::PollUntil {
+        reg: 0x03,
+        mask: 0xff,
+        val: !IDLE,
+        timeout_ms: 2,
+    },
+    InitStep::Write(&[0x02]),
+];
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_tx_idle_byte(Some(IDLE)))
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut regs = SpiRegisterMap::new(&mut spim, ChipSelect::Cs0, AddressFormat::default());
+    let mut ok = true;
+
+    let mut init = InitRunner::new(GOOD);
+    let mut polls = 0;
+    let status = loop {
+        polls += 1;
+        match init.poll(&mut regs) {
+            InitStatus::Pending => {}
+            status => break status,
+        }
+    };
+    sprintln!("cooperative run took {} polls", polls);
+    // The delay takes more than one poll
+    ok &= status == InitStatus::Done && polls > GOOD.len();
+
+    let mut init = InitRunner::new(BAD);
+    let res = init.run_blocking(&mut regs);
+    ok &= res == Err((1, InitError::Timeout));
+    ok &= init.step() == 1;
+    init.restart();
+    ok &= init.step() == 0;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,143 @@
+//! Measure how long a control write on CS0 waits behind bulk writes on CS1,
+//! with and without segmenting the bulk transfers. Polls instead of using
+//! the SPIM interrupt.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, QueueEvent, SpimConfig, SpimQueue, SpimTransfer},
+            Enabled, Udma, UdmaSpim,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const BULK_TRANSFERS: usize = 3;
+const SEGMENT_LEN: usize = 512;
+
+dma_static!(BULK: [u8; 8192]);
+dma_static!(CTRL: [u8; 4]);
+
+/// Returns the cycles from pushing the control write to its completion, and
+/// the order in which the control and bulk transfers completed
+fn run(
+    spim: &mut UdmaSpim<'_, Enabled>,
+    segment_len: Option<usize>,
+    bulk: &'static [u8],
+    ctrl: &'static [u8],
+) -> (u32, [Option<ChipSelect>; BULK_TRANSFERS + 1]) {
+    let mut queue = SpimQueue::<{ BULK_TRANSFERS + 1 }>::new();
+    queue.set_segment_len(segment_len);
+    for _ in 0..BULK_TRANSFERS {
+        queue
+            .push(SpimTransfer {
+                cs: ChipSelect::Cs1,
+                tx: bulk,
+                rx: None,
+                priority: 0,
+            })
+            .unwrap();
+    }
+    queue.pump(spim);
+
+    // Full bulk load from here on
+    let start = mcycle::read64();
+    queue
+        .push(SpimTransfer {
+            cs: ChipSelect::Cs0,
+            tx: ctrl,
+            rx: None,
+            priority: 10,
+        })
+        .unwrap();
+
+    let mut order = [None; BULK_TRANSFERS + 1];
+    let (mut done, mut latency) = (0, 0);
+    loop {
+        let completed = match queue.pump(spim) {
+            QueueEvent::Busy => continue,
+            QueueEvent::Dispatched { completed } => completed,
+            QueueEvent::Empty { completed } => match completed {
+                Some(t) => Some(t),
+                None => break,
+            },
+        };
+        if let Some(t) = completed {
+            if t.cs == ChipSelect::Cs0 {
+                latency = (mcycle::read64() - start) as u32;
+            }
+            order[done] = Some(t.cs);
+            done += 1;
+        }
+    }
+    (latency, order)
+}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (bulk, ctrl) = unsafe { (BULK.get_mut(), CTRL.get_mut()) };
+    bulk.fill(0x55);
+    ctrl.copy_from_slice(&[0x80, 0x01, 0x02, 0x03]);
+    let (bulk, ctrl): (&'static [u8], &'static [u8]) = (bulk, ctrl);
+
+    let (serial, serial_order) = run(&mut spim, None, bulk, ctrl);
+    let (interleaved, interleaved_order) = run(&mut spim, Some(SEGMENT_LEN), bulk, ctrl);
+    sprintln!(
+        "control latency under bulk load: {} cycles serialized, {} cycles interleaved",
+        serial,
+        interleaved
+    );
+
+    // Serialized, the control write waits for the bulk transfer in flight.
+    // Interleaved, for one segment of it.
+    let expected = [
+        ChipSelect::Cs1,
+        ChipSelect::Cs0,
+        ChipSelect::Cs1,
+        ChipSelect::Cs1,
+    ]
+    .map(Some);
+    let in_between = [
+        ChipSelect::Cs0,
+        ChipSelect::Cs1,
+        ChipSelect::Cs1,
+        ChipSelect::Cs1,
+    ]
+    .map(Some);
+    if serial_order == expected && interleaved_order == in_between && interleaved < serial {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,72 @@
+//! SPIM self-test. Connect MOSI to MISO.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// Awkward sizes to catch off-by-one errors in length encoding
+const LENS: [usize; 5] = [1, 3, 4, 255, 1021];
+
+dma_static!(TX_BUF: [u8; 1021]);
+dma_static!(RX_BUF: [u8; 1021]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+    for (seed, len) in LENS.into_iter().enumerate() {
+        let (tx, rx) = (&mut tx[..len], &mut rx[..len]);
+        fill_pattern(tx, seed as u32);
+        spim.transaction(ChipSelect::Cs0)
+            .unwrap()
+            .transfer(rx, tx)
+            .unwrap();
+
+        match verify_pattern(rx, seed as u32) {
+            Ok(()) => sprintln!("len {}: [ok]", len),
+            Err(e) => sprintln!(
+                "len {}: first mismatch at {}, expected {:#x}, got {:#x}",
+                len,
+                e.index,
+                e.expected,
+                e.got
+            ),
+        }
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
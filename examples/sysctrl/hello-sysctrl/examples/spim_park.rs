@@ -0,0 +1,79 @@
+//! Park the SPIM pads low, then high-Z, and check that transfers are refused
+//! while parked and work again after unparking. Connect MOSI to MISO.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, PinState, PowerPolicy, SpimConfig, SpimError},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+/// Pads routed to the SPIM on the board under test, adjust as needed
+const SPIM_PADS: u32 = 0b1111 << 4;
+/// Time to take a measurement in each parked state
+const PARK_US: u32 = 1_000_000;
+
+dma_static!(TX_BUF: [u8; 16]);
+dma_static!(RX_BUF: [u8; 16]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_power(PowerPolicy::AutoGate { settle_cycles: 100 }))
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+    let mut ok = true;
+    for (seed, state) in [PinState::Low, PinState::HighZ].into_iter().enumerate() {
+        spim.park(SPIM_PADS, state).unwrap();
+        sprintln!("parked {}", seed);
+        ok &= spim.transaction(ChipSelect::Cs0).err() == Some(SpimError::Parked);
+        ok &= spim.park(SPIM_PADS, state) == Err(SpimError::Parked);
+        delay::micros(PARK_US);
+        ok &= spim.unpark();
+        ok &= !spim.unpark();
+
+        fill_pattern(tx, seed as u32);
+        spim.transaction(ChipSelect::Cs0)
+            .unwrap()
+            .transfer(rx, tx)
+            .unwrap();
+        ok &= verify_pattern(rx, seed as u32).is_ok();
+    }
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
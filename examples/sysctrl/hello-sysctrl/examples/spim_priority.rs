@@ -0,0 +1,130 @@
+//! Share the SPIM between a sensor on CS0 that must be read every
+//! millisecond and bulk writes on CS1 that would hold the bus for longer.
+//! The bulk device is split into 512-byte chip select windows, and the
+//! sensor has the higher priority, so it must never wait longer than its
+//! period. The bulk throughput is measured alone first, and must not drop
+//! below half of that with the sensor. Polls instead of using the SPIM
+//! interrupt.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, DeviceTransfer, SpimConfig, SpimDevice, SpimRoundRobin},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const SAMPLES: u32 = 200;
+const PERIOD_US: u32 = 1_000;
+const BULK_LEN: usize = 2048;
+/// Bulk transfers to time without the sensor
+const BASELINE_TRANSFERS: u32 = 16;
+
+dma_static!(SENSOR_TX: [u8; 4]);
+dma_static!(SENSOR_RX: [u8; 4]);
+dma_static!(BULK_TX: [u8; BULK_LEN]);
+
+static SENSOR: SpimDevice = SpimDevice::new(ChipSelect::Cs0, SpimConfig::DEFAULT).with_priority(1);
+static BULK: SpimDevice = SpimDevice::new(ChipSelect::Cs1, SpimConfig::DEFAULT).with_max_chunk(512);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (sensor_tx, sensor_rx, bulk_tx) =
+        unsafe { (SENSOR_TX.get_mut(), SENSOR_RX.get_mut(), BULK_TX.get_mut()) };
+    sensor_tx.copy_from_slice(&[0x80, 0, 0, 0]);
+    bulk_tx.fill(0x5a);
+    BULK.request(DeviceTransfer {
+        tx: bulk_tx,
+        rx: None,
+    })
+    .unwrap();
+    let mut sensor_bufs: Option<(&'static [u8], &'static mut [u8])> = Some((sensor_tx, sensor_rx));
+
+    let mut rr = SpimRoundRobin::new([&SENSOR, &BULK]);
+    let start = mcycle::read64();
+    let mut transfers = 0;
+    while transfers < BASELINE_TRANSFERS {
+        rr.poll(&mut spim);
+        if let Some(t) = BULK.take_completed() {
+            transfers += 1;
+            BULK.request(t).unwrap();
+        }
+    }
+    let baseline = bytes_per_s(BASELINE_TRANSFERS as u64 * BULK_LEN as u64, start);
+    rr.reset_stats();
+
+    let period = delay::us_to_cycles(PERIOD_US);
+    let (mut samples, mut bulk_bytes) = (0, 0u64);
+    let start = mcycle::read64();
+    let mut next_sample = start;
+    while samples < SAMPLES {
+        rr.poll(&mut spim);
+
+        if mcycle::read64() >= next_sample {
+            if let Some((tx, rx)) = sensor_bufs.take() {
+                SENSOR.request(DeviceTransfer { tx, rx: Some(rx) }).unwrap();
+                next_sample += period;
+            }
+        }
+        if let Some(t) = SENSOR.take_completed() {
+            samples += 1;
+            sensor_bufs = Some((t.tx, t.rx.unwrap()));
+        }
+        if let Some(t) = BULK.take_completed() {
+            bulk_bytes += t.tx.len() as u64;
+            BULK.request(t).unwrap();
+        }
+    }
+    let bulk = bytes_per_s(bulk_bytes, start);
+
+    let [sensor_wait, bulk_wait] = rr.max_wait_cycles();
+    sprintln!(
+        "sensor max wait {} cycles, bulk max wait {} cycles",
+        sensor_wait,
+        bulk_wait
+    );
+    sprintln!("bulk {} B/s, {} B/s alone", bulk, baseline);
+
+    if sensor_wait <= period && 2 * bulk >= baseline {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
+
+fn bytes_per_s(bytes: u64, start: u64) -> u64 {
+    let elapsed_us = (mcycle::read64() - start) * 1_000_000 / delay::core_hz() as u64;
+    bytes * 1_000_000 / elapsed_us.max(1)
+}
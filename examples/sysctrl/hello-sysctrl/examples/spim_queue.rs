@@ -0,0 +1,98 @@
+//! Queue low-priority log writes on CS1 and urgent sensor reads on CS0, and
+//! check that the reads are dispatched first. Polls instead of using the SPIM
+//! interrupt.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, QueueEvent, SpimConfig, SpimQueue, SpimTransfer},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+dma_static!(LOG: [u8; 64]);
+dma_static!(CMD: [u8; 4]);
+dma_static!(SAMPLE: [u8; 4]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (log, cmd, sample) = unsafe { (LOG.get_mut(), CMD.get_mut(), SAMPLE.get_mut()) };
+    log.fill(b'.');
+    cmd.copy_from_slice(&[0x80, 0, 0, 0]);
+
+    let (log, cmd): (&'static [u8], &'static [u8]) = (log, cmd);
+    let mut queue = SpimQueue::<4>::new();
+    for _ in 0..2 {
+        queue
+            .push(SpimTransfer {
+                cs: ChipSelect::Cs1,
+                tx: log,
+                rx: None,
+                priority: 0,
+            })
+            .unwrap();
+    }
+    queue
+        .push(SpimTransfer {
+            cs: ChipSelect::Cs0,
+            tx: cmd,
+            rx: Some(sample),
+            priority: 10,
+        })
+        .unwrap();
+
+    let mut order = [None; 3];
+    let mut done = 0;
+    loop {
+        let completed = match queue.pump(&mut spim) {
+            QueueEvent::Busy => continue,
+            QueueEvent::Dispatched { completed } => completed,
+            QueueEvent::Empty { completed } => {
+                if completed.is_none() {
+                    break;
+                }
+                completed
+            }
+        };
+        if let Some(t) = completed {
+            order[done] = Some(t.cs);
+            done += 1;
+        }
+    }
+
+    let expected = [ChipSelect::Cs0, ChipSelect::Cs1, ChipSelect::Cs1].map(Some);
+    if order == expected {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL] urgent transfer was not dispatched first");
+    }
+    loop {}
+}
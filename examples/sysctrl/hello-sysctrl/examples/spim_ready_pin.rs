@@ -0,0 +1,77 @@
+//! Send a frame to a device that drives pad 9 low when ready for the next
+//! byte. Without a device, tie pad 9 low to pass, or high to see the
+//! timeout.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig, SpimError},
+            Udma,
+        },
+    },
+    testutil::fill_pattern,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const READY_TIMEOUT_US: u32 = 1_000;
+
+dma_static!(TX_BUF: [u8; 16]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let pads = unsafe { soc_ctrl::Pads::steal() };
+    let ready = pads.p9.into_gpio().into_input();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let tx = unsafe { TX_BUF.get_mut() };
+    fill_pattern(tx, 0);
+    let ok = match spim.send_with_ready_check(ChipSelect::Cs0, tx, &ready, false, READY_TIMEOUT_US)
+    {
+        Ok(()) => {
+            sprintln!("sent {} bytes", tx.len());
+            true
+        }
+        Err(SpimError::NotReady { sent }) => {
+            sprintln!("device not ready after {} bytes", sent);
+            false
+        }
+        Err(_) => {
+            sprintln!("SPIM error");
+            false
+        }
+    };
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
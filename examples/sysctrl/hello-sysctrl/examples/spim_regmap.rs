@@ -0,0 +1,68 @@
+//! Access byte registers over the SPIM. Connect MOSI to MISO, so register
+//! reads return the TX idle byte.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{
+                regmap::{AddressFormat, SpiRegisterMap},
+                ChipSelect, SpimConfig,
+            },
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const IDLE: u8 = 0x5a;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_tx_idle_byte(Some(IDLE)))
+        .map_err(|(_, e)| e)
+        .unwrap();
+    let mut regs = SpiRegisterMap::new(&mut spim, ChipSelect::Cs0, AddressFormat::default());
+    let mut ok = true;
+
+    let id = regs.read_reg(0x50).unwrap();
+    sprintln!("read_reg: {}", id);
+    ok &= id == IDLE;
+
+    regs.write_reg(0x74, 0x27).unwrap();
+    regs.modify_reg(0x74, |v| v & 0x0f).unwrap();
+
+    let mut sample = [0u8; 6];
+    regs.burst_read_regs(0x77, &mut sample).unwrap();
+    ok &= sample.iter().all(|&b| b == IDLE);
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
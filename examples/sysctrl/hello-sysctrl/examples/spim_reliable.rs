@@ -0,0 +1,75 @@
+//! Write CRC-checked frames from the fastest SCK to an FPGA test endpoint
+//! on CS0 that echoes the CRC of what it received. Frames that fail the
+//! check are retried at lower clocks. Every frame must get through, and the
+//! divider it settles on is printed.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig, SpimReliableTransfer},
+            Udma,
+        },
+    },
+    testutil,
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FRAMES: u32 = 64;
+const RETRIES: u8 = 8;
+
+dma_static!(FRAME: [u8; 128]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_clk_div(0))
+        .map_err(|(_, e)| e)
+        .unwrap();
+    unsafe { dma::init() };
+    let frame = unsafe { FRAME.get_mut() };
+
+    let mut link = SpimReliableTransfer::new(&mut spim, RETRIES);
+    let mut ok = true;
+    for seed in 0..FRAMES {
+        testutil::fill_pattern(frame, seed);
+        if link.write(ChipSelect::Cs0, frame).is_err() {
+            sprintln!("frame {} failed at clk_div {}", seed, link.clk_div());
+            ok = false;
+            break;
+        }
+    }
+    sprintln!(
+        "clk_div {} after {} slowdowns",
+        link.clk_div(),
+        link.slowdowns()
+    );
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,124 @@
+//! Inject DMA faults into transfers of a device with a retry policy. A
+//! repeatable read must be retried and complete, and a write must fail
+//! without a retry. Counterpart of `scripts/robot/spim_retry.robot`, which
+//! runs it against a stub SPI device in the VP.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{
+                ChipSelect, DeviceTransfer, DmaFaultCode, DmaFaultHandler, RetryPolicy, SpimConfig,
+                SpimDevice, SpimRoundRobin, UdmaSpim,
+            },
+            Enabled, Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const POLICY: RetryPolicy = RetryPolicy {
+    timeout_cycles: 100_000,
+    attempts: 2,
+    backoff_cycles: 1_000,
+};
+
+dma_static!(CMD: [u8; 4]);
+dma_static!(REPLY: [u8; 4]);
+
+static DEVICE: SpimDevice =
+    SpimDevice::new(ChipSelect::Cs0, SpimConfig::DEFAULT).with_retry(POLICY);
+
+type Outcome = Result<DeviceTransfer, (DeviceTransfer, DmaFaultCode)>;
+
+/// Start the requested transfer, fault it with `code` and poll until it is
+/// done
+fn fault_once(
+    rr: &mut SpimRoundRobin<'_, 1>,
+    spim: &mut UdmaSpim<'_, Enabled>,
+    code: DmaFaultCode,
+) -> Outcome {
+    rr.poll(spim);
+    DmaFaultHandler::handle(code);
+    loop {
+        rr.poll(spim);
+        if let Some(t) = DEVICE.take_completed() {
+            return Ok(t);
+        }
+        if let Some(failed) = DEVICE.take_failed() {
+            return Err(failed);
+        }
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (cmd, reply) = unsafe { (CMD.get_mut(), REPLY.get_mut()) };
+    cmd.copy_from_slice(&[0x05, 0, 0, 0]);
+    let mut rr = SpimRoundRobin::new([&DEVICE]);
+    let mut ok = true;
+
+    // A status read is safe to repeat
+    DEVICE
+        .request_repeatable(DeviceTransfer {
+            tx: cmd,
+            rx: Some(reply),
+        })
+        .unwrap();
+    let t = match fault_once(&mut rr, &mut spim, DmaFaultCode::Rx) {
+        Ok(t) => t,
+        Err((t, code)) => {
+            sprintln!("read failed with fault {}", code as u8);
+            ok = false;
+            t
+        }
+    };
+    ok &= rr.retries() == [1];
+
+    // A write is not, unless requested as repeatable
+    DEVICE
+        .request(DeviceTransfer { tx: t.tx, rx: None })
+        .unwrap();
+    match fault_once(&mut rr, &mut spim, DmaFaultCode::Tx) {
+        Ok(_) => {
+            sprintln!("write was retried");
+            ok = false;
+        }
+        Err((_, code)) => ok &= code == DmaFaultCode::Tx,
+    }
+    ok &= rr.retries() == [1];
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
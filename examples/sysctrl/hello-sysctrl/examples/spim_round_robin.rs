@@ -0,0 +1,115 @@
+//! Share the SPIM between a device on CS0 that always has data to send and
+//! one on CS1 that sends now and then, with different clock dividers. The
+//! CS1 device must be serviced by the first dispatch after its request, at
+//! most one CS0 transfer later. Polls instead of using the SPIM interrupt.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{
+                ChipSelect, DeviceTransfer, RoundRobinEvent, SpimConfig, SpimDevice, SpimRoundRobin,
+            },
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const ROUNDS: u32 = 200;
+/// CS1 requests a transfer after every this many CS0 transfers
+const SLOW_EVERY: u32 = 4;
+
+dma_static!(FAST_TX: [u8; 64]);
+dma_static!(SLOW_TX: [u8; 8]);
+
+static FAST: SpimDevice = SpimDevice::new(ChipSelect::Cs0, SpimConfig::DEFAULT);
+static SLOW: SpimDevice = SpimDevice::new(
+    ChipSelect::Cs1,
+    SpimConfig::DEFAULT.with_clk_div(9).with_mode(true, true),
+);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (fast_tx, slow_tx) = unsafe { (FAST_TX.get_mut(), SLOW_TX.get_mut()) };
+    fast_tx.fill(0xa5);
+    slow_tx.fill(0x3c);
+    FAST.request(DeviceTransfer {
+        tx: fast_tx,
+        rx: None,
+    })
+    .unwrap();
+    let mut slow_tx: Option<&'static [u8]> = Some(slow_tx);
+
+    let mut rr = SpimRoundRobin::new([&FAST, &SLOW]);
+    let mut ok = true;
+    let (mut fast_done, mut slow_done) = (0, 0);
+    // CS0 transfers started while CS1 was waiting
+    let mut slow_wait = None;
+    while fast_done < ROUNDS {
+        let event = rr.poll(&mut spim);
+        if let RoundRobinEvent::Serviced { device, .. } = event {
+            match (device, slow_wait.as_mut()) {
+                (0, Some(waited)) => *waited += 1,
+                (1, Some(waited)) => {
+                    ok &= *waited <= 1;
+                    slow_wait = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(t) = FAST.take_completed() {
+            fast_done += 1;
+            FAST.request(t).unwrap();
+            if fast_done % SLOW_EVERY == 0 {
+                if let Some(tx) = slow_tx.take() {
+                    SLOW.request(DeviceTransfer { tx, rx: None }).unwrap();
+                    slow_wait = Some(0);
+                }
+            }
+        }
+        if let Some(t) = SLOW.take_completed() {
+            slow_done += 1;
+            slow_tx = Some(t.tx);
+        }
+    }
+
+    let expected = ROUNDS / SLOW_EVERY;
+    sprintln!("CS0 {} transfers, CS1 {} transfers", fast_done, slow_done);
+    ok &= slow_done + 1 >= expected;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,86 @@
+//! Time out a slow SPIM read, check that the partial count is reported and
+//! that transfers work again afterwards. Connect MOSI to MISO.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    dma, dma_static, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig, SpimError},
+            Udma,
+        },
+    },
+    testutil::{fill_pattern, verify_pattern},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const LEN: usize = 1021;
+/// Far too short for `LEN` bytes at the slowest clock
+const SHORT_TIMEOUT_CYCLES: u64 = 200_000;
+
+dma_static!(TX_BUF: [u8; LEN]);
+dma_static!(RX_BUF: [u8; LEN]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_clk_div(0xff))
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let (tx, rx) = unsafe { (TX_BUF.get_mut(), RX_BUF.get_mut()) };
+    let mut ok = true;
+
+    let res = spim
+        .transaction(ChipSelect::Cs0)
+        .unwrap()
+        .read_timeout(rx, SHORT_TIMEOUT_CYCLES);
+    match res {
+        Err(SpimError::ShortTransfer { expected, received }) => {
+            sprintln!("{} of {} bytes before the timeout", received, expected);
+            ok &= expected == LEN && received < LEN;
+        }
+        _ => {
+            sprintln!("read was not cut short");
+            ok = false;
+        }
+    }
+    ok &= spim.asserted_cs().is_none();
+
+    spim.set_config(SpimConfig::default());
+    fill_pattern(tx, 1);
+    spim.transaction(ChipSelect::Cs0)
+        .unwrap()
+        .transfer(rx, tx)
+        .unwrap();
+    ok &= verify_pattern(rx, 1).is_ok();
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,78 @@
+//! Timestamp SPIM reads, e.g., ADC samples, and check that each timestamp
+//! falls within its read and is unaffected by processing afterwards.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const SAMPLES: usize = 8;
+const PERIOD_US: u32 = 1000;
+
+dma_static!(SAMPLE: [u8; 2]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default().with_tx_idle_byte(Some(0)))
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    unsafe { dma::init() };
+    let sample = unsafe { SAMPLE.get_mut() };
+    let mut ok = true;
+    let mut stamps = [0u64; SAMPLES];
+    for ts in stamps.iter_mut() {
+        let before = mcycle::read64();
+        *ts = spim
+            .transaction(ChipSelect::Cs0)
+            .unwrap()
+            .read_timestamped(sample)
+            .unwrap();
+        // Processing the sample, not part of the timestamp
+        delay::micros(PERIOD_US);
+        ok &= before < *ts && *ts < mcycle::read64();
+    }
+
+    for pair in stamps.windows(2) {
+        let interval = (pair[1] - pair[0]) as u32;
+        sprintln!("{} cycles between samples", interval);
+        ok &= interval as u64 >= delay::us_to_cycles(PERIOD_US);
+    }
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
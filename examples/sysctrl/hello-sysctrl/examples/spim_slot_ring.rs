@@ -0,0 +1,148 @@
+//! Receive packets from an FPGA test endpoint on CS0 into a ring of slots,
+//! while the consumer falls behind in bursts. The endpoint sends packet `i`
+//! as `1 + i % 256` bytes of `i as u8`, back to back. Every packet must
+//! arrive in order and intact, and each gap in the sequence must be
+//! accounted for as a drop. Polls instead of using the SPIM interrupt.
+//!
+//! With `-Fvp`, the packets come from the stub SPI device of
+//! `scripts/robot/spim_slot_ring.robot`, which queues them all up front.
+//! Transfers complete at once there, so each stall lasts a number of packets
+//! rather than a time, and the stream ends when the device runs dry.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::ptr::addr_of_mut;
+
+#[cfg(not(feature = "vp"))]
+use headsail_bsp::riscv::register::mcycle;
+use headsail_bsp::{
+    delay, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SlotEvent, SpimConfig, SpimSlotRing},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+#[cfg(not(feature = "vp"))]
+const PACKETS: u32 = 2000;
+/// The consumer sleeps this long after every this many packets
+#[cfg(not(feature = "vp"))]
+const BURST: u32 = 50;
+#[cfg(not(feature = "vp"))]
+const STALL_US: u32 = 2_000;
+
+#[cfg(feature = "vp")]
+const PACKETS: u32 = 48;
+/// The consumer stalls while this many packets arrive, after every this many
+#[cfg(feature = "vp")]
+const BURST: u32 = 12;
+#[cfg(feature = "vp")]
+const STALL_PACKETS: u32 = 12;
+
+static mut RING: SpimSlotRing<8, 256> = SpimSlotRing::new();
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    let (mut producer, mut consumer) =
+        unsafe { (*addr_of_mut!(RING)).split(ChipSelect::Cs0) }.unwrap();
+
+    let mut ok = true;
+    let (mut seen, mut gaps) = (0, 0);
+    let mut expected = 0u32;
+    // Set once the stub device has no more packets
+    let mut done = false;
+    while seen + gaps < PACKETS {
+        match producer.pump(&mut spim) {
+            SlotEvent::Oversized { len } => {
+                sprintln!("oversized packet of {} bytes", len);
+                ok = false;
+                break;
+            }
+            SlotEvent::Empty if cfg!(feature = "vp") => done = true,
+            SlotEvent::Empty => delay::micros(10),
+            _ => {}
+        }
+
+        let Some(packet) = consumer.next_packet() else {
+            if done {
+                break;
+            }
+            continue;
+        };
+        // Find the sequence number from the length, which wraps every 256
+        let seq = (expected & !0xff) | (packet.len() as u32 - 1);
+        let seq = if seq < expected { seq + 256 } else { seq };
+        ok &= packet.iter().all(|&b| b == seq as u8);
+        gaps += seq - expected;
+        expected = seq + 1;
+        seen += 1;
+        drop(packet);
+
+        if seen % BURST == 0 {
+            // The producer must keep up without the consumer
+            #[cfg(not(feature = "vp"))]
+            {
+                let (start, stall) = (mcycle::read64(), delay::us_to_cycles(STALL_US));
+                while mcycle::read64() - start < stall {
+                    producer.pump(&mut spim);
+                }
+            }
+            #[cfg(feature = "vp")]
+            {
+                let mut arrived = 0;
+                while arrived < STALL_PACKETS {
+                    match producer.pump(&mut spim) {
+                        SlotEvent::Received { .. } | SlotEvent::Dropped { .. } => arrived += 1,
+                        SlotEvent::Busy => {}
+                        _ => break,
+                    }
+                }
+            }
+        }
+    }
+    // Packets lost after the last one seen, when the stub device ran dry
+    gaps += PACKETS.saturating_sub(expected);
+
+    let stats = consumer.stats();
+    sprintln!(
+        "received {} dropped {} gaps {}",
+        stats.received,
+        stats.dropped,
+        gaps
+    );
+    ok &= stats.dropped == gaps && stats.oversized == 0;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,59 @@
+//! Send a JEDEC ID command on CS0 and check the 3-byte reply. Counterpart of
+//! `scripts/robot/spim_vp_stub.robot`, which queues the reply on a stub SPI
+//! device in the VP and checks the command byte.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const EXPECTED_ID: [u8; 3] = [0xef, 0x40, 0x18];
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    let mut id = [0u8; 3];
+    {
+        let mut t = spim.transaction(ChipSelect::Cs0).unwrap();
+        t.write(&[0x9f]).unwrap();
+        t.read(&mut id).unwrap();
+    }
+
+    if id == EXPECTED_ID {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL] id {} {} {}", id[0], id[1], id[2]);
+    }
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,68 @@
+//! Close the SPIM clock gate behind the driver's back with
+//! `UdmaSpim::with_raw` and check that the driver restores it, so that the
+//! JEDEC ID of the SPI NOR flash on CS0 still reads back the same.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        spi_flash::SpiFlash,
+        udma::{
+            spim::{ChipSelect, SpimConfig},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const FLASH_CAPACITY: u32 = 16 * 1024 * 1024;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut spim = udma
+        .split()
+        .unwrap()
+        .spim
+        .unwrap()
+        .enable(SpimConfig::default())
+        .map_err(|(_, e)| e)
+        .unwrap();
+
+    let before = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY)
+        .jedec_id()
+        .unwrap();
+    sprintln!("JEDEC ID {} {} {}", before[0], before[1], before[2]);
+
+    let was_open = spim.with_raw(|udma| {
+        let open = udma.ctrl_cfg_cg().read().cg_spim().bit_is_set();
+        udma.ctrl_cfg_cg().modify(|_r, w| w.cg_spim().clear_bit());
+        open
+    });
+    let reopened = sysctrl.udma().ctrl_cfg_cg().read().cg_spim().bit_is_set();
+
+    let after = SpiFlash::new(&mut spim, ChipSelect::Cs0, FLASH_CAPACITY).jedec_id();
+
+    if was_open && reopened && after == Ok(before) && before != [0xff; 3] {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
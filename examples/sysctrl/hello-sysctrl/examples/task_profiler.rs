@@ -0,0 +1,50 @@
+//! Profile two cooperative tasks of known relative cost and print the
+//! per-task table. The longer task must account for more cycles.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{profile::TaskProfiler, rt::entry, sysctrl::soc_ctrl};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const TASK_SHORT: u8 = 0;
+const TASK_LONG: u8 = 1;
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    for _ in 0..8 {
+        TaskProfiler::enter(TASK_SHORT);
+        spin(100);
+        TaskProfiler::exit(TASK_SHORT);
+
+        TaskProfiler::enter(TASK_LONG);
+        spin(1000);
+        TaskProfiler::exit(TASK_LONG);
+    }
+    TaskProfiler::report(&mut hello_sysctrl::UdmaUart).unwrap();
+
+    let (short, long) = (TaskProfiler::get(TASK_SHORT), TaskProfiler::get(TASK_LONG));
+    if short.runs == 8 && long.runs == 8 && long.cycles > 5 * short.cycles {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
+
+#[inline(never)]
+fn spin(n: u32) {
+    for _ in 0..n {
+        unsafe { core::arch::asm!("nop") };
+    }
+}
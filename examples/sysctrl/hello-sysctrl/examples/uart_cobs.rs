@@ -0,0 +1,53 @@
+//! Echo COBS frames received on the uDMA UART. Frames that fail to decode are
+//! answered with an empty frame.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            uart::cobs::{CobsDecoder, CobsEncoder},
+            Udma,
+        },
+    },
+};
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+
+    let (soc_freq, baud) = (30_000_000, 9600_u32);
+    let clk_div: u16 = (soc_freq / baud) as u16;
+    let uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .polling_en()
+            .bit(true)
+            .tx_ena()
+            .bit(true)
+            .rx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+
+    let mut buf = [0u8; 256];
+    let mut rx = CobsDecoder::new(uart);
+    loop {
+        let len = rx.receive_frame(&mut buf).unwrap_or(0);
+
+        let mut tx = CobsEncoder::new(rx.free());
+        tx.send_frame(&buf[..len]).unwrap();
+        rx = CobsDecoder::new(tx.free());
+    }
+}
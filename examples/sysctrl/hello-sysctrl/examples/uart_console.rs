@@ -0,0 +1,70 @@
+//! Print through a `UartConsole` shared in a static. Lines are prefixed with
+//! `mcycle`, debug messages are only shown after raising the level, and a
+//! line too long for the console is cut short.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    console_log, console_println, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            uart::console::{Level, UartConsole},
+            Udma,
+        },
+    },
+};
+
+fn timestamp() -> u64 {
+    mcycle::read64()
+}
+
+static CONSOLE: UartConsole<'static> = UartConsole::new(Some(timestamp));
+
+#[entry]
+fn main() -> ! {
+    let udma = Udma(unsafe { (*pac::Sysctrl::ptr()).udma() });
+
+    soc_ctrl::periph_clk_div_set(0);
+
+    let (soc_freq, baud) = (30_000_000, 115_200_u32);
+    let clk_div: u16 = (soc_freq / baud) as u16;
+    let uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .tx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+    CONSOLE.attach(uart);
+
+    console_println!(CONSOLE, "uart_console");
+    console_log!(CONSOLE, Level::Debug, "hidden at the default level");
+    console_log!(CONSOLE, Level::Warn, "warnings are shown");
+
+    CONSOLE.set_level(Level::Debug);
+    for n in 0..3_u32 {
+        console_log!(CONSOLE, Level::Debug, "debug {}", n);
+    }
+    console_log!(
+        CONSOLE,
+        Level::Info,
+        "this line is too long for the console and ends in a marker, {} {} {} {}",
+        u32::MAX,
+        u32::MAX,
+        u32::MAX,
+        u32::MAX
+    );
+
+    console_println!(CONSOLE, "[PASS]");
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
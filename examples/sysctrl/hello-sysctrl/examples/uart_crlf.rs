@@ -0,0 +1,65 @@
+//! Line-editing echo on the uDMA UART. Lines end with CR, LF or CRLF,
+//! whatever the terminal sends, and each is answered as `> line` ending in
+//! CRLF.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{uart::UartNormalizer, Udma},
+    },
+    ufmt::uwriteln,
+};
+
+const LINE_LEN: usize = 80;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+
+    let (soc_freq, baud) = (30_000_000, 9600_u32);
+    let clk_div: u16 = (soc_freq / baud) as u16;
+    let uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .polling_en()
+            .bit(true)
+            .tx_ena()
+            .bit(true)
+            .rx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+
+    let mut term = UartNormalizer::new(uart);
+    uwriteln!(term, "type a line").unwrap();
+
+    let mut line = [0u8; LINE_LEN];
+    let mut len = 0;
+    loop {
+        match term.read_byte() {
+            b'\n' => {
+                term.write(b"> ").unwrap();
+                term.write(&line[..len]).unwrap();
+                term.write(b"\n").unwrap();
+                len = 0;
+            }
+            byte if len < LINE_LEN => {
+                line[len] = byte;
+                len += 1;
+            }
+            _ => {}
+        }
+    }
+}
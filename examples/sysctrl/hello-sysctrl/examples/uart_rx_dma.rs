@@ -0,0 +1,112 @@
+//! Receive over the uDMA UART RX channel. Send a burst of up to 4 KiB, then a
+//! stream of SLIP frames, then keep sending without pause while the ring is
+//! not drained.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, dma, dma_static, pac,
+    riscv::register::mcycle,
+    rt::entry,
+    slip,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            uart::{UartError, UartRxRing},
+            Udma,
+        },
+    },
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+const BAUD: u32 = 921_600;
+const FRAMES: u32 = 100;
+
+dma_static!(BURST: [u8; 4096]);
+dma_static!(RING: [u8; 256]);
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+    let clk_div = (30_000_000 / BAUD) as u16;
+    let mut uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .tx_ena()
+            .bit(true)
+            .rx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+    print_example_name!();
+
+    unsafe { dma::init() };
+    let (burst, ring_buf) = unsafe { (BURST.get_mut(), RING.get_mut()) };
+
+    // Ten bit times of silence end the burst
+    let idle = (delay::core_hz() / BAUD * 10) as u64;
+    sprintln!("send a burst");
+    // Wait for the first byte before timing the idle gap
+    let mut len = 0;
+    let mut start = 0;
+    while len == 0 {
+        start = mcycle::read64();
+        len = match uart.read_dma_idle(burst, idle) {
+            Err(UartError::Overrun { received }) => {
+                sprintln!("FIFO overrun after {} bytes", received);
+                received
+            }
+            res => res.unwrap(),
+        };
+    }
+    let cycles = (mcycle::read64() - start) as u32;
+    sprintln!("{} bytes in {} cycles", len, cycles);
+
+    sprintln!("send {} SLIP frames", FRAMES);
+    let mut ring = UartRxRing::new(&mut uart, ring_buf).unwrap();
+    let mut dec = slip::Decoder::new();
+    let mut frame = [0u8; 128];
+    let (mut frames, mut bytes, mut errors) = (0, 0, 0);
+    let start = mcycle::read64();
+    while frames < FRAMES {
+        ring.drain(|byte| match dec.push(byte, &mut frame) {
+            Some(Ok(len)) => {
+                frames += 1;
+                bytes += len as u32;
+            }
+            Some(Err(_)) => errors += 1,
+            None => {}
+        });
+    }
+    let cycles = (mcycle::read64() - start) as u32;
+    sprintln!(
+        "{} frames, {} payload bytes in {} cycles, {} errors, FIFO overrun {}",
+        frames,
+        bytes,
+        cycles,
+        errors,
+        ring.overrun() as u8
+    );
+
+    sprintln!("keep sending");
+    // Long enough for the ring to wrap a few times at BAUD
+    delay::micros(4 * 256 * 10_000 / (BAUD / 100));
+    sprintln!(
+        "{} bytes available after lapping, FIFO overrun {}",
+        ring.available(),
+        ring.overrun() as u8
+    );
+    drop(ring);
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
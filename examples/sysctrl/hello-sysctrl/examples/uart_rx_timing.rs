@@ -0,0 +1,71 @@
+//! Echo Modbus RTU style frames received on the uDMA UART. A frame ends after
+//! 3.5 characters of silence on the line, and is echoed back with the largest
+//! gap between its bytes in microseconds appended as a little-endian u32.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{
+            uart::timing::{modbus_t35_cycles, RxByteTimer},
+            Udma,
+        },
+    },
+};
+
+const FRAME_LEN: usize = 256;
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+
+    let (soc_freq, baud) = (30_000_000, 9600_u32);
+    let clk_div: u16 = (soc_freq / baud) as u16;
+    let mut uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .polling_en()
+            .bit(true)
+            .tx_ena()
+            .bit(true)
+            .rx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+
+    let t35 = modbus_t35_cycles(baud);
+    let mut timer = RxByteTimer::<FRAME_LEN>::new();
+    let mut frame = [0u8; FRAME_LEN];
+    let mut stamps = [0u64; FRAME_LEN];
+    let mut len = 0;
+    loop {
+        if let Some(byte) = timer.poll(&mut uart) {
+            if len < FRAME_LEN {
+                frame[len] = byte;
+                len += 1;
+            }
+        } else if len > 0 && timer.idle_gap().is_some_and(|gap| gap >= t35) {
+            let n = timer.byte_timestamps(&mut stamps);
+            let max_gap = stamps[..n]
+                .windows(2)
+                .map(|w| w[1] - w[0])
+                .max()
+                .unwrap_or(0);
+            uart.write(&frame[..len]);
+            uart.write(&(delay::cycles_to_us(max_gap) as u32).to_le_bytes());
+            timer.clear();
+            len = 0;
+        }
+    }
+}
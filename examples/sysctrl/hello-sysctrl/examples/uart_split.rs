@@ -0,0 +1,72 @@
+//! Split the uDMA UART into its TX and RX halves. Every key typed is
+//! answered with its count, while a heartbeat goes out in between.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{
+    delay, pac,
+    rt::entry,
+    sysctrl::{
+        soc_ctrl,
+        udma::{uart::UartRx, Udma},
+    },
+    ufmt::uwriteln,
+};
+
+/// Stands in for the UART interrupt handler, which the RX half could be
+/// moved to
+fn on_rx(rx: &mut UartRx, keys: &mut u32) -> bool {
+    let mut got = false;
+    while rx.try_read_byte().is_some() {
+        *keys += 1;
+        got = true;
+    }
+    got
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[entry]
+fn main() -> ! {
+    let sysctrl = unsafe { pac::Sysctrl::steal() };
+    let udma = Udma(sysctrl.udma());
+
+    soc_ctrl::periph_clk_div_set(0);
+
+    let (soc_freq, baud) = (30_000_000, 9600_u32);
+    let clk_div: u16 = (soc_freq / baud) as u16;
+    let uart = udma.split().unwrap().uart.unwrap().enable(|w| unsafe {
+        w.bit_length()
+            .bits(0b11)
+            .polling_en()
+            .bit(true)
+            .tx_ena()
+            .bit(true)
+            .rx_ena()
+            .bit(true)
+            .clkdiv()
+            .bits(clk_div)
+    });
+
+    let (mut tx, mut rx) = uart.split();
+    assert_send(&tx);
+    assert_send(&rx);
+    uwriteln!(tx, "type some keys").unwrap();
+
+    let mut keys = 0;
+    let mut beats = 0u32;
+    loop {
+        if on_rx(&mut rx, &mut keys) {
+            uwriteln!(tx, "{} keys", keys).unwrap();
+        }
+        beats += 1;
+        if beats.is_multiple_of(50) {
+            uwriteln!(tx, ".").unwrap();
+        }
+        delay::micros(20_000);
+    }
+}
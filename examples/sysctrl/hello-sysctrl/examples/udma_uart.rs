@@ -21,7 +21,7 @@ fn main() -> ! {
     // Set the bit length, enable TX, set clk_div
     let (soc_freq, baud) = (30_000_000, 9600_u32);
     let clk_div: u16 = (soc_freq / baud) as u16;
-    let mut uart = udma.split().uart.enable(|w| {
+    let mut uart = udma.split().unwrap().uart.unwrap().enable(|w| {
         unsafe {
             w
                 // Use this if using parity bit
@@ -0,0 +1,42 @@
+//! Feed through a WatchdogRefresher only once every slot has checked in.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use headsail_bsp::{rt::entry, sysctrl::soc_ctrl, watchdog::WatchdogRefresher};
+use hello_sysctrl::{print_example_name, sprintln};
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut wdt = WatchdogRefresher::<3>::new();
+    let mut feeds = 0;
+    let mut ok = true;
+
+    // Slot 1 is stuck
+    wdt.check_in(0);
+    wdt.check_in(2);
+    ok &= !wdt.feed(|| feeds += 1);
+    ok &= wdt.first_missing() == Some(1);
+
+    wdt.check_in(1);
+    ok &= wdt.feed(|| feeds += 1);
+    ok &= wdt.first_missing() == Some(0);
+    ok &= feeds == 1 && wdt.missed() == 1;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -0,0 +1,68 @@
+//! Run deferred work in order, including work deferred while draining, and
+//! count the entries dropped when the queue is full.
+//!
+//! | Date              | Status     | Changes   |
+//! | :-                | :-:        | :-        |
+//! | 2026-10-14        | *Untested* |           |
+#![no_std]
+#![no_main]
+
+use core::ptr::addr_of_mut;
+
+use headsail_bsp::{
+    rt::entry,
+    sysctrl::soc_ctrl,
+    work::{self, WORK_QUEUE_LEN},
+};
+use hello_sysctrl::{print_example_name, sprintln};
+
+static mut LOG: [usize; 2 * WORK_QUEUE_LEN] = [0; 2 * WORK_QUEUE_LEN];
+static mut LOGGED: usize = 0;
+
+fn record(ctx: usize) {
+    unsafe {
+        (*addr_of_mut!(LOG))[LOGGED] = ctx;
+        LOGGED += 1;
+    }
+}
+
+/// Queues a follow-up, like a handler that chains the next transfer
+fn chain(ctx: usize) {
+    record(ctx);
+    work::defer(record, ctx + 1).unwrap();
+}
+
+#[entry]
+fn main() -> ! {
+    soc_ctrl::periph_clk_div_set(0);
+    hello_sysctrl::UdmaUart::init();
+    print_example_name!();
+
+    let mut ok = true;
+
+    work::defer(record, 1).unwrap();
+    work::defer(chain, 2).unwrap();
+    work::defer(record, 4).unwrap();
+    ok &= work::run_pending() == 4;
+    ok &= unsafe { (*addr_of_mut!(LOG))[..LOGGED] == [1, 2, 4, 3] };
+    ok &= work::pending() == 0;
+
+    unsafe { LOGGED = 0 };
+    for ctx in 0..WORK_QUEUE_LEN {
+        work::defer(record, ctx).unwrap();
+    }
+    ok &= work::defer(record, WORK_QUEUE_LEN).is_err();
+    ok &= work::overflows() == 1;
+    ok &= work::run_pending() == WORK_QUEUE_LEN;
+    ok &= unsafe { LOGGED } == WORK_QUEUE_LEN;
+
+    if ok {
+        sprintln!("[PASS]");
+    } else {
+        sprintln!("[FAIL]");
+    }
+
+    loop {
+        unsafe { core::arch::asm!("wfi") };
+    }
+}
@@ -6,7 +6,8 @@ use headsail_bsp::{
     ufmt,
 };
 
-/// Print the name of the current file, i.e., test name.
+/// Print the name of the current file, i.e., test name, followed by the BSP
+/// version banner.
 ///
 /// This must be a macro to make sure core::file matches the file this is
 /// invoked in.
@@ -17,6 +18,7 @@ macro_rules! print_example_name {
         sysctrl_print(b"[");
         sysctrl_print(core::file!().as_bytes());
         sysctrl_print(b"]\r\n");
+        $crate::print_bsp_version();
     };
 }
 
@@ -69,7 +71,7 @@ impl UdmaUart {
         // Set the bit length, enable TX, set clk_div
         let (soc_freq, baud) = (30_000_000, 9600_u32);
         let clk_div: u16 = (soc_freq / baud) as u16;
-        let _uart = udma.split().uart.enable(|w| {
+        let _uart = udma.split().unwrap().uart.unwrap().enable(|w| {
             unsafe {
                 w
                     // Use this if using parity bit
@@ -125,3 +127,8 @@ macro_rules! sprintln {
         sprint!("\r\n");
     }};
 }
+
+/// Make sure to enable uDMA UART prior to using this function
+pub fn print_bsp_version() {
+    sprintln!("{}", headsail_bsp::version());
+}
@@ -0,0 +1,31 @@
+#!/bin/sh
+
+# Fails if the .text of the ELF in $2 is more than $3 percent (default 2)
+# larger than that of the reference ELF in $1. Uses llvm-size from $SIZE or
+# $PATH.
+
+BASE=$1
+NEW=$2
+MAX_GROWTH_PERCENT=${3:-2}
+if [ -z "$BASE" ] || [ -z "$NEW" ]; then
+    echo "!! Usage: $0 BASE_ELF NEW_ELF [MAX_GROWTH_PERCENT]"
+    exit 1
+fi
+
+SIZE=${SIZE:-llvm-size}
+text_size() {
+    $SIZE -A "$1" | awk '$1 == ".text" { print $2 }'
+}
+
+BASE_TEXT=$(text_size "$BASE")
+NEW_TEXT=$(text_size "$NEW")
+if [ -z "$BASE_TEXT" ] || [ -z "$NEW_TEXT" ]; then
+    echo "!! No .text section found"
+    exit 1
+fi
+
+echo ".text: $BASE_TEXT -> $NEW_TEXT bytes (max growth $MAX_GROWTH_PERCENT%)"
+if [ $((NEW_TEXT * 100)) -gt $((BASE_TEXT * (100 + MAX_GROWTH_PERCENT))) ]; then
+    echo "!! .text grew by more than $MAX_GROWTH_PERCENT%"
+    exit 1
+fi